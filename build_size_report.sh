@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+# Size report of the contract wasm blobs
+#
+# Builds every contract of the workspace for each feature combination, prints the size of each
+# blob (and its largest items when twiggy is installed) and fails if a default-feature blob is
+# above the budget. These are plain release builds, build_release.sh gives the deployed sizes.
+#
+# usage: WASM_SIZE_BUDGET=<bytes> ./build_size_report.sh [package...]
+set -euo pipefail
+
+BUDGET="${WASM_SIZE_BUDGET:-800000}"
+FEATURE_SETS=("" "backtraces" "library")
+TARGET_DIR="$(cargo metadata --format-version 1 --no-deps | sed -n 's/.*"target_directory":"\([^"]*\)".*/\1/p')"
+WASM_DIR="$TARGET_DIR/wasm32-unknown-unknown/release"
+
+if [ "$#" -gt 0 ]; then
+  PACKAGES=("$@")
+else
+  PACKAGES=()
+  for manifest in contracts/*/Cargo.toml; do
+    PACKAGES+=("$(sed -n 's/^name = "\(.*\)"/\1/p' "$manifest" | head -n 1)")
+  done
+fi
+
+failed=0
+for package in "${PACKAGES[@]}"; do
+  for features in "${FEATURE_SETS[@]}"; do
+    RUSTFLAGS="-C link-arg=-s" cargo build --release --lib --target wasm32-unknown-unknown \
+      --locked -p "$package" ${features:+--features "$features"} >/dev/null
+    wasm="$WASM_DIR/${package//-/_}.wasm"
+    size=$(wc -c <"$wasm")
+    echo "$package [${features:-default}]: $size bytes"
+
+    if [ -z "$features" ]; then
+      if command -v twiggy >/dev/null; then
+        twiggy top -n 10 "$wasm"
+      fi
+      if [ "$size" -gt "$BUDGET" ]; then
+        echo "$package exceeds the size budget of $BUDGET bytes" >&2
+        failed=1
+      fi
+    fi
+  done
+done
+
+exit "$failed"
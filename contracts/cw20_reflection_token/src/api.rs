@@ -0,0 +1,42 @@
+use cosmwasm_std::{Response, StdResult, Storage};
+use cw2::get_contract_version;
+
+use crate::msg::{ApiVersionResponse, DeprecatedVariant, FeaturesResponse};
+
+/// Semver of the message API, independent of the contract version. Minor bumps only add messages
+/// or optional fields, deprecated shapes keep working until the announced major version
+pub const API_VERSION: &str = "1.1.0";
+
+/// (variant, replacement, major version removing it) of the legacy message shapes still accepted
+const DEPRECATIONS: &[(&str, &str, &str)] = &[("cw20_code_id", "treasury_code_id", "2.0.0")];
+
+/// Flags a response to a legacy message shape with a `deprecated` attribute naming the variant
+pub fn deprecated(res: Response, variant: &str) -> Response {
+    debug_assert!(DEPRECATIONS.iter().any(|(name, _, _)| *name == variant));
+    res.add_attribute("deprecated", variant)
+}
+
+pub fn query_api_version(storage: &dyn Storage) -> StdResult<ApiVersionResponse> {
+    let version = get_contract_version(storage)?;
+
+    Ok(ApiVersionResponse {
+        contract: version.contract,
+        version: version.version,
+        api_version: API_VERSION.to_string(),
+        deprecated: DEPRECATIONS
+            .iter()
+            .map(|(variant, replacement, removed_in)| DeprecatedVariant {
+                variant: variant.to_string(),
+                replacement: replacement.to_string(),
+                removed_in: removed_in.to_string(),
+            })
+            .collect(),
+    })
+}
+
+pub fn query_features() -> FeaturesResponse {
+    FeaturesResponse {
+        clawback: cfg!(feature = "clawback"),
+        debug: cfg!(feature = "debug"),
+    }
+}
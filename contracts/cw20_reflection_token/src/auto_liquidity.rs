@@ -0,0 +1,303 @@
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, Decimal, Deps, DepsMut, Env, MessageInfo, QuerierWrapper,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
+};
+use cw20::{AllowanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration};
+use cw20_base::state::ALLOWANCES;
+use cw20_base::ContractError;
+use dojoswap::asset::{Asset, AssetInfo};
+use dojoswap::pair::{Cw20HookMsg as PairHookMsg, ExecuteMsg as PairExecuteMsg};
+use dojoswap::querier::{query_balance, query_token_balance};
+use dojoswap::taxed::TransferTaxedToken;
+
+use crate::contract::ensure_admin;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::AutoLiquidityResponse;
+use crate::state::{AutoLiquidityConfig, LiquifyRun, AUTO_LIQUIDITY, AUTO_LIQUIDITY_STATE};
+use crate::tax_config::ensure_tax_destination;
+
+/// Reply id of the swap of half the tokens set aside
+pub const AUTO_LIQUIDITY_SWAP_REPLY_ID: u64 = 4;
+/// Reply id of the liquidity provided with the other half and what the swap returned
+pub const AUTO_LIQUIDITY_PROVIDE_REPLY_ID: u64 = 5;
+
+/// Sets aside a share of every tax for liquidity, None stops setting aside. What is already set
+/// aside stays for the next run
+pub fn set_auto_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Option<AutoLiquidityConfig>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &config {
+        Some(config) => {
+            if config.rate > Decimal::one() {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "auto-liquidity rate must be <= 1",
+                )));
+            }
+            if config.threshold.is_zero() {
+                return Err(ContractError::InvalidZeroAmount {});
+            }
+            deps.api.addr_validate(&config.pair)?;
+            deps.api.addr_validate(&config.lock_address)?;
+            if let AssetInfo::Token { contract_addr } = &config.quote {
+                if deps.api.addr_validate(contract_addr)? == env.contract.address {
+                    return Err(ContractError::Std(StdError::generic_err(
+                        "The token cannot be paired with itself",
+                    )));
+                }
+            }
+            ensure_tax_destination(deps.storage)?;
+            AUTO_LIQUIDITY.save(deps.storage, config)?;
+        }
+        None => AUTO_LIQUIDITY.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_auto_liquidity")
+        .add_attribute("enabled", config.is_some().to_string()))
+}
+
+/// Sets aside the auto-liquidity share of `taxed_amount` on the token contract balance, taken out
+/// of `available`. Returns the amount set aside
+pub fn credit_auto_liquidity(
+    storage: &mut dyn Storage,
+    env: &Env,
+    taxed_amount: Uint128,
+    available: Uint128,
+) -> StdResult<Uint128> {
+    let config = match AUTO_LIQUIDITY.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok(Uint128::zero()),
+    };
+    let amount = (taxed_amount * config.rate).min(available);
+    if amount.is_zero() {
+        return Ok(amount);
+    }
+
+    increase_balance(storage, &env.contract.address, amount)?;
+    let mut state = AUTO_LIQUIDITY_STATE.may_load(storage)?.unwrap_or_default();
+    state.accumulated = state.accumulated.checked_add(amount)?;
+    AUTO_LIQUIDITY_STATE.save(storage, &state)?;
+
+    Ok(amount)
+}
+
+fn quote_balance(querier: &QuerierWrapper, env: &Env, quote: &AssetInfo) -> StdResult<Uint128> {
+    match quote {
+        AssetInfo::NativeToken { denom } => {
+            query_balance(querier, env.contract.address.clone(), denom.to_string())
+        }
+        AssetInfo::Token { contract_addr } => query_token_balance(
+            querier,
+            Addr::unchecked(contract_addr),
+            env.contract.address.clone(),
+        ),
+    }
+}
+
+/// Starts a run once the tokens set aside reach the threshold: half is swapped on the pair for the
+/// quote asset, the reply provides liquidity with the other half. Called from `TransferEvent`, so
+/// at most one run is in flight whatever the transfers of its own swap trigger
+pub fn auto_liquify(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+) -> StdResult<Option<SubMsg>> {
+    let config = match AUTO_LIQUIDITY.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    let mut state = AUTO_LIQUIDITY_STATE.may_load(storage)?.unwrap_or_default();
+    if state.in_flight.is_some() || state.accumulated < config.threshold {
+        return Ok(None);
+    }
+
+    let swapped = state.accumulated.multiply_ratio(1u128, 2u128);
+    let run = LiquifyRun {
+        swapped,
+        kept: state.accumulated - swapped,
+        quote_before: quote_balance(querier, env, &config.quote)?,
+        quote: Uint128::zero(),
+    };
+    state.accumulated = Uint128::zero();
+    state.in_flight = Some(run);
+    AUTO_LIQUIDITY_STATE.save(storage, &state)?;
+
+    // the tax was already collected, the swap moves it to the pair without another tax the way
+    // `Send` calls its receive hook
+    let pair = Addr::unchecked(&config.pair);
+    decrease_balance(storage, &env.contract.address, swapped)?;
+    increase_balance(storage, &pair, swapped)?;
+    let swap = WasmMsg::Execute {
+        contract_addr: config.pair,
+        msg: to_json_binary(&PairExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: env.contract.address.to_string(),
+            amount: swapped,
+            msg: to_json_binary(&PairHookMsg::Swap {
+                belief_price: None,
+                max_spread: config.max_spread,
+                to: None,
+                deadline: None,
+            })?,
+        }))?,
+        funds: vec![],
+    };
+
+    Ok(Some(SubMsg::reply_always(
+        swap,
+        AUTO_LIQUIDITY_SWAP_REPLY_ID,
+    )))
+}
+
+/// Provides liquidity with the tokens kept and what the swap returned, a failed swap (e.g. above
+/// the max spread) takes the tokens back from the pair and sets them aside again
+pub fn swap_replied(
+    deps: DepsMut,
+    env: &Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut state = AUTO_LIQUIDITY_STATE.load(deps.storage)?;
+    let mut run = state
+        .in_flight
+        .take()
+        .ok_or_else(|| StdError::generic_err("No auto-liquidity run in flight"))?;
+    let config = AUTO_LIQUIDITY
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("Auto-liquidity is not configured"))?;
+    let pair = Addr::unchecked(&config.pair);
+
+    if let SubMsgResult::Err(reason) = result {
+        // the pair changes were reverted with the swap, not the credit of the pair
+        decrease_balance(deps.storage, &pair, run.swapped)?;
+        increase_balance(deps.storage, &env.contract.address, run.swapped)?;
+        state.accumulated += run.swapped + run.kept;
+        state.last_failure = Some(reason.clone());
+        AUTO_LIQUIDITY_STATE.save(deps.storage, &state)?;
+        return Ok(Response::new()
+            .add_attribute("action", "auto_liquidity_swap_failed")
+            .add_attribute("amount", run.swapped)
+            .add_attribute("reason", reason));
+    }
+
+    let received =
+        config
+            .quote
+            .received_since(&deps.querier, &env.contract.address, run.quote_before)?;
+    run.quote = received + state.quote_held;
+    state.in_flight = Some(run.clone());
+    AUTO_LIQUIDITY_STATE.save(deps.storage, &state)?;
+
+    // the pair takes the tokens kept with `TransferFrom`, untaxed from the token contract
+    ALLOWANCES.save(
+        deps.storage,
+        (&env.contract.address, &pair),
+        &AllowanceResponse {
+            allowance: run.kept,
+            expires: Expiration::Never {},
+        },
+    )?;
+    let assets = [
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: env.contract.address.to_string(),
+            },
+            amount: run.kept,
+        },
+        Asset {
+            info: config.quote.clone(),
+            amount: run.quote,
+        },
+    ];
+    let provide = PairExecuteMsg::ProvideLiquidity {
+        assets,
+        receiver: Some(config.lock_address),
+        deadline: None,
+        slippage_tolerance: config.max_spread,
+    };
+    let mut messages = vec![];
+    let funds = match &config.quote {
+        AssetInfo::NativeToken { denom } => coins(run.quote.u128(), denom),
+        AssetInfo::Token { contract_addr } => {
+            messages.push(SubMsg::new(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: config.pair.clone(),
+                    amount: run.quote,
+                    expires: None,
+                })?,
+                funds: vec![],
+            }));
+            vec![]
+        }
+    };
+    messages.push(SubMsg::reply_always(
+        WasmMsg::Execute {
+            contract_addr: config.pair,
+            msg: to_json_binary(&provide)?,
+            funds,
+        },
+        AUTO_LIQUIDITY_PROVIDE_REPLY_ID,
+    ));
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "auto_liquidity_swap")
+        .add_attribute("swapped", run.swapped)
+        .add_attribute("received", received))
+}
+
+/// Ends the run. A failed provide (e.g. above the slippage tolerance) sets the tokens kept aside
+/// again and holds the quote asset for the next run
+pub fn provide_replied(
+    storage: &mut dyn Storage,
+    env: &Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut state = AUTO_LIQUIDITY_STATE.load(storage)?;
+    let run = state
+        .in_flight
+        .take()
+        .ok_or_else(|| StdError::generic_err("No auto-liquidity run in flight"))?;
+    let config = AUTO_LIQUIDITY
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("Auto-liquidity is not configured"))?;
+    ALLOWANCES.remove(
+        storage,
+        (&env.contract.address, &Addr::unchecked(&config.pair)),
+    );
+
+    let res = match result {
+        SubMsgResult::Ok(_) => {
+            state.provided += run.kept;
+            state.quote_held = Uint128::zero();
+            Response::new()
+                .add_attribute("action", "auto_liquidity")
+                .add_attribute("token_amount", run.kept)
+                .add_attribute("quote_amount", run.quote)
+                .add_attribute("lock_address", config.lock_address)
+        }
+        SubMsgResult::Err(reason) => {
+            state.accumulated += run.kept;
+            state.quote_held = run.quote;
+            state.last_failure = Some(reason.clone());
+            Response::new()
+                .add_attribute("action", "auto_liquidity_provide_failed")
+                .add_attribute("amount", run.kept)
+                .add_attribute("reason", reason)
+        }
+    };
+    AUTO_LIQUIDITY_STATE.save(storage, &state)?;
+    Ok(res)
+}
+
+pub fn query_auto_liquidity(deps: Deps) -> StdResult<AutoLiquidityResponse> {
+    Ok(AutoLiquidityResponse {
+        config: AUTO_LIQUIDITY.may_load(deps.storage)?,
+        state: AUTO_LIQUIDITY_STATE
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,101 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Event, MessageInfo, Response, StdError, Uint128};
+use cw20::Cw20Coin;
+use cw20_base::ContractError;
+
+use crate::bridge::bridge_events;
+use crate::contract::{is_untaxed, transfer_leg};
+use crate::drip::tax_transfer_events;
+use crate::hooks::transfer_hooks;
+use crate::tax_strategy::gross_up_transfer;
+
+/// Most recipients of a single `MultiTransfer`, larger distributions are split over several
+/// messages
+pub const MAX_RECIPIENTS: usize = 300;
+
+/// Transfers to every recipient from `info.sender`, each transfer checked and taxed like
+/// `Transfer`. With `exact_net` the amounts are what the recipients receive and the sender is
+/// charged the gross amounts. The tax credits are summed per address so that a single
+/// `TransferEvent` is logged for each
+pub fn execute_multi_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<Cw20Coin>,
+    exact_net: bool,
+) -> Result<Response, ContractError> {
+    if recipients.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err("No recipients")));
+    }
+    if recipients.len() > MAX_RECIPIENTS {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "At most {} recipients per transfer",
+            MAX_RECIPIENTS
+        ))));
+    }
+
+    let mut total_sent = Uint128::zero();
+    let mut total_received = Uint128::zero();
+    let mut total_tax = Uint128::zero();
+    let mut credits: Vec<(Addr, Uint128)> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let mut hooks = vec![];
+    for recipient in recipients.iter() {
+        let amount = if exact_net
+            && !recipient.amount.is_zero()
+            && !is_untaxed(deps.storage, info.sender.as_str(), &recipient.address)?
+        {
+            gross_up_transfer(deps.storage, &env, info.sender.as_str(), recipient.amount)?
+        } else {
+            recipient.amount
+        };
+        let leg = transfer_leg(
+            &mut deps,
+            &env,
+            &info.sender,
+            None,
+            &recipient.address,
+            amount,
+            None,
+            None,
+        )?;
+
+        total_sent += amount;
+        total_received += leg.outgoing_amount;
+        total_tax += leg.taxed_amount;
+        for (address, amount) in leg.credits {
+            match credits
+                .iter_mut()
+                .find(|(credited, _)| *credited == address)
+            {
+                Some((_, total)) => *total += amount,
+                None => credits.push((address, amount)),
+            }
+        }
+        hooks.extend(transfer_hooks(
+            deps.storage,
+            info.sender.as_str(),
+            &recipient.address,
+            amount,
+            leg.taxed_amount,
+        )?);
+        events.extend(bridge_events(
+            deps.storage,
+            info.sender.as_str(),
+            &recipient.address,
+            leg.outgoing_amount,
+        ));
+        events.push(leg.tax_event);
+    }
+    let messages = tax_transfer_events(&env, info.sender.as_str(), credits)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attribute("action", "multi_transfer")
+        .add_attribute("from", info.sender)
+        .add_attribute("recipients", recipients.len().to_string())
+        .add_attribute("total_sent", total_sent)
+        .add_attribute("total_received", total_received)
+        .add_attribute("total_tax", total_tax))
+}
@@ -0,0 +1,58 @@
+use cosmwasm_std::{Deps, DepsMut, Event, MessageInfo, Response, StdResult, Storage, Uint128};
+use cw20_base::ContractError;
+
+use crate::contract::ensure_admin;
+use crate::msg::BridgeConfigResponse;
+use crate::state::BRIDGE;
+
+/// Registers the cw20-ics20 or other bridge contract escrowing the token, None unregisters it.
+/// Transfers and sends from and to the bridge are not taxed and skip the anti-whale and wallet
+/// limits, so the escrowed amount matches what was bridged and refunds always go through
+pub fn set_bridge(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &contract {
+        Some(contract) => BRIDGE.save(deps.storage, &deps.api.addr_validate(contract)?)?,
+        None => BRIDGE.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_bridge")
+        .add_attribute("contract", contract.unwrap_or_default()))
+}
+
+/// True when `address` is the registered bridge
+pub fn is_bridge(storage: &dyn Storage, address: &str) -> bool {
+    BRIDGE
+        .may_load(storage)
+        .ok()
+        .flatten()
+        .is_some_and(|bridge| bridge.as_str() == address)
+}
+
+/// `bridge_out` event of tokens escrowed by the bridge, `bridge_in` of tokens it releases or
+/// refunds, none for other transfers
+pub fn bridge_events(storage: &dyn Storage, from: &str, to: &str, amount: Uint128) -> Vec<Event> {
+    let direction = if is_bridge(storage, to) {
+        "bridge_out"
+    } else if is_bridge(storage, from) {
+        "bridge_in"
+    } else {
+        return vec![];
+    };
+    vec![Event::new(direction)
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", amount)]
+}
+
+pub fn query_bridge_config(deps: Deps) -> StdResult<BridgeConfigResponse> {
+    Ok(BridgeConfigResponse {
+        bridge: BRIDGE
+            .may_load(deps.storage)?
+            .map(|bridge| bridge.to_string()),
+    })
+}
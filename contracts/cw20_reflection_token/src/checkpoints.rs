@@ -0,0 +1,175 @@
+use cosmwasm_std::{Addr, Deps, Env, Order, StdError, StdResult, Storage, Uint128};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw_storage_plus::Bound;
+
+use crate::custodial::is_custodial;
+use crate::disputes::is_accrual_paused;
+use crate::msg::{BalanceAtResponse, TotalSupplyAtResponse};
+use crate::reflections::is_claim_mode;
+use crate::state::{
+    BalanceCheckpoint, SupplyCheckpoint, BALANCE_CHECKPOINTS, CHECKPOINTS_SINCE, CHECKPOINT_HEIGHT,
+    HOLDER_REFLECTIONS, REFLECTION_EXCLUDED, REFLECTION_INDEX, SUPPLY_CHECKPOINTS,
+};
+
+/// Opens the block of `env` for checkpoints: on its first execution the total supply and the
+/// reflection index are recorded as they were at the start of the block. Called at the start of
+/// every entry point before any balance moves, like `take_due_snapshot`
+pub fn begin_checkpoints(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    let height = env.block.height;
+    match CHECKPOINT_HEIGHT.may_load(storage)? {
+        Some(last) if last >= height => return Ok(()),
+        Some(_) => {}
+        None => CHECKPOINTS_SINCE.save(storage, &height)?,
+    }
+    CHECKPOINT_HEIGHT.save(storage, &height)?;
+    let checkpoint = current_supply(storage)?;
+    SUPPLY_CHECKPOINTS.save(storage, height, &checkpoint)
+}
+
+fn current_supply(storage: &dyn Storage) -> StdResult<SupplyCheckpoint> {
+    Ok(SupplyCheckpoint {
+        // nothing is minted yet during instantiation
+        total_supply: TOKEN_INFO
+            .may_load(storage)?
+            .map(|info| info.total_supply)
+            .unwrap_or_default(),
+        per_token: REFLECTION_INDEX
+            .may_load(storage)?
+            .unwrap_or_default()
+            .per_token,
+    })
+}
+
+/// Keeps the balance and the reflections `address` had at the start of the block before they
+/// change for the first time in it, called from `after_balance_change` and before a claim
+pub fn record_balance_checkpoint(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+) -> StdResult<()> {
+    let height = match CHECKPOINT_HEIGHT.may_load(storage)? {
+        Some(height) => height,
+        None => return Ok(()),
+    };
+    if BALANCE_CHECKPOINTS.has(storage, (address, height)) {
+        return Ok(());
+    }
+    let holder = HOLDER_REFLECTIONS
+        .may_load(storage, address)?
+        .unwrap_or_default();
+    BALANCE_CHECKPOINTS.save(
+        storage,
+        (address, height),
+        &BalanceCheckpoint {
+            balance: old,
+            per_token: holder.per_token,
+            pending: holder.pending,
+        },
+    )
+}
+
+/// Height queried, checkpoints only cover the blocks since they were first recorded
+fn checked_height(storage: &dyn Storage, env: &Env, height: Option<u64>) -> StdResult<u64> {
+    let height = height.unwrap_or(env.block.height);
+    let since = CHECKPOINTS_SINCE
+        .may_load(storage)?
+        .unwrap_or(env.block.height);
+    if height < since {
+        return Err(StdError::generic_err(format!(
+            "Balances are checkpointed from height {}",
+            since
+        )));
+    }
+    Ok(height)
+}
+
+/// Total supply and reflection index at the start of block `height`: the first checkpoint recorded
+/// since, the current values if nothing executed since
+fn supply_at(storage: &dyn Storage, height: u64) -> StdResult<SupplyCheckpoint> {
+    let recorded = SUPPLY_CHECKPOINTS
+        .range(
+            storage,
+            Some(Bound::inclusive(height)),
+            None,
+            Order::Ascending,
+        )
+        .next()
+        .transpose()?;
+    match recorded {
+        Some((_, checkpoint)) => Ok(checkpoint),
+        None => current_supply(storage),
+    }
+}
+
+/// Balance of `address` at the start of block `height`, plus in claim mode the reflections it had
+/// accrued by then. Excluded and custodial addresses accrue nothing, paused addresses keep what
+/// they accrued before the pause
+pub fn balance_at(storage: &dyn Storage, address: &Addr, height: u64) -> StdResult<Uint128> {
+    let recorded = BALANCE_CHECKPOINTS
+        .prefix(address)
+        .range(
+            storage,
+            Some(Bound::inclusive(height)),
+            None,
+            Order::Ascending,
+        )
+        .next()
+        .transpose()?;
+    let checkpoint = match recorded {
+        Some((_, checkpoint)) => checkpoint,
+        None => {
+            let holder = HOLDER_REFLECTIONS
+                .may_load(storage, address)?
+                .unwrap_or_default();
+            BalanceCheckpoint {
+                balance: BALANCES.may_load(storage, address)?.unwrap_or_default(),
+                per_token: holder.per_token,
+                pending: holder.pending,
+            }
+        }
+    };
+    if !is_claim_mode(storage)?
+        || REFLECTION_EXCLUDED.has(storage, address)
+        || is_custodial(storage, address)
+    {
+        return Ok(checkpoint.balance);
+    }
+    if is_accrual_paused(storage, address) {
+        return Ok(checkpoint.balance.checked_add(checkpoint.pending)?);
+    }
+
+    let per_token = supply_at(storage, height)?.per_token;
+    let accrued = checkpoint
+        .balance
+        .mul_floor(per_token.saturating_sub(checkpoint.per_token));
+    Ok(checkpoint
+        .balance
+        .checked_add(checkpoint.pending)?
+        .checked_add(accrued)?)
+}
+
+pub fn query_balance_at(
+    deps: Deps,
+    env: Env,
+    address: String,
+    height: Option<u64>,
+) -> StdResult<BalanceAtResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let height = checked_height(deps.storage, &env, height)?;
+    Ok(BalanceAtResponse {
+        balance: balance_at(deps.storage, &address, height)?,
+        height,
+    })
+}
+
+pub fn query_total_supply_at(
+    deps: Deps,
+    env: Env,
+    height: Option<u64>,
+) -> StdResult<TotalSupplyAtResponse> {
+    let height = checked_height(deps.storage, &env, height)?;
+    Ok(TotalSupplyAtResponse {
+        total_supply: supply_at(deps.storage, height)?.total_supply,
+        height,
+    })
+}
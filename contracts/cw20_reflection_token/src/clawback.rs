@@ -0,0 +1,45 @@
+use cosmwasm_std::{DepsMut, Event, MessageInfo, Response, StdError, Uint128};
+use cw20_base::ContractError;
+
+use crate::contract::ensure_admin;
+use crate::ledger::{claw_back_balance, increase_balance};
+
+/// Moves `amount` from `from` to the issuer (the admin) untaxed, e.g. to execute a court order.
+/// Frozen and vesting balances can be clawed back, `reason` is mandatory and logged in a
+/// `clawback` event so that every use can be audited on chain
+pub fn execute_clawback(
+    deps: DepsMut,
+    info: MessageInfo,
+    from: String,
+    amount: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if reason.trim().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "A clawback needs a reason",
+        )));
+    }
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let from = deps.api.addr_validate(&from)?;
+    if from == info.sender {
+        return Err(ContractError::Std(StdError::generic_err(
+            "The issuer cannot claw back its own tokens",
+        )));
+    }
+
+    claw_back_balance(deps.storage, &from, amount)?;
+    increase_balance(deps.storage, &info.sender, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "clawback")
+        .add_event(
+            Event::new("clawback")
+                .add_attribute("from", from)
+                .add_attribute("to", info.sender)
+                .add_attribute("amount", amount)
+                .add_attribute("reason", reason),
+        ))
+}
@@ -0,0 +1,291 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::execute_transfer;
+use crate::fees::DAY;
+use crate::msg::{CoSignedTransfersResponse, CoSignerResponse};
+use crate::state::{
+    CoSignRule, CoSignRuleChange, CoSignedTransfer, CO_SIGNED_RELEASE, CO_SIGNED_TRANSFERS,
+    CO_SIGN_RULES, NEXT_CO_SIGNED_ID, PENDING_CO_SIGN_RULES,
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Seconds before replacing or removing a co-signing rule takes effect, so that a stolen key
+/// cannot lift the rule before moving the funds
+pub const CO_SIGNER_DELAY: u64 = 2 * DAY;
+
+/// Seconds during which a proposed transfer can be approved and executed
+pub const CO_SIGNED_TRANSFER_EXPIRY: u64 = 3 * DAY;
+
+/// Requires the approval of `co_signer` for transfers out of the account of `info.sender` above
+/// `threshold`. The first rule applies at once, replacing or removing it is delayed
+pub fn set_co_signer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    co_signer: Option<String>,
+    threshold: Uint128,
+) -> Result<Response, ContractError> {
+    let rule = match co_signer {
+        Some(co_signer) => {
+            let co_signer = deps.api.addr_validate(&co_signer)?;
+            if co_signer == info.sender {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "An account cannot co-sign its own transfers",
+                )));
+            }
+            Some(CoSignRule {
+                co_signer,
+                threshold,
+            })
+        }
+        None => None,
+    };
+    let now = env.block.time.seconds();
+    // a pending change already in force becomes the current rule
+    let current = co_sign_rule(deps.storage, &info.sender, now)?;
+    PENDING_CO_SIGN_RULES.remove(deps.storage, &info.sender);
+
+    let effective_at = match (&current, &rule) {
+        (None, None) => {
+            CO_SIGN_RULES.remove(deps.storage, &info.sender);
+            now
+        }
+        (None, Some(rule)) => {
+            CO_SIGN_RULES.save(deps.storage, &info.sender, rule)?;
+            now
+        }
+        (Some(current), _) => {
+            CO_SIGN_RULES.save(deps.storage, &info.sender, current)?;
+            let change = CoSignRuleChange {
+                rule: rule.clone(),
+                effective_at: now + CO_SIGNER_DELAY,
+            };
+            PENDING_CO_SIGN_RULES.save(deps.storage, &info.sender, &change)?;
+            change.effective_at
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "set_co_signer")
+        .add_attribute("owner", info.sender)
+        .add_attribute(
+            "co_signer",
+            rule.map(|rule| rule.co_signer.to_string())
+                .unwrap_or_default(),
+        )
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+/// Rule of `owner` in force at `now`, a pending change counts once its delay passed
+fn co_sign_rule(storage: &dyn Storage, owner: &Addr, now: u64) -> StdResult<Option<CoSignRule>> {
+    if let Some(change) = PENDING_CO_SIGN_RULES.may_load(storage, owner)? {
+        if now >= change.effective_at {
+            return Ok(change.rule);
+        }
+    }
+    CO_SIGN_RULES.may_load(storage, owner)
+}
+
+/// Fails for a transfer of `amount` from `owner` to `to` above the threshold of its rule, unless
+/// it is the approved transfer being executed by `ExecuteCoSignedTransfer`
+pub fn ensure_co_signed(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let owner = Addr::unchecked(owner);
+    let rule = match co_sign_rule(storage, &owner, env.block.time.seconds())? {
+        Some(rule) if amount > rule.threshold => rule,
+        _ => return Ok(()),
+    };
+    if let Some((released, transfer)) = CO_SIGNED_RELEASE.may_load(storage)? {
+        if released == owner && transfer.recipient == to && transfer.amount == amount {
+            CO_SIGNED_RELEASE.remove(storage);
+            return Ok(());
+        }
+    }
+    Err(ContractError::Std(StdError::generic_err(format!(
+        "Transfers above {} from {} need the approval of {}, see ProposeCoSignedTransfer",
+        rule.threshold, owner, rule.co_signer
+    ))))
+}
+
+/// Proposes a transfer from the account of `info.sender` for its co-signer to approve
+pub fn propose_co_signed_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if co_sign_rule(deps.storage, &info.sender, env.block.time.seconds())?.is_none() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "No co-signer set for this account",
+        )));
+    }
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    deps.api.addr_validate(&recipient)?;
+    let id = NEXT_CO_SIGNED_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_CO_SIGNED_ID.save(deps.storage, &(id + 1))?;
+    let transfer = CoSignedTransfer {
+        id,
+        recipient,
+        amount,
+        expires_at: env.block.time.seconds() + CO_SIGNED_TRANSFER_EXPIRY,
+        approved: false,
+    };
+    CO_SIGNED_TRANSFERS.save(deps.storage, (&info.sender, id), &transfer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_co_signed_transfer")
+        .add_attribute("owner", info.sender)
+        .add_attribute("id", id.to_string())
+        .add_attribute("recipient", transfer.recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("expires_at", transfer.expires_at.to_string()))
+}
+
+fn load_live_transfer(
+    storage: &dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    id: u64,
+) -> Result<CoSignedTransfer, ContractError> {
+    let transfer = CO_SIGNED_TRANSFERS
+        .may_load(storage, (owner, id))?
+        .ok_or_else(|| StdError::generic_err(format!("No co-signed transfer {}", id)))?;
+    if env.block.time.seconds() >= transfer.expires_at {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Co-signed transfer {} expired",
+            id
+        ))));
+    }
+    Ok(transfer)
+}
+
+/// Approves transfer `id` of `owner`, only its current co-signer can
+pub fn approve_co_signed_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let mut transfer = load_live_transfer(deps.storage, &env, &owner, id)?;
+    let rule = co_sign_rule(deps.storage, &owner, env.block.time.seconds())?;
+    if rule.map(|rule| rule.co_signer) != Some(info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: not co-signer",
+        )));
+    }
+    transfer.approved = true;
+    CO_SIGNED_TRANSFERS.save(deps.storage, (&owner, id), &transfer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_co_signed_transfer")
+        .add_attribute("owner", owner)
+        .add_attribute("id", id.to_string()))
+}
+
+/// Executes approved transfer `id` of `info.sender`, checked and taxed like `Transfer`
+pub fn execute_co_signed_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let transfer = load_live_transfer(deps.storage, &env, &info.sender, id)?;
+    if !transfer.approved {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Co-signed transfer {} is not approved",
+            id
+        ))));
+    }
+    CO_SIGNED_TRANSFERS.remove(deps.storage, (&info.sender, id));
+    CO_SIGNED_RELEASE.save(deps.storage, &(info.sender.clone(), transfer.clone()))?;
+    let res = execute_transfer(
+        deps.branch(),
+        env,
+        info,
+        transfer.recipient,
+        transfer.amount,
+        None,
+    )?;
+    // not taken when the rule no longer covers the amount
+    CO_SIGNED_RELEASE.remove(deps.storage);
+
+    Ok(res.add_attribute("co_signed_transfer", id.to_string()))
+}
+
+/// Drops transfer `id` of `owner`, sent by the owner or its co-signer
+pub fn cancel_co_signed_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    if !CO_SIGNED_TRANSFERS.has(deps.storage, (&owner, id)) {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "No co-signed transfer {}",
+            id
+        ))));
+    }
+    let co_signer =
+        co_sign_rule(deps.storage, &owner, env.block.time.seconds())?.map(|rule| rule.co_signer);
+    if info.sender != owner && Some(&info.sender) != co_signer.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+    CO_SIGNED_TRANSFERS.remove(deps.storage, (&owner, id));
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_co_signed_transfer")
+        .add_attribute("owner", owner)
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn query_co_signer(deps: Deps, env: Env, address: String) -> StdResult<CoSignerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let now = env.block.time.seconds();
+    Ok(CoSignerResponse {
+        rule: co_sign_rule(deps.storage, &address, now)?,
+        pending: PENDING_CO_SIGN_RULES
+            .may_load(deps.storage, &address)?
+            .filter(|change| now < change.effective_at),
+    })
+}
+
+pub fn query_co_signed_transfers(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CoSignedTransfersResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let transfers = CO_SIGNED_TRANSFERS
+        .prefix(&owner)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, transfer)| transfer))
+        .collect::<StdResult<_>>()?;
+
+    Ok(CoSignedTransfersResponse { transfers })
+}
@@ -4,11 +4,14 @@ use std::str::FromStr;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_json_binary, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
-    Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse, Uint128, WasmMsg,
+    attr, to_json_binary, to_json_vec, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, Event,
+    MessageInfo, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse,
+    SubMsgResult, Uint128, WasmMsg,
 };
 
 use cw2::set_contract_version;
+use sha2::{Digest, Sha256};
+
 use cw20::{Cw20ReceiveMsg, Logo, LogoInfo, MarketingInfoResponse};
 use cw20_base::allowances::{
     deduct_allowance, execute_burn_from, execute_decrease_allowance, execute_increase_allowance,
@@ -20,10 +23,115 @@ use cw20_base::contract::{
 };
 use cw20_base::enumerable::{query_all_accounts, query_all_allowances};
 
+use crate::api::{deprecated, query_api_version, query_features};
+use crate::auto_liquidity::{
+    auto_liquify, provide_replied, query_auto_liquidity, set_auto_liquidity, swap_replied,
+    AUTO_LIQUIDITY_PROVIDE_REPLY_ID, AUTO_LIQUIDITY_SWAP_REPLY_ID,
+};
+use crate::batch::execute_multi_transfer;
+use crate::bridge::{bridge_events, is_bridge, query_bridge_config, set_bridge};
+use crate::checkpoints::{begin_checkpoints, query_balance_at, query_total_supply_at};
+#[cfg(feature = "clawback")]
+use crate::clawback::execute_clawback;
+use crate::co_signing::{
+    approve_co_signed_transfer, cancel_co_signed_transfer, ensure_co_signed,
+    execute_co_signed_transfer, propose_co_signed_transfer, query_co_signed_transfers,
+    query_co_signer, set_co_signer,
+};
+use crate::custodial::{query_custodial, route_custodial_excess, set_custodial};
+#[cfg(feature = "debug")]
+use crate::debug::query_raw_range;
+use crate::display::{query_display_info, query_reflection_summary, set_display_decimals};
+use crate::disputes::{pause_accrual, query_accrual_pause, release_accrual};
+use crate::distribution::{
+    backfill_distribution, query_concentration, query_holder_distribution,
+    start_distribution_backfill,
+};
+use crate::drip::{
+    credit_tax, query_reflection_drip, release_drip, set_reflection_drip, tax_transfer_events,
+};
+use crate::effective::{query_effective_balance, query_spendable_via};
+use crate::emergency::{emergency_drain, query_rescue_address, set_rescue_address};
+use crate::epoch_rewards::{
+    execute_claim_epoch_reward, fund_epoch_reward, query_epoch_reward_claim, query_epoch_rewards,
+    sweep_epoch_reward,
+};
+use crate::fee_exempt::{
+    is_fee_exempt, is_tax_free_hook, query_fee_exemptions, query_tax_free_hooks, set_fee_exempt,
+    set_tax_free_hook,
+};
+use crate::fees::{query_reflection_apr, query_tax_stats, record_reflection, record_tax};
+use crate::gated::execute_gated_forward;
+use crate::grants::{
+    execute_as_operator, execute_grant_operator, execute_revoke_operator, query_operator_grants,
+};
+use crate::hooks::{add_transfer_hook, query_transfer_hooks, remove_transfer_hook, transfer_hooks};
+use crate::insurance::{
+    execute_insurance_withdrawal, query_insurance_fund, query_insurance_withdrawals,
+    queue_insurance_withdrawal, set_insurance_rate,
+};
+use crate::invariants::verify_invariants;
+use crate::labels::{label_attributes, query_address_labels, set_address_label};
+use crate::ledger::{after_balance_change, decrease_balance, increase_balance, track_balance};
+use crate::links::{accept_link, is_linked, propose_link, query_linked_addresses, unlink};
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, QueryTaxResponse, TreasuryExecuteMsg,
+    ConfigHashResponse, EconomicParams, ExecuteMsg, InstantiateMsg, MigrateAction, MigrateMsg,
+    OwnershipResponse, QueryMsg, QueryTaxResponse, Role, SudoMsg, TreasuryExecuteMsg,
     TreasuryInstantiateMsg,
 };
+use crate::permit::execute_permit;
+use crate::policy::{ensure_spending_allowed, query_spending_policy, set_spending_policy};
+use crate::presets::{apply_preset, query_preset_preview};
+use crate::quarantine::{
+    execute_claim_quarantined, hook_replied, query_quarantined_sends, receive_hook,
+    set_hook_quarantine, HOOK_QUARANTINE_REPLY_ID,
+};
+use crate::rate_limit::{query_tax_limits, record_tax_rate_change};
+use crate::reflections::{
+    exclude_dead_address, exclude_from_reflections, execute_claim_reflections, query_claim_history,
+    query_pending_reflections, query_reflection_index, sync_reflection_exclusions,
+};
+use crate::relay::{execute_relay, query_trusted_forwarder, set_trusted_forwarder};
+use crate::roles::{accept_ownership, ensure_role, query_roles, transfer_ownership, update_role};
+use crate::schedule::{
+    cancel_scheduled_action, execute_due, query_schedule_receipts, query_scheduled_actions,
+    schedule_action, veto_scheduled_action,
+};
+use crate::security::{
+    ensure_not_halted, execute_pause, execute_unpause, query_security_status, renounce_guardian,
+    set_frozen, set_guardian,
+};
+use crate::signing::query_signature_domain;
+use crate::simulation::query_simulate_swap_round_trip;
+use crate::snapshots::{
+    execute_take_snapshot, query_snapshot_balance, query_snapshot_diff, query_snapshots,
+    set_snapshot_schedule, take_due_snapshot,
+};
+use crate::state::{
+    DISPLAY_DECIMALS, DISTRIBUTION_TRACKED_SINCE, DRIP_PERIOD, IMMUTABLE, INSURANCE_RATE,
+    MAX_TAX_RATE, MAX_TAX_STEP, PENDING_ADMIN, REFLECTION_MODE, TAX_CONFIG, TAX_STRATEGY,
+    WALLET_LIMITS,
+};
+use crate::sunset::{
+    cancel_sunset, ensure_not_sunset, fund_settlement, query_sunset, redeem_final, schedule_sunset,
+    sweep_settlement,
+};
+use crate::tax_config::{query_tax_config, update_tax_config};
+use crate::tax_strategy::{
+    gross_up_transfer, query_tax_strategy, set_tax_strategy, tax_of, transfer_tax,
+};
+use crate::timed::ensure_within_bounds;
+use crate::trading::{
+    ensure_trading_enabled, execute_enable_trading, query_trading_gate, set_trading_gate,
+};
+use crate::trophy::{
+    burn_trophy, burn_trophy_failed, query_burn_trophy, set_burn_trophy, BURN_TROPHY_REPLY_ID,
+};
+use crate::vesting::{query_vesting_info, save_vesting, tick_vesting};
+use crate::wallet_limits::{
+    ensure_wallet_limits, query_limit_exemptions, query_limits, save_limits, set_limits_exempt,
+    update_limits,
+};
 use cw20_base::state::{MinterData, TokenInfo, BALANCES, LOGO, MARKETING_INFO, TOKEN_INFO};
 use cw20_base::ContractError;
 use cw_storage_plus::{Item, Map};
@@ -51,8 +159,10 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    begin_checkpoints(deps.storage, &env)?;
     // check valid token info
-    msg.validate()?;
+    msg.validate(deps.api).map_err(StdError::from)?;
+    let treasury_code_id = msg.treasury_code_id();
     deps.api.addr_validate(&msg.admin.to_string())?;
     ADMIN.save(deps.storage, &msg.admin.to_string())?;
 
@@ -60,19 +170,29 @@ pub fn instantiate(
     REFLECTION_RATE.save(deps.storage, &Decimal::zero())?;
     BURN_RATE.save(deps.storage, &Decimal::zero())?;
     MAX_TRANSFER_SUPPLY_RATE.save(deps.storage, &Decimal::from_str("1")?)?;
+    MAX_TAX_RATE.save(deps.storage, &msg.max_tax_rate.unwrap_or(Decimal::one()))?;
+    MAX_TAX_STEP.save(deps.storage, &msg.max_tax_step.unwrap_or(Decimal::one()))?;
+    REFLECTION_MODE.save(deps.storage, &msg.reflection_mode.unwrap_or_default())?;
+    save_limits(deps.storage, &msg.limits())?;
+    exclude_from_reflections(deps.storage, &env.contract.address)?;
     WHITELIST.save(deps.storage, info.sender.to_string(), &true)?;
+    DISTRIBUTION_TRACKED_SINCE.save(deps.storage, &env.block.height)?;
 
     // create initial accounts
     let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
-
-    if let Some(limit) = msg.get_cap() {
-        if total_supply > limit {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Initial supply greater than cap",
-            )));
-        }
+    for row in msg.initial_balances.iter() {
+        let address = deps.api.addr_validate(&row.address)?;
+        after_balance_change(deps.storage, &address, Uint128::zero(), row.amount)?;
     }
 
+    let vesting = msg
+        .vesting
+        .unwrap_or_default()
+        .into_iter()
+        .map(|schedule| Ok((deps.api.addr_validate(&schedule.address)?, schedule)))
+        .collect::<StdResult<_>>()?;
+    save_vesting(deps.storage, &env, vesting)?;
+
     let mint = match msg.mint {
         Some(m) => Some(MinterData {
             minter: deps.api.addr_validate(&m.minter)?,
@@ -109,14 +229,17 @@ pub fn instantiate(
             logo,
         };
         MARKETING_INFO.save(deps.storage, &marketing_data)?;
+        if let Some(display_decimals) = marketing.display_decimals {
+            DISPLAY_DECIMALS.save(deps.storage, &display_decimals)?;
+        }
     }
 
     TOKEN_INFO.save(deps.storage, &data)?;
 
-    Ok(Response::new().add_submessage(SubMsg::reply_on_success(
+    let res = Response::new().add_submessage(SubMsg::reply_on_success(
         CosmosMsg::Wasm(WasmMsg::Instantiate {
             admin: Some(env.contract.address.to_string()), // use the owner as admin for now; can be changed later by a `MsgUpdateAdmin`
-            code_id: msg.cw20_code_id,
+            code_id: treasury_code_id,
             msg: to_json_binary(&TreasuryInstantiateMsg {
                 admin: msg.admin.to_string(),
                 router: msg.router.to_string(),
@@ -126,7 +249,11 @@ pub fn instantiate(
             label: "CW20 Taxed - Treasury".to_string(),
         }),
         1,
-    )))
+    ));
+    Ok(match msg.cw20_code_id {
+        Some(_) => deprecated(res, "cw20_code_id"),
+        None => res,
+    })
 }
 
 /// Unwrap a `Reply` object to extract the response
@@ -134,316 +261,417 @@ pub fn unwrap_reply(reply: Reply) -> StdResult<SubMsgResponse> {
     reply.result.into_result().map_err(StdError::generic_err)
 }
 
-#[entry_point]
-pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    begin_checkpoints(deps.storage, &env)?;
+    tick_vesting(deps.storage, &env)?;
     match reply.id {
         1 => register_deployment(deps, unwrap_reply(reply)?),
+        BURN_TROPHY_REPLY_ID => match reply.result {
+            SubMsgResult::Err(error) => Ok(burn_trophy_failed(error)),
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        HOOK_QUARANTINE_REPLY_ID => hook_replied(deps.storage, &env, reply.result),
+        AUTO_LIQUIDITY_SWAP_REPLY_ID => swap_replied(deps, &env, reply.result),
+        AUTO_LIQUIDITY_PROVIDE_REPLY_ID => provide_replied(deps.storage, &env, reply.result),
         _id => Err(ContractError::Unauthorized {}),
     }
 }
 
-/// Standard CW20 transfer function that is modified to include tax functions, and anti-whale feature
-/// These modifications are all applied to the `transfer`, `send`, `transfer_from`, and `send_from` functions
-pub fn execute_transfer(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    recipient: String,
+/// Balances moved by one transfer
+pub struct TransferLeg {
+    /// Amount credited to the recipient
+    pub outgoing_amount: Uint128,
+    /// Tax collected, zero when the transfer is not taxed
+    pub taxed_amount: Uint128,
+    /// Addresses credited with the tax and their amount, see `credit_tax`
+    pub credits: Vec<(Addr, Uint128)>,
+    /// `tax` event of the transfer, see `record_tax`
+    pub tax_event: Event,
+}
+
+/// True when a transfer from `sender` to `recipient` is not taxed, either side being whitelisted or
+/// fee exempt
+pub fn is_untaxed(storage: &dyn Storage, sender: &str, recipient: &str) -> StdResult<bool> {
+    Ok(WHITELIST
+        .may_load(storage, recipient.to_string())?
+        .unwrap_or_default()
+        || WHITELIST
+            .may_load(storage, sender.to_string())?
+            .unwrap_or_default()
+        || is_fee_exempt(storage, recipient)
+        || is_fee_exempt(storage, sender))
+}
+
+/// Checks and moves `amount` from `sender` to `recipient` the way `Transfer` does, crediting the
+/// tax. With a `spender`, the move is taken from its allowance the way `TransferFrom` does, and a
+/// send is not taxed when its `hook` is a tax-free hook of the recipient. The caller logs the
+/// credits with `tax_transfer_events`
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_leg(
+    deps: &mut DepsMut,
+    env: &Env,
+    sender: &Addr,
+    spender: Option<&Addr>,
+    recipient: &str,
     amount: Uint128,
-) -> Result<Response, ContractError> {
-    ensure_antiwhale(&deps, info.sender.to_string(), amount)?;
+    hook: Option<&Binary>,
+    min_receive: Option<Uint128>,
+) -> Result<TransferLeg, ContractError> {
+    // a holder moving tokens between its own linked addresses is not a whale
+    if !is_linked(deps.storage, sender, recipient) {
+        ensure_antiwhale(deps, spender.unwrap_or(sender).to_string(), amount)?;
+    }
+    ensure_trading_enabled(deps.storage, &deps.querier, env, sender.as_str(), recipient)?;
+    ensure_spending_allowed(deps, env, sender.as_str(), recipient, amount)?;
+    ensure_co_signed(deps.storage, env, sender.as_str(), recipient, amount)?;
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
     // If whitelisetd, we simply do not apply taxes
-    let recipient_whitelist = WHITELIST
-        .may_load(deps.storage, recipient.clone())?
-        .unwrap_or_default();
-    let sender_whitelist = WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
-        .unwrap_or_default();
-    let whitelisted = recipient_whitelist || sender_whitelist;
+    let whitelisted = is_untaxed(deps.storage, sender.as_str(), recipient)?
+        || hook.is_some_and(|msg| is_tax_free_hook(deps.storage, recipient, msg))
+        || match spender {
+            Some(spender) => {
+                TRANSFER_FROM_WHITELIST
+                    .may_load(deps.storage, spender.to_string())?
+                    .unwrap_or(false)
+                    // the pair taking the auto-liquidity tokens from the token contract
+                    || *sender == env.contract.address
+            }
+            None => false,
+        };
 
     // Loads treasury addresses, and query for taxes on transfers
     let treasury = TREASURY.may_load(deps.storage)?.unwrap_or_default();
-    let rcpt_addr = deps.api.addr_validate(&recipient)?;
-    let taxes = query_tax(deps.storage, amount)?;
+    let rcpt_addr = deps.api.addr_validate(recipient)?;
+    let taxes = transfer_tax(deps.storage, env, sender.as_str(), amount, whitelisted)?;
     let outgoing_amount = if whitelisted { amount } else { taxes.after_tax };
-
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
+    ensure_min_receive(outgoing_amount, min_receive)?;
+    ensure_wallet_limits(
         deps.storage,
+        env,
+        sender.as_str(),
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default() + outgoing_amount)
-        },
+        amount,
+        outgoing_amount,
     )?;
 
-    let mut messages = vec![];
+    if let Some(spender) = spender {
+        // deduct allowance before doing anything else have enough allowance
+        deduct_allowance(deps.storage, sender, spender, &env.block, amount)?;
+    }
+    decrease_balance(deps.storage, sender, amount)?;
+    increase_balance(deps.storage, &rcpt_addr, outgoing_amount)?;
 
     // Assuming no whitelist, we apply taxes, and immediately add them to the treasury by modifying balance variables
-    // We also send generate a transfer teransaction log under `TransferEvent` to ensure explorer tracks transfer properly
-    if !recipient_whitelist && !sender_whitelist {
-        BALANCES.update(
+    // The caller generates a transfer teransaction log under `TransferEvent` to ensure explorer tracks transfer properly
+    let mut credits = vec![];
+    if !whitelisted {
+        credits = credit_tax(
             deps.storage,
+            env,
             &deps.api.addr_validate(&treasury)?,
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default() + taxes.taxed_amount)
-            },
+            &taxes,
         )?;
-
-        messages.push(WasmMsg::Execute {
-            contract_addr: env.contract.address.to_string(),
-            msg: to_json_binary(&ExecuteMsg::TransferEvent {
-                from: info.sender.to_string(),
-                to: treasury.to_string(),
-                amount: taxes.taxed_amount,
-            })?,
-            funds: vec![],
-        })
+        record_reflection(deps.storage, env.block.time, taxes.reflection_amount)?;
     }
+    let tax_event = record_tax(
+        deps.storage,
+        env.block.time,
+        sender.as_str(),
+        recipient,
+        amount,
+        &taxes,
+        whitelisted,
+    )?;
+
+    Ok(TransferLeg {
+        outgoing_amount,
+        taxed_amount: amount - outgoing_amount,
+        credits,
+        tax_event,
+    })
+}
 
+/// CW20 burn from the balance of `info.sender`, with the trophy it earns
+pub fn execute_tracked_burn(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.clone();
+    let res = track_balance(deps.branch(), &sender, |deps| {
+        execute_burn(deps, env, info, amount)
+    })?;
+    Ok(res.add_submessages(burn_trophy(deps.storage, &sender, amount)?))
+}
+
+/// Standard CW20 transfer function that is modified to include tax functions, and anti-whale feature
+/// These modifications are all applied to the `transfer`, `send`, `transfer_from`, and `send_from` functions
+pub fn execute_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+    min_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let leg = transfer_leg(
+        &mut deps,
+        &env,
+        &info.sender,
+        None,
+        &recipient,
+        amount,
+        None,
+        min_receive,
+    )?;
+    let messages = tax_transfer_events(&env, info.sender.as_str(), leg.credits)?;
+
+    let labels = label_attributes(
+        deps.storage,
+        &[("from", info.sender.as_str()), ("to", &recipient)],
+    )?;
     let res = Response::new()
         .add_messages(messages)
+        .add_submessages(transfer_hooks(
+            deps.storage,
+            info.sender.as_str(),
+            &recipient,
+            amount,
+            leg.taxed_amount,
+        )?)
+        .add_events(bridge_events(
+            deps.storage,
+            info.sender.as_str(),
+            &recipient,
+            leg.outgoing_amount,
+        ))
+        .add_event(leg.tax_event)
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
-        .add_attribute("amount", outgoing_amount);
+        .add_attribute("amount", leg.outgoing_amount)
+        .add_attributes(labels);
     Ok(res)
 }
 
-pub fn execute_send(
+/// Transfers the gross amount for which `recipient` ends up with exactly `net_amount`,
+/// whitelisted transfers are not taxed so their gross amount is the net amount
+pub fn execute_transfer_exact_net(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    contract: String,
-    amount: Uint128,
-    msg: Binary,
+    recipient: String,
+    net_amount: Uint128,
 ) -> Result<Response, ContractError> {
-    ensure_antiwhale(&deps, info.sender.to_string(), amount)?;
-    if amount == Uint128::zero() {
+    if net_amount.is_zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
-    let recipient_whitelist = WHITELIST
-        .may_load(deps.storage, contract.clone())?
-        .unwrap_or_default();
-    let sender_whitelist = WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
+    let whitelisted = is_untaxed(deps.storage, info.sender.as_str(), &recipient)?;
+    let gross_amount = if whitelisted {
+        net_amount
+    } else {
+        gross_up_transfer(deps.storage, &env, info.sender.as_str(), net_amount)?
+    };
+
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
         .unwrap_or_default();
-    let whitelisted = recipient_whitelist || sender_whitelist;
-    let treasury = TREASURY.may_load(deps.storage)?.unwrap_or_default();
-    let rcpt_addr = deps.api.addr_validate(&contract)?;
-    let taxes = query_tax(deps.storage, amount)?;
-    let outgoing_amount = if whitelisted { amount } else { taxes.after_tax };
+    if balance < gross_amount {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Insufficient balance for gross amount {}",
+            gross_amount
+        ))));
+    }
 
-    // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
+    let res = execute_transfer(deps, env, info, recipient, gross_amount, None)?;
+    Ok(res
+        .add_attribute("gross_amount", gross_amount)
+        .add_attribute("net_amount", net_amount))
+}
+
+pub fn execute_send(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+    min_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let leg = transfer_leg(
+        &mut deps,
+        &env,
         &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
+        None,
+        &contract,
+        amount,
+        Some(&msg),
+        min_receive,
     )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default() + outgoing_amount)
-        },
-    )?;
-
-    let mut messages = vec![];
-    if !recipient_whitelist && !sender_whitelist {
-        BALANCES.update(
-            deps.storage,
-            &deps.api.addr_validate(&treasury)?,
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default() + taxes.taxed_amount)
-            },
-        )?;
-
-        messages.push(WasmMsg::Execute {
-            contract_addr: env.contract.address.to_string(),
-            msg: to_json_binary(&ExecuteMsg::TransferEvent {
-                from: info.sender.to_string(),
-                to: treasury.to_string(),
-                amount: taxes.taxed_amount,
-            })?,
-            funds: vec![],
-        })
-    }
+    let messages = tax_transfer_events(&env, info.sender.as_str(), leg.credits)?;
+    let outgoing_amount = leg.outgoing_amount;
+    let rcpt_addr = deps.api.addr_validate(&contract)?;
 
     let res = Response::new()
         .add_messages(messages)
+        .add_events(bridge_events(
+            deps.storage,
+            info.sender.as_str(),
+            &contract,
+            outgoing_amount,
+        ))
+        .add_event(leg.tax_event)
         .add_attribute("action", "send")
         .add_attribute("from", &info.sender)
         .add_attribute("to", &contract)
         .add_attribute("amount", outgoing_amount)
-        .add_message(
+        .add_attributes(label_attributes(
+            deps.storage,
+            &[("from", info.sender.as_str()), ("to", &contract)],
+        )?)
+        .add_submessages(transfer_hooks(
+            deps.storage,
+            info.sender.as_str(),
+            &contract,
+            amount,
+            leg.taxed_amount,
+        )?)
+        .add_submessage(receive_hook(
+            deps.storage,
             // We do not modify the send message, but we allow the hooked contract to calculate taxes against this contract
             Cw20ReceiveMsg {
-                sender: info.sender.into(),
+                sender: info.sender.to_string(),
                 amount: outgoing_amount,
                 msg,
             }
             .into_cosmos_msg(contract)?,
-        );
+            &info.sender,
+            &rcpt_addr,
+            outgoing_amount,
+        )?);
     Ok(res)
 }
 
-pub fn execute_transfer_from(
+/// Sends `amount` to `contract` if it receives exactly `expected_net`, whitelisted sends are not
+/// taxed so their net amount is the amount sent
+pub fn execute_send_and_verify(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+    expected_net: Uint128,
+) -> Result<Response, ContractError> {
+    // the exemptions of `transfer_leg` for a send
+    let whitelisted = is_untaxed(deps.storage, info.sender.as_str(), &contract)?
+        || is_tax_free_hook(deps.storage, &contract, &msg);
+    let net_amount = if whitelisted {
+        amount
+    } else {
+        tax_of(deps.storage, &env, Some(info.sender.as_str()), amount)?.after_tax
+    };
+    if net_amount != expected_net {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Net amount {} differs from expected_net {}",
+            net_amount, expected_net
+        ))));
+    }
+
+    let res = execute_send(deps, env, info, contract, amount, msg, None)?;
+    Ok(res.add_attribute("expected_net", expected_net))
+}
+
+pub fn execute_transfer_from(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     owner: String,
     recipient: String,
     amount: Uint128,
+    min_receive: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    ensure_antiwhale(&deps, info.sender.to_string(), amount)?;
-    let is_from_whitelisted = TRANSFER_FROM_WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
-        .unwrap_or(false);
-    let recipient_whitelist = WHITELIST
-        .may_load(deps.storage, recipient.clone())?
-        .unwrap_or_default();
-    let sender_whitelist = WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
-        .unwrap_or_default();
-    let whitelisted = recipient_whitelist || sender_whitelist || is_from_whitelisted;
-    let treasury = TREASURY.may_load(deps.storage)?.unwrap_or_default();
-    let rcpt_addr = deps.api.addr_validate(&recipient)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let taxes = query_tax(deps.storage, amount)?;
-    let outgoing_amount = if whitelisted { amount } else { taxes.after_tax };
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
-
-    BALANCES.update(
-        deps.storage,
+    let leg = transfer_leg(
+        &mut deps,
+        &env,
         &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default() + outgoing_amount)
-        },
+        Some(&info.sender),
+        &recipient,
+        amount,
+        None,
+        min_receive,
     )?;
+    let messages = tax_transfer_events(&env, info.sender.as_str(), leg.credits)?;
+    let outgoing_amount = leg.outgoing_amount;
 
-    let mut messages = vec![];
-    if !recipient_whitelist && !sender_whitelist && !is_from_whitelisted {
-        BALANCES.update(
+    let labels = label_attributes(deps.storage, &[("from", &owner), ("to", &recipient)])?;
+    let hooks = transfer_hooks(deps.storage, &owner, &recipient, amount, leg.taxed_amount)?;
+    let res = Response::new()
+        .add_messages(messages)
+        .add_submessages(hooks)
+        .add_events(bridge_events(
             deps.storage,
-            &deps.api.addr_validate(&treasury)?,
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default() + taxes.taxed_amount)
-            },
-        )?;
-
-        messages.push(WasmMsg::Execute {
-            contract_addr: env.contract.address.to_string(),
-            msg: to_json_binary(&ExecuteMsg::TransferEvent {
-                from: info.sender.to_string(),
-                to: treasury.to_string(),
-                amount: taxes.taxed_amount,
-            })?,
-            funds: vec![],
-        })
-    }
-
-    let res = Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "transfer_from"),
-        attr("from", owner),
-        attr("to", recipient),
-        attr("by", info.sender),
-        attr("amount", outgoing_amount),
-    ]);
+            &owner,
+            &recipient,
+            outgoing_amount,
+        ))
+        .add_event(leg.tax_event)
+        .add_attributes(vec![
+            attr("action", "transfer_from"),
+            attr("from", owner),
+            attr("to", recipient),
+            attr("by", info.sender),
+            attr("amount", outgoing_amount),
+        ])
+        .add_attributes(labels);
     Ok(res)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_send_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     owner: String,
     contract: String,
     amount: Uint128,
     msg: Binary,
+    min_receive: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    ensure_antiwhale(&deps, info.sender.to_string(), amount)?;
-    let is_from_whitelisted = TRANSFER_FROM_WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
-        .unwrap_or(false);
-    let recipient_whitelist = WHITELIST
-        .may_load(deps.storage, contract.clone())?
-        .unwrap_or_default();
-    let sender_whitelist = WHITELIST
-        .may_load(deps.storage, info.sender.to_string())?
-        .unwrap_or_default();
-    let whitelisted = recipient_whitelist || sender_whitelist || is_from_whitelisted;
-    let treasury = TREASURY.may_load(deps.storage)?.unwrap_or_default();
-    let rcpt_addr = deps.api.addr_validate(&contract)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let taxes = query_tax(deps.storage, amount)?;
-    let outgoing_amount = if whitelisted { amount } else { taxes.after_tax };
-
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
-
-    // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
+    let leg = transfer_leg(
+        &mut deps,
+        &env,
         &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default() + outgoing_amount)
-        },
+        Some(&info.sender),
+        &contract,
+        amount,
+        Some(&msg),
+        min_receive,
     )?;
+    let messages = tax_transfer_events(&env, info.sender.as_str(), leg.credits)?;
+    let outgoing_amount = leg.outgoing_amount;
+    let rcpt_addr = deps.api.addr_validate(&contract)?;
 
-    let mut messages = vec![];
-    if !recipient_whitelist && !sender_whitelist && !is_from_whitelisted {
-        BALANCES.update(
-            deps.storage,
-            &deps.api.addr_validate(&treasury)?,
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default() + taxes.taxed_amount)
-            },
-        )?;
-
-        messages.push(WasmMsg::Execute {
-            contract_addr: env.contract.address.to_string(),
-            msg: to_json_binary(&ExecuteMsg::TransferEvent {
-                from: info.sender.to_string(),
-                to: treasury.to_string(),
-                amount: taxes.taxed_amount,
-            })?,
-            funds: vec![],
-        })
-    }
-
-    let attrs = vec![
+    let mut attrs = vec![
         attr("action", "send_from"),
         attr("from", &owner),
         attr("to", &contract),
         attr("by", &info.sender),
         attr("amount", outgoing_amount),
     ];
+    attrs.extend(label_attributes(
+        deps.storage,
+        &[("from", &owner), ("to", &contract)],
+    )?);
+
+    let mut events = bridge_events(deps.storage, &owner, &contract, outgoing_amount);
+    events.push(leg.tax_event);
 
     // create a send message
     let msg = Cw20ReceiveMsg {
@@ -452,32 +680,72 @@ pub fn execute_send_from(
         msg,
     }
     .into_cosmos_msg(contract)?;
+    let hooks = transfer_hooks(
+        deps.storage,
+        &owner,
+        rcpt_addr.as_str(),
+        amount,
+        leg.taxed_amount,
+    )?;
+    let hook = receive_hook(deps.storage, msg, &owner_addr, &rcpt_addr, outgoing_amount)?;
 
     let res = Response::new()
         .add_messages(messages)
-        .add_message(msg)
+        .add_events(events)
+        .add_submessages(hooks)
+        .add_submessage(hook)
         .add_attributes(attrs);
     Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::Transfer { recipient, amount } => {
-            execute_transfer(deps, env, info, recipient, amount)
-        }
-        ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
+    // the inner message goes through every check below as if it was sent on its own
+    if let ExecuteMsg::Timed { inner, bounds } = msg {
+        ensure_within_bounds(&env, &bounds)?;
+        return execute(deps, env, info, *inner);
+    }
+    ensure_not_halted(deps.storage, &info, &msg)?;
+    let sunset = ensure_not_sunset(deps.storage, &env, &msg)?;
+    begin_checkpoints(deps.storage, &env)?;
+    tick_vesting(deps.storage, &env)?;
+    // Scheduled snapshots are taken before anything moves, on the first touch after their boundary
+    let snapshot = take_due_snapshot(deps.storage, &env)?;
+    // Pending taxes are released on the first touch of every block, and so are the reflections
+    // diverted from custodial addresses since the last execution, until the sunset freezes the
+    // redeemable supply
+    let (released, routed) = if sunset {
+        (Uint128::zero(), Uint128::zero())
+    } else {
+        (
+            release_drip(deps.storage, &env)?,
+            route_custodial_excess(deps.storage, &env)?,
+        )
+    };
+    let res = match msg {
+        ExecuteMsg::Transfer {
+            recipient,
+            amount,
+            min_receive,
+        } => execute_transfer(deps, env, info, recipient, amount, min_receive),
+        ExecuteMsg::Burn { amount } => execute_tracked_burn(deps, env, info, amount),
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
-        } => execute_send(deps, env, info, contract, amount, msg),
-        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+            min_receive,
+        } => execute_send(deps, env, info, contract, amount, msg, min_receive),
+        ExecuteMsg::Mint { recipient, amount } => {
+            let rcpt_addr = deps.api.addr_validate(&recipient)?;
+            track_balance(deps, &rcpt_addr, |deps| {
+                execute_mint(deps, env, info, recipient, amount)
+            })
+        }
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -492,20 +760,31 @@ pub fn execute(
             owner,
             recipient,
             amount,
-        } => execute_transfer_from(deps, env, info, owner, recipient, amount),
-        ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount),
+            min_receive,
+        } => execute_transfer_from(deps, env, info, owner, recipient, amount, min_receive),
+        ExecuteMsg::BurnFrom { owner, amount } => {
+            let owner_addr = deps.api.addr_validate(&owner)?;
+            let res = track_balance(deps.branch(), &owner_addr, |deps| {
+                execute_burn_from(deps, env, info, owner, amount)
+            })?;
+            Ok(res.add_submessages(burn_trophy(deps.storage, &owner_addr, amount)?))
+        }
         ExecuteMsg::SendFrom {
             owner,
             contract,
             amount,
             msg,
-        } => execute_send_from(deps, env, info, owner, contract, amount, msg),
+            min_receive,
+        } => execute_send_from(deps, env, info, owner, contract, amount, msg, min_receive),
         ExecuteMsg::UpdateMarketing {
             project,
             description,
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
+        ExecuteMsg::SetDisplayDecimals { display_decimals } => {
+            set_display_decimals(deps, info, display_decimals)
+        }
 
         // Reflection features
         ExecuteMsg::SetWhitelist { user, enable } => set_whitelist(deps, info, user, enable),
@@ -527,11 +806,206 @@ pub fn execute(
             generate_transfer_event(deps, info, env, from, to, amount)
         }
         ExecuteMsg::MigrateTreasury { code_id } => migrate_treasury(deps, env, info, code_id),
+        ExecuteMsg::Relay {
+            sender,
+            msg,
+            nonce,
+            chain_id,
+            contract,
+        } => execute_relay(deps, env, info, sender, msg, nonce, chain_id, contract),
+        ExecuteMsg::Permit {
+            permit,
+            public_key,
+            signature,
+            key_type,
+        } => execute_permit(
+            deps,
+            env,
+            info,
+            permit,
+            public_key,
+            signature,
+            key_type.unwrap_or_default(),
+        ),
+        // unwrapped before the checks above
+        ExecuteMsg::Timed { inner, .. } => execute(deps, env, info, *inner),
+        ExecuteMsg::SetTrustedForwarder { forwarder, enable } => {
+            set_trusted_forwarder(deps, info, forwarder, enable)
+        }
+        ExecuteMsg::GrantOperator {
+            operator,
+            action,
+            cap,
+            expires,
+        } => execute_grant_operator(deps, env, info, operator, action, cap, expires),
+        ExecuteMsg::RevokeOperator { operator, action } => {
+            execute_revoke_operator(deps, info, operator, action)
+        }
+        ExecuteMsg::OperatorExecute { owner, msg } => {
+            execute_as_operator(deps, env, info, owner, msg)
+        }
+        ExecuteMsg::SetReflectionDrip { period } => set_reflection_drip(deps, env, info, period),
+        ExecuteMsg::TransferExactNet {
+            recipient,
+            net_amount,
+        } => execute_transfer_exact_net(deps, env, info, recipient, net_amount),
+        ExecuteMsg::SendAndVerify {
+            contract,
+            amount,
+            msg,
+            expected_net,
+        } => execute_send_and_verify(deps, env, info, contract, amount, msg, expected_net),
+        ExecuteMsg::MultiTransfer { recipients } => {
+            execute_multi_transfer(deps, env, info, recipients, false)
+        }
+        ExecuteMsg::MultiTransferExactNet { recipients } => {
+            execute_multi_transfer(deps, env, info, recipients, true)
+        }
+        ExecuteMsg::RenounceOwnership { confirm } => {
+            execute_renounce_ownership(deps, env, info, confirm)
+        }
+        ExecuteMsg::TransferOwnership { new_admin, expiry } => {
+            transfer_ownership(deps, env, info, new_admin, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info),
+        ExecuteMsg::UpdateRole { role, address } => update_role(deps, info, role, address),
+        ExecuteMsg::ClaimReflections {} => execute_claim_reflections(deps, env, info),
+        ExecuteMsg::ExcludeDeadAddress { address } => exclude_dead_address(deps, info, address),
+        ExecuteMsg::SetInsuranceRate { rate } => set_insurance_rate(deps, info, rate),
+        ExecuteMsg::ExecuteInsuranceWithdrawal { id } => {
+            execute_insurance_withdrawal(deps, env, id)
+        }
+        ExecuteMsg::ScheduleAction {
+            action,
+            execute_at,
+            interval,
+        } => schedule_action(deps, info, action, execute_at, interval),
+        ExecuteMsg::CancelScheduledAction { id } => cancel_scheduled_action(deps, info, id),
+        ExecuteMsg::Veto { id } => veto_scheduled_action(deps, info, id),
+        ExecuteMsg::ExecuteDue {} => execute_due(deps, env),
+        ExecuteMsg::SetBurnTrophy {
+            collection,
+            threshold,
+        } => set_burn_trophy(deps, info, collection, threshold),
+        ExecuteMsg::SetTradingGate {
+            pair,
+            min_token_reserve,
+            min_quote_reserve,
+        } => set_trading_gate(deps, info, pair, min_token_reserve, min_quote_reserve),
+        ExecuteMsg::EnableTrading {} => execute_enable_trading(deps, env),
+        ExecuteMsg::SetCustodial { address, enable } => {
+            set_custodial(deps, env, info, address, enable)
+        }
+        ExecuteMsg::PauseAccrual { address, reason } => {
+            pause_accrual(deps, env, info, address, reason)
+        }
+        ExecuteMsg::ReleaseAccrual { address, outcome } => {
+            release_accrual(deps, env, info, address, outcome)
+        }
+        ExecuteMsg::SetAddressLabel { address, label } => {
+            set_address_label(deps, info, address, label)
+        }
+        ExecuteMsg::SetHookQuarantine { enable } => set_hook_quarantine(deps, info, enable),
+        ExecuteMsg::ClaimQuarantined { id } => execute_claim_quarantined(deps, env, info, id),
+        ExecuteMsg::ProposeLink { address } => propose_link(deps, env, info, address),
+        ExecuteMsg::AcceptLink { address } => accept_link(deps, env, info, address),
+        ExecuteMsg::Unlink { address } => unlink(deps, info, address),
+        ExecuteMsg::SetSnapshotSchedule { schedule } => {
+            set_snapshot_schedule(deps, env, info, schedule)
+        }
+        ExecuteMsg::TakeSnapshot {} => execute_take_snapshot(deps, env, info),
+        ExecuteMsg::FundEpochReward {
+            epoch,
+            amount,
+            claim_period,
+        } => fund_epoch_reward(deps, env, info, epoch, amount, claim_period),
+        ExecuteMsg::ClaimEpochReward { epoch } => {
+            execute_claim_epoch_reward(deps, env, info, epoch)
+        }
+        ExecuteMsg::SweepEpochReward { epoch } => sweep_epoch_reward(deps, env, info, epoch),
+        ExecuteMsg::GatedForward {
+            min_balance,
+            contract,
+            msg,
+            funds,
+        } => execute_gated_forward(deps, info, min_balance, contract, msg, funds),
+        ExecuteMsg::SetSpendingPolicy { policy } => set_spending_policy(deps, env, info, policy),
+        ExecuteMsg::SetCoSigner {
+            co_signer,
+            threshold,
+        } => set_co_signer(deps, env, info, co_signer, threshold),
+        ExecuteMsg::ProposeCoSignedTransfer { recipient, amount } => {
+            propose_co_signed_transfer(deps, env, info, recipient, amount)
+        }
+        ExecuteMsg::ApproveCoSignedTransfer { owner, id } => {
+            approve_co_signed_transfer(deps, env, info, owner, id)
+        }
+        ExecuteMsg::ExecuteCoSignedTransfer { id } => {
+            execute_co_signed_transfer(deps, env, info, id)
+        }
+        ExecuteMsg::CancelCoSignedTransfer { owner, id } => {
+            cancel_co_signed_transfer(deps, env, info, owner, id)
+        }
+        ExecuteMsg::UpdateTaxConfig { tax_config } => {
+            update_tax_config(deps, env, info, tax_config)
+        }
+        ExecuteMsg::SetTaxStrategy { strategy } => set_tax_strategy(deps, env, info, strategy),
+        ExecuteMsg::SetFeeExempt { address, exempt } => set_fee_exempt(deps, info, address, exempt),
+        ExecuteMsg::UpdateLimits {
+            max_tx_amount,
+            max_wallet_amount,
+        } => update_limits(deps, info, max_tx_amount, max_wallet_amount),
+        ExecuteMsg::SetLimitsExempt { address, exempt } => {
+            set_limits_exempt(deps, info, address, exempt)
+        }
+        ExecuteMsg::SetTaxFreeHook {
+            contract,
+            msg_hash,
+            enable,
+        } => set_tax_free_hook(deps, info, contract, msg_hash, enable),
+        ExecuteMsg::SetBridge { contract } => set_bridge(deps, info, contract),
+        ExecuteMsg::AddTransferHook { contract } => add_transfer_hook(deps, info, contract),
+        ExecuteMsg::RemoveTransferHook { contract } => remove_transfer_hook(deps, info, contract),
+        ExecuteMsg::SetAutoLiquidity { config } => set_auto_liquidity(deps, env, info, config),
+        ExecuteMsg::ScheduleSunset { denom } => schedule_sunset(deps, env, info, denom),
+        ExecuteMsg::CancelSunset {} => cancel_sunset(deps, env, info),
+        ExecuteMsg::FundSettlement {} => fund_settlement(deps, env, info),
+        ExecuteMsg::RedeemFinal {} => redeem_final(deps, env, info),
+        ExecuteMsg::SweepSettlement {} => sweep_settlement(deps, env, info),
+        ExecuteMsg::SetGuardian { guardian } => set_guardian(deps, info, guardian),
+        ExecuteMsg::RenounceGuardian {} => renounce_guardian(deps, info),
+        ExecuteMsg::Pause {} => execute_pause(deps, env, info),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info),
+        ExecuteMsg::VerifyInvariants {} => verify_invariants(deps, env),
+        ExecuteMsg::BackfillDistribution { limit } => backfill_distribution(deps, env, limit),
+        ExecuteMsg::Freeze { address } => set_frozen(deps, env, info, address, true),
+        ExecuteMsg::Unfreeze { address } => set_frozen(deps, env, info, address, false),
+        #[cfg(feature = "clawback")]
+        ExecuteMsg::Clawback {
+            from,
+            amount,
+            reason,
+        } => execute_clawback(deps, info, from, amount, reason),
+    }?;
+
+    let res = match snapshot {
+        Some(id) => res.add_attribute("snapshot", id.to_string()),
+        None => res,
+    };
+    let res = if routed.is_zero() {
+        res
+    } else {
+        res.add_attribute("custodial_routed", routed)
+    };
+    if released.is_zero() {
+        Ok(res)
+    } else {
+        Ok(res.add_attribute("drip_released", released))
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => to_json_binary(&query_balance(deps, address)?),
         QueryMsg::TokenInfo {} => to_json_binary(&query_token_info(deps)?),
@@ -549,18 +1023,163 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::MarketingInfo {} => to_json_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_json_binary(&query_download_logo(deps)?),
-        QueryMsg::QueryTax { amount } => to_json_binary(&query_tax(deps.storage, amount)?),
+        QueryMsg::QueryTax { amount } => to_json_binary(&tax_of(deps.storage, &env, None, amount)?),
         QueryMsg::QueryRates {} => to_json_binary(&query_rate(deps.storage)?),
+        QueryMsg::BalanceAt { address, height } => {
+            to_json_binary(&query_balance_at(deps, env, address, height)?)
+        }
+        QueryMsg::TotalSupplyAt { height } => {
+            to_json_binary(&query_total_supply_at(deps, env, height)?)
+        }
+        QueryMsg::ConfigHash {} => to_json_binary(&query_config_hash(deps.storage)?),
+        QueryMsg::PresetPreview { preset } => {
+            to_json_binary(&query_preset_preview(deps, env, preset)?)
+        }
+        QueryMsg::TaxLimits {} => to_json_binary(&query_tax_limits(deps.storage, &env)?),
+        QueryMsg::Ownership {} => to_json_binary(&query_ownership(deps.storage)?),
+        QueryMsg::Roles {} => to_json_binary(&query_roles(deps)?),
+        QueryMsg::PendingReflections { address } => {
+            to_json_binary(&query_pending_reflections(deps, address)?)
+        }
+        QueryMsg::ReflectionIndex {} => to_json_binary(&query_reflection_index(deps)?),
+        QueryMsg::ClaimHistory { address } => to_json_binary(&query_claim_history(deps, address)?),
+        QueryMsg::InsuranceFund {} => to_json_binary(&query_insurance_fund(deps.storage)?),
+        QueryMsg::InsuranceWithdrawals { start_after, limit } => {
+            to_json_binary(&query_insurance_withdrawals(deps, start_after, limit)?)
+        }
+        QueryMsg::ApiVersion {} => to_json_binary(&query_api_version(deps.storage)?),
+        QueryMsg::Features {} => to_json_binary(&query_features()),
+        QueryMsg::BurnTrophy {} => to_json_binary(&query_burn_trophy(deps)?),
+        QueryMsg::TradingGate {} => to_json_binary(&query_trading_gate(deps)?),
+        QueryMsg::Custodial { address } => to_json_binary(&query_custodial(deps, address)?),
+        QueryMsg::AccrualPause { address } => to_json_binary(&query_accrual_pause(deps, address)?),
+        QueryMsg::AddressLabels { start_after, limit } => {
+            to_json_binary(&query_address_labels(deps, start_after, limit)?)
+        }
+        QueryMsg::SimulateSwapRoundTrip { pair, amount } => {
+            to_json_binary(&query_simulate_swap_round_trip(deps, env, pair, amount)?)
+        }
+        QueryMsg::QuarantinedSends {
+            sender,
+            start_after,
+            limit,
+        } => to_json_binary(&query_quarantined_sends(deps, sender, start_after, limit)?),
+        QueryMsg::Snapshots { start_after, limit } => {
+            to_json_binary(&query_snapshots(deps, start_after, limit)?)
+        }
+        QueryMsg::SnapshotBalance { address, id } => {
+            to_json_binary(&query_snapshot_balance(deps, address, id)?)
+        }
+        QueryMsg::SnapshotDiff {
+            from,
+            to,
+            start_after,
+            limit,
+        } => to_json_binary(&query_snapshot_diff(deps, from, to, start_after, limit)?),
+        QueryMsg::Sunset { address } => to_json_binary(&query_sunset(deps, env, address)?),
+        QueryMsg::AutoLiquidity {} => to_json_binary(&query_auto_liquidity(deps)?),
+        QueryMsg::SecurityStatus { start_after, limit } => {
+            to_json_binary(&query_security_status(deps, start_after, limit)?)
+        }
+        QueryMsg::DisplayInfo {} => to_json_binary(&query_display_info(deps)?),
+        QueryMsg::VestingInfo { address } => {
+            to_json_binary(&query_vesting_info(deps, env, address)?)
+        }
+        QueryMsg::EffectiveBalance { address } => {
+            to_json_binary(&query_effective_balance(deps, env, address)?)
+        }
+        QueryMsg::SpendableVia { owner, spender } => {
+            to_json_binary(&query_spendable_via(deps, env, owner, spender)?)
+        }
+        QueryMsg::SpendingPolicy { address } => {
+            to_json_binary(&query_spending_policy(deps, env, address)?)
+        }
+        QueryMsg::CoSigner { address } => to_json_binary(&query_co_signer(deps, env, address)?),
+        QueryMsg::CoSignedTransfers {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_co_signed_transfers(deps, owner, start_after, limit)?),
+        QueryMsg::TaxConfig {} => to_json_binary(&query_tax_config(deps)?),
+        QueryMsg::TaxStrategy {} => to_json_binary(&query_tax_strategy(deps, env)?),
+        QueryMsg::FeeExemptions { start_after, limit } => {
+            to_json_binary(&query_fee_exemptions(deps, start_after, limit)?)
+        }
+        QueryMsg::Limits {} => to_json_binary(&query_limits(deps)?),
+        QueryMsg::LimitExemptions { start_after, limit } => {
+            to_json_binary(&query_limit_exemptions(deps, start_after, limit)?)
+        }
+        QueryMsg::TaxFreeHooks {
+            contract,
+            start_after,
+            limit,
+        } => to_json_binary(&query_tax_free_hooks(deps, contract, start_after, limit)?),
+        QueryMsg::BridgeConfig {} => to_json_binary(&query_bridge_config(deps)?),
+        QueryMsg::TransferHooks {} => to_json_binary(&query_transfer_hooks(deps)?),
+        QueryMsg::EpochRewards { start_after, limit } => {
+            to_json_binary(&query_epoch_rewards(deps, start_after, limit)?)
+        }
+        QueryMsg::EpochRewardClaim { epoch, address } => {
+            to_json_binary(&query_epoch_reward_claim(deps, env, epoch, address)?)
+        }
+        #[cfg(feature = "debug")]
+        QueryMsg::RawRange {
+            namespace,
+            start_after,
+            limit,
+        } => to_json_binary(&query_raw_range(deps, namespace, start_after, limit)?),
+        QueryMsg::LinkedAddresses { address } => {
+            to_json_binary(&query_linked_addresses(deps, address)?)
+        }
+        QueryMsg::RescueAddress {} => to_json_binary(&query_rescue_address(deps)?),
+        QueryMsg::ScheduledActions { start_after, limit } => {
+            to_json_binary(&query_scheduled_actions(deps, start_after, limit)?)
+        }
+        QueryMsg::ScheduleReceipts {} => to_json_binary(&query_schedule_receipts(deps.storage)?),
         QueryMsg::GetWhitelist { address } => {
             to_json_binary(&query_whitelist(deps.storage, address)?)
         }
+        QueryMsg::HolderDistribution { buckets } => {
+            to_json_binary(&query_holder_distribution(deps.storage, buckets)?)
+        }
+        QueryMsg::Concentration {} => to_json_binary(&query_concentration(deps.storage)?),
+        QueryMsg::ReflectionDrip {} => to_json_binary(&query_reflection_drip(deps.storage, &env)?),
+        QueryMsg::OperatorGrants {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_operator_grants(deps, owner, start_after, limit)?),
+        QueryMsg::IsTrustedForwarder { address } => {
+            to_json_binary(&query_trusted_forwarder(deps.storage, address)?)
+        }
+        QueryMsg::SignatureDomain { address } => {
+            to_json_binary(&query_signature_domain(deps.storage, &env, address)?)
+        }
+        QueryMsg::ReflectionApr { window } => {
+            to_json_binary(&query_reflection_apr(deps.storage, env.block.time, window)?)
+        }
+        QueryMsg::TaxStats {} => to_json_binary(&query_tax_stats(deps)?),
+        QueryMsg::ReflectionSummary {} => to_json_binary(&query_reflection_summary(deps, env)?),
     }
 }
 
-/// Used to calculate the amount of taxes to be paid, to be used in all transfer functions
+/// Tax of `amount` at the global rate, the transfer functions tax at the rate of the tax strategy
+/// with `tax_strategy::transfer_tax`
 pub fn query_tax(storage: &dyn Storage, amount: Uint128) -> Result<QueryTaxResponse, StdError> {
-    let reflection_rate = REFLECTION_RATE.may_load(storage)?.unwrap();
     let tax_rate = TAX_RATE.may_load(storage)?.unwrap();
+    split_tax(storage, amount, tax_rate)
+}
+
+/// Tax of `amount` at `tax_rate`, split by the tax config or the reflection and burn rates
+pub fn split_tax(
+    storage: &dyn Storage,
+    amount: Uint128,
+    tax_rate: Decimal,
+) -> StdResult<QueryTaxResponse> {
+    if let Some(tax_config) = TAX_CONFIG.may_load(storage)? {
+        return Ok(tax_config.split(amount, tax_rate));
+    }
+    let reflection_rate = REFLECTION_RATE.may_load(storage)?.unwrap();
     let burn_rate = BURN_RATE.may_load(storage)?.unwrap();
 
     let taxed_amount = amount.mul(tax_rate);
@@ -574,9 +1193,38 @@ pub fn query_tax(storage: &dyn Storage, amount: Uint128) -> Result<QueryTaxRespo
         after_tax,
         reflection_amount,
         liquidity_amount,
+        burn_amount,
+        destination_amount: Uint128::zero(),
     })
 }
 
+/// Smallest amount whose after tax amount is `net`. The after tax amount grows by at most one per
+/// unit, so every net amount is reachable exactly
+pub fn gross_up(storage: &dyn Storage, net: Uint128) -> StdResult<Uint128> {
+    gross_up_at(net, TAX_RATE.may_load(storage)?.unwrap_or_default())
+}
+
+/// Same as `gross_up` at `tax_rate`
+pub fn gross_up_at(net: Uint128, tax_rate: Decimal) -> StdResult<Uint128> {
+    if tax_rate >= Decimal::one() {
+        return Err(StdError::generic_err("Cannot gross up a 100% tax"));
+    }
+    let after_tax = |gross: Uint128| gross - gross * tax_rate;
+
+    // net / (1 - rate) rounded up, then corrected for the rounding of the tax
+    let mut gross = net
+        .checked_mul_ceil(Decimal::one() / (Decimal::one() - tax_rate))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    while after_tax(gross) < net {
+        gross = gross.checked_add(Uint128::one())?;
+    }
+    while !gross.is_zero() && after_tax(gross - Uint128::one()) >= net {
+        gross -= Uint128::one();
+    }
+
+    Ok(gross)
+}
+
 /// Returns the current tax rates
 pub fn query_rate(storage: &dyn Storage) -> Result<(Decimal, Decimal, Decimal, Decimal), StdError> {
     let tax_rate = TAX_RATE.may_load(storage)?.unwrap();
@@ -587,6 +1235,38 @@ pub fn query_rate(storage: &dyn Storage) -> Result<(Decimal, Decimal, Decimal, D
     Ok((tax_rate, reflection_rate, burn_rate, transfer_rate))
 }
 
+/// Hashes the parameters that determine what a transfer costs, so that deployments and observations
+/// of the same deployment can be compared without diffing every rate
+pub fn query_config_hash(storage: &dyn Storage) -> StdResult<ConfigHashResponse> {
+    let (tax_rate, reflection_rate, burn_rate, antiwhale_rate) = query_rate(storage)?;
+    let params = EconomicParams {
+        tax_rate,
+        reflection_rate,
+        burn_rate,
+        antiwhale_rate,
+        drip_period: DRIP_PERIOD.may_load(storage)?.unwrap_or_default(),
+        max_tax_rate: MAX_TAX_RATE.may_load(storage)?.unwrap_or(Decimal::one()),
+        max_tax_step: MAX_TAX_STEP.may_load(storage)?.unwrap_or(Decimal::one()),
+        reflection_mode: REFLECTION_MODE.may_load(storage)?.unwrap_or_default(),
+        insurance_rate: INSURANCE_RATE.may_load(storage)?.unwrap_or_default(),
+        immutable: IMMUTABLE.may_load(storage)?.unwrap_or_default(),
+        tax_config: TAX_CONFIG.may_load(storage)?,
+        limits: WALLET_LIMITS.may_load(storage)?,
+        tax_strategy: TAX_STRATEGY.may_load(storage)?,
+    };
+    config_hash(params)
+}
+
+/// Hashes `params` the way `ConfigHash` does
+pub fn config_hash(params: EconomicParams) -> StdResult<ConfigHashResponse> {
+    let hash = Sha256::digest(&to_json_vec(&params)?);
+
+    Ok(ConfigHashResponse {
+        hash: hex::encode(hash),
+        params,
+    })
+}
+
 pub fn query_whitelist(storage: &dyn Storage, address: String) -> Result<bool, StdError> {
     let whitelist = WHITELIST.may_load(storage, address)?.unwrap();
 
@@ -599,15 +1279,39 @@ pub fn query_whitelist(storage: &dyn Storage, address: String) -> Result<bool, S
 /// Antiwhale rate is number between 0 to 1. 0.02 refers to when someone intends to move 2% of supply, anti-whale gets triggered
 pub fn set_tax_rate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     global_rate: Decimal,
     reflection_rate: Decimal,
     burn_rate: Decimal,
     antiwhale_rate: Decimal,
 ) -> Result<Response, ContractError> {
-    ensure_admin(&deps, &info)?;
+    ensure_role(&deps, &info, Role::TaxManager)?;
+    apply_tax_rate(
+        deps.storage,
+        &env,
+        global_rate,
+        reflection_rate,
+        burn_rate,
+        antiwhale_rate,
+    )?;
+    Ok(Response::default())
+}
 
+/// Validates and saves the tax rates, shared by `SetTaxRate` and scheduled actions
+pub fn apply_tax_rate(
+    storage: &mut dyn Storage,
+    env: &Env,
+    global_rate: Decimal,
+    reflection_rate: Decimal,
+    burn_rate: Decimal,
+    antiwhale_rate: Decimal,
+) -> Result<(), ContractError> {
+    if TAX_CONFIG.may_load(storage)?.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Tax rates are set by the tax config, remove it first",
+        )));
+    }
     if global_rate > Decimal::one() {
         return Err(ContractError::Std(StdError::generic_err(
             "global_rate must be <= 1",
@@ -626,11 +1330,12 @@ pub fn set_tax_rate(
         )));
     }
 
-    TAX_RATE.save(deps.storage, &global_rate)?;
-    REFLECTION_RATE.save(deps.storage, &reflection_rate)?;
-    BURN_RATE.save(deps.storage, &burn_rate)?;
-    MAX_TRANSFER_SUPPLY_RATE.save(deps.storage, &antiwhale_rate)?;
-    Ok(Response::default())
+    record_tax_rate_change(storage, env, global_rate)?;
+    TAX_RATE.save(storage, &global_rate)?;
+    REFLECTION_RATE.save(storage, &reflection_rate)?;
+    BURN_RATE.save(storage, &burn_rate)?;
+    MAX_TRANSFER_SUPPLY_RATE.save(storage, &antiwhale_rate)?;
+    Ok(())
 }
 
 /// Sets which addresses are whitelisted (not taxed)
@@ -640,14 +1345,65 @@ pub fn set_whitelist(
     user: String,
     enable: bool,
 ) -> Result<Response, ContractError> {
-    ensure_admin(&deps, &info)?;
+    ensure_role(&deps, &info, Role::ExemptionManager)?;
     deps.api.addr_validate(&user.to_string())?;
     WHITELIST.save(deps.storage, user.to_string(), &enable)?;
     Ok(Response::default())
 }
 
+/// Removes the admin for good, after which every admin-only message fails
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    confirm: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if confirm != env.contract.address.as_str() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "confirm must be the address of this token",
+        )));
+    }
+
+    ADMIN.save(deps.storage, &String::new())?;
+    IMMUTABLE.save(deps.storage, &true)?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("previous_admin", info.sender))
+}
+
+pub fn query_ownership(storage: &dyn Storage) -> StdResult<OwnershipResponse> {
+    Ok(OwnershipResponse {
+        admin: ADMIN.may_load(storage)?.filter(|admin| !admin.is_empty()),
+        immutable: IMMUTABLE.may_load(storage)?.unwrap_or_default(),
+    })
+}
+
+/// Reverts when the amount credited to the recipient after tax is below the guard of the sender,
+/// e.g. because the tax rate changed between signing and execution
+pub fn ensure_min_receive(
+    outgoing_amount: Uint128,
+    min_receive: Option<Uint128>,
+) -> Result<(), ContractError> {
+    match min_receive {
+        Some(min_receive) if outgoing_amount < min_receive => {
+            Err(ContractError::Std(StdError::generic_err(format!(
+                "Received amount {} is below min_receive {}",
+                outgoing_amount, min_receive
+            ))))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// This is used to ensure that only the admin can execute certain functions
 pub fn ensure_admin(deps: &DepsMut, info: &MessageInfo) -> Result<Response, ContractError> {
+    if IMMUTABLE.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: ownership renounced",
+        )));
+    }
     let admin = ADMIN.may_load(deps.storage)?.unwrap_or_default();
     if info.sender != admin {
         return Err(ContractError::Std(StdError::generic_err(
@@ -666,9 +1422,12 @@ pub fn ensure_antiwhale(
 ) -> Result<Response, ContractError> {
     let token_info = TOKEN_INFO.may_load(deps.storage)?.unwrap();
     let transfer_rate = MAX_TRANSFER_SUPPLY_RATE.may_load(deps.storage)?.unwrap();
-    let whitelist = WHITELIST.may_load(deps.storage, from)?.unwrap_or(false);
+    let whitelist = WHITELIST
+        .may_load(deps.storage, from.clone())?
+        .unwrap_or(false)
+        || is_bridge(deps.storage, &from);
 
-    // Whitelisted contracts can bypass antiwhale, inclusive of treasury contract
+    // Whitelisted contracts can bypass antiwhale, inclusive of treasury contract and the bridge
     if transfer_balance >= token_info.total_supply.mul(transfer_rate) && !whitelist {
         return Err(ContractError::Std(StdError::generic_err(
             "Unauthorized: anti-whale triggered",
@@ -680,6 +1439,7 @@ pub fn ensure_antiwhale(
 
 /// This is used to generate a transfer event to treasury contract (so that explorer tracks transfer events properly, and balances shows up correctly)
 /// This is also used to trigger liquify (every 10 seconds) -> prevents recursive liquify that can cause out of gas
+/// and the auto-liquidity run once enough tax is set aside
 pub fn generate_transfer_event(
     deps: DepsMut,
     info: MessageInfo,
@@ -706,19 +1466,53 @@ pub fn generate_transfer_event(
         };
         messages.push(liquify_msg);
     }
+    let auto_liquidity = auto_liquify(deps.storage, &deps.querier, &env)?;
 
+    let labels = label_attributes(deps.storage, &[("from", &from), ("to", &to)])?;
     let res = Response::new()
         .add_messages(messages)
+        .add_submessages(auto_liquidity)
         .add_attribute("action", "transfer")
         .add_attribute("from", from)
         .add_attribute("to", to)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_attributes(labels);
     Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    begin_checkpoints(deps.storage, &env)?;
+    tick_vesting(deps.storage, &env)?;
+    match msg {
+        SudoMsg::QueueInsuranceWithdrawal {
+            recipient,
+            amount,
+            reason,
+        } => queue_insurance_withdrawal(deps, env, recipient, amount, reason),
+        SudoMsg::SetRescueAddress { address } => set_rescue_address(deps, address),
+        SudoMsg::EmergencyDrain { reason } => emergency_drain(deps, env, reason),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    begin_checkpoints(deps.storage, &env)?;
+    tick_vesting(deps.storage, &env)?;
+    sync_reflection_exclusions(deps.storage, &env)?;
+    let backfill = start_distribution_backfill(deps.storage)?;
+    let res = match msg {
+        MigrateMsg::Action(MigrateAction::ApplyPreset { preset }) => {
+            apply_preset(deps, env, preset)
+        }
+        MigrateMsg::Upgrade { .. } => Ok(Response::default()),
+    }?;
+
+    Ok(if backfill {
+        res.add_attribute("distribution_backfill", "started")
+    } else {
+        res
+    })
 }
 
 pub fn migrate_treasury(
@@ -737,7 +1531,7 @@ pub fn migrate_treasury(
         Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Migrate {
             contract_addr: treasury,
             new_code_id: code_id,
-            msg: to_json_binary(&MigrateMsg {
+            msg: to_json_binary(&MigrateMsg::Upgrade {
                 msg: "".to_string(),
             })?,
         })),
@@ -778,6 +1572,7 @@ pub fn register_deployment(
 
     TREASURY.save(deps.storage, &contract_addr.to_string())?;
     WHITELIST.save(deps.storage, contract_addr.to_string(), &true)?;
+    exclude_from_reflections(deps.storage, &contract_addr)?;
 
     Ok(Response::new())
 }
@@ -0,0 +1,96 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+
+use crate::contract::{ensure_admin, TREASURY};
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::CustodialResponse;
+use crate::reflections::settle_reflections;
+use crate::state::{CustodialAccount, CUSTODIAL_ACCOUNTS, CUSTODIAL_EXCESS, REFLECTION_EXCLUDED};
+
+/// Marks `address`, typically an exchange hot wallet, as custodial: its balance only moves by what
+/// is transferred in and out, the reflections it accrues in claim mode go to the treasury instead.
+/// What it accrued before is routed to the treasury as well
+pub fn set_custodial(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    enable: bool,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+    if REFLECTION_EXCLUDED.has(deps.storage, &address) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Address is already excluded from reflections",
+        )));
+    }
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    if enable {
+        if !CUSTODIAL_ACCOUNTS.has(deps.storage, &address) {
+            CUSTODIAL_ACCOUNTS.save(
+                deps.storage,
+                &address,
+                &CustodialAccount {
+                    since: env.block.time.seconds(),
+                    routed: Uint128::zero(),
+                },
+            )?;
+        }
+        settle_reflections(deps.storage, &address, balance, balance)?;
+    } else {
+        // the accrual up to now still goes to the treasury
+        settle_reflections(deps.storage, &address, balance, balance)?;
+        CUSTODIAL_ACCOUNTS.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_custodial")
+        .add_attribute("address", address)
+        .add_attribute("enable", enable.to_string()))
+}
+
+pub fn is_custodial(storage: &dyn Storage, address: &Addr) -> bool {
+    CUSTODIAL_ACCOUNTS.has(storage, address)
+}
+
+/// Takes the reflections accrued by a custodial address, they are sent to the treasury by
+/// `route_custodial_excess` on the next execution
+pub fn divert_accrual(storage: &mut dyn Storage, address: &Addr, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let mut account = CUSTODIAL_ACCOUNTS.load(storage, address)?;
+    account.routed = account.routed.checked_add(amount)?;
+    CUSTODIAL_ACCOUNTS.save(storage, address, &account)?;
+    let excess = CUSTODIAL_EXCESS.may_load(storage)?.unwrap_or_default();
+    CUSTODIAL_EXCESS.save(storage, &excess.checked_add(amount)?)
+}
+
+/// Moves the reflections diverted from custodial addresses from the token contract to the treasury
+pub fn route_custodial_excess(storage: &mut dyn Storage, env: &Env) -> StdResult<Uint128> {
+    let excess = CUSTODIAL_EXCESS.may_load(storage)?.unwrap_or_default();
+    if excess.is_zero() {
+        return Ok(excess);
+    }
+    let treasury = Addr::unchecked(TREASURY.load(storage)?);
+    CUSTODIAL_EXCESS.remove(storage);
+    decrease_balance(storage, &env.contract.address, excess)?;
+    increase_balance(storage, &treasury, excess)?;
+
+    Ok(excess)
+}
+
+pub fn query_custodial(deps: Deps, address: String) -> StdResult<CustodialResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let account = CUSTODIAL_ACCOUNTS.may_load(deps.storage, &address)?;
+    Ok(CustodialResponse {
+        custodial: account.is_some(),
+        since: account.as_ref().map(|account| account.since),
+        routed: account.map(|account| account.routed).unwrap_or_default(),
+    })
+}
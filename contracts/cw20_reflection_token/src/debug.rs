@@ -0,0 +1,50 @@
+//! Raw storage access for local forks and audits, only compiled with the `debug` feature.
+//! Never enable it for a mainnet deployment: it exposes every storage namespace as is
+use cosmwasm_std::{Binary, Deps, Order, StdResult};
+
+use crate::msg::{RawEntry, RawRangeResponse};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Storage prefix of the entries of a `Map` saved under `namespace`
+fn map_prefix(namespace: &str) -> Vec<u8> {
+    let namespace = namespace.as_bytes();
+    let mut prefix = (namespace.len() as u16).to_be_bytes().to_vec();
+    prefix.extend_from_slice(namespace);
+    prefix
+}
+
+/// Raw entries of the map saved under `namespace`, e.g. `balance` for the cw20 balances,
+/// `reflection_flow` for the collected fees or `whitelist` for the tax exemptions. Keys are
+/// returned without the namespace prefix, as `start_after` expects them
+pub fn query_raw_range(
+    deps: Deps,
+    namespace: String,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<RawRangeResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let prefix = map_prefix(&namespace);
+    let start = match start_after {
+        Some(key) => {
+            let mut start = [prefix.as_slice(), key.as_slice()].concat();
+            // smallest key after `start_after`
+            start.push(0);
+            start
+        }
+        None => prefix.clone(),
+    };
+    let entries = deps
+        .storage
+        .range(Some(&start), None, Order::Ascending)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .take(limit)
+        .map(|(key, value)| RawEntry {
+            key: Binary::from(&key[prefix.len()..]),
+            value: Binary::from(value),
+        })
+        .collect();
+
+    Ok(RawRangeResponse { entries })
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult};
+use cw20_base::state::{MARKETING_INFO, TOKEN_INFO};
+use cw20_base::ContractError;
+
+use crate::contract::{query_ownership, WHITELIST};
+use crate::msg::{DisplayInfoResponse, ReflectionSummaryResponse};
+use crate::state::{BRIDGE, DISPLAY_DECIMALS, FEE_EXEMPT, PAUSED_AT};
+use crate::tax_strategy::query_tax_strategy;
+use crate::trading::query_trading_gate;
+
+/// Sets the fractional digits frontends should show, like the logo only the marketing address can
+pub fn set_display_decimals(
+    deps: DepsMut,
+    info: MessageInfo,
+    display_decimals: Option<u8>,
+) -> Result<Response, ContractError> {
+    let marketing = MARKETING_INFO
+        .may_load(deps.storage)?
+        .and_then(|marketing| marketing.marketing);
+    if marketing.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    match display_decimals {
+        Some(display_decimals) => {
+            if display_decimals > TOKEN_INFO.load(deps.storage)?.decimals {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "display_decimals must not exceed decimals",
+                )));
+            }
+            DISPLAY_DECIMALS.save(deps.storage, &display_decimals)?;
+        }
+        None => DISPLAY_DECIMALS.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_display_decimals")
+        .add_attribute(
+            "display_decimals",
+            display_decimals
+                .map(|display_decimals| display_decimals.to_string())
+                .unwrap_or_default(),
+        ))
+}
+
+pub fn query_display_info(deps: Deps) -> StdResult<DisplayInfoResponse> {
+    let decimals = TOKEN_INFO.load(deps.storage)?.decimals;
+    Ok(DisplayInfoResponse {
+        decimals,
+        display_decimals: DISPLAY_DECIMALS.may_load(deps.storage)?.unwrap_or(decimals),
+    })
+}
+
+pub fn query_reflection_summary(deps: Deps, env: Env) -> StdResult<ReflectionSummaryResponse> {
+    let info = TOKEN_INFO.load(deps.storage)?;
+    let tax = query_tax_strategy(deps, env)?.current_rate;
+    // an address can be both whitelisted and fee exempt
+    let mut exempt = WHITELIST
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((address, true)) => Some(Ok(address)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<StdResult<BTreeSet<_>>>()?;
+    for address in FEE_EXEMPT.keys(deps.storage, None, None, Order::Ascending) {
+        exempt.insert(address?.to_string());
+    }
+    if let Some(bridge) = BRIDGE.may_load(deps.storage)? {
+        exempt.insert(bridge.to_string());
+    }
+
+    Ok(ReflectionSummaryResponse {
+        symbol: info.symbol,
+        decimals: info.decimals,
+        buy_tax: tax,
+        sell_tax: tax,
+        transfer_tax: tax,
+        exemptions: exempt.len() as u32,
+        trading_enabled: query_trading_gate(deps)?.enabled
+            && PAUSED_AT.may_load(deps.storage)?.is_none(),
+        immutable: query_ownership(deps.storage)?.immutable,
+    })
+}
@@ -0,0 +1,171 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+
+use crate::contract::{ensure_admin, TREASURY};
+use crate::custodial::is_custodial;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::{AccrualOutcome, AccrualPauseResponse};
+use crate::reflections::{accrued, is_claim_mode, settle_reflections};
+use crate::state::{
+    AccrualPause, HolderReflections, ACCRUAL_PAUSES, HOLDER_REFLECTIONS, REFLECTION_EXCLUDED,
+    REFLECTION_INDEX,
+};
+
+/// Withholds the reflections `address` accrues from now on, without freezing its balance so that
+/// the counterparties of a suspected exploiter keep trading with it. What it accrued before stays
+/// claimable
+pub fn pause_accrual(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if reason.trim().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "An accrual pause needs a reason",
+        )));
+    }
+    if !is_claim_mode(deps.storage)? {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reflections only accrue to holders in claim mode",
+        )));
+    }
+    let address = deps.api.addr_validate(&address)?;
+    if REFLECTION_EXCLUDED.has(deps.storage, &address) || is_custodial(deps.storage, &address) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Address accrues no reflections",
+        )));
+    }
+    if ACCRUAL_PAUSES.has(deps.storage, &address) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Accrual is already paused",
+        )));
+    }
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    settle_reflections(deps.storage, &address, balance, balance)?;
+    ACCRUAL_PAUSES.save(
+        deps.storage,
+        &address,
+        &AccrualPause {
+            since: env.block.time.seconds(),
+            reason: reason.clone(),
+            withheld: Uint128::zero(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pause_accrual")
+        .add_event(
+            Event::new("accrual_paused")
+                .add_attribute("address", address)
+                .add_attribute("reason", reason),
+        ))
+}
+
+/// Ends the pause of `address`: the reflections withheld are credited to it as pending
+/// reflections, or forfeited to the treasury
+pub fn release_accrual(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    outcome: AccrualOutcome,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+    if !ACCRUAL_PAUSES.has(deps.storage, &address) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Accrual is not paused",
+        )));
+    }
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    // withholds the accrual up to now
+    settle_reflections(deps.storage, &address, balance, balance)?;
+    let pause = ACCRUAL_PAUSES.load(deps.storage, &address)?;
+    ACCRUAL_PAUSES.remove(deps.storage, &address);
+
+    let outcome = match outcome {
+        AccrualOutcome::Credit => {
+            let mut holder = HOLDER_REFLECTIONS.load(deps.storage, &address)?;
+            holder.pending = holder
+                .pending
+                .checked_add(pause.withheld)
+                .map_err(StdError::from)?;
+            HOLDER_REFLECTIONS.save(deps.storage, &address, &holder)?;
+            "credit"
+        }
+        AccrualOutcome::Forfeit => {
+            if !pause.withheld.is_zero() {
+                let treasury = Addr::unchecked(TREASURY.load(deps.storage)?);
+                decrease_balance(deps.storage, &env.contract.address, pause.withheld)?;
+                increase_balance(deps.storage, &treasury, pause.withheld)?;
+            }
+            "forfeit"
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "release_accrual")
+        .add_event(
+            Event::new("accrual_released")
+                .add_attribute("address", address)
+                .add_attribute("outcome", outcome)
+                .add_attribute("amount", pause.withheld)
+                .add_attribute("since", pause.since.to_string()),
+        ))
+}
+
+pub fn is_accrual_paused(storage: &dyn Storage, address: &Addr) -> bool {
+    ACCRUAL_PAUSES.has(storage, address)
+}
+
+/// Moves what `holder` accrued since its last settlement to the pause of `address`, if any,
+/// called from `settle_reflections`
+pub fn withhold_accrual(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    holder: &mut HolderReflections,
+) -> StdResult<()> {
+    let mut pause = match ACCRUAL_PAUSES.may_load(storage, address)? {
+        Some(pause) => pause,
+        None => return Ok(()),
+    };
+    let settled = HOLDER_REFLECTIONS
+        .may_load(storage, address)?
+        .unwrap_or_default()
+        .pending;
+    pause.withheld = pause
+        .withheld
+        .checked_add(holder.pending.checked_sub(settled)?)?;
+    holder.pending = settled;
+    ACCRUAL_PAUSES.save(storage, address, &pause)
+}
+
+pub fn query_accrual_pause(deps: Deps, address: String) -> StdResult<AccrualPauseResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let mut pause = match ACCRUAL_PAUSES.may_load(deps.storage, &address)? {
+        Some(pause) => pause,
+        None => return Ok(AccrualPauseResponse::default()),
+    };
+    // including what accrued since the last settlement
+    let index = REFLECTION_INDEX.may_load(deps.storage)?.unwrap_or_default();
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let settled = HOLDER_REFLECTIONS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default()
+        .pending;
+    pause.withheld += accrued(deps.storage, &index, &address, balance)?.pending - settled;
+
+    Ok(AccrualPauseResponse { pause: Some(pause) })
+}
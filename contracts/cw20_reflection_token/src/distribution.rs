@@ -0,0 +1,331 @@
+use std::cmp::Reverse;
+
+use cosmwasm_std::{
+    Addr, Decimal, DepsMut, Env, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20::Cw20Coin;
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::msg::{ConcentrationResponse, HolderBucket, HolderDistributionResponse};
+use crate::reflections::eligible_supply;
+use crate::state::{
+    DISTRIBUTION_BACKFILL, DISTRIBUTION_TRACKED_SINCE, HOLDER_BUCKETS, HOLDER_BUCKET_BALANCES,
+    REFLECTION_EXCLUDED, TOP_HOLDERS,
+};
+
+/// Number of holders tracked by `TOP_HOLDERS`
+pub const TOP_HOLDERS_LEN: usize = 10;
+
+const DEFAULT_BACKFILL_LIMIT: u32 = 30;
+const MAX_BACKFILL_LIMIT: u32 = 100;
+
+/// Returns the decade of a non-zero balance, i.e. the number of decimal digits minus one
+pub fn decade_of(balance: Uint128) -> u8 {
+    let mut decade = 0u8;
+    let mut rest = balance.u128() / 10;
+    while rest > 0 {
+        decade += 1;
+        rest /= 10;
+    }
+    decade
+}
+
+fn decade_floor(decade: u8) -> Uint128 {
+    Uint128::new(10u128.pow(decade as u32))
+}
+
+/// Moves `address` between decades when its balance changes from `old` to `new`.
+/// Zero balances are not counted as holders
+pub fn record_holder_change(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    if !is_counted(storage, address)? {
+        return Ok(());
+    }
+    move_holder(storage, old, new)
+}
+
+fn move_holder(storage: &mut dyn Storage, old: Uint128, new: Uint128) -> StdResult<()> {
+    let holder_decade = |balance: Uint128| (!balance.is_zero()).then(|| decade_of(balance));
+    let old_decade = holder_decade(old);
+    let new_decade = holder_decade(new);
+
+    if let Some(decade) = old_decade {
+        HOLDER_BUCKET_BALANCES.update(storage, decade, |sum| -> StdResult<_> {
+            Ok(sum.unwrap_or_default().checked_sub(old)?)
+        })?;
+    }
+    if let Some(decade) = new_decade {
+        HOLDER_BUCKET_BALANCES.update(storage, decade, |sum| -> StdResult<_> {
+            Ok(sum.unwrap_or_default().checked_add(new)?)
+        })?;
+    }
+    if old_decade == new_decade {
+        return Ok(());
+    }
+
+    if let Some(decade) = old_decade {
+        HOLDER_BUCKETS.update(storage, decade, |count| -> StdResult<_> {
+            count.unwrap_or_default().checked_sub(1).ok_or_else(|| {
+                StdError::generic_err(format!("No holder counted in decade {}", decade))
+            })
+        })?;
+    }
+    if let Some(decade) = new_decade {
+        HOLDER_BUCKETS.update(storage, decade, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + 1)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// False for a holder the backfill has not reached yet, its balance is counted when it is
+fn is_counted(storage: &dyn Storage, address: &Addr) -> StdResult<bool> {
+    Ok(match DISTRIBUTION_BACKFILL.may_load(storage)? {
+        Some(last) => last.is_some_and(|last| *address <= last),
+        None => true,
+    })
+}
+
+/// Called on migration: a token which did not keep the distribution counters since its
+/// instantiation resets them and starts their backfill, returns whether it did
+pub fn start_distribution_backfill(storage: &mut dyn Storage) -> StdResult<bool> {
+    if DISTRIBUTION_TRACKED_SINCE.may_load(storage)?.is_some()
+        || DISTRIBUTION_BACKFILL.may_load(storage)?.is_some()
+    {
+        return Ok(false);
+    }
+    let decades = HOLDER_BUCKETS
+        .keys(storage, None, None, Order::Ascending)
+        .chain(HOLDER_BUCKET_BALANCES.keys(storage, None, None, Order::Ascending))
+        .collect::<StdResult<Vec<u8>>>()?;
+    for decade in decades {
+        HOLDER_BUCKETS.remove(storage, decade);
+        HOLDER_BUCKET_BALANCES.remove(storage, decade);
+    }
+    TOP_HOLDERS.remove(storage);
+    DISTRIBUTION_BACKFILL.save(storage, &None)?;
+
+    Ok(true)
+}
+
+/// Counts the next `limit` balances into the distribution counters. Once every balance is
+/// counted, the counters are tracked from the current block on
+pub fn backfill_distribution(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let last = DISTRIBUTION_BACKFILL
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("The holder distribution is not being backfilled"))?;
+    let limit = limit
+        .unwrap_or(DEFAULT_BACKFILL_LIMIT)
+        .min(MAX_BACKFILL_LIMIT) as usize;
+    let balances = BALANCES
+        .range(
+            deps.storage,
+            last.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
+    for (address, balance) in &balances {
+        move_holder(deps.storage, Uint128::zero(), *balance)?;
+        update_top_holders(deps.storage, address, *balance)?;
+    }
+
+    let complete = balances.len() < limit;
+    if complete {
+        DISTRIBUTION_BACKFILL.remove(deps.storage);
+        DISTRIBUTION_TRACKED_SINCE.save(deps.storage, &env.block.height)?;
+    } else {
+        let last = balances.last().map(|(address, _)| address.clone());
+        DISTRIBUTION_BACKFILL.save(deps.storage, &last)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "backfill_distribution")
+        .add_attribute("counted", balances.len().to_string())
+        .add_attribute("complete", complete.to_string()))
+}
+
+/// Keeps `TOP_HOLDERS` up to date with the new balance of `address`. An address only enters the
+/// list when its own balance changes, so a member whose balance drops can stay listed until an
+/// outsider with a larger balance moves. The list is therefore an approximation
+pub fn record_top_holder(storage: &mut dyn Storage, address: &Addr, new: Uint128) -> StdResult<()> {
+    if !is_counted(storage, address)? {
+        return Ok(());
+    }
+    update_top_holders(storage, address, new)
+}
+
+fn update_top_holders(storage: &mut dyn Storage, address: &Addr, new: Uint128) -> StdResult<()> {
+    // the addresses excluded from reflections, e.g. the token contract, the treasury or a pair,
+    // are left out of the eligible supply and of the ranking alike
+    let new = if REFLECTION_EXCLUDED.has(storage, address) {
+        Uint128::zero()
+    } else {
+        new
+    };
+    let mut top_holders = TOP_HOLDERS.may_load(storage)?.unwrap_or_default();
+    let position = top_holders.iter().position(|(holder, _)| holder == address);
+
+    match position {
+        Some(index) if new.is_zero() => {
+            top_holders.remove(index);
+        }
+        Some(index) => top_holders[index].1 = new,
+        None if new.is_zero() => return Ok(()),
+        None => {
+            let smallest = top_holders.last().map(|(_, balance)| *balance);
+            if top_holders.len() >= TOP_HOLDERS_LEN && smallest.is_some_and(|s| new <= s) {
+                return Ok(());
+            }
+            top_holders.push((address.clone(), new));
+        }
+    }
+    top_holders.sort_by_key(|(_, balance)| Reverse(*balance));
+    top_holders.truncate(TOP_HOLDERS_LEN);
+
+    TOP_HOLDERS.save(storage, &top_holders)
+}
+
+/// Moves `address` in or out of `TOP_HOLDERS` when it is excluded from or included in
+/// reflections, called once `REFLECTION_EXCLUDED` is updated
+pub fn rank_top_holder(storage: &mut dyn Storage, address: &Addr) -> StdResult<()> {
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    record_top_holder(storage, address, balance)
+}
+
+/// Returns the share of the eligible supply held by the largest holders together with an
+/// approximate Gini coefficient. The Gini coefficient is computed over the decade buckets
+/// assuming equal balances within a bucket, which makes it a lower bound of the exact value
+pub fn query_concentration(storage: &dyn Storage) -> StdResult<ConcentrationResponse> {
+    let supply = eligible_supply(storage)?;
+    let top_holders = TOP_HOLDERS.may_load(storage)?.unwrap_or_default();
+    let top_balance: Uint128 = top_holders.iter().map(|(_, balance)| *balance).sum();
+    let top_holders_share = if supply.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(top_balance, supply)
+    };
+
+    let decades = HOLDER_BUCKETS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(u8, u64)>>>()?;
+    let mut groups: Vec<(u64, Uint128)> = vec![];
+    for (decade, holders) in decades {
+        let balance = HOLDER_BUCKET_BALANCES
+            .may_load(storage, decade)?
+            .unwrap_or_default();
+        if holders > 0 {
+            groups.push((holders, balance));
+        }
+    }
+    let total_holders: u64 = groups.iter().map(|(holders, _)| holders).sum();
+    let total_balance: Uint128 = groups.iter().map(|(_, balance)| *balance).sum();
+
+    // G = 1 - sum(population_share * (previous_cumulative_wealth + cumulative_wealth))
+    let mut area = Decimal::zero();
+    let mut cumulative_balance = Uint128::zero();
+    if total_holders > 0 && !total_balance.is_zero() {
+        for (holders, balance) in groups {
+            let previous = Decimal::from_ratio(cumulative_balance, total_balance);
+            cumulative_balance += balance;
+            let current = Decimal::from_ratio(cumulative_balance, total_balance);
+            area += Decimal::from_ratio(holders, total_holders) * (previous + current);
+        }
+    }
+    let gini = if total_holders > 0 {
+        Decimal::one().saturating_sub(area)
+    } else {
+        Decimal::zero()
+    };
+
+    Ok(ConcentrationResponse {
+        top_holders: top_holders
+            .into_iter()
+            .map(|(address, amount)| Cw20Coin {
+                address: address.to_string(),
+                amount,
+            })
+            .collect(),
+        top_holders_share,
+        gini,
+        tracked_since: DISTRIBUTION_TRACKED_SINCE.may_load(storage)?,
+    })
+}
+
+/// Aggregates the decade counters into the requested buckets. `buckets` are the ascending lower
+/// bounds of each bucket and must be powers of ten, since holders are only tracked per decade.
+/// Holders below the lowest bound fall in a first bucket starting at 1, so the buckets always add
+/// up to `total_holders`. When omitted, every decade between the smallest and largest holder is
+/// returned
+pub fn query_holder_distribution(
+    storage: &dyn Storage,
+    buckets: Option<Vec<Uint128>>,
+) -> StdResult<HolderDistributionResponse> {
+    let decades = HOLDER_BUCKETS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, 0))))
+        .collect::<StdResult<Vec<(u8, u64)>>>()?;
+    let total_holders = decades.iter().map(|(_, count)| count).sum();
+
+    let bounds: Vec<u8> = match buckets {
+        Some(bounds) => {
+            let mut result: Vec<u8> = vec![];
+            for bound in bounds {
+                let decade = decade_of(bound);
+                if bound.is_zero() || decade_floor(decade) != bound {
+                    return Err(StdError::generic_err("bucket bounds must be powers of ten"));
+                }
+                if result.last().is_some_and(|last| *last >= decade) {
+                    return Err(StdError::generic_err(
+                        "bucket bounds must be strictly ascending",
+                    ));
+                }
+                result.push(decade);
+            }
+            if result.first().is_some_and(|lowest| *lowest > 0) {
+                result.insert(0, 0);
+            }
+            result
+        }
+        None => match (decades.first(), decades.last()) {
+            (Some((lowest, _)), Some((highest, _))) => (*lowest..=*highest).collect(),
+            _ => vec![],
+        },
+    };
+
+    let buckets = bounds
+        .iter()
+        .enumerate()
+        .map(|(index, lower)| {
+            let upper = bounds.get(index + 1).copied();
+            let holders = decades
+                .iter()
+                .filter(|(decade, _)| decade >= lower && upper.is_none_or(|u| *decade < u))
+                .map(|(_, count)| count)
+                .sum();
+            HolderBucket {
+                min: decade_floor(*lower),
+                max: upper.map(decade_floor),
+                holders,
+            }
+        })
+        .collect();
+
+    Ok(HolderDistributionResponse {
+        total_holders,
+        buckets,
+        tracked_since: DISTRIBUTION_TRACKED_SINCE.may_load(storage)?,
+    })
+}
@@ -0,0 +1,340 @@
+use std::convert::TryFrom;
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Decimal256, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::auto_liquidity::credit_auto_liquidity;
+use crate::contract::{ensure_admin, TREASURY};
+use crate::insurance::credit_insurance;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::{ExecuteMsg, QueryTaxResponse, ReflectionDripResponse};
+use crate::reflections::{credit_reflections, is_claim_mode, spread_reflections};
+use crate::state::{
+    DripState, DRIP_ENDS, DRIP_PERIOD, DRIP_STATE, REFLECTION_DRIP_ENDS, REFLECTION_DRIP_STATE,
+    TAX_CONFIG,
+};
+use crate::tax_config::credit_split_tax;
+
+/// Credits collected taxes to the treasury, except for the insurance and auto-liquidity shares and,
+/// in claim mode, the reflection share which accrues to holders. Under a tax config they are split
+/// as it says instead. Returns the addresses credited with their amount
+pub fn credit_tax(
+    storage: &mut dyn Storage,
+    env: &Env,
+    treasury: &Addr,
+    taxes: &QueryTaxResponse,
+) -> StdResult<Vec<(Addr, Uint128)>> {
+    if let Some(tax_config) = TAX_CONFIG.may_load(storage)? {
+        return credit_split_tax(storage, env, treasury, &tax_config, taxes);
+    }
+    let claim_mode = is_claim_mode(storage)?;
+    let mut amount = taxes.taxed_amount;
+    if claim_mode {
+        amount = amount.checked_sub(taxes.reflection_amount)?;
+    }
+    amount -= credit_insurance(storage, env, taxes.taxed_amount, amount)?;
+    amount -= credit_auto_liquidity(storage, env, taxes.taxed_amount, amount)?;
+
+    // the treasury share is credited first so that the whole eligible supply is in balances
+    credit_treasury(storage, env, treasury, amount)?;
+    if claim_mode {
+        credit_claim_reflections(storage, env, taxes.reflection_amount)?;
+    }
+    Ok(vec![(treasury.clone(), amount)])
+}
+
+/// `TransferEvent` messages logging the taxes of `from` credited to each address, so that
+/// explorers track them as transfers
+pub fn tax_transfer_events(
+    env: &Env,
+    from: &str,
+    credits: Vec<(Addr, Uint128)>,
+) -> StdResult<Vec<WasmMsg>> {
+    credits
+        .into_iter()
+        .map(|(to, amount)| {
+            Ok(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_json_binary(&ExecuteMsg::TransferEvent {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            })
+        })
+        .collect()
+}
+
+/// Taxes parked on the token contract balance and released linearly, every credit over the drip
+/// period from its own collection. The release rate drops as the credits end, so a new credit
+/// never delays the ones still dripping
+struct Drip<'a> {
+    state: Item<'a, DripState>,
+    ends: Map<'a, u64, Decimal256>,
+}
+
+const TREASURY_DRIP: Drip = Drip {
+    state: DRIP_STATE,
+    ends: DRIP_ENDS,
+};
+
+const REFLECTION_DRIP: Drip = Drip {
+    state: REFLECTION_DRIP_STATE,
+    ends: REFLECTION_DRIP_ENDS,
+};
+
+impl<'a> Drip<'a> {
+    /// State accrued up to `now` with the credits that ended by then
+    fn accrue(&self, storage: &dyn Storage, now: u64) -> StdResult<(DripState, Vec<u64>)> {
+        let mut state = self.state.may_load(storage)?.unwrap_or_default();
+        if now <= state.last_update {
+            return Ok((state, vec![]));
+        }
+        let ended = self
+            .ends
+            .range(
+                storage,
+                Some(Bound::exclusive(state.last_update)),
+                Some(Bound::inclusive(now)),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut ends = Vec::with_capacity(ended.len());
+        for (end, rate) in ended {
+            state.accrued += state.rate * seconds(end - state.last_update);
+            state.rate = state.rate.saturating_sub(rate);
+            state.last_update = end;
+            ends.push(end);
+        }
+        state.accrued += state.rate * seconds(now - state.last_update);
+        state.last_update = now;
+        if now >= state.end {
+            // what the rates could not represent goes with the last credit
+            state.rate = Decimal256::zero();
+            state.accrued = Decimal256::from_ratio(state.remaining, 1u8);
+        }
+        Ok((state, ends))
+    }
+
+    /// Amount released by `release` at `now`
+    fn releasable(&self, storage: &dyn Storage, now: u64) -> StdResult<Uint128> {
+        let (state, _) = self.accrue(storage, now)?;
+        Ok(whole(&state))
+    }
+
+    /// Takes what has dripped until `now` off the pending taxes and returns it
+    fn release(&self, storage: &mut dyn Storage, now: u64) -> StdResult<Uint128> {
+        if self.state.may_load(storage)?.is_none() {
+            return Ok(Uint128::zero());
+        }
+        let (mut state, ended) = self.accrue(storage, now)?;
+        for end in ended {
+            self.ends.remove(storage, end);
+        }
+        let amount = whole(&state);
+        state.accrued = state
+            .accrued
+            .saturating_sub(Decimal256::from_ratio(amount, 1u8));
+        state.remaining -= amount;
+        self.state.save(storage, &state)?;
+        Ok(amount)
+    }
+
+    /// Parks `amount` to be released over `period` seconds from `now`
+    fn park(
+        &self,
+        storage: &mut dyn Storage,
+        now: u64,
+        period: u64,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let (mut state, ended) = self.accrue(storage, now)?;
+        for end in ended {
+            self.ends.remove(storage, end);
+        }
+        let end = now + period;
+        let rate = Decimal256::from_ratio(amount, period);
+        state.remaining = state.remaining.checked_add(amount)?;
+        state.rate += rate;
+        state.end = state.end.max(end);
+        self.ends.update(storage, end, |ending| -> StdResult<_> {
+            Ok(ending.unwrap_or_default() + rate)
+        })?;
+        self.state.save(storage, &state)
+    }
+
+    /// Makes everything pending releasable at `now`
+    fn finish(&self, storage: &mut dyn Storage, now: u64) -> StdResult<()> {
+        let mut state = match self.state.may_load(storage)? {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        let ends = self
+            .ends
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for end in ends {
+            self.ends.remove(storage, end);
+        }
+        state.rate = Decimal256::zero();
+        state.accrued = Decimal256::from_ratio(state.remaining, 1u8);
+        state.last_update = now;
+        state.end = now;
+        self.state.save(storage, &state)
+    }
+
+    /// Takes everything pending off the drip and returns it
+    fn drain(&self, storage: &mut dyn Storage, now: u64) -> StdResult<Uint128> {
+        let pending = self.pending(storage)?;
+        self.finish(storage, now)?;
+        if let Some(mut state) = self.state.may_load(storage)? {
+            state.remaining = Uint128::zero();
+            state.accrued = Decimal256::zero();
+            self.state.save(storage, &state)?;
+        }
+        Ok(pending)
+    }
+
+    fn pending(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        Ok(self.state.may_load(storage)?.unwrap_or_default().remaining)
+    }
+}
+
+fn seconds(seconds: u64) -> Decimal256 {
+    Decimal256::from_ratio(seconds, 1u8)
+}
+
+/// Whole tokens accrued, never more than what is pending
+fn whole(state: &DripState) -> Uint128 {
+    Uint128::try_from(state.accrued.to_uint_floor())
+        .unwrap_or(Uint128::MAX)
+        .min(state.remaining)
+}
+
+/// With a drip period configured, taxes are parked on the token contract balance instead and
+/// released linearly to the treasury, see `Drip`
+pub fn credit_treasury(
+    storage: &mut dyn Storage,
+    env: &Env,
+    treasury: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let period = DRIP_PERIOD.may_load(storage)?.unwrap_or_default();
+    if period == 0 {
+        increase_balance(storage, treasury, amount)?;
+        return Ok(());
+    }
+
+    increase_balance(storage, &env.contract.address, amount)?;
+    TREASURY_DRIP.park(storage, env.block.time.seconds(), period, amount)
+}
+
+/// Reflection share of the taxes in claim mode. With a drip period configured it is parked on the
+/// token contract balance and spread to holders linearly, so that buying right before a large
+/// taxed transfer and selling right after only earns a sliver of its reflections
+pub fn credit_claim_reflections(
+    storage: &mut dyn Storage,
+    env: &Env,
+    amount: Uint128,
+) -> StdResult<()> {
+    let period = DRIP_PERIOD.may_load(storage)?.unwrap_or_default();
+    if period == 0 || amount.is_zero() {
+        return credit_reflections(storage, env, amount);
+    }
+
+    increase_balance(storage, &env.contract.address, amount)?;
+    REFLECTION_DRIP.park(storage, env.block.time.seconds(), period, amount)
+}
+
+/// Moves the pending taxes that have dripped since the last release from the token contract to the
+/// treasury, and spreads the dripped reflections over holders. Called on the first execution of
+/// every block, later calls within the same block release nothing
+pub fn release_drip(storage: &mut dyn Storage, env: &Env) -> StdResult<Uint128> {
+    let now = env.block.time.seconds();
+    let amount = TREASURY_DRIP.release(storage, now)?;
+    if !amount.is_zero() {
+        let treasury = Addr::unchecked(TREASURY.load(storage)?);
+        decrease_balance(storage, &env.contract.address, amount)?;
+        increase_balance(storage, &treasury, amount)?;
+    }
+    let reflections = REFLECTION_DRIP.release(storage, now)?;
+    spread_reflections(storage, env, reflections)?;
+
+    Ok(amount + reflections)
+}
+
+/// Takes the taxes still dripping off both drips, for the emergency drain. Returns the taxes that
+/// were dripping to the treasury and the reflections that were dripping to holders
+pub fn drain_drips(storage: &mut dyn Storage, env: &Env) -> StdResult<(Uint128, Uint128)> {
+    let now = env.block.time.seconds();
+    Ok((
+        TREASURY_DRIP.drain(storage, now)?,
+        REFLECTION_DRIP.drain(storage, now)?,
+    ))
+}
+
+/// Taxes held by the token contract for both drips
+pub fn drip_pending(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(TREASURY_DRIP.pending(storage)? + REFLECTION_DRIP.pending(storage)?)
+}
+
+/// Sets the number of seconds over which collected taxes are dripped to the treasury and
+/// reflections to holders, 0 credits them instantly. Whatever is pending is released right away
+/// when the drip is disabled
+pub fn set_reflection_drip(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    period: u64,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if period > 30 * 86_400 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "drip period must be at most 30 days",
+        )));
+    }
+
+    save_drip_period(deps.storage, &env, period)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_reflection_drip")
+        .add_attribute("period", period.to_string()))
+}
+
+/// Saves the drip period, disabling it releases what is still dripping at once
+pub fn save_drip_period(storage: &mut dyn Storage, env: &Env, period: u64) -> StdResult<()> {
+    if period == 0 {
+        let now = env.block.time.seconds();
+        TREASURY_DRIP.finish(storage, now)?;
+        REFLECTION_DRIP.finish(storage, now)?;
+        release_drip(storage, env)?;
+    }
+    DRIP_PERIOD.save(storage, &period)
+}
+
+pub fn query_reflection_drip(
+    storage: &dyn Storage,
+    env: &Env,
+) -> StdResult<ReflectionDripResponse> {
+    let now = env.block.time.seconds();
+    let end = [&TREASURY_DRIP, &REFLECTION_DRIP]
+        .iter()
+        .map(|drip| Ok(drip.state.may_load(storage)?.unwrap_or_default().end))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ReflectionDripResponse {
+        period: DRIP_PERIOD.may_load(storage)?.unwrap_or_default(),
+        pending: drip_pending(storage)?,
+        releasable: TREASURY_DRIP.releasable(storage, now)?
+            + REFLECTION_DRIP.releasable(storage, now)?,
+        end: end.into_iter().max().unwrap_or_default(),
+    })
+}
@@ -0,0 +1,102 @@
+use cosmwasm_std::{Deps, Env, Order, StdResult, Uint128};
+use cw20_base::allowances::query_allowance;
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+
+use crate::contract::{MAX_TRANSFER_SUPPLY_RATE, WHITELIST};
+use crate::msg::{EffectiveBalanceResponse, SpendLimit, SpendableViaResponse};
+use crate::reflections::query_pending_reflections;
+use crate::security::is_frozen;
+use crate::state::{QUARANTINED_SENDS, WALLET_LIMITS};
+use crate::vesting::locked_balance;
+use crate::wallet_limits::is_limits_exempt;
+
+/// Everything `address` owns on the token in one place: what its balance holds, what accrued to it
+/// but is held elsewhere, and what of its balance it can move right now
+pub fn query_effective_balance(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<EffectiveBalanceResponse> {
+    let pending_reflections = query_pending_reflections(deps, address.clone())?.pending;
+    let address = deps.api.addr_validate(&address)?;
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let quarantined = QUARANTINED_SENDS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, send)| send.amount))
+        .sum::<StdResult<Uint128>>()?;
+    let locked = locked_balance(deps.storage, &env, &address)?;
+    let frozen = if is_frozen(deps.storage, &address) {
+        balance.saturating_sub(locked)
+    } else {
+        Uint128::zero()
+    };
+
+    Ok(EffectiveBalanceResponse {
+        balance,
+        pending_reflections,
+        quarantined,
+        locked,
+        frozen,
+        spendable: balance.saturating_sub(locked + frozen),
+        total: balance + pending_reflections + quarantined,
+    })
+}
+
+/// Most `spender` can move out of `owner` with one `TransferFrom` or `SendFrom` right now, and the
+/// first limit reached: the spendable balance, the allowance, the anti-whale limit of the spender
+/// or the max transaction amount. Limits depending on the recipient (exemptions, the max wallet
+/// amount, the trading gate and spending policies) are not known yet and left out
+pub fn query_spendable_via(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    spender: String,
+) -> StdResult<SpendableViaResponse> {
+    let spendable = query_effective_balance(deps, env.clone(), owner.clone())?.spendable;
+    let allowance = query_allowance(deps, owner.clone(), spender.clone())?;
+    let allowance = if allowance.expires.is_expired(&env.block) {
+        Uint128::zero()
+    } else {
+        allowance.allowance
+    };
+
+    let mut limits = vec![
+        (SpendLimit::Balance, spendable),
+        (SpendLimit::Allowance, allowance),
+    ];
+    if !WHITELIST
+        .may_load(deps.storage, spender)?
+        .unwrap_or_default()
+    {
+        // transfers of at least the share of the supply are refused
+        let total_supply = TOKEN_INFO.load(deps.storage)?.total_supply;
+        let rate = MAX_TRANSFER_SUPPLY_RATE.load(deps.storage)?;
+        limits.push((
+            SpendLimit::AntiWhale,
+            (total_supply * rate).saturating_sub(Uint128::one()),
+        ));
+    }
+    if let Some(max_tx_amount) = WALLET_LIMITS
+        .may_load(deps.storage)?
+        .and_then(|limits| limits.max_tx_amount)
+    {
+        if !is_limits_exempt(deps.storage, &env, &owner)? {
+            limits.push((SpendLimit::MaxTxAmount, max_tx_amount));
+        }
+    }
+
+    // the first of the smallest limits is reported
+    let (limiting_factor, amount) = limits
+        .into_iter()
+        .reduce(|min, limit| if limit.1 < min.1 { limit } else { min })
+        .unwrap_or((SpendLimit::Balance, spendable));
+    Ok(SpendableViaResponse {
+        spendable: amount,
+        limiting_factor,
+        balance: spendable,
+        allowance,
+    })
+}
@@ -0,0 +1,83 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, Event, Response, StdError, StdResult, Uint128};
+use cw20_base::ContractError;
+
+use crate::drip::drain_drips;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::RescueAddressResponse;
+use crate::reflections::is_claim_mode;
+use crate::state::{AUTO_LIQUIDITY_STATE, INSURANCE_FUND, REFLECTION_INDEX, RESCUE_ADDRESS};
+
+pub fn set_rescue_address(deps: DepsMut, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    RESCUE_ADDRESS.save(deps.storage, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_rescue_address")
+        .add_attribute("address", address))
+}
+
+/// Sweeps the fee buckets held by the token contract to the rescue address: taxes still dripping
+/// to the treasury, the insurance fund not owed to queued withdrawals, the tax set aside for
+/// auto-liquidity and, in claim mode, the reflections still dripping or too small to be spread.
+/// Holder balances and the reflections accrued to holders are never touched
+pub fn emergency_drain(deps: DepsMut, env: Env, reason: String) -> Result<Response, ContractError> {
+    let rescue: Addr = RESCUE_ADDRESS
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("no rescue address set"))?;
+    if reason.trim().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "emergency drains require a reason",
+        )));
+    }
+
+    let mut buckets = vec![];
+    let (dripping, dripping_reflections) = drain_drips(deps.storage, &env)?;
+    buckets.push(("drip", dripping));
+    if let Some(mut fund) = INSURANCE_FUND.may_load(deps.storage)? {
+        buckets.push(("insurance", fund.balance - fund.queued));
+        fund.balance = fund.queued;
+        INSURANCE_FUND.save(deps.storage, &fund)?;
+    }
+    if let Some(mut state) = AUTO_LIQUIDITY_STATE.may_load(deps.storage)? {
+        buckets.push(("auto_liquidity", state.accumulated));
+        state.accumulated = Uint128::zero();
+        AUTO_LIQUIDITY_STATE.save(deps.storage, &state)?;
+    }
+    if is_claim_mode(deps.storage)? {
+        let mut index = REFLECTION_INDEX.may_load(deps.storage)?.unwrap_or_default();
+        buckets.push(("reflection_drip", dripping_reflections));
+        buckets.push(("undistributed_reflections", index.undistributed));
+        index.undistributed = Uint128::zero();
+        REFLECTION_INDEX.save(deps.storage, &index)?;
+    }
+
+    let total: Uint128 = buckets.iter().map(|(_, amount)| *amount).sum();
+    if !total.is_zero() {
+        decrease_balance(deps.storage, &env.contract.address, total)?;
+        increase_balance(deps.storage, &rescue, total)?;
+    }
+
+    let events = buckets.iter().map(|(bucket, amount)| {
+        Event::new("emergency_drain_bucket")
+            .add_attribute("bucket", *bucket)
+            .add_attribute("amount", *amount)
+    });
+    Ok(Response::new()
+        .add_events(events)
+        .add_event(
+            Event::new("emergency_drain")
+                .add_attribute("rescue_address", rescue)
+                .add_attribute("reason", reason)
+                .add_attribute("total", total)
+                .add_attribute("height", env.block.height.to_string()),
+        )
+        .add_attribute("action", "emergency_drain"))
+}
+
+pub fn query_rescue_address(deps: Deps) -> StdResult<RescueAddressResponse> {
+    Ok(RescueAddressResponse {
+        address: RESCUE_ADDRESS
+            .may_load(deps.storage)?
+            .map(|address| address.to_string()),
+    })
+}
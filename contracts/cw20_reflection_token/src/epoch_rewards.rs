@@ -0,0 +1,207 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::{EpochRewardClaimResponse, EpochRewardsResponse};
+use crate::snapshots::balance_at_snapshot;
+use crate::state::{EpochReward, EPOCH_REWARDS, EPOCH_REWARD_CLAIMS, SNAPSHOTS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Moves `amount` from the admin balance to a reward pool for the holders of snapshot `epoch`,
+/// claimable for `claim_period` seconds
+pub fn fund_epoch_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    epoch: u64,
+    amount: Uint128,
+    claim_period: u64,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if claim_period == 0 {
+        return Err(ContractError::Std(StdError::generic_err(
+            "claim_period must be above 0",
+        )));
+    }
+    if !SNAPSHOTS.has(deps.storage, epoch) {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Snapshot {} does not exist or was pruned",
+            epoch
+        ))));
+    }
+    if EPOCH_REWARDS.has(deps.storage, epoch) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Epoch reward already funded",
+        )));
+    }
+
+    decrease_balance(deps.storage, &info.sender, amount)?;
+    increase_balance(deps.storage, &env.contract.address, amount)?;
+    let reward = EpochReward {
+        epoch,
+        amount,
+        claimed: Uint128::zero(),
+        expires_at: env.block.time.seconds() + claim_period,
+        swept: false,
+    };
+    EPOCH_REWARDS.save(deps.storage, epoch, &reward)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_epoch_reward")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("expires_at", reward.expires_at.to_string()))
+}
+
+/// Share of the reward of `epoch` owed to `address` and not claimed yet, pro-rata to its balance
+/// in the snapshot. The shares of addresses which never claim (pairs, the treasury) are swept
+/// with the rest once the reward expires
+fn claimable(
+    storage: &dyn Storage,
+    reward: &EpochReward,
+    address: &Addr,
+) -> StdResult<(Uint128, Uint128)> {
+    let snapshot = SNAPSHOTS.load(storage, reward.epoch)?;
+    let balance = balance_at_snapshot(storage, address, reward.epoch)?;
+    let claimed = EPOCH_REWARD_CLAIMS
+        .may_load(storage, (reward.epoch, address))?
+        .unwrap_or_default();
+    let share = if snapshot.total_supply.is_zero() {
+        Uint128::zero()
+    } else {
+        reward.amount.multiply_ratio(balance, snapshot.total_supply)
+    };
+
+    Ok((claimed, share.saturating_sub(claimed)))
+}
+
+pub fn execute_claim_epoch_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    epoch: u64,
+) -> Result<Response, ContractError> {
+    let mut reward = EPOCH_REWARDS
+        .may_load(deps.storage, epoch)?
+        .ok_or_else(|| StdError::generic_err("No reward for this epoch"))?;
+    if env.block.time.seconds() >= reward.expires_at {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Epoch reward expired",
+        )));
+    }
+    let (claimed, amount) = claimable(deps.storage, &reward, &info.sender)?;
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Nothing to claim",
+        )));
+    }
+
+    // the shares add up to at most the funded amount
+    reward.claimed += amount;
+    EPOCH_REWARDS.save(deps.storage, epoch, &reward)?;
+    EPOCH_REWARD_CLAIMS.save(deps.storage, (epoch, &info.sender), &(claimed + amount))?;
+    decrease_balance(deps.storage, &env.contract.address, amount)?;
+    increase_balance(deps.storage, &info.sender, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_epoch_reward")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("address", info.sender)
+        .add_attribute("amount", amount))
+}
+
+/// Gives what is left of an expired reward back to the admin, the snapshot of the epoch can be
+/// pruned again afterwards
+pub fn sweep_epoch_reward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    epoch: u64,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let mut reward = EPOCH_REWARDS
+        .may_load(deps.storage, epoch)?
+        .ok_or_else(|| StdError::generic_err("No reward for this epoch"))?;
+    if env.block.time.seconds() < reward.expires_at {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Epoch reward claimable until {}",
+            reward.expires_at
+        ))));
+    }
+    if reward.swept {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Epoch reward already swept",
+        )));
+    }
+
+    let unclaimed = reward.amount - reward.claimed;
+    reward.swept = true;
+    EPOCH_REWARDS.save(deps.storage, epoch, &reward)?;
+    if !unclaimed.is_zero() {
+        decrease_balance(deps.storage, &env.contract.address, unclaimed)?;
+        increase_balance(deps.storage, &info.sender, unclaimed)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "sweep_epoch_reward")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("amount", unclaimed))
+}
+
+/// True while the reward of `epoch` can still be claimed or swept, its snapshot must be kept
+pub fn is_rewarded(storage: &dyn Storage, epoch: u64) -> StdResult<bool> {
+    Ok(EPOCH_REWARDS
+        .may_load(storage, epoch)?
+        .is_some_and(|reward| !reward.swept))
+}
+
+pub fn query_epoch_rewards(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EpochRewardsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let rewards = EPOCH_REWARDS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, reward)| reward))
+        .collect::<StdResult<_>>()?;
+
+    Ok(EpochRewardsResponse { rewards })
+}
+
+pub fn query_epoch_reward_claim(
+    deps: Deps,
+    env: Env,
+    epoch: u64,
+    address: String,
+) -> StdResult<EpochRewardClaimResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let reward = EPOCH_REWARDS
+        .may_load(deps.storage, epoch)?
+        .ok_or_else(|| StdError::generic_err("No reward for this epoch"))?;
+    let (claimed, claimable) = claimable(deps.storage, &reward, &address)?;
+
+    Ok(EpochRewardClaimResponse {
+        claimed,
+        claimable: if env.block.time.seconds() < reward.expires_at {
+            claimable
+        } else {
+            Uint128::zero()
+        },
+    })
+}
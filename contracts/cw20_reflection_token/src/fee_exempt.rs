@@ -0,0 +1,125 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, MessageInfo, Order, Response, StdError, StdResult, Storage,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+use dojoswap::taxed::hook_msg_hash;
+
+use crate::bridge::is_bridge;
+use crate::msg::{FeeExemptionsResponse, Role, TaxFreeHooksResponse};
+use crate::roles::ensure_role;
+use crate::state::{FEE_EXEMPT, TAX_FREE_HOOKS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Exempts `address` from the tax on what it sends and receives, e.g. a pair or a router whose
+/// reserves must match what it was sent. Unlike the whitelist it keeps the anti-whale limit
+pub fn set_fee_exempt(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::ExemptionManager)?;
+    let address = deps.api.addr_validate(&address)?;
+    if exempt {
+        FEE_EXEMPT.save(deps.storage, &address, &Empty {})?;
+    } else {
+        FEE_EXEMPT.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_exempt")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+/// True when transfers from or to `address` are not taxed, the bridge included
+pub fn is_fee_exempt(storage: &dyn Storage, address: &str) -> bool {
+    FEE_EXEMPT.has(storage, &Addr::unchecked(address)) || is_bridge(storage, address)
+}
+
+pub fn query_fee_exemptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeExemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let addresses = FEE_EXEMPT
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| address.map(|address| address.to_string()))
+        .collect::<StdResult<_>>()?;
+
+    Ok(FeeExemptionsResponse { addresses })
+}
+
+/// Makes a `Send` to `contract` with the hook message hashed to `msg_hash` (see
+/// `dojoswap::taxed::hook_msg_hash`) tax free, e.g. providing liquidity to the official pair.
+/// Other sends to the contract, swaps included, stay taxed
+pub fn set_tax_free_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+    msg_hash: String,
+    enable: bool,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::ExemptionManager)?;
+    let contract = deps.api.addr_validate(&contract)?;
+    if msg_hash.len() != 64
+        || !msg_hash
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        return Err(ContractError::Std(StdError::generic_err(
+            "msg_hash must be a lowercase hex sha256",
+        )));
+    }
+    if enable {
+        TAX_FREE_HOOKS.save(deps.storage, (&contract, &msg_hash), &Empty {})?;
+    } else {
+        TAX_FREE_HOOKS.remove(deps.storage, (&contract, &msg_hash));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_tax_free_hook")
+        .add_attribute("contract", contract)
+        .add_attribute("msg_hash", msg_hash)
+        .add_attribute("enable", enable.to_string()))
+}
+
+/// True when a `Send` of `msg` to `contract` is not taxed
+pub fn is_tax_free_hook(storage: &dyn Storage, contract: &str, msg: &[u8]) -> bool {
+    TAX_FREE_HOOKS.has(storage, (&Addr::unchecked(contract), &hook_msg_hash(msg)))
+}
+
+pub fn query_tax_free_hooks(
+    deps: Deps,
+    contract: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TaxFreeHooksResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let contract = deps.api.addr_validate(&contract)?;
+    let msg_hashes = TAX_FREE_HOOKS
+        .prefix(&contract)
+        .keys(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(TaxFreeHooksResponse { msg_hashes })
+}
@@ -0,0 +1,152 @@
+use cosmwasm_std::{Decimal, Deps, Event, Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::msg::{QueryTaxResponse, ReflectionAprResponse, ReflectionMode, TaxStatsResponse};
+use crate::reflections::eligible_supply;
+use crate::state::{REFLECTION_FLOW, REFLECTION_MODE, TAX_STATS};
+
+pub const DAY: u64 = 86_400;
+pub const YEAR: u64 = 365 * DAY;
+/// Number of days of fee flow kept in `REFLECTION_FLOW`
+pub const FEE_FLOW_DAYS: u64 = 30;
+pub const DEFAULT_APR_WINDOW: u64 = 7 * DAY;
+
+/// Records the reflection share of a collected tax into the bucket of the current day,
+/// dropping buckets that fell out of the retention period
+pub fn record_reflection(
+    storage: &mut dyn Storage,
+    time: Timestamp,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let today = time.seconds() / DAY;
+    REFLECTION_FLOW.update(storage, today, |flow| -> StdResult<_> {
+        Ok(flow.unwrap_or_default().checked_add(amount)?)
+    })?;
+
+    let expired = REFLECTION_FLOW
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive(today.saturating_sub(FEE_FLOW_DAYS - 1))),
+            Order::Ascending,
+        )
+        .take(10)
+        .collect::<StdResult<Vec<u64>>>()?;
+    for day in expired {
+        REFLECTION_FLOW.remove(storage, day);
+    }
+
+    Ok(())
+}
+
+/// Adds the tax of a transfer of `amount` from `from` to `to` to the `TaxStats` and returns its
+/// `tax` event, so that indexers see what was taxed, burnt and reflected without replaying the
+/// tax rates. The amounts are zero when the transfer is exempt
+pub fn record_tax(
+    storage: &mut dyn Storage,
+    time: Timestamp,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+    taxes: &QueryTaxResponse,
+    exempt: bool,
+) -> StdResult<Event> {
+    let taxes = if exempt {
+        QueryTaxResponse::default()
+    } else {
+        taxes.clone()
+    };
+    let mut stats = TAX_STATS.may_load(storage)?.unwrap_or_default();
+    if stats.since == 0 {
+        stats.since = time.seconds();
+    }
+    if exempt {
+        stats.exempt_transfers += 1;
+    } else {
+        stats.taxed_transfers += 1;
+        stats.taxed_volume = stats.taxed_volume.checked_add(amount)?;
+        stats.total_tax = stats.total_tax.checked_add(taxes.taxed_amount)?;
+        stats.total_burned = stats.total_burned.checked_add(taxes.burn_amount)?;
+        stats.total_reflected = stats.total_reflected.checked_add(taxes.reflection_amount)?;
+    }
+    TAX_STATS.save(storage, &stats)?;
+
+    Ok(Event::new("tax")
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", amount)
+        .add_attribute("tax_amount", taxes.taxed_amount)
+        .add_attribute("burn_amount", taxes.burn_amount)
+        .add_attribute("reflection_amount", taxes.reflection_amount)
+        .add_attribute(
+            "effective_rate",
+            Decimal::from_ratio(taxes.taxed_amount, amount.max(Uint128::one())).to_string(),
+        )
+        .add_attribute("tax_exempt", exempt.to_string()))
+}
+
+pub fn query_tax_stats(deps: Deps) -> StdResult<TaxStatsResponse> {
+    let stats = TAX_STATS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(TaxStatsResponse {
+        effective_rate: if stats.taxed_volume.is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(stats.total_tax, stats.taxed_volume)
+        },
+        stats,
+    })
+}
+
+/// Annualizes the reflections collected over the last `window` seconds against the eligible
+/// supply. The window is rounded up to whole days, capped to the retention period and to the time
+/// since the first transfer recorded in the `TaxStats`, so a young token is not annualized over
+/// days it did not exist. Holders only earn the reflections in claim mode, in treasury mode they
+/// go to the treasury and the yield is zero
+pub fn query_reflection_apr(
+    storage: &dyn Storage,
+    time: Timestamp,
+    window: Option<u64>,
+) -> StdResult<ReflectionAprResponse> {
+    let window = window.unwrap_or(DEFAULT_APR_WINDOW);
+    let days = window.div_ceil(DAY).clamp(1, FEE_FLOW_DAYS);
+    let today = time.seconds() / DAY;
+    let first_day = (today + 1).saturating_sub(days);
+
+    let reflected: Uint128 = REFLECTION_FLOW
+        .range(
+            storage,
+            Some(Bound::inclusive(first_day)),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, amount)| amount))
+        .collect::<StdResult<Vec<Uint128>>>()?
+        .into_iter()
+        .sum();
+    // the current day has only partially elapsed, and no tax was collected before the first
+    // transfer recorded
+    let mut covered = (days - 1) * DAY + (time.seconds() % DAY).max(1);
+    let since = TAX_STATS.may_load(storage)?.unwrap_or_default().since;
+    if since > 0 {
+        covered = covered.min(time.seconds().saturating_sub(since).max(1));
+    }
+    let eligible_supply = eligible_supply(storage)?;
+    let mode = REFLECTION_MODE.may_load(storage)?.unwrap_or_default();
+
+    let apr = if mode != ReflectionMode::Claim || eligible_supply.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(reflected, eligible_supply) * Decimal::from_ratio(YEAR, covered)
+    };
+
+    Ok(ReflectionAprResponse {
+        window: covered,
+        reflected,
+        eligible_supply,
+        apr,
+        mode,
+    })
+}
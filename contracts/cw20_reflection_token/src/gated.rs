@@ -0,0 +1,54 @@
+use cosmwasm_std::{
+    to_json_binary, Binary, Coin, DepsMut, MessageInfo, Response, StdError, Uint128, WasmMsg,
+};
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+
+use crate::msg::{GatedForwardExecuteMsg, GatedForwardMsg};
+
+/// Forwards `msg` to `contract` once `info.sender` is proven to hold at least `min_balance`. The
+/// message is wrapped with the holder and its balance, the same way a cw20 `Send` wraps its hook,
+/// so the token cannot be made to call an entry point of another contract with its own authority
+pub fn execute_gated_forward(
+    deps: DepsMut,
+    info: MessageInfo,
+    min_balance: Uint128,
+    contract: String,
+    msg: Binary,
+    funds: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    // the token holds no funds of its own to forward
+    if funds != info.funds {
+        return Err(ContractError::Std(StdError::generic_err(
+            "funds must match the funds sent",
+        )));
+    }
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if balance < min_balance {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Holder balance {} below the required {}",
+            balance, min_balance
+        ))));
+    }
+
+    let forward = WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        msg: to_json_binary(&GatedForwardExecuteMsg::GatedForward(GatedForwardMsg {
+            sender: info.sender.to_string(),
+            balance,
+            msg,
+        }))?,
+        funds,
+    };
+
+    Ok(Response::new()
+        .add_message(forward)
+        .add_attribute("action", "gated_forward")
+        .add_attribute("holder", info.sender)
+        .add_attribute("balance", balance)
+        .add_attribute("min_balance", min_balance)
+        .add_attribute("contract", contract))
+}
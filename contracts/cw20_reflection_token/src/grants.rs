@@ -0,0 +1,220 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use cw20::Expiration;
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+use serde::Serialize;
+
+use crate::contract::{execute_send, execute_tracked_burn, execute_transfer};
+use crate::msg::{GrantAction, OperatorGrantsResponse, OperatorInfo, OperatorMsg};
+use crate::reflections::{execute_claim_reflections, query_pending_reflections};
+use crate::state::{OperatorGrant, OPERATOR_GRANTS};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Message of the staking vault staking the tokens sent to it
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VaultMsg {
+    Stake {},
+}
+
+/// Authorizes `operator` to execute `action` on behalf of the sender, up to `cap` tokens in total.
+/// Granting the same action again replaces the previous grant
+pub fn execute_grant_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    action: GrantAction,
+    cap: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    if operator_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if let GrantAction::Stake { vault } = &action {
+        deps.api.addr_validate(vault)?;
+    }
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    let mut grants = OPERATOR_GRANTS
+        .may_load(deps.storage, (&info.sender, &operator_addr))?
+        .unwrap_or_default();
+    grants.retain(|grant| grant.action != action);
+    grants.push(OperatorGrant {
+        action: action.clone(),
+        remaining: cap,
+        expires,
+    });
+    OPERATOR_GRANTS.save(deps.storage, (&info.sender, &operator_addr), &grants)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_operator")
+        .add_attribute("owner", info.sender)
+        .add_attribute("operator", operator)
+        .add_attribute("grant", action.as_str())
+        .add_attribute("cap", cap))
+}
+
+/// Removes the grant of `action` given to `operator`
+pub fn execute_revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+    action: GrantAction,
+) -> Result<Response, ContractError> {
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    let key = (&info.sender, &operator_addr);
+    let mut grants = OPERATOR_GRANTS
+        .may_load(deps.storage, key)?
+        .unwrap_or_default();
+    grants.retain(|grant| grant.action != action);
+    if grants.is_empty() {
+        OPERATOR_GRANTS.remove(deps.storage, key);
+    } else {
+        OPERATOR_GRANTS.save(deps.storage, key, &grants)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_operator")
+        .add_attribute("owner", info.sender)
+        .add_attribute("operator", operator)
+        .add_attribute("grant", action.as_str()))
+}
+
+/// Executes `msg` on behalf of `owner`. The grant for the action is checked and consumed before dispatching
+pub fn execute_as_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    msg: OperatorMsg,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let (action, amount) = match &msg {
+        OperatorMsg::Transfer { amount, .. } => (GrantAction::Transfer, *amount),
+        OperatorMsg::Send { amount, .. } => (GrantAction::Send, *amount),
+        OperatorMsg::Burn { amount } => (GrantAction::Burn, *amount),
+        OperatorMsg::ClaimReflections {} => (
+            GrantAction::Claim,
+            query_pending_reflections(deps.as_ref(), owner.clone())?.pending,
+        ),
+        OperatorMsg::Stake { vault, amount } => (
+            GrantAction::Stake {
+                vault: vault.clone(),
+            },
+            *amount,
+        ),
+    };
+    consume_grant(
+        deps.storage,
+        &env,
+        &owner_addr,
+        &info.sender,
+        &action,
+        amount,
+    )?;
+
+    let owner_info = MessageInfo {
+        sender: owner_addr.clone(),
+        funds: vec![],
+    };
+    let res = match msg {
+        OperatorMsg::Transfer { recipient, amount } => {
+            execute_transfer(deps, env, owner_info, recipient, amount, None)?
+        }
+        OperatorMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => execute_send(deps, env, owner_info, contract, amount, msg, None)?,
+        OperatorMsg::Burn { amount } => execute_tracked_burn(deps, env, owner_info, amount)?,
+        OperatorMsg::ClaimReflections {} => execute_claim_reflections(deps, env, owner_info)?,
+        OperatorMsg::Stake { vault, amount } => execute_send(
+            deps,
+            env,
+            owner_info,
+            vault,
+            amount,
+            to_json_binary(&VaultMsg::Stake {})?,
+            None,
+        )?,
+    };
+
+    Ok(res.add_attribute("operator", info.sender))
+}
+
+fn consume_grant(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+    action: &GrantAction,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let mut grants = OPERATOR_GRANTS
+        .may_load(storage, (owner, operator))?
+        .unwrap_or_default();
+    let grant = grants
+        .iter_mut()
+        .find(|grant| &grant.action == action)
+        .ok_or_else(|| {
+            StdError::generic_err(format!(
+                "Unauthorized: no {} grant for operator",
+                action.as_str()
+            ))
+        })?;
+    if grant.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    grant.remaining = grant
+        .remaining
+        .checked_sub(amount)
+        .map_err(|_| StdError::generic_err("Operator grant cap exceeded"))?;
+    OPERATOR_GRANTS.save(storage, (owner, operator), &grants)?;
+
+    Ok(())
+}
+
+/// Returns the grants given by `owner`, paginated by operator
+pub fn query_operator_grants(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorGrantsResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|s| deps.api.addr_validate(&s))
+        .transpose()?;
+
+    let mut operators = vec![];
+    for item in OPERATOR_GRANTS
+        .prefix(&owner_addr)
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+    {
+        let (operator, grants) = item?;
+        operators.push(OperatorInfo {
+            operator: operator.to_string(),
+            grants,
+        });
+    }
+
+    Ok(OperatorGrantsResponse { operators })
+}
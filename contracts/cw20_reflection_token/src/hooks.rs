@@ -0,0 +1,99 @@
+use cosmwasm_std::{
+    Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage, SubMsg, Uint128,
+};
+use cw20_base::ContractError;
+use dojoswap::taxed::TransferHookMsg;
+
+use crate::contract::ensure_admin;
+use crate::msg::TransferHooksResponse;
+use crate::state::TRANSFER_HOOKS;
+
+/// Most hooks called by a transfer, each one adds a message to every transfer
+pub const MAX_TRANSFER_HOOKS: usize = 10;
+
+/// Registers `contract` to be called with `TransferHookMsg` after every transfer. A failing hook
+/// reverts the transfer, so that it never misses a balance change until the admin removes it
+pub fn add_transfer_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut hooks = TRANSFER_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+    if hooks.contains(&contract) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Transfer hook already registered",
+        )));
+    }
+    if hooks.len() >= MAX_TRANSFER_HOOKS {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "At most {} transfer hooks",
+            MAX_TRANSFER_HOOKS
+        ))));
+    }
+    hooks.push(contract.clone());
+    TRANSFER_HOOKS.save(deps.storage, &hooks)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_transfer_hook")
+        .add_attribute("contract", contract))
+}
+
+pub fn remove_transfer_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let contract = deps.api.addr_validate(&contract)?;
+    let mut hooks = TRANSFER_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+    let count = hooks.len();
+    hooks.retain(|hook| *hook != contract);
+    if hooks.len() == count {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Transfer hook not registered",
+        )));
+    }
+    TRANSFER_HOOKS.save(deps.storage, &hooks)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_transfer_hook")
+        .add_attribute("contract", contract))
+}
+
+/// Calls of every registered hook for a transfer of `amount` from `from` crediting `to` with
+/// `amount - tax`
+pub fn transfer_hooks(
+    storage: &dyn Storage,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+    tax: Uint128,
+) -> StdResult<Vec<SubMsg>> {
+    TRANSFER_HOOKS
+        .may_load(storage)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|hook| {
+            let msg = TransferHookMsg {
+                from: from.to_string(),
+                to: to.to_string(),
+                amount,
+                tax,
+            };
+            Ok(SubMsg::new(msg.into_cosmos_msg(hook)?))
+        })
+        .collect()
+}
+
+pub fn query_transfer_hooks(deps: Deps) -> StdResult<TransferHooksResponse> {
+    Ok(TransferHooksResponse {
+        hooks: TRANSFER_HOOKS
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hook| hook.to_string())
+            .collect(),
+    })
+}
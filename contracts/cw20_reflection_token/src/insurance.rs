@@ -0,0 +1,180 @@
+use cosmwasm_std::{
+    Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::fees::DAY;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::{InsuranceFundResponse, InsuranceWithdrawalsResponse, Role};
+use crate::roles::ensure_role;
+use crate::state::{InsuranceWithdrawal, INSURANCE_FUND, INSURANCE_RATE, INSURANCE_WITHDRAWALS};
+use crate::tax_config::ensure_tax_destination;
+
+/// Seconds between governance queuing an insurance withdrawal and its execution
+pub const INSURANCE_WITHDRAWAL_DELAY: u64 = 3 * DAY;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Sets aside the insurance share of `taxed_amount` on the token contract balance, taken out of
+/// `available`. Returns the amount set aside
+pub fn credit_insurance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    taxed_amount: Uint128,
+    available: Uint128,
+) -> StdResult<Uint128> {
+    let rate = INSURANCE_RATE.may_load(storage)?.unwrap_or_default();
+    let amount = (taxed_amount * rate).min(available);
+    if amount.is_zero() {
+        return Ok(amount);
+    }
+
+    increase_balance(storage, &env.contract.address, amount)?;
+    let mut fund = INSURANCE_FUND.may_load(storage)?.unwrap_or_default();
+    fund.balance = fund.balance.checked_add(amount)?;
+    INSURANCE_FUND.save(storage, &fund)?;
+
+    Ok(amount)
+}
+
+pub fn set_insurance_rate(
+    deps: DepsMut,
+    info: MessageInfo,
+    rate: Decimal,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::TaxManager)?;
+    if rate > Decimal::one() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "insurance rate must be <= 1",
+        )));
+    }
+    if !rate.is_zero() {
+        ensure_tax_destination(deps.storage)?;
+    }
+    INSURANCE_RATE.save(deps.storage, &rate)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_insurance_rate")
+        .add_attribute("rate", rate.to_string()))
+}
+
+/// Governance path to the insurance fund, the withdrawal becomes executable after the delay
+pub fn queue_insurance_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    recipient: String,
+    amount: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if reason.trim().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "insurance withdrawals require a reason",
+        )));
+    }
+    let mut fund = INSURANCE_FUND.may_load(deps.storage)?.unwrap_or_default();
+    fund.queued = fund.queued.checked_add(amount).map_err(StdError::from)?;
+    if fund.queued > fund.balance {
+        return Err(ContractError::Std(StdError::generic_err(
+            "insurance fund cannot cover the withdrawal",
+        )));
+    }
+    INSURANCE_FUND.save(deps.storage, &fund)?;
+
+    let id = INSURANCE_WITHDRAWALS
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(1, |last| last + 1);
+    let now = env.block.time.seconds();
+    let withdrawal = InsuranceWithdrawal {
+        id,
+        recipient,
+        amount,
+        reason,
+        queued_at: now,
+        executable_at: now + INSURANCE_WITHDRAWAL_DELAY,
+        executed_at: None,
+    };
+    INSURANCE_WITHDRAWALS.save(deps.storage, id, &withdrawal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "queue_insurance_withdrawal")
+        .add_attribute("id", id.to_string())
+        .add_attribute("recipient", withdrawal.recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("reason", withdrawal.reason)
+        .add_attribute("executable_at", withdrawal.executable_at.to_string()))
+}
+
+pub fn execute_insurance_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let mut withdrawal = INSURANCE_WITHDRAWALS.load(deps.storage, id)?;
+    let now = env.block.time.seconds();
+    if withdrawal.executed_at.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "insurance withdrawal already executed",
+        )));
+    }
+    if now < withdrawal.executable_at {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "insurance withdrawal executable at {}",
+            withdrawal.executable_at
+        ))));
+    }
+
+    // queued withdrawals are reserved in the fund, so both cover the amount
+    let mut fund = INSURANCE_FUND.load(deps.storage)?;
+    fund.balance -= withdrawal.amount;
+    fund.queued -= withdrawal.amount;
+    INSURANCE_FUND.save(deps.storage, &fund)?;
+    decrease_balance(deps.storage, &env.contract.address, withdrawal.amount)?;
+    increase_balance(deps.storage, &withdrawal.recipient, withdrawal.amount)?;
+    withdrawal.executed_at = Some(now);
+    INSURANCE_WITHDRAWALS.save(deps.storage, id, &withdrawal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_insurance_withdrawal")
+        .add_attribute("id", id.to_string())
+        .add_attribute("recipient", withdrawal.recipient)
+        .add_attribute("amount", withdrawal.amount))
+}
+
+pub fn query_insurance_fund(storage: &dyn Storage) -> StdResult<InsuranceFundResponse> {
+    let fund = INSURANCE_FUND.may_load(storage)?.unwrap_or_default();
+    Ok(InsuranceFundResponse {
+        rate: INSURANCE_RATE.may_load(storage)?.unwrap_or_default(),
+        balance: fund.balance,
+        queued: fund.queued,
+        delay: INSURANCE_WITHDRAWAL_DELAY,
+    })
+}
+
+pub fn query_insurance_withdrawals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<InsuranceWithdrawalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let withdrawals = INSURANCE_WITHDRAWALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, withdrawal)| withdrawal))
+        .collect::<StdResult<_>>()?;
+
+    Ok(InsuranceWithdrawalsResponse { withdrawals })
+}
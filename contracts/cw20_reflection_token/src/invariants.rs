@@ -0,0 +1,192 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Event, Order, Response, StdResult, Storage, Uint128};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::custodial::is_custodial;
+use crate::drip::query_reflection_drip;
+use crate::insurance::query_insurance_fund;
+use crate::reflections::{accrued, eligible_supply, is_claim_mode};
+use crate::state::{
+    AUTO_LIQUIDITY_STATE, HOLDER_REFLECTIONS, INVARIANT_CURSOR, PAUSED_AT, REFLECTION_EXCLUDED,
+    REFLECTION_INDEX,
+};
+
+/// Balances checked per `VerifyInvariants`, the next call resumes after the last one
+pub const INVARIANT_SAMPLE_SIZE: usize = 30;
+
+/// Excluded addresses whose balances are summed to check the excluded supply, above it the check
+/// is skipped
+pub const MAX_EXCLUDED_CHECKED: usize = 100;
+
+/// Checks the accounting invariants across modules over the next sample of balances, and pauses
+/// the token with an `invariant_violation` event per violation found so that an accounting bug
+/// stops the token before it can be exploited. Violations do not revert so that the pause sticks:
+/// - supply: the sampled balances fit in the total supply
+/// - eligible supply: the excluded supply is the sum of the excluded balances and fits in the
+///   total supply, the sampled eligible balances fit in the eligible supply and no holder is
+///   ahead of the reflection index
+/// - fee pot: the token contract balance covers the taxes dripping, the insurance fund, the
+///   auto-liquidity set aside and, in claim mode, what the sampled holders can claim
+pub fn verify_invariants(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let (sampled, violations) = find_violations(deps.storage, &env)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "verify_invariants")
+        .add_attribute("sampled", sampled.to_string())
+        .add_attribute("violations", violations.len().to_string());
+    if violations.is_empty() {
+        return Ok(res);
+    }
+    for (invariant, detail) in violations {
+        res = res.add_event(
+            Event::new("invariant_violation")
+                .add_attribute("invariant", invariant)
+                .add_attribute("detail", detail),
+        );
+    }
+    if PAUSED_AT.may_load(deps.storage)?.is_none() {
+        PAUSED_AT.save(deps.storage, &env.block.time.seconds())?;
+        res = res.add_attribute("paused", "true");
+    }
+    Ok(res)
+}
+
+/// Number of balances sampled and the invariants violated with their details, moves the cursor
+/// to the next sample
+fn find_violations(
+    storage: &mut dyn Storage,
+    env: &Env,
+) -> StdResult<(usize, Vec<(&'static str, String)>)> {
+    let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+    let index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    let claim_mode = is_claim_mode(storage)?;
+    let mut violations = vec![];
+
+    if index.excluded_supply > total_supply {
+        violations.push((
+            "eligible_supply",
+            format!(
+                "excluded supply {} above the total supply {}",
+                index.excluded_supply, total_supply
+            ),
+        ));
+    }
+    if let Some(excluded) = excluded_balances(storage)? {
+        if excluded != index.excluded_supply {
+            violations.push((
+                "eligible_supply",
+                format!(
+                    "excluded balances {} differ from the excluded supply {}",
+                    excluded, index.excluded_supply
+                ),
+            ));
+        }
+    }
+
+    let cursor = INVARIANT_CURSOR.may_load(storage)?;
+    let sample = BALANCES
+        .range(
+            storage,
+            cursor.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(INVARIANT_SAMPLE_SIZE)
+        .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
+    let mut sampled = Uint128::zero();
+    let mut sampled_eligible = Uint128::zero();
+    let mut owed = Uint128::zero();
+    for (address, balance) in &sample {
+        sampled = sampled.checked_add(*balance)?;
+        if REFLECTION_EXCLUDED.has(storage, address) {
+            continue;
+        }
+        sampled_eligible = sampled_eligible.checked_add(*balance)?;
+        if !claim_mode || is_custodial(storage, address) {
+            continue;
+        }
+        let holder = HOLDER_REFLECTIONS
+            .may_load(storage, address)?
+            .unwrap_or_default();
+        if holder.per_token > index.per_token {
+            violations.push((
+                "eligible_supply",
+                format!("{} is ahead of the reflection index", address),
+            ));
+            continue;
+        }
+        owed = owed.checked_add(accrued(storage, &index, address, *balance)?.pending)?;
+    }
+    if sampled > total_supply {
+        violations.push((
+            "supply",
+            format!(
+                "sampled balances {} above the total supply {}",
+                sampled, total_supply
+            ),
+        ));
+    }
+    let eligible = eligible_supply(storage)?;
+    if sampled_eligible > eligible {
+        violations.push((
+            "eligible_supply",
+            format!(
+                "sampled eligible balances {} above the eligible supply {}",
+                sampled_eligible, eligible
+            ),
+        ));
+    }
+
+    // claims on the token contract balance, other pots only make the check weaker
+    let mut pots = query_reflection_drip(storage, env)?
+        .pending
+        .checked_add(query_insurance_fund(storage)?.balance)?
+        .checked_add(
+            AUTO_LIQUIDITY_STATE
+                .may_load(storage)?
+                .unwrap_or_default()
+                .accumulated,
+        )?;
+    if claim_mode {
+        pots = pots.checked_add(index.undistributed)?.checked_add(owed)?;
+    }
+    let held = BALANCES
+        .may_load(storage, &env.contract.address)?
+        .unwrap_or_default();
+    if pots > held {
+        violations.push((
+            "fee_pot",
+            format!(
+                "token contract holds {} for {} of taxes, insurance and reflections",
+                held, pots
+            ),
+        ));
+    }
+
+    match sample.last() {
+        Some((last, _)) if sample.len() == INVARIANT_SAMPLE_SIZE => {
+            INVARIANT_CURSOR.save(storage, last)?
+        }
+        // the next sample starts over
+        _ => INVARIANT_CURSOR.remove(storage),
+    }
+
+    Ok((sample.len(), violations))
+}
+
+/// Sum of the balances of the excluded addresses, None when there are too many to sum
+fn excluded_balances(storage: &dyn Storage) -> StdResult<Option<Uint128>> {
+    let excluded = REFLECTION_EXCLUDED
+        .keys(storage, None, None, Order::Ascending)
+        .take(MAX_EXCLUDED_CHECKED + 1)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    if excluded.len() > MAX_EXCLUDED_CHECKED {
+        return Ok(None);
+    }
+    let mut sum = Uint128::zero();
+    for address in excluded {
+        sum = sum.checked_add(BALANCES.may_load(storage, &address)?.unwrap_or_default())?;
+    }
+    Ok(Some(sum))
+}
@@ -0,0 +1,90 @@
+use cosmwasm_std::{
+    attr, Addr, Attribute, Deps, DepsMut, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::msg::{AddressLabel, AddressLabelsResponse};
+use crate::state::ADDRESS_LABELS;
+
+/// Longest label accepted by `set_address_label`
+pub const MAX_LABEL_LENGTH: usize = 32;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Attaches a short label ("treasury", "pair:INJ", "team") to `address`, None removes it
+pub fn set_address_label(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    label: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+    match &label {
+        Some(label) => {
+            if label.is_empty()
+                || label.len() > MAX_LABEL_LENGTH
+                || !label.chars().all(|c| c.is_ascii_graphic() || c == ' ')
+            {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Labels are 1 to {} printable ascii characters",
+                    MAX_LABEL_LENGTH
+                ))));
+            }
+            ADDRESS_LABELS.save(deps.storage, &address, label)?;
+        }
+        None => ADDRESS_LABELS.remove(deps.storage, &address),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_address_label")
+        .add_attribute("address", address)
+        .add_attribute("label", label.unwrap_or_default()))
+}
+
+/// `{key}_label` attributes of the labelled addresses among `parties`, added next to the address
+/// attributes of transfers so that explorers can render fee flows
+pub fn label_attributes(
+    storage: &dyn Storage,
+    parties: &[(&str, &str)],
+) -> StdResult<Vec<Attribute>> {
+    let mut attributes = vec![];
+    for (key, address) in parties {
+        if let Some(label) = ADDRESS_LABELS.may_load(storage, &Addr::unchecked(*address))? {
+            attributes.push(attr(format!("{}_label", key), label));
+        }
+    }
+    Ok(attributes)
+}
+
+pub fn query_address_labels(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AddressLabelsResponse> {
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let labels = ADDRESS_LABELS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, label)| AddressLabel {
+                address: address.to_string(),
+                label,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(AddressLabelsResponse { labels })
+}
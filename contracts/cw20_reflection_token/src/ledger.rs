@@ -0,0 +1,108 @@
+use cosmwasm_std::{Addr, DepsMut, Response, StdResult, Storage, Uint128};
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+
+use crate::checkpoints::record_balance_checkpoint;
+use crate::distribution::{record_holder_change, record_top_holder};
+use crate::reflections::settle_reflections;
+use crate::security::ensure_not_frozen;
+use crate::snapshots::record_snapshot_balance;
+use crate::vesting::ensure_vested;
+
+/// Credits `amount` to the balance of `address`, returns the new balance
+pub fn increase_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let old = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let new = old.checked_add(amount)?;
+    BALANCES.save(storage, address, &new)?;
+    after_balance_change(storage, address, old, new)?;
+
+    Ok(new)
+}
+
+/// Debits `amount` from the balance of `address`, returns the new balance
+pub fn decrease_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let old = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let new = old.checked_sub(amount)?;
+    BALANCES.save(storage, address, &new)?;
+    after_balance_change(storage, address, old, new)?;
+
+    Ok(new)
+}
+
+/// Wraps a cw20-base handler that mutates the balance of `address` on its own (burn, mint, etc.)
+/// so that the same bookkeeping as `increase_balance` / `decrease_balance` is applied afterwards
+pub fn track_balance<F>(
+    mut deps: DepsMut,
+    address: &Addr,
+    handler: F,
+) -> Result<Response, ContractError>
+where
+    F: FnOnce(DepsMut) -> Result<Response, ContractError>,
+{
+    let old = BALANCES
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    let res = handler(deps.branch())?;
+    let new = BALANCES
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    if old != new {
+        after_balance_change(deps.storage, address, old, new)?;
+    }
+
+    Ok(res)
+}
+
+/// Single place where state derived from balances is kept in sync.
+/// Every balance mutation must end up here
+pub fn after_balance_change(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    ensure_not_frozen(storage, address, old, new)?;
+    ensure_vested(storage, address, old, new)?;
+    sync_balance_change(storage, address, old, new)
+}
+
+/// Bookkeeping of `after_balance_change` without the freeze and vesting checks
+fn sync_balance_change(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    // before the reflections of the old balance are settled
+    record_balance_checkpoint(storage, address, old)?;
+    record_holder_change(storage, address, old, new)?;
+    record_top_holder(storage, address, new)?;
+    settle_reflections(storage, address, old, new)?;
+    record_snapshot_balance(storage, address, old)?;
+
+    Ok(())
+}
+
+/// Debits `amount` from the balance of `address` even if it is frozen or locked by its vesting
+/// schedule, only for the issuer clawback
+#[cfg(feature = "clawback")]
+pub fn claw_back_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let old = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let new = old.checked_sub(amount)?;
+    BALANCES.save(storage, address, &new)?;
+    sync_balance_change(storage, address, old, new)?;
+
+    Ok(new)
+}
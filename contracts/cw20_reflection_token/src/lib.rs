@@ -1,2 +1,55 @@
+pub mod api;
+pub mod auto_liquidity;
+pub mod batch;
+pub mod bridge;
+pub mod checkpoints;
+#[cfg(feature = "clawback")]
+pub mod clawback;
+pub mod co_signing;
 pub mod contract;
-pub mod msg;
\ No newline at end of file
+pub mod custodial;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod display;
+pub mod disputes;
+pub mod distribution;
+pub mod drip;
+pub mod effective;
+pub mod emergency;
+pub mod epoch_rewards;
+pub mod fee_exempt;
+pub mod fees;
+pub mod gated;
+pub mod grants;
+pub mod hooks;
+pub mod insurance;
+pub mod invariants;
+pub mod labels;
+pub mod ledger;
+pub mod links;
+pub mod msg;
+pub mod permit;
+pub mod policy;
+pub mod presets;
+pub mod quarantine;
+pub mod rate_limit;
+pub mod reflections;
+pub mod relay;
+pub mod roles;
+pub mod schedule;
+pub mod security;
+pub mod signing;
+pub mod simulation;
+pub mod snapshots;
+pub mod state;
+pub mod sunset;
+pub mod tax_config;
+pub mod tax_strategy;
+pub mod timed;
+pub mod trading;
+pub mod trophy;
+pub mod vesting;
+pub mod wallet_limits;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,121 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+};
+use cw20_base::ContractError;
+
+use crate::msg::LinkedAddressesResponse;
+use crate::state::{ADDRESS_LINKS, LINK_PROPOSALS};
+
+/// Most addresses a single address can be linked to
+pub const MAX_LINKS: usize = 5;
+
+/// First half of a self-link: `info.sender` offers to link with `address`, which accepts with
+/// `AcceptLink`. Each side signs its own message, proving control of both addresses
+pub fn propose_link(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    if address == info.sender {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Cannot link an address to itself",
+        )));
+    }
+    if is_linked(deps.storage, &info.sender, address.as_str()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Addresses are already linked",
+        )));
+    }
+    LINK_PROPOSALS.save(
+        deps.storage,
+        (&info.sender, &address),
+        &env.block.time.seconds(),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_link")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", address))
+}
+
+/// Second half of a self-link: `info.sender` accepts the link proposed by `address`
+pub fn accept_link(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    if !LINK_PROPOSALS.has(deps.storage, (&address, &info.sender)) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "No link proposed by this address",
+        )));
+    }
+    for side in [&address, &info.sender] {
+        if links_of(deps.storage, side)?.len() >= MAX_LINKS {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "{} already has {} linked addresses",
+                side, MAX_LINKS
+            ))));
+        }
+    }
+    LINK_PROPOSALS.remove(deps.storage, (&address, &info.sender));
+    let now = env.block.time.seconds();
+    ADDRESS_LINKS.save(deps.storage, (&address, &info.sender), &now)?;
+    ADDRESS_LINKS.save(deps.storage, (&info.sender, &address), &now)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_link")
+        .add_attribute("from", address)
+        .add_attribute("to", info.sender))
+}
+
+/// Removes the link between `info.sender` and `address`, or the link `info.sender` proposed to it.
+/// Either side can unlink
+pub fn unlink(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    LINK_PROPOSALS.remove(deps.storage, (&info.sender, &address));
+    ADDRESS_LINKS.remove(deps.storage, (&address, &info.sender));
+    ADDRESS_LINKS.remove(deps.storage, (&info.sender, &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "unlink")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", address))
+}
+
+/// True when `from` and `to` are linked addresses of the same holder, transfers between them skip
+/// the anti-whale limit but are still taxed
+pub fn is_linked(storage: &dyn Storage, from: &Addr, to: &str) -> bool {
+    ADDRESS_LINKS.has(storage, (from, &Addr::unchecked(to)))
+}
+
+fn links_of(storage: &dyn Storage, address: &Addr) -> StdResult<Vec<Addr>> {
+    ADDRESS_LINKS
+        .prefix(address)
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn query_linked_addresses(deps: Deps, address: String) -> StdResult<LinkedAddressesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let proposed = LINK_PROPOSALS
+        .prefix(&address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.map(|address| address.to_string()))
+        .collect::<StdResult<_>>()?;
+
+    Ok(LinkedAddressesResponse {
+        linked: links_of(deps.storage, &address)?
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect(),
+        proposed,
+    })
+}
@@ -1,15 +1,50 @@
-use cosmwasm_std::{Addr, Binary};
-use cosmwasm_std::{Decimal, StdError, StdResult, Uint128};
+use cosmwasm_std::{Addr, Api, Binary, Coin, Empty};
+use cosmwasm_std::{Decimal, Int128, StdError, Uint128};
 use cw20::{Cw20Coin, Expiration, Logo, MinterResponse};
+use dojoswap::signing::KeyType;
+use dojoswap::token::{validate_token_info, InstantiateMsgError, ValidationConfig};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    AccrualPause, ActionReceipt, AutoLiquidityConfig, AutoLiquidityState, CoSignRule,
+    CoSignRuleChange, CoSignedTransfer, EpochReward, InsuranceWithdrawal, OperatorGrant,
+    PendingAdmin, PolicyChange, QuarantinedSend, ScheduledEntry, Snapshot, Sunset, TaxConfig,
+    TaxStats, TaxStrategyConfig, Vesting, WalletLimits,
+};
+use crate::vesting::validate_vesting;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMarketingInfo {
     pub project: Option<String>,
     pub description: Option<String>,
     pub marketing: Option<String>,
     pub logo: Option<Logo>,
+    /// Fractional digits frontends should show, at most `decimals`. Defaults to `decimals`
+    pub display_decimals: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReflectionMode {
+    /// The reflection share of taxes is credited to the treasury along with the rest of the tax
+    #[default]
+    Treasury,
+    /// The reflection share of taxes is held by the token and accrues to holders pro rata to their
+    /// balance, each holder pulls it with `ClaimReflections`
+    Claim,
+}
+
+/// Admin powers delegated to another address, e.g. a multisig
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Sets the tax rates, tax config and insurance rate next to the admin
+    TaxManager,
+    /// Sets the whitelist, fee and limit exemptions and tax free hooks next to the admin
+    ExemptionManager,
+    /// Pauses the token and freezes addresses, the admin cannot
+    Guardian,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
@@ -17,12 +52,43 @@ pub struct InstantiateMsg {
     pub name: String,
     pub symbol: String,
     pub decimals: u8,
-    pub cw20_code_id: u64,
+    /// Code id of the treasury instantiated along with the token
+    #[serde(default)]
+    pub treasury_code_id: u64,
+    /// Deprecated name of `treasury_code_id`, flagged in the instantiate response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cw20_code_id: Option<u64>,
     pub initial_balances: Vec<Cw20Coin>,
     pub admin: String,
     pub router: String,
     pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
+    /// Hard ceiling of the global tax rate, cannot be changed after instantiation. Defaults to 1
+    pub max_tax_rate: Option<Decimal>,
+    /// Maximum move of the global tax rate within 24 hours. Defaults to 1, i.e. unlimited
+    pub max_tax_step: Option<Decimal>,
+    /// Where the reflection share of taxes goes, cannot be changed after instantiation.
+    /// Defaults to `treasury`
+    pub reflection_mode: Option<ReflectionMode>,
+    /// Most tokens a single transfer can move, unlimited by default
+    pub max_tx_amount: Option<Uint128>,
+    /// Most tokens an address can hold after receiving a transfer, unlimited by default
+    pub max_wallet_amount: Option<Uint128>,
+    /// Parts of `initial_balances` released over time, e.g. team and investor allocations
+    pub vesting: Option<Vec<VestingSchedule>>,
+}
+
+/// Locks `amount` of the initial balance of `address`, released from the instantiation
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct VestingSchedule {
+    pub address: String,
+    pub amount: Uint128,
+    /// Seconds before anything is released
+    pub cliff: u64,
+    /// Seconds until everything is released
+    pub duration: u64,
+    /// Seconds between releases, each one releases `amount * period / duration`
+    pub period: u64,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
@@ -39,6 +105,9 @@ pub enum ExecuteMsg {
     Transfer {
         recipient: String,
         amount: Uint128,
+        /// Reverts if the amount received after tax is below this
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_receive: Option<Uint128>,
     },
     /// Burn is a base message to destroy tokens forever
     Burn {
@@ -50,6 +119,9 @@ pub enum ExecuteMsg {
         contract: String,
         amount: Uint128,
         msg: Binary,
+        /// Reverts if the amount received after tax is below this
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_receive: Option<Uint128>,
     },
     /// Only with "approval" extension. Allows spender to access an additional amount tokens
     /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
@@ -73,6 +145,9 @@ pub enum ExecuteMsg {
         owner: String,
         recipient: String,
         amount: Uint128,
+        /// Reverts if the amount received after tax is below this
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_receive: Option<Uint128>,
     },
     /// Only with "approval" extension. Sends amount tokens from owner -> contract
     /// if `env.sender` has sufficient pre-approval.
@@ -81,6 +156,9 @@ pub enum ExecuteMsg {
         contract: String,
         amount: Uint128,
         msg: Binary,
+        /// Reverts if the amount received after tax is below this
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_receive: Option<Uint128>,
     },
     /// Only with "approval" extension. Destroys tokens forever
     BurnFrom {
@@ -106,6 +184,11 @@ pub enum ExecuteMsg {
     },
     /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
     UploadLogo(Logo),
+    /// If set as the "marketing" role on the contract, set the fractional digits frontends should
+    /// show, at most `decimals`. None goes back to `decimals`
+    SetDisplayDecimals {
+        display_decimals: Option<u8>,
+    },
     SetTaxRate {
         global_rate: Decimal,
         reflection_rate: Decimal,
@@ -124,6 +207,502 @@ pub enum ExecuteMsg {
     MigrateTreasury {
         code_id: u64,
     },
+    /// Only callable by a trusted forwarder. Executes `msg` as if it was sent by `sender`,
+    /// the forwarder is responsible for verifying the intent of `sender`, whose signature covers
+    /// the whole relay under the `SignatureDomain` of this token. The relay fails unless `nonce` is
+    /// the next one of `sender` and `chain_id` and `contract` are this chain and token
+    Relay {
+        sender: String,
+        msg: RelayMsg,
+        nonce: u64,
+        chain_id: String,
+        contract: String,
+    },
+    /// Sets the allowance of `permit.spender` on the tokens of `permit.owner` to `permit.amount`,
+    /// as signed off-chain by the owner with ADR-36 `signArbitrary` over the `signing_digest` of
+    /// the JSON of `permit` under the `SignatureDomain` of this token. Anyone can submit it, e.g.
+    /// a frontend approving and swapping in a single transaction of the user. `public_key` must
+    /// be the compressed secp256k1 key of the owner address, of type `key_type`: `secp256k1` by
+    /// default, `eth_secp256k1` for Injective accounts
+    Permit {
+        permit: PermitMsg,
+        public_key: Binary,
+        signature: Binary,
+        key_type: Option<KeyType>,
+    },
+    /// Executes `inner` only within the block height window of `bounds`, so that a pre-signed
+    /// admin or keeper transaction cannot land at a stale or dangerous time
+    Timed {
+        inner: Box<ExecuteMsg>,
+        bounds: ExecutionBounds,
+    },
+    /// Sets which contracts are trusted to relay messages on behalf of users
+    SetTrustedForwarder {
+        forwarder: String,
+        enable: bool,
+    },
+    /// Authorizes `operator` to execute a single action type on behalf of `env.sender`, moving at most
+    /// `cap` tokens in total before `expires`. Replaces any previous grant of the same action
+    GrantOperator {
+        operator: String,
+        action: GrantAction,
+        cap: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Removes the grant of `action` previously given to `operator`
+    RevokeOperator {
+        operator: String,
+        action: GrantAction,
+    },
+    /// Executes `msg` on behalf of `owner` if `env.sender` holds a grant for its action
+    OperatorExecute {
+        owner: String,
+        msg: OperatorMsg,
+    },
+    /// Sets the number of seconds over which collected taxes are linearly released to the treasury,
+    /// and in claim mode the reflection share spread to holders, instead of being credited at once.
+    /// Every collection drips over the period from its own collection. 0 disables dripping
+    SetReflectionDrip {
+        period: u64,
+    },
+    /// Transfer charging the sender the gross amount for which `recipient` receives exactly
+    /// `net_amount` after tax. Fails if the sender cannot cover the gross amount
+    TransferExactNet {
+        recipient: String,
+        net_amount: Uint128,
+    },
+    /// Send failing unless `contract` receives exactly `expected_net` after tax, so an integrator
+    /// quoting the net amount detects a tax change between the quote and the execution
+    SendAndVerify {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+        expected_net: Uint128,
+    },
+    /// Transfers each amount to its recipient like `Transfer`, taxing every transfer on its own.
+    /// Fails as a whole if any transfer fails
+    MultiTransfer {
+        recipients: Vec<Cw20Coin>,
+    },
+    /// `MultiTransfer` where each recipient receives exactly its amount after tax, like
+    /// `TransferExactNet`
+    MultiTransferExactNet {
+        recipients: Vec<Cw20Coin>,
+    },
+    /// Permanently removes the admin, locking the tax rates, whitelist, drip period and treasury
+    /// code. `confirm` must be the address of this token
+    RenounceOwnership {
+        confirm: String,
+    },
+    /// Proposes `new_admin` as the admin, which takes over once it sends `AcceptOwnership` before
+    /// `expiry` (never by default). A new proposal replaces the pending one
+    TransferOwnership {
+        new_admin: String,
+        expiry: Option<Expiration>,
+    },
+    /// Sent by the proposed admin to take over
+    AcceptOwnership {},
+    /// Gives `role` to `address`, None takes it back. The admin keeps the powers of the tax and
+    /// exemption managers
+    UpdateRole {
+        role: Role,
+        address: Option<String>,
+    },
+    /// Transfers the reflections accrued by `env.sender` in claim mode
+    ClaimReflections {},
+    /// Excludes `address` from reflections for good, for an address nobody controls tokens are
+    /// sent to instead of burnt. What it accrued goes back to the holders
+    ExcludeDeadAddress {
+        address: String,
+    },
+    /// Sets the share of every tax set aside in the insurance fund
+    SetInsuranceRate {
+        rate: Decimal,
+    },
+    /// Pays out a withdrawal queued through governance once its delay elapsed, callable by anyone
+    ExecuteInsuranceWithdrawal {
+        id: u64,
+    },
+    /// Queues `action` to run once `execute_at` is reached, then every `interval` seconds if set
+    ScheduleAction {
+        action: ScheduledAction,
+        execute_at: u64,
+        interval: Option<u64>,
+    },
+    /// Removes a scheduled action before it runs (again)
+    CancelScheduledAction {
+        id: u64,
+    },
+    /// Guardian only, removes a scheduled action before it runs (again). The guardian cannot
+    /// schedule actions
+    Veto {
+        id: u64,
+    },
+    /// Runs the scheduled actions that matured, callable by anyone
+    ExecuteDue {},
+    /// Mints a trophy of `collection` to whoever burns at least `threshold` tokens in a single
+    /// `Burn` or `BurnFrom`. None disables trophies
+    SetBurnTrophy {
+        collection: Option<String>,
+        threshold: Uint128,
+    },
+    /// Rejects transfers with `pair` until its reserves reach `min_token_reserve` tokens and
+    /// `min_quote_reserve` of the quote asset, whitelisted parties excepted. None removes the gate.
+    /// Cannot be changed once trading is enabled
+    SetTradingGate {
+        pair: Option<String>,
+        min_token_reserve: Uint128,
+        min_quote_reserve: Uint128,
+    },
+    /// Enables trading if the gated pair holds the minimum reserves, anyone can call it
+    EnableTrading {},
+    /// Marks `address` (e.g. an exchange hot wallet) as custodial: its balance only moves by what
+    /// is transferred in and out, the reflections it accrues in claim mode go to the treasury
+    SetCustodial {
+        address: String,
+        enable: bool,
+    },
+    /// Withholds the reflections `address` accrues in claim mode from now on, e.g. while exploit
+    /// proceeds are investigated. Its transfers are not affected
+    PauseAccrual {
+        address: String,
+        reason: String,
+    },
+    /// Ends the pause of `address`, the reflections withheld are credited to it or forfeited to the
+    /// treasury depending on `outcome`
+    ReleaseAccrual {
+        address: String,
+        outcome: AccrualOutcome,
+    },
+    /// Attaches a short label ("treasury", "pair:INJ", "team") to `address`, reported next to it in
+    /// transfer events. None removes the label
+    SetAddressLabel {
+        address: String,
+        label: Option<String>,
+    },
+    /// When enabled, a `Send` or `SendFrom` whose receiving contract fails is not reverted, the net
+    /// amount is quarantined until the sender claims it back with `ClaimQuarantined`
+    SetHookQuarantine {
+        enable: bool,
+    },
+    /// Gives a quarantined send back to the sender
+    ClaimQuarantined {
+        id: u64,
+    },
+    /// Offers to link `env.sender` with `address`, another address of the same holder. Transfers
+    /// between linked addresses skip the anti-whale limit and are still taxed
+    ProposeLink {
+        address: String,
+    },
+    /// Accepts the link proposed by `address`
+    AcceptLink {
+        address: String,
+    },
+    /// Removes the link with `address`, or the link proposed to it
+    Unlink {
+        address: String,
+    },
+    /// Takes a snapshot of the balances every `interval` blocks, lazily on the first execution
+    /// after each boundary, and keeps the last `retain` ones. None stops the automatic snapshots
+    SetSnapshotSchedule {
+        schedule: Option<SnapshotSchedule>,
+    },
+    /// Takes a snapshot of the balances now
+    TakeSnapshot {},
+    /// Moves `amount` from the admin balance to a reward for the holders of snapshot `epoch`,
+    /// shared pro-rata to their balance in it and claimable for `claim_period` seconds
+    FundEpochReward {
+        epoch: u64,
+        amount: Uint128,
+        claim_period: u64,
+    },
+    /// Claims the share of `env.sender` in the reward of `epoch`
+    ClaimEpochReward {
+        epoch: u64,
+    },
+    /// Gives the unclaimed part of an expired reward back to the admin
+    SweepEpochReward {
+        epoch: u64,
+    },
+    /// Forwards `msg` to `contract` if `env.sender` holds at least `min_balance`, wrapped in a
+    /// `GatedForwardMsg` attesting the holder and its balance. `funds` must be sent along
+    GatedForward {
+        min_balance: Uint128,
+        contract: String,
+        msg: Binary,
+        funds: Vec<Coin>,
+    },
+    /// Attaches a spending policy contract to the account of `env.sender`, queried with
+    /// `SpendingPolicyQueryMsg::Allow` before every transfer out of it. Setting the first policy
+    /// applies at once, replacing or removing it (None) takes effect after a day
+    SetSpendingPolicy {
+        policy: Option<String>,
+    },
+    /// Requires the approval of `co_signer` for transfers out of the account of `env.sender` above
+    /// `threshold`. Setting the first co-signer applies at once, replacing or removing it (None)
+    /// takes effect after `co_signing::CO_SIGNER_DELAY`
+    SetCoSigner {
+        co_signer: Option<String>,
+        threshold: Uint128,
+    },
+    /// Proposes a transfer above the threshold of `env.sender` for its co-signer to approve, the
+    /// proposal expires after `co_signing::CO_SIGNED_TRANSFER_EXPIRY`
+    ProposeCoSignedTransfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Co-signer of `owner` only, approves its transfer `id`
+    ApproveCoSignedTransfer {
+        owner: String,
+        id: u64,
+    },
+    /// Executes the approved transfer `id` of `env.sender` like `Transfer`
+    ExecuteCoSignedTransfer {
+        id: u64,
+    },
+    /// Drops the transfer `id` of `owner`, sent by the owner or its co-signer
+    CancelCoSignedTransfer {
+        owner: String,
+        id: u64,
+    },
+    /// Splits the tax over the reflection pool, a burn and a treasury address, each with its own
+    /// rate in basis points. The total becomes the global tax rate, within the max tax rate and
+    /// step. None restores the global rate from before the config and goes back to the
+    /// reflection and burn rates of `SetTaxRate`
+    UpdateTaxConfig {
+        tax_config: Option<TaxConfig>,
+    },
+    /// Selects how the tax rate of each transfer is chosen: flat at the global rate, by amount
+    /// bracket, decaying from a launch rate or by the volume of the sender. The tax is split at that
+    /// rate like at the global rate, every rate must be within the max tax rate
+    SetTaxStrategy {
+        strategy: TaxStrategyConfig,
+    },
+    /// Exempts `address` from the tax on what it sends and receives, e.g. a pair or a router.
+    /// Unlike `SetWhitelist` the anti-whale limit still applies
+    SetFeeExempt {
+        address: String,
+        exempt: bool,
+    },
+    /// Replaces the max transaction and max wallet amounts enforced on transfers, None lifts a
+    /// limit
+    UpdateLimits {
+        max_tx_amount: Option<Uint128>,
+        max_wallet_amount: Option<Uint128>,
+    },
+    /// Exempts `address` from the max transaction and max wallet amounts, e.g. the pair. The token
+    /// and the treasury are always exempt
+    SetLimitsExempt {
+        address: String,
+        exempt: bool,
+    },
+    /// Makes a `Send` to `contract` with the hook message hashed to `msg_hash` tax free, see
+    /// `dojoswap::taxed::hook_msg_hash`. Other sends to the contract stay taxed
+    SetTaxFreeHook {
+        contract: String,
+        msg_hash: String,
+        enable: bool,
+    },
+    /// Registers the cw20-ics20 or other bridge contract escrowing the token, None unregisters it.
+    /// Transfers and sends from and to it are not taxed and skip the anti-whale and wallet limits
+    SetBridge {
+        contract: Option<String>,
+    },
+    /// Calls `contract` with `dojoswap::taxed::TransferHookMsg` after every transfer, sharing its
+    /// failures. At most `hooks::MAX_TRANSFER_HOOKS` contracts
+    AddTransferHook {
+        contract: String,
+    },
+    RemoveTransferHook {
+        contract: String,
+    },
+    /// Sets aside a share of every tax which is swapped half for the quote asset and added as
+    /// liquidity to the pair once it reaches the threshold, None stops setting aside
+    SetAutoLiquidity {
+        config: Option<AutoLiquidityConfig>,
+    },
+    /// Winds the token down after `sunset::SUNSET_DELAY`: every other message is then refused and
+    /// holders redeem their balance for a pro-rata share of a settlement pool of `denom`
+    ScheduleSunset {
+        denom: String,
+    },
+    /// Cancels the sunset before it activates, the settlement pool is refunded to the admin
+    CancelSunset {},
+    /// Adds the `denom` sent to the settlement pool until the sunset activates
+    FundSettlement {},
+    /// Once the sunset is active, burns the balance, pending reflections and quarantined sends of
+    /// `env.sender` and pays their share of the settlement pool
+    RedeemFinal {},
+    /// Sends what is left in the settlement pool to the admin once `sunset::SETTLEMENT_CLAIM_PERIOD`
+    /// elapsed since the activation
+    SweepSettlement {},
+    /// Sets the guardian allowed to pause the token and freeze addresses, None removes it. Same as
+    /// `UpdateRole` with the guardian role
+    SetGuardian {
+        guardian: Option<String>,
+    },
+    /// Guardian only, gives up the guardian role
+    RenounceGuardian {},
+    /// Guardian only, rejects every other message until `Unpause`
+    Pause {},
+    /// Guardian only, lifts `Pause`
+    Unpause {},
+    /// Anyone, e.g. a keeper, checks the supply, eligible supply and fee pot invariants over the
+    /// next sample of balances. A violation pauses the token and emits `invariant_violation`
+    VerifyInvariants {},
+    /// Anyone, counts the next `limit` balances (default 30, max 100) into the holder distribution
+    /// and concentration while they are backfilled after a migration, see `tracked_since`
+    BackfillDistribution {
+        limit: Option<u32>,
+    },
+    /// Guardian only, blocks a compromised `address`: it cannot execute anything and its balance
+    /// cannot be debited, it still receives transfers but accrues no reflections while frozen.
+    /// The token, the admin and the treasury cannot be frozen
+    Freeze {
+        address: String,
+    },
+    /// Guardian only, lifts `Freeze`
+    Unfreeze {
+        address: String,
+    },
+    /// Issuer only, moves `amount` from `from` to the issuer with a mandatory `reason`, only with
+    /// the `clawback` feature
+    #[cfg(feature = "clawback")]
+    Clawback {
+        from: String,
+        amount: Uint128,
+        reason: String,
+    },
+}
+
+/// Messages only the chain governance can send
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Queues a payment of `amount` from the insurance fund to `recipient`, executable after
+    /// `insurance::INSURANCE_WITHDRAWAL_DELAY`. `reason` is kept in the withdrawal history
+    QueueInsuranceWithdrawal {
+        recipient: String,
+        amount: Uint128,
+        reason: String,
+    },
+    /// Sets the address `EmergencyDrain` sweeps the fee buckets to
+    SetRescueAddress { address: String },
+    /// Sweeps every pending fee bucket to the rescue address, never holder balances. Meant for a
+    /// bug in the distribution logic, `reason` is emitted with the audit events
+    EmergencyDrain { reason: String },
+}
+
+/// Actions a holder can delegate to an operator
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantAction {
+    Transfer,
+    Send,
+    Burn,
+    /// Claims the pending reflections of the holder to its own balance, the cap bounds the amount
+    /// claimed
+    Claim,
+    /// Stakes in `vault` only, a `Send` of `{"stake":{}}` to it
+    Stake {
+        vault: String,
+    },
+}
+
+impl GrantAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrantAction::Transfer => "transfer",
+            GrantAction::Send => "send",
+            GrantAction::Burn => "burn",
+            GrantAction::Claim => "claim",
+            GrantAction::Stake { .. } => "stake",
+        }
+    }
+}
+
+/// Messages an operator can execute on behalf of a holder, each requires a grant for its action
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    Burn {
+        amount: Uint128,
+    },
+    ClaimReflections {},
+    Stake {
+        vault: String,
+        amount: Uint128,
+    },
+}
+
+/// Admin actions that can be scheduled, they run with the checks of their immediate counterpart
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    SetTaxRate {
+        global_rate: Decimal,
+        reflection_rate: Decimal,
+        burn_rate: Decimal,
+        antiwhale_rate: Decimal,
+    },
+    SetWhitelist {
+        user: String,
+        enable: bool,
+    },
+    /// Fires `Liquify` on the treasury
+    LiquifyTreasury {},
+}
+
+/// Allowance signed off-chain by `owner`, see `ExecuteMsg::Permit`. `nonce` must be the next permit
+/// nonce of the owner, returned by `SignatureDomain`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PermitMsg {
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub expires: Option<Expiration>,
+    pub nonce: u64,
+}
+
+/// Block height window of `ExecuteMsg::Timed`, both ends included
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema, Debug)]
+pub struct ExecutionBounds {
+    pub not_before_height: Option<u64>,
+    pub not_after_height: Option<u64>,
+}
+
+/// What happens to the reflections withheld by an accrual pause, see `ExecuteMsg::ReleaseAccrual`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccrualOutcome {
+    /// Credited to the address as if it never was paused
+    Credit,
+    /// Sent to the treasury
+    Forfeit,
+}
+
+/// Messages a trusted forwarder can relay on behalf of a user
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
 }
 
 impl InstantiateMsg {
@@ -131,44 +710,65 @@ impl InstantiateMsg {
         self.mint.as_ref().and_then(|v| v.cap)
     }
 
-    pub fn validate(&self) -> StdResult<()> {
-        // Check name, symbol, decimals
-        if !is_valid_name(&self.name) {
-            return Err(StdError::generic_err(
-                "Name is not in the expected format (3-50 UTF-8 bytes)",
-            ));
+    /// `treasury_code_id`, or `cw20_code_id` in the legacy shape
+    pub fn treasury_code_id(&self) -> u64 {
+        self.cw20_code_id.unwrap_or(self.treasury_code_id)
+    }
+
+    /// Checks the message with the typed errors of `dojoswap::token`, so that callers such as the
+    /// factory can match on the reason
+    pub fn validate(&self, api: &dyn Api) -> Result<(), InstantiateMsgError> {
+        validate_token_info(
+            api,
+            &ValidationConfig::default(),
+            &self.name,
+            &self.symbol,
+            self.decimals,
+            &self.initial_balances,
+            self.mint.as_ref(),
+            self.marketing
+                .as_ref()
+                .and_then(|marketing| marketing.logo.as_ref()),
+        )?;
+        match (self.treasury_code_id, self.cw20_code_id) {
+            (0, None) => {
+                return Err(StdError::generic_err("treasury_code_id is required").into());
+            }
+            (0, Some(_)) | (_, None) => {}
+            (_, Some(_)) => {
+                return Err(StdError::generic_err(
+                    "cw20_code_id is the deprecated name of treasury_code_id, set only one",
+                )
+                .into());
+            }
         }
-        if !is_valid_symbol(&self.symbol) {
-            return Err(StdError::generic_err(
-                "Ticker symbol is not in expected format [a-zA-Z\\-]{3,12}",
-            ));
+        if self
+            .marketing
+            .as_ref()
+            .and_then(|marketing| marketing.display_decimals)
+            .is_some_and(|display_decimals| display_decimals > self.decimals)
+        {
+            return Err(InstantiateMsgError::InvalidDisplayDecimals {});
         }
-        if self.decimals > 18 {
-            return Err(StdError::generic_err("Decimals must not exceed 18"));
+        if self.max_tax_rate.is_some_and(|rate| rate > Decimal::one())
+            || self.max_tax_step.is_some_and(|step| step > Decimal::one())
+        {
+            return Err(InstantiateMsgError::InvalidTaxLimits {});
         }
+        self.limits().validate()?;
+        validate_vesting(
+            self.vesting.as_deref().unwrap_or_default(),
+            &self.initial_balances,
+        )?;
         Ok(())
     }
-}
 
-fn is_valid_name(name: &str) -> bool {
-    let bytes = name.as_bytes();
-    if bytes.len() < 3 || bytes.len() > 50 {
-        return false;
-    }
-    true
-}
-
-fn is_valid_symbol(symbol: &str) -> bool {
-    let bytes = symbol.as_bytes();
-    if bytes.len() < 3 || bytes.len() > 12 {
-        return false;
-    }
-    for byte in bytes.iter() {
-        if (*byte != 45) && (*byte < 65 || *byte > 90) && (*byte < 97 || *byte > 122) {
-            return false;
+    pub fn limits(&self) -> WalletLimits {
+        WalletLimits {
+            max_tx_amount: self.max_tx_amount,
+            max_wallet_amount: self.max_wallet_amount,
         }
     }
-    true
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -225,6 +825,299 @@ pub enum QueryMsg {
     GetWhitelist {
         address: String,
     },
+    /// Returns the number of holders per balance bucket. `buckets` are ascending lower bounds, each a
+    /// power of ten, the holders below the lowest bound are returned in an extra first bucket.
+    /// Defaults to one bucket per decade between the smallest and largest holder.
+    /// Return type: HolderDistributionResponse.
+    HolderDistribution {
+        buckets: Option<Vec<Uint128>>,
+    },
+    /// Returns the largest holders, their share of the supply and an approximate Gini coefficient.
+    /// Return type: ConcentrationResponse.
+    Concentration {},
+    /// Estimates the annualized reflection yield from the reflections collected over the last `window`
+    /// seconds (default 7 days, max 30 days and no more than since the first transfer) and the
+    /// eligible supply, zero outside claim mode.
+    /// Return type: ReflectionAprResponse.
+    ReflectionApr {
+        window: Option<u64>,
+    },
+    /// Returns the cumulative taxes of the transfers since the stats were introduced: how many were
+    /// taxed or exempt, and what was taxed, burnt and reflected.
+    /// Return type: TaxStatsResponse.
+    TaxStats {},
+    /// Returns in one compact response what token lists show: symbol, decimals, tax rates,
+    /// how many addresses are untaxed, whether trading is enabled and whether the token is immutable.
+    /// Return type: ReflectionSummaryResponse.
+    ReflectionSummary {},
+    /// Returns whether the address is allowed to relay messages on behalf of users.
+    /// Return type: bool.
+    IsTrustedForwarder {
+        address: String,
+    },
+    /// Returns the domain separator signatures meant for this token must be bound to, and the
+    /// next relay nonce of `address` if given.
+    /// Return type: SignatureDomainResponse.
+    SignatureDomain {
+        address: Option<String>,
+    },
+    /// Returns the operator grants given by `owner`. Supports pagination.
+    /// Return type: OperatorGrantsResponse.
+    OperatorGrants {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the drip configuration and taxes pending release to the treasury or holders.
+    /// Return type: ReflectionDripResponse.
+    ReflectionDrip {},
+    /// Returns the economic parameters of the token together with their sha256 hash,
+    /// two deployments with the same hash charge the same taxes.
+    /// Return type: ConfigHashResponse.
+    ConfigHash {},
+    /// Dry run of `MigrateAction::ApplyPreset`: the economic parameters and hash the token would
+    /// have with `preset` applied now, failing where the migration would fail.
+    /// Return type: ConfigHashResponse.
+    PresetPreview {
+        preset: String,
+    },
+    /// Returns the ceiling and the 24 hours step limit of the global tax rate, together with the
+    /// range a new rate can currently be set within.
+    /// Return type: TaxLimitsResponse.
+    TaxLimits {},
+    /// Returns the admin and whether ownership was renounced.
+    /// Return type: OwnershipResponse.
+    Ownership {},
+    /// Returns the admin, the admin proposed by `TransferOwnership` and the holder of each role.
+    /// Return type: RolesResponse.
+    Roles {},
+    /// Returns the reflections `address` can claim in claim mode.
+    /// Return type: PendingReflectionsResponse.
+    PendingReflections {
+        address: String,
+    },
+    /// Returns the reflections credited per token held in claim mode, from which dashboards derive
+    /// what any balance earned.
+    /// Return type: ReflectionIndexResponse.
+    ReflectionIndex {},
+    /// Returns the contract version, the semver of the message API and the legacy message shapes
+    /// that are still accepted but flagged with a `deprecated` attribute.
+    /// Return type: ApiVersionResponse.
+    ApiVersion {},
+    /// Returns the compile-time features the contract was built with, so that holders can verify
+    /// which variant of the token they hold.
+    /// Return type: FeaturesResponse.
+    Features {},
+    /// Returns the last claims of `address` in claim mode, oldest first.
+    /// Return type: ClaimHistoryResponse.
+    ClaimHistory {
+        address: String,
+    },
+    /// Returns the insurance rate and the funds held for insurance.
+    /// Return type: InsuranceFundResponse.
+    InsuranceFund {},
+    /// Returns the insurance withdrawals by ascending id, queued and executed.
+    /// Return type: InsuranceWithdrawalsResponse.
+    InsuranceWithdrawals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the burn trophy collection, its threshold and the number of trophies minted.
+    /// Return type: BurnTrophyResponse.
+    BurnTrophy {},
+    /// Returns the trading gate and whether trading with the pair is enabled.
+    /// Return type: TradingGateResponse.
+    TradingGate {},
+    /// Returns whether `address` is custodial and the reflections routed away from it.
+    /// Return type: CustodialResponse.
+    Custodial {
+        address: String,
+    },
+    /// Returns the accrual pause of `address` with the reflections withheld so far.
+    /// Return type: AccrualPauseResponse.
+    AccrualPause {
+        address: String,
+    },
+    /// Returns the labelled addresses ordered by address.
+    /// Return type: AddressLabelsResponse.
+    AddressLabels {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the sends of `sender` quarantined after their receive hook failed, by ascending id.
+    /// Return type: QuarantinedSendsResponse.
+    QuarantinedSends {
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the addresses linked to `address` and those it proposed a link to.
+    /// Return type: LinkedAddressesResponse.
+    LinkedAddresses {
+        address: String,
+    },
+    /// Returns the snapshot schedule and the retained snapshots.
+    /// Return type: SnapshotsResponse.
+    Snapshots {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the balance of `address` in snapshot `id`.
+    /// Return type: SnapshotBalanceResponse.
+    SnapshotBalance {
+        address: String,
+        id: u64,
+    },
+    /// Returns the balance of `address` at the start of block `height`, the current block by
+    /// default, including in claim mode the reflections accrued by then. For voting modules.
+    /// Return type: BalanceAtResponse.
+    BalanceAt {
+        address: String,
+        height: Option<u64>,
+    },
+    /// Returns the total supply at the start of block `height`, the current block by default.
+    /// Return type: TotalSupplyAtResponse.
+    TotalSupplyAt {
+        height: Option<u64>,
+    },
+    /// Returns the addresses whose balance changed between snapshots `from` and `to`, with the
+    /// balances in both and the change, sorted by address.
+    /// Return type: SnapshotDiffResponse.
+    SnapshotDiff {
+        from: u64,
+        to: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the balance of `address` broken down into what it holds, what accrued to it and
+    /// what it can spend right now.
+    /// Return type: EffectiveBalanceResponse.
+    EffectiveBalance {
+        address: String,
+    },
+    /// Returns the most `spender` can move out of `owner` with `TransferFrom` right now and the
+    /// limit reached first.
+    /// Return type: SpendableViaResponse.
+    SpendableVia {
+        owner: String,
+        spender: String,
+    },
+    /// Returns the decimals of the token and the fractional digits frontends should show.
+    /// Return type: DisplayInfoResponse.
+    DisplayInfo {},
+    /// Returns the scheduled sunset with its settlement pool, and what `address` would be paid by
+    /// `RedeemFinal` now.
+    /// Return type: SunsetResponse.
+    Sunset {
+        address: Option<String>,
+    },
+    /// Returns the auto-liquidity config with the tokens set aside and provided so far.
+    /// Return type: AutoLiquidityResponse.
+    AutoLiquidity {},
+    /// Returns the guardian, whether the token is paused and the frozen addresses after
+    /// `start_after`.
+    /// Return type: SecurityStatusResponse.
+    SecurityStatus {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the vesting schedule of `address` with what it still locks.
+    /// Return type: VestingInfoResponse.
+    VestingInfo {
+        address: String,
+    },
+    /// Returns the spending policy of `address` and its pending change.
+    /// Return type: SpendingPolicyResponse.
+    SpendingPolicy {
+        address: String,
+    },
+    /// Returns the co-signing rule of `address` and its pending change.
+    /// Return type: CoSignerResponse.
+    CoSigner {
+        address: String,
+    },
+    /// Returns the transfers `owner` proposed to its co-signer by ascending id.
+    /// Return type: CoSignedTransfersResponse.
+    CoSignedTransfers {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the split of the tax set with `UpdateTaxConfig`.
+    /// Return type: TaxConfigResponse.
+    TaxConfig {},
+    /// Returns the tax strategy set with `SetTaxStrategy` with its parameters, the global rate and
+    /// the rate it charges now.
+    /// Return type: TaxStrategyResponse.
+    TaxStrategy {},
+    /// Returns the addresses exempted from the tax with `SetFeeExempt`.
+    /// Return type: FeeExemptionsResponse.
+    FeeExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the max transaction and max wallet amounts.
+    /// Return type: LimitsResponse.
+    Limits {},
+    /// Returns the addresses exempted from the limits with `SetLimitsExempt`.
+    /// Return type: LimitExemptionsResponse.
+    LimitExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the hashes of the hook messages `contract` is sent without tax.
+    /// Return type: TaxFreeHooksResponse.
+    TaxFreeHooks {
+        contract: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the registered bridge contract.
+    /// Return type: BridgeConfigResponse.
+    BridgeConfig {},
+    /// Returns the contracts called after every transfer, in call order.
+    /// Return type: TransferHooksResponse.
+    TransferHooks {},
+    /// Returns the funded epoch rewards.
+    /// Return type: EpochRewardsResponse.
+    EpochRewards {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns what `address` claimed and can still claim of the reward of `epoch`.
+    /// Return type: EpochRewardClaimResponse.
+    EpochRewardClaim {
+        epoch: u64,
+        address: String,
+    },
+    /// Returns the raw entries of the storage map saved under `namespace`, only with the `debug`
+    /// feature.
+    /// Return type: RawRangeResponse.
+    #[cfg(feature = "debug")]
+    RawRange {
+        namespace: String,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// Returns the effective cost of buying tokens with `amount` of the quote asset of `pair` and
+    /// selling them right back, token taxes and live pair commissions included.
+    /// Return type: SimulateSwapRoundTripResponse.
+    SimulateSwapRoundTrip {
+        pair: String,
+        amount: Uint128,
+    },
+    /// Returns the address governance designated for emergency drains.
+    /// Return type: RescueAddressResponse.
+    RescueAddress {},
+    /// Returns the pending scheduled actions by ascending id.
+    /// Return type: ScheduledActionsResponse.
+    ScheduledActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the receipts of the last scheduled actions run, oldest first.
+    /// Return type: ScheduleReceiptsResponse.
+    ScheduleReceipts {},
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
@@ -233,11 +1126,551 @@ pub struct QueryTaxResponse {
     pub after_tax: Uint128,
     pub reflection_amount: Uint128,
     pub liquidity_amount: Uint128,
+    /// Burnt by the token on transfer under a tax config, by the treasury otherwise
+    #[serde(default)]
+    pub burn_amount: Uint128,
+    /// Credited to the treasury address of the tax config
+    #[serde(default)]
+    pub destination_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ReflectionSummaryResponse {
+    pub symbol: String,
+    pub decimals: u8,
+    /// The tax does not depend on the direction of the transfer, buys, sells and transfers are
+    /// taxed at the current rate of the tax strategy
+    pub buy_tax: Decimal,
+    pub sell_tax: Decimal,
+    pub transfer_tax: Decimal,
+    /// Whitelisted and fee exempt addresses, the bridge included
+    pub exemptions: u32,
+    /// False before the trading gate opens and while the token is paused
+    pub trading_enabled: bool,
+    pub immutable: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxStatsResponse {
+    pub stats: TaxStats,
+    /// Average tax rate of the taxed transfers
+    pub effective_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HolderBucket {
+    /// Inclusive lower bound of the bucket
+    pub min: Uint128,
+    /// Exclusive upper bound of the bucket, None for the last bucket
+    pub max: Option<Uint128>,
+    pub holders: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HolderDistributionResponse {
+    pub total_holders: u64,
+    pub buckets: Vec<HolderBucket>,
+    /// Block height since which every holder is counted, None while the holders of a migrated
+    /// token are backfilled, see `BackfillDistribution`
+    pub tracked_since: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConcentrationResponse {
+    /// Largest holders by descending balance, the addresses excluded from reflections are not
+    /// ranked
+    pub top_holders: Vec<Cw20Coin>,
+    /// Share of the eligible supply, the supply minus the balances excluded from reflections, held
+    /// by `top_holders`
+    pub top_holders_share: Decimal,
+    /// Gini coefficient over the holder distribution buckets, between 0 (equal) and 1 (concentrated)
+    pub gini: Decimal,
+    /// Block height since which every holder is counted, None while the holders of a migrated
+    /// token are backfilled, see `BackfillDistribution`
+    pub tracked_since: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReflectionAprResponse {
+    /// Number of seconds actually covered by the estimate
+    pub window: u64,
+    /// Reflections collected over the window
+    pub reflected: Uint128,
+    pub eligible_supply: Uint128,
+    /// Annualized yield, 0.12 refers to 12% a year. Zero unless `mode` is claim, the reflections
+    /// then go to the treasury
+    pub apr: Decimal,
+    pub mode: ReflectionMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorInfo {
+    pub operator: String,
+    pub grants: Vec<OperatorGrant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorGrantsResponse {
+    pub operators: Vec<OperatorInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReflectionDripResponse {
+    /// Drip period in seconds, 0 when disabled
+    pub period: u64,
+    /// Taxes not yet released to the treasury or, in claim mode, spread to holders
+    pub pending: Uint128,
+    /// Part of `pending` that would be released by the next execution
+    pub releasable: Uint128,
+    /// Time at which `pending` is fully released, the end of the last collection
+    pub end: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct EconomicParams {
+    pub tax_rate: Decimal,
+    pub reflection_rate: Decimal,
+    pub burn_rate: Decimal,
+    pub antiwhale_rate: Decimal,
+    pub drip_period: u64,
+    pub max_tax_rate: Decimal,
+    pub max_tax_step: Decimal,
+    pub reflection_mode: ReflectionMode,
+    pub insurance_rate: Decimal,
+    /// True once ownership was renounced, none of the above can change anymore
+    pub immutable: bool,
+    /// Split of the tax replacing the reflection and burn rates, left out of the hash when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_config: Option<TaxConfig>,
+    /// Max transaction and max wallet amounts, left out of the hash when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<WalletLimits>,
+    /// Strategy choosing the tax rate of transfers, left out of the hash when flat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_strategy: Option<TaxStrategyConfig>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
-pub struct MigrateMsg {
-    pub msg: String,
+pub struct PendingReflectionsResponse {
+    pub reflection_mode: ReflectionMode,
+    pub pending: Uint128,
+    /// Reflections claimed so far, what the address earned is `pending + claimed`
+    #[serde(default)]
+    pub claimed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReflectionIndexResponse {
+    pub reflection_mode: ReflectionMode,
+    /// Reflections credited per token held since the first one, a holder accrues its balance
+    /// times the growth of the index while it holds it
+    pub per_token: Decimal,
+    /// Reflections too small to move the index yet, carried over into the next credit
+    pub undistributed: Uint128,
+    /// Supply accruing reflections
+    pub eligible_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ClaimRecord {
+    pub amount: Uint128,
+    /// Reflections credited per token held over the period
+    pub rate: Decimal,
+    /// Start of the period covered: the previous claim, or the first reflection credited by the token
+    pub from: u64,
+    /// Time of the claim
+    pub to: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ClaimHistoryResponse {
+    pub claims: Vec<ClaimRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SignatureDomainResponse {
+    /// Version tag of the domain construction, see `signing::domain_separator`
+    pub tag: String,
+    pub chain_id: String,
+    pub contract: String,
+    /// Hex encoded domain separator
+    pub separator: String,
+    pub next_relay_nonce: u64,
+    pub next_permit_nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct InsuranceFundResponse {
+    pub rate: Decimal,
+    /// Funds held by the token contract for insurance, including queued withdrawals
+    pub balance: Uint128,
+    /// Part of `balance` owed to queued withdrawals
+    pub queued: Uint128,
+    /// Seconds between queuing a withdrawal and executing it
+    pub delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct InsuranceWithdrawalsResponse {
+    pub withdrawals: Vec<InsuranceWithdrawal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ApiVersionResponse {
+    /// cw2 contract name and version
+    pub contract: String,
+    pub version: String,
+    pub api_version: String,
+    pub deprecated: Vec<DeprecatedVariant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct FeaturesResponse {
+    /// The issuer can claw back tokens from any address
+    pub clawback: bool,
+    /// Raw storage queries are exposed
+    pub debug: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct DeprecatedVariant {
+    /// Value of the `deprecated` attribute emitted when the variant is used
+    pub variant: String,
+    pub replacement: String,
+    /// API version from which the variant is rejected
+    pub removed_in: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BurnTrophyResponse {
+    pub collection: Option<String>,
+    pub threshold: Uint128,
+    /// Trophy mints requested, including the ones the collection rejected
+    pub minted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TradingGateResponse {
+    pub pair: Option<String>,
+    pub min_token_reserve: Uint128,
+    pub min_quote_reserve: Uint128,
+    /// True when no gate is set
+    pub enabled: bool,
+    pub enabled_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct CustodialResponse {
+    pub custodial: bool,
+    pub since: Option<u64>,
+    /// Reflections accrued by the address and sent to the treasury instead
+    pub routed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct AccrualPauseResponse {
+    pub pause: Option<AccrualPause>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AddressLabel {
+    pub address: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct AddressLabelsResponse {
+    pub labels: Vec<AddressLabel>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct QuarantinedSendsResponse {
+    /// Whether failing sends are currently quarantined
+    pub enabled: bool,
+    pub sends: Vec<QuarantinedSend>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SnapshotSchedule {
+    /// Blocks between two snapshots, they are taken at multiples of it
+    pub interval: u64,
+    /// Number of snapshots kept, older ones are pruned
+    pub retain: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SnapshotsResponse {
+    pub schedule: Option<SnapshotSchedule>,
+    pub snapshots: Vec<Snapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SnapshotBalanceResponse {
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BalanceDelta {
+    pub address: String,
+    /// Balance in the `from` snapshot
+    pub before: Uint128,
+    /// Balance in the `to` snapshot
+    pub after: Uint128,
+    pub delta: Int128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SnapshotDiffResponse {
+    pub changes: Vec<BalanceDelta>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RawEntry {
+    /// Key within the namespace
+    pub key: Binary,
+    pub value: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RawRangeResponse {
+    pub entries: Vec<RawEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SunsetResponse {
+    /// None without a sunset scheduled
+    pub sunset: Option<Sunset>,
+    pub active: bool,
+    /// Tokens sharing the pool, as recorded at the activation or as it would be now
+    pub redeemable_supply: Uint128,
+    /// Share of the pool of `address`
+    pub payout: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AutoLiquidityResponse {
+    pub config: Option<AutoLiquidityConfig>,
+    pub state: AutoLiquidityState,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FrozenAddress {
+    pub address: String,
+    /// Time it was frozen
+    pub since: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SecurityStatusResponse {
+    pub guardian: Option<String>,
+    pub paused: bool,
+    /// Time of the `Pause`
+    pub paused_at: Option<u64>,
+    pub frozen: Vec<FrozenAddress>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DisplayInfoResponse {
+    pub decimals: u8,
+    /// Fractional digits to show, see `dojoswap::amount::format_amount`
+    pub display_decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct VestingInfoResponse {
+    /// None without a schedule or once it was fully released and removed
+    pub vesting: Option<Vesting>,
+    pub locked: Uint128,
+    /// Time of the next release
+    pub next_release: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EffectiveBalanceResponse {
+    /// Balance as reported by `Balance`
+    pub balance: Uint128,
+    /// Reflections accrued in claim mode, not in the balance until claimed
+    pub pending_reflections: Uint128,
+    /// Sends whose receive hook failed, held by the token until claimed back
+    pub quarantined: Uint128,
+    /// Part of the balance locked until a later time
+    pub locked: Uint128,
+    /// Whole balance while the address is frozen by the guardian
+    pub frozen: Uint128,
+    /// Part of the balance that can be transferred now
+    pub spendable: Uint128,
+    /// Balance, pending reflections and quarantined sends together
+    pub total: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendLimit {
+    /// The spendable balance of the owner
+    Balance,
+    /// What the spender is allowed to move, zero once expired
+    Allowance,
+    /// Share of the supply a transfer must stay below unless the spender is whitelisted
+    AntiWhale,
+    /// Max transaction amount unless the owner is exempt
+    MaxTxAmount,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SpendableViaResponse {
+    /// Most the spender can move out of the owner right now, before the limits depending on the
+    /// recipient
+    pub spendable: Uint128,
+    /// Limit `spendable` is set by, the first one listed in `SpendLimit` on a tie
+    pub limiting_factor: SpendLimit,
+    /// Spendable balance of the owner, see `EffectiveBalance`
+    pub balance: Uint128,
+    /// Allowance of the spender, zero once expired
+    pub allowance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SpendingPolicyResponse {
+    /// Policy in force
+    pub policy: Option<String>,
+    /// Change of the policy waiting for its delay
+    pub pending: Option<PolicyChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CoSignerResponse {
+    /// Rule in force
+    pub rule: Option<CoSignRule>,
+    /// Change of the rule waiting for its delay
+    pub pending: Option<CoSignRuleChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CoSignedTransfersResponse {
+    pub transfers: Vec<CoSignedTransfer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EpochRewardsResponse {
+    pub rewards: Vec<EpochReward>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EpochRewardClaimResponse {
+    pub claimed: Uint128,
+    /// Zero once the reward expired
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct LinkedAddressesResponse {
+    pub linked: Vec<String>,
+    /// Links proposed by the address and not accepted yet
+    pub proposed: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SimulateSwapRoundTripResponse {
+    /// Quote asset offered for the buy
+    pub offer_amount: Uint128,
+    /// Tokens kept by the pair as commission and lost to the spread on the buy
+    pub buy_commission: Uint128,
+    pub buy_spread: Uint128,
+    /// Token tax of the transfer from the pair to the buyer
+    pub buy_tax: Uint128,
+    pub tokens_received: Uint128,
+    /// Token tax of the transfer from the seller to the pair
+    pub sell_tax: Uint128,
+    /// Quote asset kept by the pair as commission and lost to the spread on the sell
+    pub sell_commission: Uint128,
+    pub sell_spread: Uint128,
+    /// Quote asset received back
+    pub return_amount: Uint128,
+    /// `offer_amount - return_amount`
+    pub cost: Uint128,
+    pub cost_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct RescueAddressResponse {
+    pub address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ScheduledActionsResponse {
+    pub actions: Vec<ScheduledEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ScheduleReceiptsResponse {
+    pub receipts: Vec<ActionReceipt>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct OwnershipResponse {
+    /// None once ownership was renounced
+    pub admin: Option<String>,
+    pub immutable: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct RolesResponse {
+    pub admin: Option<String>,
+    /// Proposed admin which did not accept yet, expired proposals included
+    pub pending_admin: Option<PendingAdmin>,
+    pub tax_manager: Option<String>,
+    pub exemption_manager: Option<String>,
+    pub guardian: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxLimitsResponse {
+    pub max_tax_rate: Decimal,
+    pub max_tax_step: Decimal,
+    /// Period in seconds over which the global tax rate moves by at most `max_tax_step`
+    pub period: u64,
+    /// Global tax rate `period` seconds ago, the step limit applies to it
+    pub reference_rate: Decimal,
+    pub min_allowed: Decimal,
+    pub max_allowed: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BalanceAtResponse {
+    pub balance: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TotalSupplyAtResponse {
+    pub total_supply: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ConfigHashResponse {
+    /// Hex encoded sha256 of the JSON serialization of `params`
+    pub hash: String,
+    pub params: EconomicParams,
+}
+
+/// Migration of the token: a code upgrade only, or an upgrade also running a `MigrateAction`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(untagged)]
+pub enum MigrateMsg {
+    Action(MigrateAction),
+    /// The shape migrations were sent in before the actions, `msg` is ignored
+    Upgrade {
+        msg: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrateAction {
+    /// Reconfigures the economic parameters from the `dojoswap::presets` library in the migration,
+    /// `PresetPreview` reports the resulting config beforehand
+    ApplyPreset { preset: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -245,3 +1678,94 @@ pub struct MigrateMsg {
 pub enum TreasuryExecuteMsg {
     Liquify {},
 }
+
+/// Query answered by the spending policy contract of a holder before each transfer out of it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendingPolicyQueryMsg {
+    /// Return type: SpendingVerdict.
+    Allow {
+        owner: String,
+        to: String,
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpendingVerdict {
+    pub allowed: bool,
+    /// Why the transfer is rejected, reported in the error
+    pub reason: Option<String>,
+}
+
+/// Message received by the contract of a `GatedForward`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GatedForwardExecuteMsg {
+    GatedForward(GatedForwardMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GatedForwardMsg {
+    /// Holder who forwarded the message
+    pub sender: String,
+    /// Balance of the holder when the message was forwarded
+    pub balance: Uint128,
+    pub msg: Binary,
+}
+
+/// Mint message of a cw721 collection, see `trophy::burn_trophy`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Empty,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxConfigResponse {
+    pub tax_config: Option<TaxConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxStrategyResponse {
+    pub strategy: TaxStrategyConfig,
+    pub global_rate: Decimal,
+    /// Rate of a transfer below the first bracket and without volume at the current block time
+    pub current_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct FeeExemptionsResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct LimitsResponse {
+    pub max_tx_amount: Option<Uint128>,
+    pub max_wallet_amount: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct LimitExemptionsResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxFreeHooksResponse {
+    pub msg_hashes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BridgeConfigResponse {
+    pub bridge: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TransferHooksResponse {
+    pub hooks: Vec<String>,
+}
@@ -0,0 +1,96 @@
+use cosmwasm_std::{to_json_vec, Binary, DepsMut, Env, MessageInfo, Response, StdError};
+use cw20::AllowanceResponse;
+use cw20_base::state::ALLOWANCES;
+use cw20_base::ContractError;
+
+use crate::msg::PermitMsg;
+use crate::signing::{address_of_public_key, domain_separator, verify_adr36_signature, KeyType};
+use crate::state::PERMIT_NONCES;
+
+/// Sets the allowance signed by `permit.owner`. The owner is authenticated by its public key of
+/// `key_type`, whose address must be the owner, and its ADR-36 signature of the permit. Each
+/// permit consumes the next permit nonce of the owner, so it cannot be submitted twice
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    permit: PermitMsg,
+    public_key: Binary,
+    signature: Binary,
+    key_type: KeyType,
+) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&permit.owner)?;
+    let spender = deps.api.addr_validate(&permit.spender)?;
+    if owner == spender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if permit
+        .expires
+        .is_some_and(|expires| expires.is_expired(&env.block))
+    {
+        return Err(ContractError::Expired {});
+    }
+
+    let hrp = match permit.owner.rsplit_once('1') {
+        Some((hrp, _)) if !hrp.is_empty() => hrp,
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Permit owner is not a bech32 address",
+            )))
+        }
+    };
+    if address_of_public_key(hrp, &public_key, key_type)? != permit.owner {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Permit public key does not match the owner",
+        )));
+    }
+    let separator = domain_separator(&env.block.chain_id, env.contract.address.as_str());
+    if !verify_adr36_signature(
+        deps.api,
+        &separator,
+        &permit.owner,
+        &to_json_vec(&permit)?,
+        &signature,
+        &public_key,
+        key_type,
+    )? {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Invalid permit signature",
+        )));
+    }
+
+    let expected = PERMIT_NONCES
+        .may_load(deps.storage, &owner)?
+        .unwrap_or_default();
+    if permit.nonce != expected {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Invalid permit nonce {}, expected {}",
+            permit.nonce, expected
+        ))));
+    }
+    PERMIT_NONCES.save(deps.storage, &owner, &(expected + 1))?;
+
+    // like an approval by the owner, the signed amount replaces the current allowance
+    let expires = permit.expires.unwrap_or_default();
+    if permit.amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, (&owner, &spender));
+    } else {
+        ALLOWANCES.save(
+            deps.storage,
+            (&owner, &spender),
+            &AllowanceResponse {
+                allowance: permit.amount,
+                expires,
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "permit")
+        .add_attribute("owner", permit.owner)
+        .add_attribute("spender", permit.spender)
+        .add_attribute("amount", permit.amount)
+        .add_attribute("expires", expires.to_string())
+        .add_attribute("nonce", permit.nonce.to_string())
+        .add_attribute("submitter", info.sender))
+}
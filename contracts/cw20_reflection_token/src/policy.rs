@@ -0,0 +1,113 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::ContractError;
+
+use crate::fees::DAY;
+use crate::msg::{SpendingPolicyQueryMsg, SpendingPolicyResponse, SpendingVerdict};
+use crate::state::{PolicyChange, PENDING_POLICY_CHANGES, SPENDING_POLICIES};
+
+/// Seconds before replacing or removing a spending policy takes effect, so that a stolen key
+/// cannot lift a limit the holder imposed on itself
+pub const SPENDING_POLICY_DELAY: u64 = DAY;
+
+/// Attaches `policy` to the account of `info.sender`, the contract is queried before every
+/// transfer out of it. The first policy applies at once, replacing or removing it is delayed
+pub fn set_spending_policy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    policy: Option<String>,
+) -> Result<Response, ContractError> {
+    let policy = policy
+        .map(|policy| deps.api.addr_validate(&policy))
+        .transpose()?;
+    let now = env.block.time.seconds();
+    // a pending change already in force becomes the current policy
+    let current = spending_policy(deps.storage, &info.sender, now)?;
+    PENDING_POLICY_CHANGES.remove(deps.storage, &info.sender);
+
+    let effective_at = match (&current, &policy) {
+        (None, None) => {
+            SPENDING_POLICIES.remove(deps.storage, &info.sender);
+            now
+        }
+        (None, Some(policy)) => {
+            SPENDING_POLICIES.save(deps.storage, &info.sender, policy)?;
+            now
+        }
+        (Some(current), _) => {
+            SPENDING_POLICIES.save(deps.storage, &info.sender, current)?;
+            let change = PolicyChange {
+                policy: policy.clone(),
+                effective_at: now + SPENDING_POLICY_DELAY,
+            };
+            PENDING_POLICY_CHANGES.save(deps.storage, &info.sender, &change)?;
+            change.effective_at
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "set_spending_policy")
+        .add_attribute("owner", info.sender)
+        .add_attribute(
+            "policy",
+            policy.map(|policy| policy.to_string()).unwrap_or_default(),
+        )
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+/// Policy of `owner` in force at `now`, a pending change counts once its delay passed
+fn spending_policy(storage: &dyn Storage, owner: &Addr, now: u64) -> StdResult<Option<Addr>> {
+    if let Some(change) = PENDING_POLICY_CHANGES.may_load(storage, owner)? {
+        if now >= change.effective_at {
+            return Ok(change.policy);
+        }
+    }
+    SPENDING_POLICIES.may_load(storage, owner)
+}
+
+/// Asks the spending policy of `owner`, if any, whether `amount` may go to `to`
+pub fn ensure_spending_allowed(
+    deps: &DepsMut,
+    env: &Env,
+    owner: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let owner = Addr::unchecked(owner);
+    let policy = match spending_policy(deps.storage, &owner, env.block.time.seconds())? {
+        Some(policy) => policy,
+        None => return Ok(()),
+    };
+    let verdict: SpendingVerdict = deps.querier.query_wasm_smart(
+        policy,
+        &SpendingPolicyQueryMsg::Allow {
+            owner: owner.to_string(),
+            to: to.to_string(),
+            amount,
+        },
+    )?;
+    if !verdict.allowed {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Spending policy rejected the transfer: {}",
+            verdict.reason.unwrap_or_default()
+        ))));
+    }
+    Ok(())
+}
+
+pub fn query_spending_policy(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<SpendingPolicyResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let now = env.block.time.seconds();
+    Ok(SpendingPolicyResponse {
+        policy: spending_policy(deps.storage, &address, now)?.map(|policy| policy.to_string()),
+        pending: PENDING_POLICY_CHANGES
+            .may_load(deps.storage, &address)?
+            .filter(|change| now < change.effective_at),
+    })
+}
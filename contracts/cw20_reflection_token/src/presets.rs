@@ -0,0 +1,64 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Response, StdError, StdResult, Storage};
+use cw20_base::ContractError;
+use dojoswap::presets::{find_preset, TokenPreset};
+
+use crate::contract::{apply_tax_rate, config_hash, query_config_hash};
+use crate::drip::save_drip_period;
+use crate::msg::{ConfigHashResponse, EconomicParams};
+use crate::rate_limit::ensure_tax_rate_allowed;
+use crate::state::{IMMUTABLE, INSURANCE_RATE, TAX_CONFIG, TAX_RATE_BEFORE_CONFIG};
+
+/// Preset published under `name`, the economic parameters of a token whose ownership was renounced
+/// are locked even for the code admin
+fn load_preset(storage: &dyn Storage, name: &str) -> StdResult<TokenPreset> {
+    if IMMUTABLE.may_load(storage)?.unwrap_or_default() {
+        return Err(StdError::generic_err(
+            "Ownership was renounced, the economic parameters are locked",
+        ));
+    }
+    find_preset(name).ok_or_else(|| StdError::generic_err(format!("Unknown preset {}", name)))
+}
+
+/// Economic parameters once `preset` is applied. The preset rates replace the tax config, the
+/// parameters it does not cover (tax ceiling and step, reflection mode, limits) are kept
+fn preset_params(storage: &dyn Storage, preset: &TokenPreset) -> StdResult<EconomicParams> {
+    let params = query_config_hash(storage)?.params;
+    Ok(EconomicParams {
+        tax_rate: preset.tax_rate,
+        reflection_rate: preset.reflection_rate,
+        burn_rate: preset.burn_rate,
+        antiwhale_rate: preset.antiwhale_rate,
+        drip_period: preset.drip_period,
+        insurance_rate: preset.insurance_rate,
+        tax_config: None,
+        ..params
+    })
+}
+
+/// Migration applying `preset`, the tax rate still moves within the max tax rate and step
+pub fn apply_preset(deps: DepsMut, env: Env, preset: String) -> Result<Response, ContractError> {
+    let preset = load_preset(deps.storage, &preset)?;
+    TAX_CONFIG.remove(deps.storage);
+    TAX_RATE_BEFORE_CONFIG.remove(deps.storage);
+    apply_tax_rate(
+        deps.storage,
+        &env,
+        preset.tax_rate,
+        preset.reflection_rate,
+        preset.burn_rate,
+        preset.antiwhale_rate,
+    )?;
+    save_drip_period(deps.storage, &env, preset.drip_period)?;
+    INSURANCE_RATE.save(deps.storage, &preset.insurance_rate)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "apply_preset")
+        .add_attribute("preset", preset.name)
+        .add_attribute("config_hash", query_config_hash(deps.storage)?.hash))
+}
+
+pub fn query_preset_preview(deps: Deps, env: Env, preset: String) -> StdResult<ConfigHashResponse> {
+    let preset = load_preset(deps.storage, &preset)?;
+    ensure_tax_rate_allowed(deps.storage, &env, preset.tax_rate)?;
+    config_hash(preset_params(deps.storage, &preset)?)
+}
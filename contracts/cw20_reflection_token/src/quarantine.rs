@@ -0,0 +1,147 @@
+use cosmwasm_std::{
+    Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResult, Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::QuarantinedSendsResponse;
+use crate::state::{
+    PendingHook, QuarantinedSend, HOOK_QUARANTINE, NEXT_QUARANTINE_ID, PENDING_HOOKS,
+    QUARANTINED_SENDS,
+};
+
+/// Reply id of the receive hooks of sends while the quarantine is enabled
+pub const HOOK_QUARANTINE_REPLY_ID: u64 = 3;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// With the quarantine enabled, a `Send` whose receiving contract fails is not reverted: the net
+/// amount sent is held by the token until the sender claims it back
+pub fn set_hook_quarantine(
+    deps: DepsMut,
+    info: MessageInfo,
+    enable: bool,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    HOOK_QUARANTINE.save(deps.storage, &enable)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_hook_quarantine")
+        .add_attribute("enable", enable.to_string()))
+}
+
+/// Submessage calling the receive hook of a send of `amount` from `sender` to `contract`. With the
+/// quarantine enabled it replies either way, sends nest so their hooks are kept in a stack
+pub fn receive_hook(
+    storage: &mut dyn Storage,
+    hook: CosmosMsg,
+    sender: &Addr,
+    contract: &Addr,
+    amount: Uint128,
+) -> StdResult<SubMsg> {
+    if !HOOK_QUARANTINE.may_load(storage)?.unwrap_or_default() {
+        return Ok(SubMsg::new(hook));
+    }
+    let mut pending = PENDING_HOOKS.may_load(storage)?.unwrap_or_default();
+    pending.push(PendingHook {
+        sender: sender.clone(),
+        contract: contract.clone(),
+        amount,
+    });
+    PENDING_HOOKS.save(storage, &pending)?;
+
+    Ok(SubMsg::reply_always(hook, HOOK_QUARANTINE_REPLY_ID))
+}
+
+/// Quarantines the net amount of a send whose receive hook failed, taking it back from the
+/// receiving contract whose own changes were reverted with the hook
+pub fn hook_replied(
+    storage: &mut dyn Storage,
+    env: &Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut pending = PENDING_HOOKS.may_load(storage)?.unwrap_or_default();
+    let hook = pending
+        .pop()
+        .ok_or_else(|| StdError::generic_err("No pending receive hook"))?;
+    PENDING_HOOKS.save(storage, &pending)?;
+    let reason = match result {
+        SubMsgResult::Ok(_) => return Ok(Response::default()),
+        SubMsgResult::Err(reason) => reason,
+    };
+
+    decrease_balance(storage, &hook.contract, hook.amount)?;
+    increase_balance(storage, &env.contract.address, hook.amount)?;
+    let id = NEXT_QUARANTINE_ID.may_load(storage)?.unwrap_or(1);
+    NEXT_QUARANTINE_ID.save(storage, &(id + 1))?;
+    QUARANTINED_SENDS.save(
+        storage,
+        (&hook.sender, id),
+        &QuarantinedSend {
+            id,
+            contract: hook.contract.clone(),
+            amount: hook.amount,
+            reason: reason.clone(),
+            quarantined_at: env.block.time.seconds(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "quarantine_send")
+        .add_attribute("id", id.to_string())
+        .add_attribute("sender", hook.sender)
+        .add_attribute("contract", hook.contract)
+        .add_attribute("amount", hook.amount)
+        .add_attribute("reason", reason))
+}
+
+/// Gives a quarantined send back to its sender
+pub fn execute_claim_quarantined(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let send = QUARANTINED_SENDS
+        .may_load(deps.storage, (&info.sender, id))?
+        .ok_or_else(|| StdError::generic_err("No quarantined send with this id"))?;
+    QUARANTINED_SENDS.remove(deps.storage, (&info.sender, id));
+    decrease_balance(deps.storage, &env.contract.address, send.amount)?;
+    increase_balance(deps.storage, &info.sender, send.amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_quarantined")
+        .add_attribute("id", id.to_string())
+        .add_attribute("to", info.sender)
+        .add_attribute("amount", send.amount))
+}
+
+pub fn query_quarantined_sends(
+    deps: Deps,
+    sender: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<QuarantinedSendsResponse> {
+    let sender = deps.api.addr_validate(&sender)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let sends = QUARANTINED_SENDS
+        .prefix(&sender)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, send)| send))
+        .collect::<StdResult<_>>()?;
+
+    Ok(QuarantinedSendsResponse {
+        enabled: HOOK_QUARANTINE.may_load(deps.storage)?.unwrap_or_default(),
+        sends,
+    })
+}
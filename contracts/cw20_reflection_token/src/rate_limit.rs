@@ -0,0 +1,84 @@
+use cosmwasm_std::{Decimal, Env, StdError, StdResult, Storage};
+
+use crate::contract::TAX_RATE;
+use crate::fees::DAY;
+use crate::msg::TaxLimitsResponse;
+use crate::state::{MAX_TAX_RATE, MAX_TAX_STEP, TAX_RATE_HISTORY};
+
+/// Period over which the global tax rate moves by at most `MAX_TAX_STEP`
+pub const TAX_STEP_PERIOD: u64 = DAY;
+
+/// Global tax rate at `TAX_STEP_PERIOD` before `now`: the rate before the oldest change since then
+fn reference_rate(history: &[(u64, Decimal)], now: u64, current: Decimal) -> Decimal {
+    let since = now.saturating_sub(TAX_STEP_PERIOD);
+    history
+        .iter()
+        .find(|(time, _)| *time > since)
+        .map(|(_, before)| *before)
+        .unwrap_or(current)
+}
+
+/// Returns the range the global tax rate can be set within at `env.block.time`
+fn allowed_range(storage: &dyn Storage, env: &Env) -> StdResult<TaxLimitsResponse> {
+    let max_tax_rate = MAX_TAX_RATE.may_load(storage)?.unwrap_or(Decimal::one());
+    let max_tax_step = MAX_TAX_STEP.may_load(storage)?.unwrap_or(Decimal::one());
+    let current = TAX_RATE.may_load(storage)?.unwrap_or_default();
+    let history = TAX_RATE_HISTORY.may_load(storage)?.unwrap_or_default();
+    let reference_rate = reference_rate(&history, env.block.time.seconds(), current);
+
+    Ok(TaxLimitsResponse {
+        max_tax_rate,
+        max_tax_step,
+        period: TAX_STEP_PERIOD,
+        reference_rate,
+        min_allowed: reference_rate.saturating_sub(max_tax_step),
+        max_allowed: (reference_rate + max_tax_step).min(max_tax_rate),
+    })
+}
+
+/// Fails if `new_rate` is above the ceiling or moves the global tax rate by more than the step limit
+/// over the last period
+pub fn ensure_tax_rate_allowed(
+    storage: &dyn Storage,
+    env: &Env,
+    new_rate: Decimal,
+) -> StdResult<()> {
+    let limits = allowed_range(storage, env)?;
+    if new_rate > limits.max_tax_rate {
+        return Err(StdError::generic_err(format!(
+            "global_rate must be <= max_tax_rate {}",
+            limits.max_tax_rate
+        )));
+    }
+    if new_rate < limits.min_allowed || new_rate > limits.max_allowed {
+        return Err(StdError::generic_err(format!(
+            "global_rate must stay within {} and {} until the step period elapses",
+            limits.min_allowed, limits.max_allowed
+        )));
+    }
+    Ok(())
+}
+
+/// Checks `new_rate` with `ensure_tax_rate_allowed`, then records the change
+pub fn record_tax_rate_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    new_rate: Decimal,
+) -> StdResult<()> {
+    ensure_tax_rate_allowed(storage, env, new_rate)?;
+
+    let current = TAX_RATE.may_load(storage)?.unwrap_or_default();
+    if new_rate == current {
+        return Ok(());
+    }
+    let now = env.block.time.seconds();
+    let since = now.saturating_sub(TAX_STEP_PERIOD);
+    let mut history = TAX_RATE_HISTORY.may_load(storage)?.unwrap_or_default();
+    history.retain(|(time, _)| *time > since);
+    history.push((now, current));
+    TAX_RATE_HISTORY.save(storage, &history)
+}
+
+pub fn query_tax_limits(storage: &dyn Storage, env: &Env) -> StdResult<TaxLimitsResponse> {
+    allowed_range(storage, env)
+}
@@ -0,0 +1,401 @@
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdError,
+    StdResult, Storage, Uint128,
+};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw20_base::ContractError;
+
+use crate::checkpoints::record_balance_checkpoint;
+use crate::contract::{ensure_admin, TREASURY};
+use crate::custodial::{divert_accrual, is_custodial};
+use crate::disputes::{is_accrual_paused, withhold_accrual};
+use crate::distribution::rank_top_holder;
+use crate::ledger::{decrease_balance, increase_balance};
+use crate::msg::{
+    ClaimHistoryResponse, ClaimRecord, PendingReflectionsResponse, ReflectionIndexResponse,
+    ReflectionMode,
+};
+use crate::state::{
+    HolderReflections, ReflectionIndex, CLAIM_HISTORY, DEAD_ADDRESSES, FROZEN, HOLDER_REFLECTIONS,
+    REFLECTION_EXCLUDED, REFLECTION_INDEX, REFLECTION_MODE,
+};
+
+/// Number of claims kept per holder in `CLAIM_HISTORY`
+pub const CLAIM_HISTORY_SIZE: usize = 20;
+
+pub fn is_claim_mode(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(REFLECTION_MODE.may_load(storage)?.unwrap_or_default() == ReflectionMode::Claim)
+}
+
+/// Stops `address` from accruing reflections, its balance no longer counts towards the eligible supply.
+/// Exclusions are tracked in every reflection mode so that `eligible_supply` is always accurate.
+/// What it accrued up to now stays pending
+pub fn exclude_from_reflections(storage: &mut dyn Storage, address: &Addr) -> StdResult<()> {
+    if REFLECTION_EXCLUDED.has(storage, address) {
+        return Ok(());
+    }
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    settle_reflections(storage, address, balance, balance)?;
+    let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    index.excluded_supply = index.excluded_supply.checked_add(balance)?;
+    REFLECTION_EXCLUDED.save(storage, address, &true)?;
+    REFLECTION_INDEX.save(storage, &index)?;
+    rank_top_holder(storage, address)
+}
+
+/// Lifts the exclusion of `address`, which accrues again from now on. The token contract, the
+/// treasury and the dead addresses stay excluded
+pub fn include_in_reflections(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+) -> StdResult<()> {
+    if !REFLECTION_EXCLUDED.has(storage, address) || is_excluded_for_good(storage, env, address)? {
+        return Ok(());
+    }
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    index.excluded_supply = index.excluded_supply.checked_sub(balance)?;
+    REFLECTION_EXCLUDED.remove(storage, address);
+    let mut holder = HOLDER_REFLECTIONS
+        .may_load(storage, address)?
+        .unwrap_or_default();
+    holder.per_token = index.per_token;
+    HOLDER_REFLECTIONS.save(storage, address, &holder)?;
+    REFLECTION_INDEX.save(storage, &index)?;
+    rank_top_holder(storage, address)
+}
+
+fn is_excluded_for_good(storage: &dyn Storage, env: &Env, address: &Addr) -> StdResult<bool> {
+    Ok(*address == env.contract.address
+        || TREASURY
+            .may_load(storage)?
+            .is_some_and(|treasury| *address == treasury)
+        || DEAD_ADDRESSES.has(storage, address))
+}
+
+/// Hands what `address` accrued back to the holders with the next credit, for an address which
+/// never claims it
+fn forfeit_accrual(storage: &mut dyn Storage, address: &Addr) -> StdResult<()> {
+    if !is_claim_mode(storage)? {
+        return Ok(());
+    }
+    let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    let balance = BALANCES.may_load(storage, address)?.unwrap_or_default();
+    let mut holder = accrued(storage, &index, address, balance)?;
+    index.undistributed = index.undistributed.checked_add(holder.pending)?;
+    holder.pending = Uint128::zero();
+    HOLDER_REFLECTIONS.save(storage, address, &holder)?;
+    REFLECTION_INDEX.save(storage, &index)
+}
+
+/// Excludes an address nobody controls, e.g. where tokens are sent instead of burnt, for good
+pub fn exclude_dead_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+    if DEAD_ADDRESSES.has(deps.storage, &address) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Address is already a dead address",
+        )));
+    }
+    if !REFLECTION_EXCLUDED.has(deps.storage, &address) {
+        forfeit_accrual(deps.storage, &address)?;
+    }
+    exclude_from_reflections(deps.storage, &address)?;
+    DEAD_ADDRESSES.save(deps.storage, &address, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "exclude_dead_address")
+        .add_attribute("address", address))
+}
+
+/// Migration step for tokens from before the token contract, the treasury and the frozen
+/// addresses were excluded: excludes them, what the token contract and the treasury accrued goes
+/// back to the holders with the next credit, and recomputes the excluded supply from the balances
+pub fn sync_reflection_exclusions(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    let mut fee_holders = vec![env.contract.address.clone()];
+    if let Some(treasury) = TREASURY.may_load(storage)? {
+        fee_holders.push(Addr::unchecked(treasury));
+    }
+    for address in fee_holders {
+        if !REFLECTION_EXCLUDED.has(storage, &address) {
+            forfeit_accrual(storage, &address)?;
+            exclude_from_reflections(storage, &address)?;
+        }
+    }
+    // addresses frozen before freezing excluded them
+    let frozen = FROZEN
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    for address in frozen {
+        exclude_from_reflections(storage, &address)?;
+    }
+
+    let excluded = REFLECTION_EXCLUDED
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    index.excluded_supply = Uint128::zero();
+    for address in excluded {
+        let balance = BALANCES.may_load(storage, &address)?.unwrap_or_default();
+        index.excluded_supply = index.excluded_supply.checked_add(balance)?;
+    }
+    REFLECTION_INDEX.save(storage, &index)
+}
+
+/// Supply that receives reflections: the total supply minus the balances of the excluded
+/// addresses, i.e. the token contract (taxes dripping to the treasury, the insurance fund and
+/// reflections not claimed yet), the treasury, the dead addresses and the frozen addresses. Burnt
+/// tokens already left the total supply. Every computation of the eligible supply must go through
+/// this function
+pub fn eligible_supply(storage: &dyn Storage) -> StdResult<Uint128> {
+    let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+    let excluded_supply = REFLECTION_INDEX
+        .may_load(storage)?
+        .unwrap_or_default()
+        .excluded_supply;
+
+    Ok(total_supply.saturating_sub(excluded_supply))
+}
+
+/// Reflections of `address` once the accrual of `balance` up to the current index is settled,
+/// excluded addresses accrue nothing after their exclusion
+pub fn accrued(
+    storage: &dyn Storage,
+    index: &ReflectionIndex,
+    address: &Addr,
+    balance: Uint128,
+) -> StdResult<HolderReflections> {
+    let mut holder = HOLDER_REFLECTIONS
+        .may_load(storage, address)?
+        .unwrap_or_default();
+    if REFLECTION_EXCLUDED.has(storage, address) {
+        return Ok(holder);
+    }
+    holder.pending = holder
+        .pending
+        .checked_add(balance.mul_floor(index.per_token - holder.per_token))?;
+    holder.per_token = index.per_token;
+
+    Ok(holder)
+}
+
+/// Settles what the `old` balance of `address` accrued before it changes to `new`,
+/// called from `after_balance_change`
+pub fn settle_reflections(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    if REFLECTION_EXCLUDED.has(storage, address) {
+        let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+        index.excluded_supply = index.excluded_supply.checked_add(new)?.checked_sub(old)?;
+        return REFLECTION_INDEX.save(storage, &index);
+    }
+    if !is_claim_mode(storage)? {
+        return Ok(());
+    }
+    let index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+
+    let mut holder = accrued(storage, &index, address, old)?;
+    if is_custodial(storage, address) {
+        divert_accrual(storage, address, holder.pending)?;
+        holder.pending = Uint128::zero();
+    } else {
+        withhold_accrual(storage, address, &mut holder)?;
+    }
+    HOLDER_REFLECTIONS.save(storage, address, &holder)
+}
+
+/// Moves `amount` to the token contract and spreads it over the eligible supply
+pub fn credit_reflections(storage: &mut dyn Storage, env: &Env, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    increase_balance(storage, &env.contract.address, amount)?;
+    spread_reflections(storage, env, amount)
+}
+
+/// Spreads `amount` already held by the token contract over the eligible supply, what the eligible
+/// supply is too small to represent is carried over into the next credit
+pub fn spread_reflections(storage: &mut dyn Storage, env: &Env, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let eligible_supply = eligible_supply(storage)?;
+    let mut index = REFLECTION_INDEX.may_load(storage)?.unwrap_or_default();
+    if index.first_credit == 0 {
+        index.first_credit = env.block.time.seconds();
+    }
+    let amount = amount.checked_add(index.undistributed)?;
+    match Decimal::checked_from_ratio(amount, eligible_supply) {
+        Ok(per_token) if !eligible_supply.is_zero() => {
+            index.per_token = index.per_token.checked_add(per_token)?;
+            // rounded up, holders accrue the sum of their shares of every credit rounded down once
+            index.undistributed = amount - eligible_supply.mul_ceil(per_token);
+        }
+        _ => index.undistributed = amount,
+    }
+
+    REFLECTION_INDEX.save(storage, &index)
+}
+
+/// Takes the reflections accrued by `address` off the token contract balance, for `RedeemFinal`
+/// which burns them with the balance. The balance change of the redemption settled them first
+pub fn take_pending_reflections(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+) -> StdResult<Uint128> {
+    let mut holder = match HOLDER_REFLECTIONS.may_load(storage, address)? {
+        Some(holder) if is_claim_mode(storage)? => holder,
+        _ => return Ok(Uint128::zero()),
+    };
+    let amount = holder.pending;
+    if amount.is_zero() {
+        return Ok(amount);
+    }
+    holder.pending = Uint128::zero();
+    holder.claimed += amount;
+    HOLDER_REFLECTIONS.save(storage, address, &holder)?;
+    decrease_balance(storage, &env.contract.address, amount)?;
+    Ok(amount)
+}
+
+pub fn execute_claim_reflections(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if !is_claim_mode(deps.storage)? {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reflections are credited to the treasury",
+        )));
+    }
+    if is_custodial(deps.storage, &info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reflections of custodial addresses go to the treasury",
+        )));
+    }
+    if is_accrual_paused(deps.storage, &info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Reflection accrual of this address is paused",
+        )));
+    }
+    let index = REFLECTION_INDEX.may_load(deps.storage)?.unwrap_or_default();
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let mut holder = accrued(deps.storage, &index, &info.sender, balance)?;
+    let amount = holder.pending;
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "No reflections to claim",
+        )));
+    }
+
+    let now = env.block.time.seconds();
+    let record = ClaimRecord {
+        amount,
+        rate: index.per_token - holder.claimed_per_token,
+        from: if holder.last_claim == 0 {
+            index.first_credit
+        } else {
+            holder.last_claim
+        },
+        to: now,
+    };
+    record_balance_checkpoint(deps.storage, &info.sender, balance)?;
+    holder.pending = Uint128::zero();
+    holder.claimed += amount;
+    holder.claimed_per_token = index.per_token;
+    holder.last_claim = now;
+    HOLDER_REFLECTIONS.save(deps.storage, &info.sender, &holder)?;
+    decrease_balance(deps.storage, &env.contract.address, amount)?;
+    increase_balance(deps.storage, &info.sender, amount)?;
+
+    let mut history = CLAIM_HISTORY
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if history.len() >= CLAIM_HISTORY_SIZE {
+        history.remove(0);
+    }
+    history.push(record.clone());
+    CLAIM_HISTORY.save(deps.storage, &info.sender, &history)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("reflection_credited")
+                .add_attribute("address", &info.sender)
+                .add_attribute("amount", amount)
+                .add_attribute("rate", record.rate.to_string())
+                .add_attribute("from", record.from.to_string())
+                .add_attribute("to", record.to.to_string()),
+        )
+        .add_attribute("action", "claim_reflections")
+        .add_attribute("to", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn query_pending_reflections(
+    deps: Deps,
+    address: String,
+) -> StdResult<PendingReflectionsResponse> {
+    let reflection_mode = REFLECTION_MODE.may_load(deps.storage)?.unwrap_or_default();
+    let address = deps.api.addr_validate(&address)?;
+    if reflection_mode != ReflectionMode::Claim
+        || REFLECTION_EXCLUDED.has(deps.storage, &address)
+        || is_custodial(deps.storage, &address)
+    {
+        return Ok(PendingReflectionsResponse {
+            reflection_mode,
+            pending: Uint128::zero(),
+            claimed: HOLDER_REFLECTIONS
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default()
+                .claimed,
+        });
+    }
+
+    let index = REFLECTION_INDEX.may_load(deps.storage)?.unwrap_or_default();
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    // a paused address keeps what it accrued before the pause, the rest is withheld
+    let holder = if is_accrual_paused(deps.storage, &address) {
+        HOLDER_REFLECTIONS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default()
+    } else {
+        accrued(deps.storage, &index, &address, balance)?
+    };
+    Ok(PendingReflectionsResponse {
+        reflection_mode,
+        pending: holder.pending,
+        claimed: holder.claimed,
+    })
+}
+
+pub fn query_reflection_index(deps: Deps) -> StdResult<ReflectionIndexResponse> {
+    let index = REFLECTION_INDEX.may_load(deps.storage)?.unwrap_or_default();
+    Ok(ReflectionIndexResponse {
+        reflection_mode: REFLECTION_MODE.may_load(deps.storage)?.unwrap_or_default(),
+        per_token: index.per_token,
+        undistributed: index.undistributed,
+        eligible_supply: eligible_supply(deps.storage)?,
+    })
+}
+
+pub fn query_claim_history(deps: Deps, address: String) -> StdResult<ClaimHistoryResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(ClaimHistoryResponse {
+        claims: CLAIM_HISTORY
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,73 @@
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
+use cw20_base::ContractError;
+
+use crate::contract::{ensure_admin, execute_send, execute_transfer};
+use crate::msg::RelayMsg;
+use crate::signing::{consume_relay_nonce, ensure_signature_domain};
+use crate::state::TRUSTED_FORWARDERS;
+
+/// Executes a transfer or send on behalf of `sender`. Only trusted forwarders can relay, they are
+/// responsible for verifying the intent of `sender` (signature, session key, etc.) before calling.
+/// Each relay consumes the next relay nonce of `sender` and is bound to the chain and the token
+#[allow(clippy::too_many_arguments)]
+pub fn execute_relay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    msg: RelayMsg,
+    nonce: u64,
+    chain_id: String,
+    contract: String,
+) -> Result<Response, ContractError> {
+    if !query_trusted_forwarder(deps.storage, info.sender.to_string())? {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: not a trusted forwarder",
+        )));
+    }
+
+    let original = MessageInfo {
+        sender: deps.api.addr_validate(&sender)?,
+        funds: vec![],
+    };
+    ensure_signature_domain(&env, &chain_id, &contract)?;
+    consume_relay_nonce(deps.storage, &original.sender, nonce)?;
+    let res = match msg {
+        RelayMsg::Transfer { recipient, amount } => {
+            execute_transfer(deps, env, original, recipient, amount, None)?
+        }
+        RelayMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => execute_send(deps, env, original, contract, amount, msg, None)?,
+    };
+
+    Ok(res
+        .add_attribute("relayer", info.sender)
+        .add_attribute("original_sender", sender)
+        .add_attribute("nonce", nonce.to_string()))
+}
+
+/// Sets which contracts are allowed to relay transfers on behalf of users
+pub fn set_trusted_forwarder(
+    deps: DepsMut,
+    info: MessageInfo,
+    forwarder: String,
+    enable: bool,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    deps.api.addr_validate(&forwarder)?;
+    TRUSTED_FORWARDERS.save(deps.storage, forwarder.clone(), &enable)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_trusted_forwarder")
+        .add_attribute("forwarder", forwarder)
+        .add_attribute("enable", enable.to_string()))
+}
+
+pub fn query_trusted_forwarder(storage: &dyn Storage, address: String) -> StdResult<bool> {
+    Ok(TRUSTED_FORWARDERS
+        .may_load(storage, address)?
+        .unwrap_or_default())
+}
@@ -0,0 +1,143 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
+use cw20::Expiration;
+use cw20_base::ContractError;
+use cw_storage_plus::Item;
+
+use crate::contract::{ensure_admin, ADMIN};
+use crate::msg::{Role, RolesResponse};
+use crate::state::{
+    PendingAdmin, EXEMPTION_MANAGER, GUARDIAN, IMMUTABLE, PENDING_ADMIN, TAX_MANAGER,
+};
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::TaxManager => "tax_manager",
+            Role::ExemptionManager => "exemption_manager",
+            Role::Guardian => "guardian",
+        }
+    }
+
+    fn holder(&self) -> Item<'static, Addr> {
+        match self {
+            Role::TaxManager => TAX_MANAGER,
+            Role::ExemptionManager => EXEMPTION_MANAGER,
+            Role::Guardian => GUARDIAN,
+        }
+    }
+}
+
+/// Proposes `new_admin`, nothing changes until it accepts so a mistyped address can be replaced
+/// by another proposal
+pub fn transfer_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_admin: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&new_admin)?;
+    let expires = expiry.unwrap_or(Expiration::Never {});
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "expiry is already reached",
+        )));
+    }
+    PENDING_ADMIN.save(deps.storage, &PendingAdmin { address, expires })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("pending_admin", new_admin)
+        .add_attribute("expires", expires.to_string()))
+}
+
+/// Makes the proposed admin the admin
+pub fn accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No ownership transfer pending"))?;
+    if pending.address != info.sender {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: not pending admin",
+        )));
+    }
+    if pending.expires.is_expired(&env.block) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Ownership transfer expired",
+        )));
+    }
+    let previous_admin = ADMIN.load(deps.storage)?;
+    ADMIN.save(deps.storage, &info.sender.to_string())?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_admin", previous_admin)
+        .add_attribute("admin", info.sender))
+}
+
+pub fn update_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &address {
+        Some(address) => {
+            let address = deps.api.addr_validate(address)?;
+            role.holder().save(deps.storage, &address)?;
+        }
+        None => role.holder().remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address.unwrap_or_default()))
+}
+
+/// Passes for the admin and the holder of `role`. Renouncing ownership locks what the roles
+/// manage, so their holders are refused as well afterwards
+pub fn ensure_role(
+    deps: &DepsMut,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<Response, ContractError> {
+    let admin = ensure_admin(deps, info);
+    if admin.is_ok() || IMMUTABLE.may_load(deps.storage)?.unwrap_or_default() {
+        return admin;
+    }
+    if role.holder().may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Unauthorized: not admin or {}",
+            role.as_str()
+        ))));
+    }
+
+    Ok(Response::default())
+}
+
+pub fn query_roles(deps: Deps) -> StdResult<RolesResponse> {
+    let holder = |role: Role| -> StdResult<Option<String>> {
+        Ok(role
+            .holder()
+            .may_load(deps.storage)?
+            .map(|address| address.to_string()))
+    };
+
+    Ok(RolesResponse {
+        admin: ADMIN
+            .may_load(deps.storage)?
+            .filter(|admin| !admin.is_empty()),
+        pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+        tax_manager: holder(Role::TaxManager)?,
+        exemption_manager: holder(Role::ExemptionManager)?,
+        guardian: holder(Role::Guardian)?,
+    })
+}
@@ -0,0 +1,233 @@
+use cosmwasm_std::{
+    to_json_binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    WasmMsg,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::{apply_tax_rate, ensure_admin, TREASURY, WHITELIST};
+use crate::msg::{
+    ScheduleReceiptsResponse, ScheduledAction, ScheduledActionsResponse, TreasuryExecuteMsg,
+};
+use crate::security::ensure_guardian;
+use crate::state::{
+    ActionReceipt, ScheduledEntry, IMMUTABLE, NEXT_SCHEDULED_ID, SCHEDULED_ACTIONS,
+    SCHEDULE_RECEIPTS,
+};
+
+/// Maximum number of pending scheduled actions
+pub const MAX_SCHEDULED_ACTIONS: usize = 50;
+
+/// Maximum number of matured actions run by a single `ExecuteDue`, the rest waits for the next one
+pub const MAX_DUE_PER_CRANK: usize = 10;
+
+/// Number of receipts kept in `SCHEDULE_RECEIPTS`
+pub const RECEIPT_HISTORY_SIZE: usize = 50;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn schedule_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    action: ScheduledAction,
+    execute_at: u64,
+    interval: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if interval == Some(0) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "interval must be positive",
+        )));
+    }
+    if let ScheduledAction::SetWhitelist { user, .. } = &action {
+        deps.api.addr_validate(user)?;
+    }
+    let pending = SCHEDULED_ACTIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if pending >= MAX_SCHEDULED_ACTIONS {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "at most {} actions can be scheduled",
+            MAX_SCHEDULED_ACTIONS
+        ))));
+    }
+
+    let id = NEXT_SCHEDULED_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_SCHEDULED_ID.save(deps.storage, &(id + 1))?;
+    SCHEDULED_ACTIONS.save(
+        deps.storage,
+        id,
+        &ScheduledEntry {
+            id,
+            action,
+            execute_at,
+            interval,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "schedule_action")
+        .add_attribute("id", id.to_string())
+        .add_attribute("execute_at", execute_at.to_string()))
+}
+
+pub fn cancel_scheduled_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if !SCHEDULED_ACTIONS.has(deps.storage, id) {
+        return Err(ContractError::Std(StdError::not_found("scheduled action")));
+    }
+    SCHEDULED_ACTIONS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_scheduled_action")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Lets the guardian remove a queued action it considers harmful, it cannot queue any
+pub fn veto_scheduled_action(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    ensure_guardian(deps.storage, &info)?;
+    if !SCHEDULED_ACTIONS.has(deps.storage, id) {
+        return Err(ContractError::Std(StdError::not_found("scheduled action")));
+    }
+    SCHEDULED_ACTIONS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "veto")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Runs `action`, returning the message to dispatch if any. Fails before writing anything, so a
+/// failed action leaves the state untouched
+fn run_action(
+    storage: &mut dyn Storage,
+    env: &Env,
+    action: &ScheduledAction,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let economic = !matches!(action, ScheduledAction::LiquifyTreasury {});
+    if economic && IMMUTABLE.may_load(storage)?.unwrap_or_default() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: ownership renounced",
+        )));
+    }
+
+    match action {
+        ScheduledAction::SetTaxRate {
+            global_rate,
+            reflection_rate,
+            burn_rate,
+            antiwhale_rate,
+        } => {
+            apply_tax_rate(
+                storage,
+                env,
+                *global_rate,
+                *reflection_rate,
+                *burn_rate,
+                *antiwhale_rate,
+            )?;
+            Ok(None)
+        }
+        ScheduledAction::SetWhitelist { user, enable } => {
+            WHITELIST.save(storage, user.clone(), enable)?;
+            Ok(None)
+        }
+        ScheduledAction::LiquifyTreasury {} => Ok(Some(WasmMsg::Execute {
+            contract_addr: TREASURY.load(storage)?,
+            msg: to_json_binary(&TreasuryExecuteMsg::Liquify {})?,
+            funds: vec![],
+        })),
+    }
+}
+
+/// Runs the matured actions by execution time. A failing action does not block the others, its
+/// error is kept in its receipt. One-off actions are removed, recurring ones are moved to their
+/// next run after `env.block.time`
+pub fn execute_due(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let mut due = SCHEDULED_ACTIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, entry)| entry.execute_at <= now)
+        })
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<_>>>()?;
+    due.sort_by_key(|entry| (entry.execute_at, entry.id));
+    due.truncate(MAX_DUE_PER_CRANK);
+
+    let mut receipts = SCHEDULE_RECEIPTS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut res = Response::new()
+        .add_attribute("action", "execute_due")
+        .add_attribute("executed", due.len().to_string());
+    for mut entry in due {
+        let error = match run_action(deps.storage, &env, &entry.action) {
+            Ok(msg) => {
+                res = res.add_messages(msg);
+                None
+            }
+            Err(err) => Some(err.to_string()),
+        };
+        res = res.add_attribute(
+            format!("scheduled_action_{}", entry.id),
+            error.as_deref().unwrap_or("ok"),
+        );
+        receipts.push(ActionReceipt {
+            id: entry.id,
+            action: entry.action.clone(),
+            executed_at: now,
+            error,
+        });
+
+        match entry.interval {
+            Some(interval) => {
+                let missed = (now - entry.execute_at) / interval;
+                entry.execute_at += (missed + 1) * interval;
+                SCHEDULED_ACTIONS.save(deps.storage, entry.id, &entry)?;
+            }
+            None => SCHEDULED_ACTIONS.remove(deps.storage, entry.id),
+        }
+    }
+    if receipts.len() > RECEIPT_HISTORY_SIZE {
+        receipts.drain(..receipts.len() - RECEIPT_HISTORY_SIZE);
+    }
+    SCHEDULE_RECEIPTS.save(deps.storage, &receipts)?;
+
+    Ok(res)
+}
+
+pub fn query_scheduled_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ScheduledActionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let actions = SCHEDULED_ACTIONS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ScheduledActionsResponse { actions })
+}
+
+pub fn query_schedule_receipts(storage: &dyn Storage) -> StdResult<ScheduleReceiptsResponse> {
+    Ok(ScheduleReceiptsResponse {
+        receipts: SCHEDULE_RECEIPTS.may_load(storage)?.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,209 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::{ensure_admin, ADMIN, TREASURY};
+use crate::msg::{ExecuteMsg, FrozenAddress, SecurityStatusResponse};
+use crate::reflections::{exclude_from_reflections, include_in_reflections};
+use crate::state::{FROZEN, GUARDIAN, PAUSED_AT};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Sets the guardian which pauses the token and freezes addresses, None removes it. The guardian
+/// is kept when ownership is renounced
+pub fn set_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &guardian {
+        Some(guardian) => {
+            let guardian = deps.api.addr_validate(guardian)?;
+            GUARDIAN.save(deps.storage, &guardian)?;
+        }
+        None => GUARDIAN.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_guardian")
+        .add_attribute("guardian", guardian.unwrap_or_default()))
+}
+
+pub(crate) fn ensure_guardian(
+    storage: &dyn Storage,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    if GUARDIAN.may_load(storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Unauthorized: not guardian",
+        )));
+    }
+    Ok(())
+}
+
+pub fn renounce_guardian(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_guardian(deps.storage, &info)?;
+    GUARDIAN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "renounce_guardian"))
+}
+
+/// Rejects every execution but the security messages until `Unpause`
+pub fn execute_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_guardian(deps.storage, &info)?;
+    if PAUSED_AT.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Token is already paused",
+        )));
+    }
+    PAUSED_AT.save(deps.storage, &env.block.time.seconds())?;
+
+    Ok(Response::new().add_attribute("action", "pause"))
+}
+
+pub fn execute_unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_guardian(deps.storage, &info)?;
+    if PAUSED_AT.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Token is not paused",
+        )));
+    }
+    PAUSED_AT.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "unpause"))
+}
+
+/// Freezes the balance of a compromised `address`: it cannot execute anything and nothing can
+/// debit it, it still receives transfers. Its balance is excluded from reflections while frozen,
+/// what it accrued before stays pending. `freeze` false unfreezes it. The token, its admin and
+/// its treasury cannot be frozen, the guardian would lock their management out
+pub fn set_frozen(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    freeze: bool,
+) -> Result<Response, ContractError> {
+    ensure_guardian(deps.storage, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+    if freeze {
+        if address == env.contract.address {
+            return Err(ContractError::Std(StdError::generic_err(
+                "The token cannot be frozen",
+            )));
+        }
+        if ADMIN.may_load(deps.storage)?.as_deref() == Some(address.as_str()) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "The admin cannot be frozen",
+            )));
+        }
+        if TREASURY.may_load(deps.storage)?.as_deref() == Some(address.as_str()) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "The treasury cannot be frozen",
+            )));
+        }
+        FROZEN.save(deps.storage, &address, &env.block.time.seconds())?;
+        exclude_from_reflections(deps.storage, &address)?;
+    } else if FROZEN.has(deps.storage, &address) {
+        FROZEN.remove(deps.storage, &address);
+        include_in_reflections(deps.storage, &env, &address)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if freeze { "freeze" } else { "unfreeze" })
+        .add_attribute("address", address))
+}
+
+/// Called at the top of `execute`: while paused or from a frozen sender only the security
+/// messages go through, so the guardian can always act
+pub fn ensure_not_halted(
+    storage: &dyn Storage,
+    info: &MessageInfo,
+    msg: &ExecuteMsg,
+) -> Result<(), ContractError> {
+    if matches!(
+        msg,
+        ExecuteMsg::SetGuardian { .. }
+            | ExecuteMsg::RenounceGuardian {}
+            | ExecuteMsg::Veto { .. }
+            | ExecuteMsg::Pause {}
+            | ExecuteMsg::Unpause {}
+            | ExecuteMsg::VerifyInvariants {}
+            | ExecuteMsg::Freeze { .. }
+            | ExecuteMsg::Unfreeze { .. }
+    ) {
+        return Ok(());
+    }
+    if PAUSED_AT.may_load(storage)?.is_some() {
+        return Err(ContractError::Std(StdError::generic_err("Token is paused")));
+    }
+    if is_frozen(storage, &info.sender) {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "{} is frozen",
+            info.sender
+        ))));
+    }
+    Ok(())
+}
+
+pub fn is_frozen(storage: &dyn Storage, address: &Addr) -> bool {
+    FROZEN.has(storage, address)
+}
+
+/// Fails if a frozen `address` is debited, called from `after_balance_change` so that allowances,
+/// operators and relays cannot move a frozen balance either
+pub fn ensure_not_frozen(
+    storage: &dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    if new < old && is_frozen(storage, address) {
+        return Err(StdError::generic_err(format!("{} is frozen", address)));
+    }
+    Ok(())
+}
+
+pub fn query_security_status(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SecurityStatusResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let frozen = FROZEN
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, since)| FrozenAddress {
+                address: address.to_string(),
+                since,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let paused_at = PAUSED_AT.may_load(deps.storage)?;
+
+    Ok(SecurityStatusResponse {
+        guardian: GUARDIAN
+            .may_load(deps.storage)?
+            .map(|guardian| guardian.to_string()),
+        paused: paused_at.is_some(),
+        paused_at,
+        frozen,
+    })
+}
@@ -0,0 +1,66 @@
+use cosmwasm_std::{Addr, Env, StdError, StdResult, Storage};
+
+use crate::msg::SignatureDomainResponse;
+use crate::state::{PERMIT_NONCES, RELAY_NONCES};
+
+pub use dojoswap::signing::{
+    address_of_public_key, domain_separator, signing_digest, verify_adr36_signature, KeyType,
+    SIGNATURE_DOMAIN_TAG,
+};
+
+/// Fails unless `nonce` is the next relay nonce of `sender`, then consumes it
+pub fn consume_relay_nonce(storage: &mut dyn Storage, sender: &Addr, nonce: u64) -> StdResult<()> {
+    let expected = RELAY_NONCES.may_load(storage, sender)?.unwrap_or_default();
+    if nonce != expected {
+        return Err(StdError::generic_err(format!(
+            "Invalid relay nonce {}, expected {}",
+            nonce, expected
+        )));
+    }
+    RELAY_NONCES.save(storage, sender, &(expected + 1))
+}
+
+/// Fails unless `chain_id` and `contract`, the domain a payload was signed for, are this chain and
+/// this token
+pub fn ensure_signature_domain(env: &Env, chain_id: &str, contract: &str) -> StdResult<()> {
+    if chain_id != env.block.chain_id || contract != env.contract.address.as_str() {
+        return Err(StdError::generic_err(format!(
+            "Signed for chain {} and contract {}, not this token",
+            chain_id, contract
+        )));
+    }
+    Ok(())
+}
+
+pub fn query_signature_domain(
+    storage: &dyn Storage,
+    env: &Env,
+    address: Option<String>,
+) -> StdResult<SignatureDomainResponse> {
+    let (next_relay_nonce, next_permit_nonce) = match address {
+        Some(address) => {
+            let address = Addr::unchecked(address);
+            (
+                RELAY_NONCES
+                    .may_load(storage, &address)?
+                    .unwrap_or_default(),
+                PERMIT_NONCES
+                    .may_load(storage, &address)?
+                    .unwrap_or_default(),
+            )
+        }
+        None => (0, 0),
+    };
+
+    Ok(SignatureDomainResponse {
+        tag: SIGNATURE_DOMAIN_TAG.to_string(),
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        separator: hex::encode(domain_separator(
+            &env.block.chain_id,
+            env.contract.address.as_str(),
+        )),
+        next_relay_nonce,
+        next_permit_nonce,
+    })
+}
@@ -0,0 +1,77 @@
+use cosmwasm_std::{Decimal, Deps, Env, StdError, StdResult, Uint128};
+use dojoswap::asset::{Asset, AssetInfo};
+use dojoswap::pair::{PoolResponse, QueryMsg as PairQueryMsg, SimulationResponse};
+
+use crate::contract::WHITELIST;
+use crate::fee_exempt::is_fee_exempt;
+use crate::msg::SimulateSwapRoundTripResponse;
+use crate::tax_strategy::tax_of;
+
+/// Buys tokens with `amount` of the quote asset of `pair` and sells them right back, combining the
+/// token tax of both transfers with the commission and spread the pair reports live. Both legs are
+/// simulated against the current reserves, so the price impact of the buy on the sell is ignored
+pub fn query_simulate_swap_round_trip(
+    deps: Deps,
+    env: Env,
+    pair: String,
+    amount: Uint128,
+) -> StdResult<SimulateSwapRoundTripResponse> {
+    let pair = deps.api.addr_validate(&pair)?;
+    let token = AssetInfo::Token {
+        contract_addr: env.contract.address.to_string(),
+    };
+    let pool: PoolResponse = deps
+        .querier
+        .query_wasm_smart(&pair, &PairQueryMsg::Pool {})?;
+    let quote = match &pool.assets {
+        [a, b] if a.info.equal(&token) && !b.info.equal(&token) => b.info.clone(),
+        [a, b] if b.info.equal(&token) && !a.info.equal(&token) => a.info.clone(),
+        _ => return Err(StdError::generic_err("Pair does not trade this token")),
+    };
+    // transfers from and to a whitelisted or fee exempt pair are not taxed
+    let taxed = !WHITELIST
+        .may_load(deps.storage, pair.to_string())?
+        .unwrap_or_default()
+        && !is_fee_exempt(deps.storage, pair.as_str());
+    // at the rate of the tax strategy, the seller is unknown
+    let tax = |sender: Option<&str>, amount: Uint128| -> StdResult<Uint128> {
+        if taxed {
+            Ok(tax_of(deps.storage, &env, sender, amount)?.taxed_amount)
+        } else {
+            Ok(Uint128::zero())
+        }
+    };
+    let simulate = |info: AssetInfo, amount: Uint128| -> StdResult<SimulationResponse> {
+        deps.querier.query_wasm_smart(
+            &pair,
+            &PairQueryMsg::Simulation {
+                offer_asset: Asset { info, amount },
+            },
+        )
+    };
+
+    let buy = simulate(quote, amount)?;
+    let buy_tax = tax(Some(pair.as_str()), buy.return_amount)?;
+    let tokens_received = buy.return_amount - buy_tax;
+    let sell_tax = tax(None, tokens_received)?;
+    let sell = simulate(token, tokens_received - sell_tax)?;
+
+    let cost = amount.saturating_sub(sell.return_amount);
+    Ok(SimulateSwapRoundTripResponse {
+        offer_amount: amount,
+        buy_commission: buy.commission_amount,
+        buy_spread: buy.spread_amount,
+        buy_tax,
+        tokens_received,
+        sell_tax,
+        sell_commission: sell.commission_amount,
+        sell_spread: sell.spread_amount,
+        return_amount: sell.return_amount,
+        cost,
+        cost_rate: if amount.is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(cost, amount)
+        },
+    })
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeSet;
+
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, Env, Int128, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::epoch_rewards::is_rewarded;
+use crate::msg::{
+    BalanceDelta, SnapshotBalanceResponse, SnapshotDiffResponse, SnapshotSchedule,
+    SnapshotsResponse,
+};
+use crate::state::{
+    Snapshot, NEXT_SNAPSHOT_ID, PRUNED_SNAPSHOTS, SNAPSHOTS, SNAPSHOT_BALANCES, SNAPSHOT_BOUNDARY,
+    SNAPSHOT_CHANGES, SNAPSHOT_SCHEDULE,
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Balances of pruned snapshots deleted per execution, pruning a snapshot touched by many
+/// addresses is spread over the next executions
+const PRUNE_BATCH: usize = 20;
+
+/// Takes a snapshot every `interval` blocks and keeps the last `retain` ones, None stops the
+/// automatic snapshots and the pruning
+pub fn set_snapshot_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    schedule: Option<SnapshotSchedule>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &schedule {
+        Some(schedule) => {
+            if schedule.interval == 0 || schedule.retain == 0 {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "interval and retain must be above 0",
+                )));
+            }
+            SNAPSHOT_SCHEDULE.save(deps.storage, schedule)?;
+            // the boundary already passed was not observed, the first snapshot is the next one
+            SNAPSHOT_BOUNDARY.save(deps.storage, &boundary(env.block.height, schedule))?;
+        }
+        None => SNAPSHOT_SCHEDULE.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_snapshot_schedule")
+        .add_attribute("enabled", schedule.is_some().to_string()))
+}
+
+/// Takes a snapshot of the balances now
+pub fn execute_take_snapshot(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let id = take_snapshot(deps.storage, &env, env.block.height)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "take_snapshot")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Last block height at or before `height` where a scheduled snapshot is due
+fn boundary(height: u64, schedule: &SnapshotSchedule) -> u64 {
+    height - height % schedule.interval
+}
+
+/// Takes the scheduled snapshot if a boundary was crossed since the last one, and prunes what is
+/// beyond the retained count. Called at the start of every execution before any balance moves, so
+/// on the first touch after the boundary the balances are still those at the boundary. Returns the
+/// id of the snapshot taken
+pub fn take_due_snapshot(storage: &mut dyn Storage, env: &Env) -> StdResult<Option<u64>> {
+    prune_balances(storage)?;
+    let schedule = match SNAPSHOT_SCHEDULE.may_load(storage)? {
+        Some(schedule) => schedule,
+        None => return Ok(None),
+    };
+    let due = boundary(env.block.height, &schedule);
+    if due <= SNAPSHOT_BOUNDARY.may_load(storage)?.unwrap_or_default() {
+        return Ok(None);
+    }
+    SNAPSHOT_BOUNDARY.save(storage, &due)?;
+    // boundaries crossed while nobody touched the token all share the same balances, only the
+    // last one is taken
+    take_snapshot(storage, env, due).map(Some)
+}
+
+fn take_snapshot(storage: &mut dyn Storage, env: &Env, height: u64) -> StdResult<u64> {
+    let id = NEXT_SNAPSHOT_ID.may_load(storage)?.unwrap_or(1);
+    NEXT_SNAPSHOT_ID.save(storage, &(id + 1))?;
+    let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+    SNAPSHOTS.save(
+        storage,
+        id,
+        &Snapshot {
+            id,
+            height,
+            time: env.block.time.seconds(),
+            total_supply,
+        },
+    )?;
+
+    if let Some(schedule) = SNAPSHOT_SCHEDULE.may_load(storage)? {
+        let pruned: Vec<u64> = SNAPSHOTS
+            .keys(storage, None, None, Order::Descending)
+            .skip(schedule.retain as usize)
+            .collect::<StdResult<_>>()?;
+        for id in pruned {
+            // holders must be able to claim the reward of an epoch until it is swept
+            if is_rewarded(storage, id)? {
+                continue;
+            }
+            SNAPSHOTS.remove(storage, id);
+            PRUNED_SNAPSHOTS.save(storage, id, &Empty {})?;
+        }
+    }
+    Ok(id)
+}
+
+/// Deletes up to `PRUNE_BATCH` balances recorded for pruned snapshots. The balances recorded after
+/// a snapshot are also those of every earlier one, so they are kept while an earlier snapshot is
+/// retained
+fn prune_balances(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut budget = PRUNE_BATCH;
+    let oldest = SNAPSHOTS
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?;
+    let pruned: Vec<u64> = PRUNED_SNAPSHOTS
+        .keys(
+            storage,
+            None,
+            oldest.map(Bound::exclusive),
+            Order::Ascending,
+        )
+        .collect::<StdResult<_>>()?;
+    for id in pruned {
+        let addresses: Vec<Addr> = SNAPSHOT_CHANGES
+            .prefix(id)
+            .keys(storage, None, None, Order::Ascending)
+            .take(budget)
+            .collect::<StdResult<_>>()?;
+        budget -= addresses.len();
+        for address in addresses {
+            SNAPSHOT_CHANGES.remove(storage, (id, &address));
+            SNAPSHOT_BALANCES.remove(storage, (&address, id));
+        }
+        if budget == 0 {
+            return Ok(());
+        }
+        PRUNED_SNAPSHOTS.remove(storage, id);
+    }
+    Ok(())
+}
+
+/// Keeps the balance `address` had in the latest snapshot before it changes for the first time,
+/// called from `after_balance_change`
+pub fn record_snapshot_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+) -> StdResult<()> {
+    let latest = match NEXT_SNAPSHOT_ID.may_load(storage)? {
+        Some(next) => next - 1,
+        None => return Ok(()),
+    };
+    if SNAPSHOT_BALANCES.has(storage, (address, latest)) {
+        return Ok(());
+    }
+    SNAPSHOT_BALANCES.save(storage, (address, latest), &old)?;
+    SNAPSHOT_CHANGES.save(storage, (latest, address), &Empty {})
+}
+
+/// Balance of `address` in snapshot `id`: the balance recorded by the first change after `id` or
+/// any later snapshot, the current balance if it did not change since
+pub fn balance_at_snapshot(storage: &dyn Storage, address: &Addr, id: u64) -> StdResult<Uint128> {
+    if !SNAPSHOTS.has(storage, id) {
+        return Err(StdError::generic_err(format!(
+            "Snapshot {} does not exist or was pruned",
+            id
+        )));
+    }
+    let recorded = SNAPSHOT_BALANCES
+        .prefix(address)
+        .range(storage, Some(Bound::inclusive(id)), None, Order::Ascending)
+        .next()
+        .transpose()?;
+    match recorded {
+        Some((_, balance)) => Ok(balance),
+        None => Ok(BALANCES.may_load(storage, address)?.unwrap_or_default()),
+    }
+}
+
+pub fn query_snapshots(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SnapshotsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let snapshots = SNAPSHOTS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, snapshot)| snapshot))
+        .collect::<StdResult<_>>()?;
+
+    Ok(SnapshotsResponse {
+        schedule: SNAPSHOT_SCHEDULE.may_load(deps.storage)?,
+        snapshots,
+    })
+}
+
+pub fn query_snapshot_balance(
+    deps: Deps,
+    address: String,
+    id: u64,
+) -> StdResult<SnapshotBalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(SnapshotBalanceResponse {
+        balance: balance_at_snapshot(deps.storage, &address, id)?,
+    })
+}
+
+/// Addresses after `start_after` whose balance changed between snapshots `from` and `to`, with
+/// the change. Only the addresses recorded by a change after one of the snapshots in between are
+/// looked at, those whose balance came back to where it was are left out
+pub fn query_snapshot_diff(
+    deps: Deps,
+    from: u64,
+    to: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SnapshotDiffResponse> {
+    if from >= to {
+        return Err(StdError::generic_err("from must be before to"));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut cursor = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+
+    let mut changes = vec![];
+    while changes.len() < limit {
+        // the first addresses touched after any snapshot from `from` up to `to` excluded
+        let mut candidates = BTreeSet::new();
+        for id in from..to {
+            let touched = SNAPSHOT_CHANGES
+                .prefix(id)
+                .keys(
+                    deps.storage,
+                    cursor.as_ref().map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit);
+            for address in touched {
+                candidates.insert(address?);
+            }
+        }
+        if candidates.is_empty() {
+            break;
+        }
+        for address in candidates.into_iter().take(limit) {
+            let before = balance_at_snapshot(deps.storage, &address, from)?;
+            let after = balance_at_snapshot(deps.storage, &address, to)?;
+            if before != after && changes.len() < limit {
+                changes.push(BalanceDelta {
+                    address: address.to_string(),
+                    before,
+                    after,
+                    delta: Int128::new(after.u128() as i128) - Int128::new(before.u128() as i128),
+                });
+            }
+            cursor = Some(address);
+        }
+    }
+
+    Ok(SnapshotDiffResponse { changes })
+}
@@ -0,0 +1,643 @@
+use cosmwasm_std::{Addr, Decimal, Decimal256, Empty, Uint128};
+use cw20::Expiration;
+use dojoswap::asset::AssetInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::{ClaimRecord, GrantAction, ReflectionMode, ScheduledAction, SnapshotSchedule};
+use cw_storage_plus::{Item, Map};
+
+// Number of holders per balance decade, decade `n` holds balances in [10^n, 10^(n+1))
+pub const HOLDER_BUCKETS: Map<u8, u64> = Map::new("holder_buckets");
+
+// Sum of the balances held within each decade of `HOLDER_BUCKETS`
+pub const HOLDER_BUCKET_BALANCES: Map<u8, Uint128> = Map::new("holder_bucket_balances");
+
+// Largest holders sorted by descending balance, see `distribution::record_top_holder`
+pub const TOP_HOLDERS: Item<Vec<(Addr, Uint128)>> = Item::new("top_holders");
+
+// Block height since which the distribution counters above cover every holder, missing until the
+// backfill of a token migrated from before they were kept completes
+pub const DISTRIBUTION_TRACKED_SINCE: Item<u64> = Item::new("distribution_tracked_since");
+
+// Set while the distribution counters are backfilled, to the last holder counted (None before the
+// first one). Balances after it are counted by the backfill, not as they change
+pub const DISTRIBUTION_BACKFILL: Item<Option<Addr>> = Item::new("distribution_backfill");
+
+// Reflection share of collected taxes per day (unix time / 86400), see `fees::record_reflection`
+pub const REFLECTION_FLOW: Map<u64, Uint128> = Map::new("reflection_flow");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxStats {
+    /// Time of the first transfer recorded, transfers before the upgrade adding the stats are
+    /// not covered
+    pub since: u64,
+    pub taxed_transfers: u64,
+    pub exempt_transfers: u64,
+    /// Gross amount of the taxed transfers
+    pub taxed_volume: Uint128,
+    pub total_tax: Uint128,
+    pub total_burned: Uint128,
+    pub total_reflected: Uint128,
+}
+
+// Cumulative taxes of `Transfer`, `Send`, `TransferFrom`, `SendFrom` and batch transfers, see
+// `fees::record_tax`
+pub const TAX_STATS: Item<TaxStats> = Item::new("tax_stats");
+
+// Contracts allowed to relay transfers on behalf of users, see `relay::execute_relay`
+pub const TRUSTED_FORWARDERS: Map<String, bool> = Map::new("trusted_forwarders");
+
+// Next nonce of each sender for relays carrying one, see `signing::consume_relay_nonce`
+pub const RELAY_NONCES: Map<&Addr, u64> = Map::new("relay_nonces");
+
+// Next permit nonce of each owner, see `permit::execute_permit`
+pub const PERMIT_NONCES: Map<&Addr, u64> = Map::new("permit_nonces");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorGrant {
+    pub action: GrantAction,
+    /// Number of tokens the operator can still move with this grant
+    pub remaining: Uint128,
+    pub expires: Expiration,
+}
+
+// (owner, operator) -> grants given by owner to operator, one per action
+pub const OPERATOR_GRANTS: Map<(&Addr, &Addr), Vec<OperatorGrant>> = Map::new("operator_grants");
+
+// Seconds over which collected taxes are dripped to the treasury, and in claim mode reflections to
+// holders, 0 credits them instantly
+pub const DRIP_PERIOD: Item<u64> = Item::new("drip_period");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct DripState {
+    /// Taxes held by the token contract that are still to be released
+    pub remaining: Uint128,
+    /// Last time the release was accrued
+    pub last_update: u64,
+    /// Time at which `remaining` is fully released
+    pub end: u64,
+    /// Tokens released per second by the credits still dripping
+    pub rate: Decimal256,
+    /// Accrued since the last release, released once it adds up to whole tokens
+    pub accrued: Decimal256,
+}
+
+// Taxes dripping to the treasury
+pub const DRIP_STATE: Item<DripState> = Item::new("drip_state");
+
+// End time -> release rate of the credits of `DRIP_STATE` ending then
+pub const DRIP_ENDS: Map<u64, Decimal256> = Map::new("drip_ends");
+
+// Reflection share of the taxes dripping into the reflection index in claim mode
+pub const REFLECTION_DRIP_STATE: Item<DripState> = Item::new("reflection_drip_state");
+
+// End time -> release rate of the credits of `REFLECTION_DRIP_STATE` ending then
+pub const REFLECTION_DRIP_ENDS: Map<u64, Decimal256> = Map::new("reflection_drip_ends");
+
+// Hard ceiling of the global tax rate, set at instantiation only
+pub const MAX_TAX_RATE: Item<Decimal> = Item::new("max_tax_rate");
+
+// Maximum move of the global tax rate within `rate_limit::TAX_STEP_PERIOD`
+pub const MAX_TAX_STEP: Item<Decimal> = Item::new("max_tax_step");
+
+// (time, rate before the change) of the global tax rate changes of the last step period
+pub const TAX_RATE_HISTORY: Item<Vec<(u64, Decimal)>> = Item::new("tax_rate_history");
+
+// Set once ownership is renounced, the economic parameters can never change afterwards
+pub const IMMUTABLE: Item<bool> = Item::new("immutable");
+
+// Where the reflection share of taxes goes, set at instantiation only
+pub const REFLECTION_MODE: Item<ReflectionMode> = Item::new("reflection_mode");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ReflectionIndex {
+    /// Reflections credited per token held, summed over every tax collected in claim mode
+    pub per_token: Decimal,
+    /// Reflections lost to the rounding of `per_token`, carried over into the next credit
+    pub undistributed: Uint128,
+    /// Sum of the balances of `REFLECTION_EXCLUDED` addresses
+    pub excluded_supply: Uint128,
+    /// Time of the first reflection credited, 0 until then
+    pub first_credit: u64,
+}
+
+pub const REFLECTION_INDEX: Item<ReflectionIndex> = Item::new("reflection_index");
+
+// Addresses excluded from the eligible supply and from claim mode accruals: the token contract,
+// the treasury, the dead addresses and the frozen addresses, see `reflections::eligible_supply`
+pub const REFLECTION_EXCLUDED: Map<&Addr, bool> = Map::new("reflection_excluded");
+
+// Addresses nobody controls tokens are sent to instead of burnt, excluded for good
+pub const DEAD_ADDRESSES: Map<&Addr, Empty> = Map::new("dead_addresses");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct HolderReflections {
+    /// `ReflectionIndex::per_token` at which `pending` was last settled
+    pub per_token: Decimal,
+    /// Reflections accrued and not claimed yet
+    pub pending: Uint128,
+    /// `ReflectionIndex::per_token` at the last claim
+    pub claimed_per_token: Decimal,
+    /// Time of the last claim, 0 if never claimed
+    pub last_claim: u64,
+    /// Reflections claimed so far
+    #[serde(default)]
+    pub claimed: Uint128,
+}
+
+pub const HOLDER_REFLECTIONS: Map<&Addr, HolderReflections> = Map::new("holder_reflections");
+
+// Last `reflections::CLAIM_HISTORY_SIZE` claims of each holder, oldest first
+pub const CLAIM_HISTORY: Map<&Addr, Vec<ClaimRecord>> = Map::new("claim_history");
+
+// Share of every tax set aside in the insurance fund
+pub const INSURANCE_RATE: Item<Decimal> = Item::new("insurance_rate");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct InsuranceFund {
+    /// Funds held by the token contract for insurance, including queued withdrawals
+    pub balance: Uint128,
+    /// Part of `balance` owed to queued withdrawals
+    pub queued: Uint128,
+}
+
+pub const INSURANCE_FUND: Item<InsuranceFund> = Item::new("insurance_fund");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct InsuranceWithdrawal {
+    pub id: u64,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    pub reason: String,
+    pub queued_at: u64,
+    pub executable_at: u64,
+    pub executed_at: Option<u64>,
+}
+
+// Where governance sweeps the fee buckets in an emergency, see `emergency::emergency_drain`
+pub const RESCUE_ADDRESS: Item<Addr> = Item::new("rescue_address");
+
+pub const INSURANCE_WITHDRAWALS: Map<u64, InsuranceWithdrawal> = Map::new("insurance_withdrawals");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ScheduledEntry {
+    pub id: u64,
+    pub action: ScheduledAction,
+    /// Time from which `ExecuteDue` runs the action
+    pub execute_at: u64,
+    /// Seconds between two runs of a recurring action, None for a one-off action
+    pub interval: Option<u64>,
+}
+
+pub const SCHEDULED_ACTIONS: Map<u64, ScheduledEntry> = Map::new("scheduled_actions");
+
+// Id of the next scheduled action
+pub const NEXT_SCHEDULED_ID: Item<u64> = Item::new("next_scheduled_id");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ActionReceipt {
+    pub id: u64,
+    pub action: ScheduledAction,
+    pub executed_at: u64,
+    /// Why the action failed, None if it succeeded
+    pub error: Option<String>,
+}
+
+// Last `schedule::RECEIPT_HISTORY_SIZE` receipts of scheduled actions, oldest first
+pub const SCHEDULE_RECEIPTS: Item<Vec<ActionReceipt>> = Item::new("schedule_receipts");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BurnTrophy {
+    /// cw721 collection minting the trophies
+    pub collection: Addr,
+    /// Minimum amount burnt in a single message to earn a trophy
+    pub threshold: Uint128,
+}
+
+pub const BURN_TROPHY: Item<BurnTrophy> = Item::new("burn_trophy");
+
+// Number of trophy mints requested, the id of the next trophy is `burn-{TROPHIES_MINTED + 1}`
+pub const TROPHIES_MINTED: Item<u64> = Item::new("trophies_minted");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TradingGate {
+    pub pair: Addr,
+    /// Token reserve of `pair` from which trading is enabled
+    pub min_token_reserve: Uint128,
+    /// Quote asset reserve of `pair` from which trading is enabled
+    pub min_quote_reserve: Uint128,
+    /// Time trading was enabled, None while transfers with `pair` are rejected
+    pub enabled_at: Option<u64>,
+}
+
+// Holds trading with the pair until its liquidity is seeded, see `trading::ensure_trading_enabled`
+pub const TRADING_GATE: Item<TradingGate> = Item::new("trading_gate");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CustodialAccount {
+    /// Time the address became custodial
+    pub since: u64,
+    /// Reflections accrued by the address and routed to the treasury
+    pub routed: Uint128,
+}
+
+// Addresses whose reflections go to the treasury so that their balance never drifts, see
+// `custodial::set_custodial`
+pub const CUSTODIAL_ACCOUNTS: Map<&Addr, CustodialAccount> = Map::new("custodial_accounts");
+
+// Reflections diverted from custodial addresses, held by the token contract until the next
+// execution sends them to the treasury, see `custodial::route_custodial_excess`
+pub const CUSTODIAL_EXCESS: Item<Uint128> = Item::new("custodial_excess");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AccrualPause {
+    /// Time the accrual was paused
+    pub since: u64,
+    pub reason: String,
+    /// Reflections accrued since, held by the token contract until the release
+    pub withheld: Uint128,
+}
+
+// Addresses whose reflections are withheld pending an investigation, see
+// `disputes::pause_accrual`
+pub const ACCRUAL_PAUSES: Map<&Addr, AccrualPause> = Map::new("accrual_pauses");
+
+// Short labels of known addresses, added to transfer events, see `labels::label_attributes`
+pub const ADDRESS_LABELS: Map<&Addr, String> = Map::new("address_labels");
+
+// Whether sends whose receive hook fails are quarantined instead of reverted
+pub const HOOK_QUARANTINE: Item<bool> = Item::new("hook_quarantine");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingHook {
+    pub sender: Addr,
+    pub contract: Addr,
+    pub amount: Uint128,
+}
+
+// Receive hooks dispatched and not replied yet, innermost send last, only lives within a transaction
+pub const PENDING_HOOKS: Item<Vec<PendingHook>> = Item::new("pending_hooks");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct QuarantinedSend {
+    pub id: u64,
+    /// Contract whose receive hook failed
+    pub contract: Addr,
+    /// Net amount sent, held by the token until the sender claims it
+    pub amount: Uint128,
+    /// Error returned by the receive hook
+    pub reason: String,
+    pub quarantined_at: u64,
+}
+
+// (sender, id) -> send quarantined after its receive hook failed
+pub const QUARANTINED_SENDS: Map<(&Addr, u64), QuarantinedSend> = Map::new("quarantined_sends");
+
+// Id of the next quarantined send
+pub const NEXT_QUARANTINE_ID: Item<u64> = Item::new("next_quarantine_id");
+
+// (proposer, address) -> time the proposer offered to link with the address
+pub const LINK_PROPOSALS: Map<(&Addr, &Addr), u64> = Map::new("link_proposals");
+
+// (address, linked address) -> time of the link, saved in both directions
+pub const ADDRESS_LINKS: Map<(&Addr, &Addr), u64> = Map::new("address_links");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Snapshot {
+    pub id: u64,
+    /// Height the balances were taken at, the boundary for scheduled snapshots
+    pub height: u64,
+    /// Time the snapshot was created, the first touch after the boundary for scheduled snapshots
+    pub time: u64,
+    pub total_supply: Uint128,
+}
+
+// Retained snapshots by id
+pub const SNAPSHOTS: Map<u64, Snapshot> = Map::new("snapshots");
+
+// Id of the next snapshot
+pub const NEXT_SNAPSHOT_ID: Item<u64> = Item::new("next_snapshot_id");
+
+// Interval and retained count of the automatic snapshots
+pub const SNAPSHOT_SCHEDULE: Item<SnapshotSchedule> = Item::new("snapshot_schedule");
+
+// Last boundary of the schedule a snapshot was taken for
+pub const SNAPSHOT_BOUNDARY: Item<u64> = Item::new("snapshot_boundary");
+
+// (address, snapshot id) -> balance in the snapshot, recorded by the first change after it
+pub const SNAPSHOT_BALANCES: Map<(&Addr, u64), Uint128> = Map::new("snapshot_balances");
+
+// (snapshot id, address) of `SNAPSHOT_BALANCES`, lists the addresses that changed after a snapshot
+pub const SNAPSHOT_CHANGES: Map<(u64, &Addr), Empty> = Map::new("snapshot_changes");
+
+// Snapshots pruned whose balances are still being deleted, see `snapshots::prune_balances`
+pub const PRUNED_SNAPSHOTS: Map<u64, Empty> = Map::new("pruned_snapshots");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EpochReward {
+    /// Snapshot the reward is shared by
+    pub epoch: u64,
+    /// Tokens held by the token contract for the holders of the snapshot
+    pub amount: Uint128,
+    pub claimed: Uint128,
+    pub expires_at: u64,
+    /// Whether the unclaimed amount went back to the admin after the expiry
+    pub swept: bool,
+}
+
+// Reward pools by snapshot id, see `epoch_rewards::fund_epoch_reward`
+pub const EPOCH_REWARDS: Map<u64, EpochReward> = Map::new("epoch_rewards");
+
+// (snapshot id, holder) -> reward claimed by the holder
+pub const EPOCH_REWARD_CLAIMS: Map<(u64, &Addr), Uint128> = Map::new("epoch_reward_claims");
+
+// Spending policy contract attached by a holder to its account, see `policy::set_spending_policy`
+pub const SPENDING_POLICIES: Map<&Addr, Addr> = Map::new("spending_policies");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PolicyChange {
+    /// Policy replacing the current one, None removes it
+    pub policy: Option<Addr>,
+    pub effective_at: u64,
+}
+
+// Delayed replacement or removal of the spending policy of a holder
+pub const PENDING_POLICY_CHANGES: Map<&Addr, PolicyChange> = Map::new("pending_policy_changes");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TaxDestination {
+    pub address: String,
+    /// Share of every taxed transfer credited to `address`, in basis points
+    pub bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TaxConfig {
+    /// Share of every taxed transfer accruing to holders in claim mode, credited to the treasury
+    /// in treasury mode, in basis points
+    pub reflection_bps: u16,
+    /// Share of every taxed transfer burnt on the spot, in basis points
+    pub burn_bps: u16,
+    /// Address receiving the rest of the tax, e.g. a treasury or a fee splitter
+    pub treasury: Option<TaxDestination>,
+}
+
+// Split of the tax over its destinations, replaces the reflection and burn rates while set
+pub const TAX_CONFIG: Item<TaxConfig> = Item::new("tax_config");
+
+// Global tax rate before the tax config was set, restored when the tax config is removed
+pub const TAX_RATE_BEFORE_CONFIG: Item<Decimal> = Item::new("tax_rate_before_config");
+
+/// How the tax rate of a transfer is chosen, see `tax_strategy`. The tax is split at that rate the
+/// way it is split at the global rate
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxStrategyConfig {
+    /// Every transfer pays the global rate
+    Flat {},
+    /// Transfers pay the rate of the largest bracket they reach, the global rate below the first
+    Bracketed { brackets: Vec<TaxBracket> },
+    /// The rate moves linearly from `start_rate` at `start` (seconds) to the global rate over
+    /// `duration` seconds, e.g. a launch tax against snipers
+    Decaying {
+        start_rate: Decimal,
+        start: u64,
+        duration: u64,
+    },
+    /// Senders pay the rate of the largest tier their volume reached in the current window of
+    /// `window` seconds, before the transfer, the global rate below the first tier
+    VolumeBased { window: u64, tiers: Vec<VolumeTier> },
+}
+
+impl Default for TaxStrategyConfig {
+    fn default() -> Self {
+        TaxStrategyConfig::Flat {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TaxBracket {
+    pub min_amount: Uint128,
+    pub rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VolumeTier {
+    pub min_volume: Uint128,
+    pub rate: Decimal,
+}
+
+// Strategy choosing the tax rate of transfers, flat when unset
+pub const TAX_STRATEGY: Item<TaxStrategyConfig> = Item::new("tax_strategy");
+
+// (window, volume) sent by an address in its last window, see `TaxStrategyConfig::VolumeBased`
+pub const SENDER_VOLUMES: Map<&Addr, (u64, Uint128)> = Map::new("sender_volumes");
+
+// Addresses sending and receiving without tax, see `fee_exempt::set_fee_exempt`
+pub const FEE_EXEMPT: Map<&Addr, Empty> = Map::new("fee_exempt");
+
+// (contract, hook message hash) of the sends without tax, see `fee_exempt::set_tax_free_hook`
+pub const TAX_FREE_HOOKS: Map<(&Addr, &str), Empty> = Map::new("tax_free_hooks");
+
+// cw20-ics20 or other bridge contract escrowing the token, see `bridge::set_bridge`
+pub const BRIDGE: Item<Addr> = Item::new("bridge");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct WalletLimits {
+    /// Most tokens a single transfer can move
+    pub max_tx_amount: Option<Uint128>,
+    /// Most tokens an address can hold after receiving a transfer
+    pub max_wallet_amount: Option<Uint128>,
+}
+
+// Anti-sniping limits of transfers, unset while neither limit is
+pub const WALLET_LIMITS: Item<WalletLimits> = Item::new("wallet_limits");
+
+// Addresses out of reach of the wallet limits, see `wallet_limits::set_limits_exempt`
+pub const LIMITS_EXEMPT: Map<&Addr, Empty> = Map::new("limits_exempt");
+
+// Height of the last block opened for checkpoints, see `checkpoints::begin_checkpoints`
+pub const CHECKPOINT_HEIGHT: Item<u64> = Item::new("checkpoint_height");
+
+// First height covered by the checkpoints
+pub const CHECKPOINTS_SINCE: Item<u64> = Item::new("checkpoints_since");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SupplyCheckpoint {
+    pub total_supply: Uint128,
+    /// `ReflectionIndex::per_token`
+    pub per_token: Decimal,
+}
+
+// height -> total supply and reflection index at the start of the block, recorded by its first
+// execution
+pub const SUPPLY_CHECKPOINTS: Map<u64, SupplyCheckpoint> = Map::new("supply_checkpoints");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BalanceCheckpoint {
+    pub balance: Uint128,
+    /// `HolderReflections::per_token`
+    pub per_token: Decimal,
+    /// `HolderReflections::pending`
+    pub pending: Uint128,
+}
+
+// (address, height) -> balance and reflections at the start of the block, recorded by the first
+// change in it
+pub const BALANCE_CHECKPOINTS: Map<(&Addr, u64), BalanceCheckpoint> =
+    Map::new("balance_checkpoints");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct Vesting {
+    /// Part of the initial balance locked by the schedule
+    pub amount: Uint128,
+    /// Time of the instantiation, the schedule runs from it
+    pub start: u64,
+    pub cliff: u64,
+    pub duration: u64,
+    pub period: u64,
+}
+
+// Vesting schedules of the initial balances, removed once released, see `vesting::ensure_vested`
+pub const VESTING: Map<&Addr, Vesting> = Map::new("vesting");
+
+// Block time of the current execution while vesting schedules are left, see `vesting::tick_vesting`
+pub const VESTING_CLOCK: Item<u64> = Item::new("vesting_clock");
+
+// Fractional digits frontends should show, `decimals` when not set
+pub const DISPLAY_DECIMALS: Item<u8> = Item::new("display_decimals");
+
+// Address allowed to pause the token and freeze addresses, see `security`
+pub const GUARDIAN: Item<Addr> = Item::new("guardian");
+
+// Address allowed to change the tax next to the admin, see `roles`
+pub const TAX_MANAGER: Item<Addr> = Item::new("tax_manager");
+
+// Address allowed to change the tax and limit exemptions next to the admin, see `roles`
+pub const EXEMPTION_MANAGER: Item<Addr> = Item::new("exemption_manager");
+
+// Time of the `Pause`, removed by `Unpause`
+pub const PAUSED_AT: Item<u64> = Item::new("paused_at");
+
+// Last balance checked by `VerifyInvariants`, the next sample starts after it
+pub const INVARIANT_CURSOR: Item<Addr> = Item::new("invariant_cursor");
+
+// Frozen addresses with the time they were frozen
+pub const FROZEN: Map<&Addr, u64> = Map::new("frozen");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AutoLiquidityConfig {
+    /// DojoSwap pair of the token and `quote`
+    pub pair: String,
+    pub quote: AssetInfo,
+    /// Share of every tax set aside for liquidity, taken out of the treasury share
+    pub rate: Decimal,
+    /// Tokens set aside from which half is swapped and liquidity added
+    pub threshold: Uint128,
+    /// Address receiving the LP tokens, e.g. a locker or a burn address
+    pub lock_address: String,
+    /// Max spread of the swap and slippage tolerance of the liquidity, unchecked by default
+    pub max_spread: Option<Decimal>,
+}
+
+// Swap-and-liquify of a share of the tax, see `auto_liquidity`
+pub const AUTO_LIQUIDITY: Item<AutoLiquidityConfig> = Item::new("auto_liquidity");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct AutoLiquidityState {
+    /// Tokens set aside on the token contract balance and not swapped yet
+    pub accumulated: Uint128,
+    /// Quote asset left on the token contract by a failed provide, added to the next one
+    pub quote_held: Uint128,
+    /// Run between its swap and its provide
+    pub in_flight: Option<LiquifyRun>,
+    /// Tokens added as liquidity so far
+    pub provided: Uint128,
+    /// Error of the last swap or provide which failed
+    pub last_failure: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct LiquifyRun {
+    /// Tokens sent to the pair by the swap
+    pub swapped: Uint128,
+    /// Tokens provided with what the swap returns
+    pub kept: Uint128,
+    /// Quote balance of the token contract before the swap
+    pub quote_before: Uint128,
+    /// Quote asset provided, known once the swap replied
+    pub quote: Uint128,
+}
+
+pub const AUTO_LIQUIDITY_STATE: Item<AutoLiquidityState> = Item::new("auto_liquidity_state");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Sunset {
+    /// Native denom of the settlement pool
+    pub denom: String,
+    /// Time after which only `RedeemFinal` is accepted
+    pub activates_at: u64,
+    pub pool: Uint128,
+    /// Tokens sharing the pool, recorded by the first execution after the activation
+    pub supply: Option<Uint128>,
+    /// Tokens burnt by `RedeemFinal` so far
+    pub redeemed: Uint128,
+    /// Part of the pool paid so far
+    pub paid: Uint128,
+}
+
+// Wind-down of the token, see `sunset`
+pub const SUNSET: Item<Sunset> = Item::new("sunset");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingAdmin {
+    pub address: Addr,
+    /// The transfer can no longer be accepted after it
+    pub expires: Expiration,
+}
+
+// Admin proposed by `TransferOwnership` until it accepts, see `roles`
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CoSignRule {
+    pub co_signer: Addr,
+    /// Transfers out of the account above it need the approval of the co-signer
+    pub threshold: Uint128,
+}
+
+// Co-signing rule of each account which opted in, see `co_signing`
+pub const CO_SIGN_RULES: Map<&Addr, CoSignRule> = Map::new("co_sign_rules");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CoSignRuleChange {
+    /// Rule replacing the current one, None removes it
+    pub rule: Option<CoSignRule>,
+    pub effective_at: u64,
+}
+
+// Delayed replacement or removal of the co-signing rule of an account
+pub const PENDING_CO_SIGN_RULES: Map<&Addr, CoSignRuleChange> = Map::new("pending_co_sign_rules");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CoSignedTransfer {
+    pub id: u64,
+    pub recipient: String,
+    pub amount: Uint128,
+    /// The transfer can no longer be approved or executed from this time
+    pub expires_at: u64,
+    pub approved: bool,
+}
+
+// Transfers proposed by each account for its co-signer, by id
+pub const CO_SIGNED_TRANSFERS: Map<(&Addr, u64), CoSignedTransfer> =
+    Map::new("co_signed_transfers");
+
+pub const NEXT_CO_SIGNED_ID: Item<u64> = Item::new("next_co_signed_id");
+
+// Approved transfer being executed, taken by the co-signing check of its transfer
+pub const CO_SIGNED_RELEASE: Item<(Addr, CoSignedTransfer)> = Item::new("co_signed_release");
+
+// Contracts called with `TransferHookMsg` after every transfer, see `hooks`
+pub const TRANSFER_HOOKS: Item<Vec<Addr>> = Item::new("transfer_hooks");
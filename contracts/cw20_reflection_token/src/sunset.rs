@@ -0,0 +1,315 @@
+use cosmwasm_std::{
+    coins, BankMsg, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw20_base::ContractError;
+
+use crate::contract::ensure_admin;
+use crate::drip::drip_pending;
+use crate::effective::query_effective_balance;
+use crate::fees::DAY;
+use crate::ledger::decrease_balance;
+use crate::msg::{ExecuteMsg, SunsetResponse};
+use crate::reflections::{is_claim_mode, take_pending_reflections};
+use crate::state::{
+    Sunset, AUTO_LIQUIDITY_STATE, CUSTODIAL_EXCESS, INSURANCE_FUND, QUARANTINED_SENDS,
+    REFLECTION_INDEX, SUNSET,
+};
+
+/// Seconds between the admin scheduling the sunset and transfers stopping, holders can still
+/// leave the token in the meantime
+pub const SUNSET_DELAY: u64 = 7 * DAY;
+
+/// Seconds after the activation during which holders redeem, what is left in the pool can then be
+/// swept by the admin
+pub const SETTLEMENT_CLAIM_PERIOD: u64 = 365 * DAY;
+
+/// Schedules the wind-down of the token: after `SUNSET_DELAY` only `RedeemFinal` is accepted, paying
+/// holders their pro-rata share of a settlement pool of `denom` funded with `FundSettlement`
+pub fn schedule_sunset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if SUNSET.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Sunset already scheduled",
+        )));
+    }
+    let sunset = Sunset {
+        denom,
+        activates_at: env.block.time.seconds() + SUNSET_DELAY,
+        pool: Uint128::zero(),
+        supply: None,
+        redeemed: Uint128::zero(),
+        paid: Uint128::zero(),
+    };
+    SUNSET.save(deps.storage, &sunset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "schedule_sunset")
+        .add_attribute("denom", sunset.denom)
+        .add_attribute("activates_at", sunset.activates_at.to_string()))
+}
+
+fn pending_sunset(storage: &dyn Storage, env: &Env) -> Result<Sunset, ContractError> {
+    let sunset = SUNSET
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("No sunset scheduled"))?;
+    if env.block.time.seconds() >= sunset.activates_at {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Sunset is already active",
+        )));
+    }
+    Ok(sunset)
+}
+
+/// Cancels the sunset before it activates, the settlement pool goes back to the admin
+pub fn cancel_sunset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let sunset = pending_sunset(deps.storage, &env)?;
+    SUNSET.remove(deps.storage);
+
+    let mut res = Response::new()
+        .add_attribute("action", "cancel_sunset")
+        .add_attribute("refunded", sunset.pool);
+    if !sunset.pool.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(sunset.pool.u128(), sunset.denom),
+        });
+    }
+    Ok(res)
+}
+
+/// Adds the funds sent to the settlement pool, only until the sunset activates so that every
+/// holder redeems at the same rate
+pub fn fund_settlement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let mut sunset = pending_sunset(deps.storage, &env)?;
+    let amount = match info.funds.as_slice() {
+        [coin] if coin.denom == sunset.denom && !coin.amount.is_zero() => coin.amount,
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Send only {} to the settlement pool",
+                sunset.denom
+            ))))
+        }
+    };
+    sunset.pool += amount;
+    SUNSET.save(deps.storage, &sunset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_settlement")
+        .add_attribute("amount", amount)
+        .add_attribute("pool", sunset.pool))
+}
+
+/// Supply the pool is shared over: the total supply minus the fee buckets held by the token
+/// contract, which nobody redeems. Reflections accrued to holders and quarantined sends are
+/// redeemed by their owners
+fn redeemable_supply(storage: &dyn Storage) -> StdResult<Uint128> {
+    let mut buckets = drip_pending(storage)?;
+    buckets += CUSTODIAL_EXCESS.may_load(storage)?.unwrap_or_default();
+    buckets += INSURANCE_FUND
+        .may_load(storage)?
+        .unwrap_or_default()
+        .balance;
+    buckets += AUTO_LIQUIDITY_STATE
+        .may_load(storage)?
+        .unwrap_or_default()
+        .accumulated;
+    if is_claim_mode(storage)? {
+        buckets += REFLECTION_INDEX
+            .may_load(storage)?
+            .unwrap_or_default()
+            .undistributed;
+    }
+    Ok(TOKEN_INFO
+        .load(storage)?
+        .total_supply
+        .saturating_sub(buckets))
+}
+
+/// Called at the top of `execute`: once the sunset is active only `RedeemFinal`, `SweepSettlement`
+/// and the guardian messages go through. The first execution after the activation records the
+/// redeemable supply, which nothing can change afterwards: true is returned once the sunset is
+/// active, and `execute` then stops releasing the taxes dripping and the custodial reflections
+pub fn ensure_not_sunset(
+    storage: &mut dyn Storage,
+    env: &Env,
+    msg: &ExecuteMsg,
+) -> Result<bool, ContractError> {
+    let mut sunset = match SUNSET.may_load(storage)? {
+        Some(sunset) if env.block.time.seconds() >= sunset.activates_at => sunset,
+        _ => return Ok(false),
+    };
+    if sunset.supply.is_none() {
+        sunset.supply = Some(redeemable_supply(storage)?);
+        SUNSET.save(storage, &sunset)?;
+    }
+    if matches!(
+        msg,
+        ExecuteMsg::RedeemFinal {}
+            | ExecuteMsg::SweepSettlement {}
+            | ExecuteMsg::RenounceGuardian {}
+            | ExecuteMsg::Pause {}
+            | ExecuteMsg::Unpause {}
+            | ExecuteMsg::Freeze { .. }
+            | ExecuteMsg::Unfreeze { .. }
+    ) {
+        return Ok(true);
+    }
+    Err(ContractError::Std(StdError::generic_err(
+        "Token is sunset, only RedeemFinal is accepted",
+    )))
+}
+
+/// Burns the balance of `env.sender` with its pending reflections and quarantined sends, and pays
+/// it the same share of the settlement pool
+pub fn redeem_final(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut sunset = SUNSET
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No sunset scheduled"))?;
+    let supply = match sunset.supply {
+        Some(supply) => supply,
+        None => {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Sunset activates at {}",
+                sunset.activates_at
+            ))))
+        }
+    };
+
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if !balance.is_zero() {
+        decrease_balance(deps.storage, &info.sender, balance)?;
+    }
+    let reflections = take_pending_reflections(deps.storage, &env, &info.sender)?;
+    let quarantined: Vec<(u64, Uint128)> = QUARANTINED_SENDS
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(id, send)| (id, send.amount)))
+        .collect::<StdResult<_>>()?;
+    let mut quarantined_amount = Uint128::zero();
+    for (id, amount) in quarantined {
+        QUARANTINED_SENDS.remove(deps.storage, (&info.sender, id));
+        quarantined_amount += amount;
+    }
+    if !quarantined_amount.is_zero() {
+        decrease_balance(deps.storage, &env.contract.address, quarantined_amount)?;
+    }
+    let amount = balance + reflections + quarantined_amount;
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Nothing to redeem",
+        )));
+    }
+
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+    token_info.total_supply = token_info
+        .total_supply
+        .checked_sub(amount)
+        .map_err(StdError::from)?;
+    TOKEN_INFO.save(deps.storage, &token_info)?;
+    // rounded down, the payouts never add up to more than the pool
+    let payout = sunset
+        .pool
+        .multiply_ratio(amount, supply.max(Uint128::one()))
+        .min(sunset.pool - sunset.paid);
+    sunset.redeemed += amount;
+    sunset.paid += payout;
+    SUNSET.save(deps.storage, &sunset)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "redeem_final")
+        .add_attribute("from", info.sender.clone())
+        .add_attribute("amount", amount)
+        .add_attribute("payout", payout);
+    if !payout.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(payout.u128(), sunset.denom),
+        });
+    }
+    Ok(res)
+}
+
+/// Sends what is left in the pool to the admin once `SETTLEMENT_CLAIM_PERIOD` elapsed since the
+/// activation, the share of the holders which never redeemed, such as pairs and the treasury
+pub fn sweep_settlement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let mut sunset = SUNSET
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No sunset scheduled"))?;
+    let sweepable_at = sunset.activates_at + SETTLEMENT_CLAIM_PERIOD;
+    if env.block.time.seconds() < sweepable_at {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Settlement can be swept from {}",
+            sweepable_at
+        ))));
+    }
+    let swept = sunset.pool - sunset.paid;
+    if swept.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Nothing to sweep",
+        )));
+    }
+    sunset.paid = sunset.pool;
+    SUNSET.save(deps.storage, &sunset)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sweep_settlement")
+        .add_attribute("swept", swept)
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(swept.u128(), sunset.denom),
+        }))
+}
+
+pub fn query_sunset(deps: Deps, env: Env, address: Option<String>) -> StdResult<SunsetResponse> {
+    let sunset = match SUNSET.may_load(deps.storage)? {
+        Some(sunset) => sunset,
+        None => return Ok(SunsetResponse::default()),
+    };
+    let supply = match sunset.supply {
+        Some(supply) => supply,
+        None => redeemable_supply(deps.storage)?,
+    };
+    let payout = match address {
+        Some(address) => {
+            let redeemable = query_effective_balance(deps, env.clone(), address)?.total;
+            Some(
+                sunset
+                    .pool
+                    .multiply_ratio(redeemable, supply.max(Uint128::one()))
+                    .min(sunset.pool - sunset.paid),
+            )
+        }
+        None => None,
+    };
+
+    Ok(SunsetResponse {
+        active: env.block.time.seconds() >= sunset.activates_at,
+        redeemable_supply: supply,
+        payout,
+        sunset: Some(sunset),
+    })
+}
@@ -0,0 +1,188 @@
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::state::TOKEN_INFO;
+use cw20_base::ContractError;
+
+use crate::auto_liquidity::credit_auto_liquidity;
+use crate::contract::TAX_RATE;
+use crate::drip::{credit_claim_reflections, credit_treasury};
+use crate::insurance::credit_insurance;
+use crate::ledger::increase_balance;
+use crate::msg::{QueryTaxResponse, Role, TaxConfigResponse};
+use crate::rate_limit::record_tax_rate_change;
+use crate::reflections::is_claim_mode;
+use crate::roles::ensure_role;
+use crate::state::{TaxConfig, AUTO_LIQUIDITY, INSURANCE_RATE, TAX_CONFIG, TAX_RATE_BEFORE_CONFIG};
+
+/// Basis points of a whole transfer
+pub const BPS: u32 = 10_000;
+
+impl TaxConfig {
+    pub fn total_bps(&self) -> u32 {
+        self.reflection_bps as u32
+            + self.burn_bps as u32
+            + self
+                .treasury
+                .as_ref()
+                .map_or(0, |treasury| treasury.bps as u32)
+    }
+
+    /// Global tax rate of the split
+    pub fn rate(&self) -> Decimal {
+        Decimal::from_ratio(self.total_bps(), BPS)
+    }
+
+    /// Splits the tax on `amount` at `tax_rate`, the global rate or the rate of the tax strategy,
+    /// over the destinations in proportion of their bps. The rounding of the shares goes to the
+    /// treasury address, or to the burn without one
+    pub fn split(&self, amount: Uint128, tax_rate: Decimal) -> QueryTaxResponse {
+        let total = self.total_bps().max(1);
+        let taxed_amount = amount * tax_rate;
+        let reflection_amount = taxed_amount.multiply_ratio(self.reflection_bps, total);
+        let mut burn_amount = taxed_amount.multiply_ratio(self.burn_bps, total);
+        let mut destination_amount = taxed_amount - reflection_amount - burn_amount;
+        if self.treasury.is_none() {
+            burn_amount += destination_amount;
+            destination_amount = Uint128::zero();
+        }
+
+        QueryTaxResponse {
+            taxed_amount,
+            after_tax: amount - taxed_amount,
+            reflection_amount,
+            liquidity_amount: Uint128::zero(),
+            burn_amount,
+            destination_amount,
+        }
+    }
+}
+
+/// Insurance and auto-liquidity shares are taken out of the share of the treasury address of a
+/// tax config, a config without one would leave them empty
+pub fn ensure_no_destination_shares(storage: &dyn Storage) -> StdResult<()> {
+    let insurance = !INSURANCE_RATE
+        .may_load(storage)?
+        .unwrap_or_default()
+        .is_zero();
+    if insurance || AUTO_LIQUIDITY.may_load(storage)?.is_some() {
+        return Err(StdError::generic_err(
+            "Insurance and auto-liquidity are taken from the treasury address of the tax config",
+        ));
+    }
+    Ok(())
+}
+
+/// Fails under a tax config without a treasury address, see `ensure_no_destination_shares`
+pub fn ensure_tax_destination(storage: &dyn Storage) -> StdResult<()> {
+    if TAX_CONFIG
+        .may_load(storage)?
+        .is_some_and(|tax_config| tax_config.treasury.is_none())
+    {
+        return Err(StdError::generic_err(
+            "The tax config has no treasury address to take the share from",
+        ));
+    }
+    Ok(())
+}
+
+/// Sets how the tax is split, the total is the new global tax rate and moves within the max tax
+/// rate and step like `SetTaxRate`. None restores the global rate from before the tax config and
+/// goes back to the reflection and burn rates
+pub fn update_tax_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tax_config: Option<TaxConfig>,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::TaxManager)?;
+    match &tax_config {
+        Some(tax_config) => {
+            if tax_config.total_bps() > BPS {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Total tax must be at most {} bps",
+                    BPS
+                ))));
+            }
+            match &tax_config.treasury {
+                Some(treasury) => {
+                    let address = deps.api.addr_validate(&treasury.address)?;
+                    if address == env.contract.address {
+                        return Err(ContractError::Std(StdError::generic_err(
+                            "The tax cannot be credited to the token",
+                        )));
+                    }
+                }
+                None => ensure_no_destination_shares(deps.storage)?,
+            }
+            record_tax_rate_change(deps.storage, &env, tax_config.rate())?;
+            if TAX_CONFIG.may_load(deps.storage)?.is_none() {
+                let rate = TAX_RATE.load(deps.storage)?;
+                TAX_RATE_BEFORE_CONFIG.save(deps.storage, &rate)?;
+            }
+            TAX_RATE.save(deps.storage, &tax_config.rate())?;
+            TAX_CONFIG.save(deps.storage, tax_config)?;
+        }
+        None => {
+            if let Some(rate) = TAX_RATE_BEFORE_CONFIG.may_load(deps.storage)? {
+                record_tax_rate_change(deps.storage, &env, rate)?;
+                TAX_RATE.save(deps.storage, &rate)?;
+                TAX_RATE_BEFORE_CONFIG.remove(deps.storage);
+            }
+            TAX_CONFIG.remove(deps.storage);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_tax_config")
+        .add_attribute(
+            "total_bps",
+            tax_config
+                .map(|tax_config| tax_config.total_bps().to_string())
+                .unwrap_or_default(),
+        ))
+}
+
+/// Credits the tax split by `tax_config`: burns its burn share, credits the treasury address its
+/// share minus the insurance and auto-liquidity shares, and the reflection share to holders in claim mode or to the
+/// treasury otherwise. Returns the addresses credited with their amount
+pub fn credit_split_tax(
+    storage: &mut dyn Storage,
+    env: &Env,
+    treasury: &Addr,
+    tax_config: &TaxConfig,
+    taxes: &QueryTaxResponse,
+) -> StdResult<Vec<(Addr, Uint128)>> {
+    let mut credits = vec![];
+    if !taxes.burn_amount.is_zero() {
+        // the tokens were debited from the sender and are credited to nobody
+        let mut token_info = TOKEN_INFO.load(storage)?;
+        token_info.total_supply = token_info.total_supply.checked_sub(taxes.burn_amount)?;
+        TOKEN_INFO.save(storage, &token_info)?;
+    }
+    if let Some(destination) = &tax_config.treasury {
+        let mut amount = taxes.destination_amount
+            - credit_insurance(storage, env, taxes.taxed_amount, taxes.destination_amount)?;
+        amount -= credit_auto_liquidity(storage, env, taxes.taxed_amount, amount)?;
+        let address = Addr::unchecked(&destination.address);
+        if !amount.is_zero() {
+            increase_balance(storage, &address, amount)?;
+        }
+        credits.push((address, amount));
+    }
+
+    // the other shares are credited first so that the whole eligible supply is in balances
+    if is_claim_mode(storage)? {
+        credit_claim_reflections(storage, env, taxes.reflection_amount)?;
+    } else if !taxes.reflection_amount.is_zero() {
+        credit_treasury(storage, env, treasury, taxes.reflection_amount)?;
+        credits.push((treasury.clone(), taxes.reflection_amount));
+    }
+    Ok(credits)
+}
+
+pub fn query_tax_config(deps: Deps) -> StdResult<TaxConfigResponse> {
+    Ok(TaxConfigResponse {
+        tax_config: TAX_CONFIG.may_load(deps.storage)?,
+    })
+}
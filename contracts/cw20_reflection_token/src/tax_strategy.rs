@@ -0,0 +1,338 @@
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::ContractError;
+
+use crate::contract::{gross_up_at, split_tax, TAX_RATE};
+use crate::msg::{QueryTaxResponse, Role, TaxStrategyResponse};
+use crate::rate_limit::{ensure_tax_rate_allowed, record_tax_rate_change};
+use crate::roles::ensure_role;
+use crate::state::{
+    TaxBracket, TaxStrategyConfig, VolumeTier, MAX_TAX_RATE, SENDER_VOLUMES, TAX_STRATEGY,
+};
+
+/// A transfer whose tax rate is chosen
+pub struct TaxedTransfer<'a> {
+    /// None when quoting without a sender
+    pub sender: Option<&'a str>,
+    pub amount: Uint128,
+    pub now: u64,
+    /// Global tax rate, see `SetTaxRate` and `UpdateTaxConfig`
+    pub global_rate: Decimal,
+}
+
+/// Chooses the tax rate of a transfer. A new strategy implements it and gets a variant of
+/// `TaxStrategyConfig`, the transfer path only goes through `transfer_tax`
+pub trait TaxStrategy {
+    fn rate(&self, storage: &dyn Storage, transfer: &TaxedTransfer) -> StdResult<Decimal>;
+
+    /// Called once the tax of `transfer` is charged, for strategies keeping state
+    fn record(&self, _storage: &mut dyn Storage, _transfer: &TaxedTransfer) -> StdResult<()> {
+        Ok(())
+    }
+
+    /// False when the rate depends on the amount, net amounts are then grossed up at the rate of
+    /// the gross amount
+    fn flat_in_amount(&self) -> bool {
+        true
+    }
+}
+
+struct Flat;
+
+impl TaxStrategy for Flat {
+    fn rate(&self, _storage: &dyn Storage, transfer: &TaxedTransfer) -> StdResult<Decimal> {
+        Ok(transfer.global_rate)
+    }
+}
+
+struct Bracketed<'a> {
+    brackets: &'a [TaxBracket],
+}
+
+impl TaxStrategy for Bracketed<'_> {
+    fn rate(&self, _storage: &dyn Storage, transfer: &TaxedTransfer) -> StdResult<Decimal> {
+        Ok(self
+            .brackets
+            .iter()
+            .rev()
+            .find(|bracket| transfer.amount >= bracket.min_amount)
+            .map_or(transfer.global_rate, |bracket| bracket.rate))
+    }
+
+    fn flat_in_amount(&self) -> bool {
+        false
+    }
+}
+
+struct Decaying {
+    start_rate: Decimal,
+    start: u64,
+    duration: u64,
+}
+
+impl TaxStrategy for Decaying {
+    fn rate(&self, _storage: &dyn Storage, transfer: &TaxedTransfer) -> StdResult<Decimal> {
+        let elapsed = transfer.now.saturating_sub(self.start).min(self.duration);
+        let remaining = Decimal::from_ratio(self.duration - elapsed, self.duration);
+        let global_rate = transfer.global_rate;
+        Ok(if self.start_rate >= global_rate {
+            global_rate + (self.start_rate - global_rate) * remaining
+        } else {
+            global_rate - (global_rate - self.start_rate) * remaining
+        })
+    }
+}
+
+struct VolumeBased<'a> {
+    window: u64,
+    tiers: &'a [VolumeTier],
+}
+
+impl VolumeBased<'_> {
+    fn volume(&self, storage: &dyn Storage, sender: &str, now: u64) -> StdResult<Uint128> {
+        Ok(
+            match SENDER_VOLUMES.may_load(storage, &Addr::unchecked(sender))? {
+                Some((window, volume)) if window == now / self.window => volume,
+                _ => Uint128::zero(),
+            },
+        )
+    }
+}
+
+impl TaxStrategy for VolumeBased<'_> {
+    fn rate(&self, storage: &dyn Storage, transfer: &TaxedTransfer) -> StdResult<Decimal> {
+        let volume = match transfer.sender {
+            Some(sender) => self.volume(storage, sender, transfer.now)?,
+            None => Uint128::zero(),
+        };
+        Ok(self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+            .map_or(transfer.global_rate, |tier| tier.rate))
+    }
+
+    fn record(&self, storage: &mut dyn Storage, transfer: &TaxedTransfer) -> StdResult<()> {
+        let sender = match transfer.sender {
+            Some(sender) => sender,
+            None => return Ok(()),
+        };
+        let volume = self
+            .volume(storage, sender, transfer.now)?
+            .checked_add(transfer.amount)?;
+        SENDER_VOLUMES.save(
+            storage,
+            &Addr::unchecked(sender),
+            &(transfer.now / self.window, volume),
+        )
+    }
+}
+
+impl TaxStrategyConfig {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaxStrategyConfig::Flat {} => "flat",
+            TaxStrategyConfig::Bracketed { .. } => "bracketed",
+            TaxStrategyConfig::Decaying { .. } => "decaying",
+            TaxStrategyConfig::VolumeBased { .. } => "volume_based",
+        }
+    }
+
+    pub fn strategy(&self) -> Box<dyn TaxStrategy + '_> {
+        match self {
+            TaxStrategyConfig::Flat {} => Box::new(Flat),
+            TaxStrategyConfig::Bracketed { brackets } => Box::new(Bracketed { brackets }),
+            TaxStrategyConfig::Decaying {
+                start_rate,
+                start,
+                duration,
+            } => Box::new(Decaying {
+                start_rate: *start_rate,
+                start: *start,
+                duration: *duration,
+            }),
+            TaxStrategyConfig::VolumeBased { window, tiers } => Box::new(VolumeBased {
+                window: *window,
+                tiers,
+            }),
+        }
+    }
+
+    fn rates(&self) -> Vec<Decimal> {
+        match self {
+            TaxStrategyConfig::Flat {} => vec![],
+            TaxStrategyConfig::Bracketed { brackets } => {
+                brackets.iter().map(|bracket| bracket.rate).collect()
+            }
+            TaxStrategyConfig::Decaying { start_rate, .. } => vec![*start_rate],
+            TaxStrategyConfig::VolumeBased { tiers, .. } => {
+                tiers.iter().map(|tier| tier.rate).collect()
+            }
+        }
+    }
+
+    fn validate(&self, max_tax_rate: Decimal) -> StdResult<()> {
+        let ascending = |thresholds: Vec<Uint128>| thresholds.windows(2).all(|w| w[0] < w[1]);
+        match self {
+            TaxStrategyConfig::Flat {} => {}
+            TaxStrategyConfig::Bracketed { brackets } => {
+                if brackets.is_empty()
+                    || !ascending(brackets.iter().map(|b| b.min_amount).collect())
+                {
+                    return Err(StdError::generic_err(
+                        "Brackets must be non empty and sorted by strictly increasing min_amount",
+                    ));
+                }
+            }
+            TaxStrategyConfig::Decaying { duration, .. } => {
+                if *duration == 0 {
+                    return Err(StdError::generic_err("duration must be non zero"));
+                }
+            }
+            TaxStrategyConfig::VolumeBased { window, tiers } => {
+                if *window == 0 {
+                    return Err(StdError::generic_err("window must be non zero"));
+                }
+                if tiers.is_empty() || !ascending(tiers.iter().map(|t| t.min_volume).collect()) {
+                    return Err(StdError::generic_err(
+                        "Tiers must be non empty and sorted by strictly increasing min_volume",
+                    ));
+                }
+            }
+        }
+        if self.rates().iter().any(|rate| *rate > max_tax_rate) {
+            return Err(StdError::generic_err(format!(
+                "Strategy rates must be <= max_tax_rate {}",
+                max_tax_rate
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Selects how the tax rate of transfers is chosen, `Flat` goes back to the global rate. The
+/// rates of the strategy are bounded by the max tax rate and, like a change of the global rate,
+/// stay within the step limit of the reference rate
+pub fn set_tax_strategy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    strategy: TaxStrategyConfig,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::TaxManager)?;
+    let max_tax_rate = MAX_TAX_RATE
+        .may_load(deps.storage)?
+        .unwrap_or(Decimal::one());
+    strategy.validate(max_tax_rate)?;
+    let rates = strategy.rates();
+    for rate in &rates {
+        ensure_tax_rate_allowed(deps.storage, &env, *rate)?;
+    }
+    // pins the reference rate for the step period, so the rates cannot creep by a step per call
+    let current = TAX_RATE.may_load(deps.storage)?.unwrap_or_default();
+    if let Some(rate) = rates.into_iter().find(|rate| *rate != current) {
+        record_tax_rate_change(deps.storage, &env, rate)?;
+    }
+    match strategy {
+        TaxStrategyConfig::Flat {} => TAX_STRATEGY.remove(deps.storage),
+        _ => TAX_STRATEGY.save(deps.storage, &strategy)?,
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_tax_strategy")
+        .add_attribute("strategy", strategy.name()))
+}
+
+fn taxed_transfer<'a>(
+    storage: &dyn Storage,
+    env: &Env,
+    sender: Option<&'a str>,
+    amount: Uint128,
+) -> StdResult<TaxedTransfer<'a>> {
+    Ok(TaxedTransfer {
+        sender,
+        amount,
+        now: env.block.time.seconds(),
+        global_rate: TAX_RATE.may_load(storage)?.unwrap_or_default(),
+    })
+}
+
+/// Tax of a transfer of `amount` from `sender` at the rate of the strategy, without charging it
+pub fn tax_of(
+    storage: &dyn Storage,
+    env: &Env,
+    sender: Option<&str>,
+    amount: Uint128,
+) -> StdResult<QueryTaxResponse> {
+    let config = TAX_STRATEGY.may_load(storage)?.unwrap_or_default();
+    let transfer = taxed_transfer(storage, env, sender, amount)?;
+    let rate = config.strategy().rate(storage, &transfer)?;
+    split_tax(storage, amount, rate)
+}
+
+/// Tax of a transfer of `amount` from `sender`, recorded by the strategy as charged unless the
+/// transfer is `exempt`
+pub fn transfer_tax(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &str,
+    amount: Uint128,
+    exempt: bool,
+) -> StdResult<QueryTaxResponse> {
+    let taxes = tax_of(storage, env, Some(sender), amount)?;
+    if exempt {
+        return Ok(taxes);
+    }
+    if let Some(config) = TAX_STRATEGY.may_load(storage)? {
+        let transfer = taxed_transfer(storage, env, Some(sender), amount)?;
+        config.strategy().record(storage, &transfer)?;
+    }
+    Ok(taxes)
+}
+
+/// Smallest amount `sender` transfers for the recipient to get `net`. When the rate depends on
+/// the amount, fails if no gross amount is taxed at the rate it was grossed up at
+pub fn gross_up_transfer(
+    storage: &dyn Storage,
+    env: &Env,
+    sender: &str,
+    net: Uint128,
+) -> StdResult<Uint128> {
+    let config = TAX_STRATEGY.may_load(storage)?.unwrap_or_default();
+    let strategy = config.strategy();
+    let rate_of = |amount: Uint128| -> StdResult<Decimal> {
+        strategy.rate(
+            storage,
+            &taxed_transfer(storage, env, Some(sender), amount)?,
+        )
+    };
+    let rate = rate_of(net)?;
+    let gross = gross_up_at(net, rate)?;
+    if strategy.flat_in_amount() {
+        return Ok(gross);
+    }
+    let gross_rate = rate_of(gross)?;
+    let gross = gross_up_at(net, gross_rate)?;
+    if rate_of(gross)? != gross_rate {
+        return Err(StdError::generic_err(format!(
+            "Cannot gross up {} across tax brackets",
+            net
+        )));
+    }
+    Ok(gross)
+}
+
+pub fn query_tax_strategy(deps: Deps, env: Env) -> StdResult<TaxStrategyResponse> {
+    let strategy = TAX_STRATEGY.may_load(deps.storage)?.unwrap_or_default();
+    let transfer = taxed_transfer(deps.storage, &env, None, Uint128::zero())?;
+    // what a quote without a sender pays now below the first bracket
+    let current_rate = strategy.strategy().rate(deps.storage, &transfer)?;
+
+    Ok(TaxStrategyResponse {
+        strategy,
+        global_rate: transfer.global_rate,
+        current_rate,
+    })
+}
@@ -0,0 +1,6594 @@
+use crate::quarantine::HOOK_QUARANTINE_REPLY_ID;
+use crate::signing::domain_separator;
+use crate::trophy::BURN_TROPHY_REPLY_ID;
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Decimal,
+    Empty, Env, Event, MemoryStorage, OwnedDeps, Reply, ReplyOn, SubMsg, SubMsgResponse,
+    SubMsgResult, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use cw20::{Cw20Coin, Expiration};
+use cw20_base::state::TOKEN_INFO;
+use dojoswap::asset::{Asset, AssetInfo};
+use dojoswap::mock_querier::compute_swap;
+use dojoswap::pair::{PoolResponse, QueryMsg as PairQueryMsg, SimulationResponse};
+use dojoswap::taxed::hook_msg_hash;
+
+use crate::api::API_VERSION;
+use crate::auto_liquidity::{AUTO_LIQUIDITY_PROVIDE_REPLY_ID, AUTO_LIQUIDITY_SWAP_REPLY_ID};
+use crate::co_signing::{CO_SIGNED_TRANSFER_EXPIRY, CO_SIGNER_DELAY};
+use crate::contract::{execute, gross_up, instantiate, migrate, query, query_rate, reply, sudo};
+use crate::fees::{DAY, YEAR};
+use crate::insurance::INSURANCE_WITHDRAWAL_DELAY;
+use crate::msg::{
+    AddressLabel, AddressLabelsResponse, ApiVersionResponse, AutoLiquidityResponse,
+    BalanceAtResponse, BridgeConfigResponse, BurnTrophyResponse, ClaimHistoryResponse,
+    CoSignerResponse, ConcentrationResponse, ConfigHashResponse, CustodialResponse,
+    Cw721ExecuteMsg, DisplayInfoResponse, EffectiveBalanceResponse, EpochRewardClaimResponse,
+    ExecuteMsg, FeeExemptionsResponse, GatedForwardExecuteMsg, GatedForwardMsg, GrantAction,
+    HolderBucket, HolderDistributionResponse, InstantiateMarketingInfo, InstantiateMsg,
+    InsuranceFundResponse, InsuranceWithdrawalsResponse, LimitExemptionsResponse, LimitsResponse,
+    LinkedAddressesResponse, MigrateAction, MigrateMsg, OperatorMsg, OwnershipResponse,
+    PendingReflectionsResponse, QuarantinedSendsResponse, QueryMsg, QueryTaxResponse,
+    ReflectionAprResponse, ReflectionDripResponse, ReflectionIndexResponse, ReflectionMode,
+    RelayMsg, Role, RolesResponse, ScheduleReceiptsResponse, ScheduledAction,
+    ScheduledActionsResponse, SecurityStatusResponse, SignatureDomainResponse,
+    SimulateSwapRoundTripResponse, SnapshotBalanceResponse, SnapshotDiffResponse, SnapshotSchedule,
+    SnapshotsResponse, SpendLimit, SpendableViaResponse, SpendingPolicyQueryMsg,
+    SpendingPolicyResponse, SpendingVerdict, SudoMsg, SunsetResponse, TaxConfigResponse,
+    TaxFreeHooksResponse, TaxLimitsResponse, TotalSupplyAtResponse, TradingGateResponse,
+    TransferHooksResponse, VestingInfoResponse, VestingSchedule,
+};
+use crate::policy::SPENDING_POLICY_DELAY;
+use crate::reflections::{eligible_supply, CLAIM_HISTORY_SIZE};
+use crate::state::{AutoLiquidityConfig, TaxConfig, TaxDestination, MAX_TAX_RATE, MAX_TAX_STEP};
+use crate::sunset::SUNSET_DELAY;
+use crate::vesting::validate_vesting;
+
+type MockDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+pub const ADMIN: &str = "admin0000";
+pub const TREASURY: &str = "treasury0000";
+
+/// Instantiates the token and registers the treasury the same way the instantiate reply would
+pub fn setup(initial_balances: &[(&str, u128)]) -> MockDeps {
+    setup_with(initial_balances, |_| {})
+}
+
+/// Same as `setup`, `configure` can change the instantiate message before it is executed
+pub fn setup_with<F>(initial_balances: &[(&str, u128)], configure: F) -> MockDeps
+where
+    F: FnOnce(&mut InstantiateMsg),
+{
+    let mut deps = mock_dependencies();
+    let mut msg = InstantiateMsg {
+        name: "Reflection".to_string(),
+        symbol: "RFL".to_string(),
+        decimals: 6,
+        treasury_code_id: 1,
+        cw20_code_id: None,
+        initial_balances: initial_balances
+            .iter()
+            .map(|(address, amount)| Cw20Coin {
+                address: address.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .collect(),
+        admin: ADMIN.to_string(),
+        router: "router0000".to_string(),
+        mint: None,
+        marketing: None,
+        max_tax_rate: None,
+        max_tax_step: None,
+        reflection_mode: None,
+        max_tx_amount: None,
+        max_wallet_amount: None,
+        vesting: None,
+    };
+    configure(&mut msg);
+    instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("instantiate").add_attribute("_contract_address", TREASURY)],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    deps
+}
+
+fn bucket(min: u128, max: Option<u128>, holders: u64) -> HolderBucket {
+    HolderBucket {
+        min: Uint128::new(min),
+        max: max.map(Uint128::new),
+        holders,
+    }
+}
+
+#[test]
+fn holder_distribution_follows_balances() {
+    let mut deps = setup(&[("addr0000", 5), ("addr0001", 50), ("addr0002", 5000)]);
+
+    let res: HolderDistributionResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HolderDistribution { buckets: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_holders, 3);
+    assert_eq!(
+        res.buckets,
+        vec![
+            bucket(1, Some(10), 1),
+            bucket(10, Some(100), 1),
+            bucket(100, Some(1000), 0),
+            bucket(1000, None, 1),
+        ]
+    );
+
+    // addr0000 leaves, addr0003 joins in the lowest decade
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0003".to_string(),
+            amount: Uint128::new(5),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+
+    let res: HolderDistributionResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HolderDistribution {
+                buckets: Some(vec![Uint128::new(1), Uint128::new(100)]),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_holders, 3);
+    assert_eq!(
+        res.buckets,
+        vec![bucket(1, Some(100), 2), bucket(100, None, 1)]
+    );
+
+    // the holders below the lowest bound are counted in a first bucket
+    let res: HolderDistributionResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HolderDistribution {
+                buckets: Some(vec![Uint128::new(1000)]),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.total_holders, 3);
+    assert_eq!(
+        res.buckets,
+        vec![bucket(1, Some(1000), 2), bucket(1000, None, 1)]
+    );
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::HolderDistribution {
+            buckets: Some(vec![Uint128::new(50)]),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: bucket bounds must be powers of ten"
+    );
+}
+
+#[test]
+fn concentration_tracks_top_holders() {
+    let balances: Vec<(String, u128)> = (0..12)
+        .map(|i| (format!("addr{:04}", i), 100 + i as u128))
+        .collect();
+    let balances: Vec<(&str, u128)> = balances.iter().map(|(a, b)| (a.as_str(), *b)).collect();
+    let mut deps = setup(&balances);
+
+    let res: ConcentrationResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Concentration {}).unwrap()).unwrap();
+    assert_eq!(res.top_holders.len(), 10);
+    assert_eq!(res.top_holders[0].address, "addr0011");
+    assert_eq!(res.top_holders[9].address, "addr0002");
+    // every holder sits in the same decade, so buckets cannot tell them apart
+    assert_eq!(res.gini, Decimal::zero());
+
+    // addr0000 concentrates most of the supply into a single account
+    for i in 1..12 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&format!("addr{:04}", i), &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::new(99),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    }
+
+    let res: ConcentrationResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Concentration {}).unwrap()).unwrap();
+    assert_eq!(res.top_holders[0].address, "addr0000");
+    assert_eq!(res.top_holders[0].amount, Uint128::new(100 + 11 * 99));
+    assert!(res.top_holders_share > Decimal::percent(99));
+    assert!(res.gini > Decimal::percent(80));
+}
+
+#[test]
+fn concentration_leaves_out_excluded_addresses() {
+    let mut deps = setup(&[
+        (TREASURY, 5_000),
+        ("addr0000", 3_000),
+        ("addr0001", 1_000),
+        ("pair0000", 1_000),
+    ]);
+    let concentration = |deps: &MockDeps| -> ConcentrationResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Concentration {}).unwrap()).unwrap()
+    };
+    let ranked = |res: &ConcentrationResponse| -> Vec<String> {
+        res.top_holders
+            .iter()
+            .map(|holder| holder.address.clone())
+            .collect()
+    };
+
+    // the treasury is excluded from reflections by the reply
+    let res = concentration(&deps);
+    assert_eq!(ranked(&res), vec!["addr0000", "addr0001", "pair0000"]);
+    assert_eq!(res.top_holders_share, Decimal::one());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::ExcludeDeadAddress {
+            address: "pair0000".to_string(),
+        },
+    )
+    .unwrap();
+    let res = concentration(&deps);
+    assert_eq!(ranked(&res), vec!["addr0000", "addr0001"]);
+    assert_eq!(res.top_holders_share, Decimal::one());
+
+    // an excluded address is not ranked when its balance changes either
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "pair0000".to_string(),
+            amount: Uint128::new(500),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(ranked(&concentration(&deps)), vec!["addr0000", "addr0001"]);
+}
+
+#[test]
+fn reflection_apr_from_fee_flow() {
+    let mut deps = setup_with(&[("addr0000", 1_000_000), ("addr0001", 1_000_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+
+    // the token is younger than the window, only the hour since its first transfer is covered
+    let mut hour_later = mock_env();
+    hour_later.block.time = hour_later.block.time.plus_seconds(3_600);
+    let res: ReflectionAprResponse = from_json(
+        query(
+            deps.as_ref(),
+            hour_later,
+            QueryMsg::ReflectionApr { window: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.reflected, Uint128::new(5_000));
+    assert_eq!(res.mode, ReflectionMode::Claim);
+    // the 10_000 taxed tokens sit in the treasury
+    assert_eq!(res.eligible_supply, Uint128::new(1_990_000));
+    assert_eq!(res.window, 3_600);
+    assert_eq!(
+        res.apr,
+        Decimal::from_ratio(5_000u128, 1_990_000u128) * Decimal::from_ratio(YEAR, res.window)
+    );
+
+    // flows older than the window are ignored
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(3 * DAY);
+    let res: ReflectionAprResponse = from_json(
+        query(
+            deps.as_ref(),
+            later,
+            QueryMsg::ReflectionApr { window: Some(DAY) },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.reflected, Uint128::zero());
+}
+
+#[test]
+fn reflection_apr_is_zero_in_treasury_mode() {
+    let mut deps = setup(&[("addr0000", 1_000_000), ("addr0001", 1_000_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+
+    // the reflection share went to the treasury, holders earn nothing
+    let res: ReflectionAprResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReflectionApr { window: Some(DAY) },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.mode, ReflectionMode::Treasury);
+    assert_eq!(res.reflected, Uint128::new(5_000));
+    assert_eq!(res.apr, Decimal::zero());
+}
+
+#[test]
+fn relay_requires_trusted_forwarder() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let relay = ExecuteMsg::Relay {
+        sender: "addr0000".to_string(),
+        msg: RelayMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(400),
+        },
+        nonce: 0,
+        chain_id: mock_env().block.chain_id,
+        contract: MOCK_CONTRACT_ADDR.to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("forwarder0000", &[]),
+        relay.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Unauthorized: not a trusted forwarder"
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTrustedForwarder {
+            forwarder: "forwarder0000".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("forwarder0000", &[]),
+        relay,
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "from" && attr.value == "addr0000"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "relayer" && attr.value == "forwarder0000"));
+    let api: ApiVersionResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ApiVersion {}).unwrap()).unwrap();
+    assert_eq!(api.api_version, API_VERSION);
+    assert_eq!(api.contract, "dojoswap:reflection");
+
+    let balance: cw20::BalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Balance {
+                address: "addr0001".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(balance.balance, Uint128::new(400));
+}
+
+#[test]
+fn operator_grants_are_scoped_and_capped() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::GrantOperator {
+            operator: "operator0000".to_string(),
+            action: GrantAction::Transfer,
+            cap: Uint128::new(500),
+            expires: None,
+        },
+    )
+    .unwrap();
+
+    let transfer = |amount: u128| ExecuteMsg::OperatorExecute {
+        owner: "addr0000".to_string(),
+        msg: OperatorMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(amount),
+        },
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator0000", &[]),
+        transfer(300),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator0000", &[]),
+        transfer(300),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Operator grant cap exceeded"
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator0000", &[]),
+        ExecuteMsg::OperatorExecute {
+            owner: "addr0000".to_string(),
+            msg: OperatorMsg::Burn {
+                amount: Uint128::new(1),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Unauthorized: no burn grant for operator"
+    );
+}
+
+fn balance_of(deps: &MockDeps, address: &str) -> Uint128 {
+    let res: cw20::BalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.balance
+}
+
+#[test]
+fn operator_grants_scope_claims_and_stakes() {
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let grant = |deps: &mut MockDeps, action: GrantAction, cap: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::GrantOperator {
+                operator: "operator0000".to_string(),
+                action,
+                cap: Uint128::new(cap),
+                expires: None,
+            },
+        )
+        .unwrap();
+    };
+    let operate = |deps: &mut MockDeps, msg: OperatorMsg| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator0000", &[]),
+            ExecuteMsg::OperatorExecute {
+                owner: "addr0000".to_string(),
+                msg,
+            },
+        )
+    };
+
+    // a claim grant neither moves tokens nor claims more than its cap
+    grant(&mut deps, GrantAction::Claim, 2_000);
+    let err = operate(
+        &mut deps,
+        OperatorMsg::Transfer {
+            recipient: "operator0000".to_string(),
+            amount: Uint128::new(1),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no transfer grant"));
+    let err = operate(&mut deps, OperatorMsg::ClaimReflections {}).unwrap_err();
+    assert!(err.to_string().contains("cap exceeded"));
+    grant(&mut deps, GrantAction::Claim, 3_000);
+    operate(&mut deps, OperatorMsg::ClaimReflections {}).unwrap();
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(502_525));
+
+    // a stake grant only sends to its vault, with the stake message
+    grant(
+        &mut deps,
+        GrantAction::Stake {
+            vault: "vault0000".to_string(),
+        },
+        1_000,
+    );
+    let err = operate(
+        &mut deps,
+        OperatorMsg::Stake {
+            vault: "vault0001".to_string(),
+            amount: Uint128::new(1_000),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no stake grant"));
+    let err = operate(
+        &mut deps,
+        OperatorMsg::Send {
+            contract: "vault0000".to_string(),
+            amount: Uint128::new(1_000),
+            msg: Binary::default(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no send grant"));
+    let res = operate(
+        &mut deps,
+        OperatorMsg::Stake {
+            vault: "vault0000".to_string(),
+            amount: Uint128::new(1_000),
+        },
+    )
+    .unwrap();
+    let send = res
+        .messages
+        .iter()
+        .find_map(|msg| match &msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) if contract_addr == "vault0000" => Some(msg.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let receive: serde_json::Value = from_json(send).unwrap();
+    assert_eq!(receive["receive"]["sender"], "addr0000");
+    let stake = Binary::from_base64(receive["receive"]["msg"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        from_json::<serde_json::Value>(stake).unwrap(),
+        serde_json::json!({ "stake": {} })
+    );
+}
+
+#[test]
+fn drip_releases_taxes_linearly() {
+    let mut deps = setup(&[("addr0000", 1_000_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetReflectionDrip { period: 1_000 },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+
+    let transfer = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::new(100_000),
+        min_receive: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer.clone(),
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(10_000));
+
+    // half of the period later, half of the pending taxes reach the treasury on the first touch
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(500);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(10),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "drip_released" && attr.value == "5000"));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(5_000));
+
+    env.block.time = env.block.time.plus_seconds(2_000);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetReflectionDrip { period: 0 },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(10_001));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::zero());
+}
+
+#[test]
+fn drip_keeps_each_collection_on_its_own_period() {
+    let mut deps = setup(&[("addr0000", 1_000_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetReflectionDrip { period: 1_000 },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    let transfer_at = |deps: &mut MockDeps, seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+        .unwrap()
+    };
+    let drip_at = |deps: &MockDeps, seconds: u64| -> ReflectionDripResponse {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        from_json(query(deps.as_ref(), env, QueryMsg::ReflectionDrip {}).unwrap()).unwrap()
+    };
+
+    transfer_at(&mut deps, 0);
+    transfer_at(&mut deps, 500);
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(5_000));
+    let drip = drip_at(&deps, 500);
+    assert_eq!(drip.pending, Uint128::new(15_000));
+    assert_eq!(drip.end, mock_env().block.time.seconds() + 1_500);
+
+    // the first collection ends on time, the second one is half way through
+    assert_eq!(drip_at(&deps, 1_000).releasable, Uint128::new(10_000));
+    let res = transfer_at(&mut deps, 1_000);
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "drip_released" && attr.value == "10000"));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(15_000));
+
+    transfer_at(&mut deps, 1_500);
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(25_000));
+    assert_eq!(drip_at(&deps, 1_500).pending, Uint128::new(15_000));
+}
+
+#[test]
+fn claim_mode_drips_reflections_to_holders() {
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetReflectionDrip { period: 1_000 },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    let env_at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    };
+    let pending = |deps: &MockDeps, address: &str| -> Uint128 {
+        let res: PendingReflectionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pending
+    };
+    let transfer = |deps: &mut MockDeps, seconds: u64, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            env_at(seconds),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+
+    // a sniper buying right before a large taxed transfer and selling right after
+    transfer(&mut deps, 0, "addr0001", "sniper0000", 100_000);
+    transfer(&mut deps, 0, "addr0000", "addr0001", 100_000);
+    assert_eq!(pending(&deps, "sniper0000"), Uint128::zero());
+    let drip: ReflectionDripResponse =
+        from_json(query(deps.as_ref(), env_at(0), QueryMsg::ReflectionDrip {}).unwrap()).unwrap();
+    assert_eq!(drip.pending, Uint128::new(20_000));
+
+    transfer(&mut deps, 10, "sniper0000", "addr0001", 90_000);
+    let sniped = pending(&deps, "sniper0000");
+    assert!(!sniped.is_zero() && sniped < Uint128::new(20), "{}", sniped);
+
+    // the holders that stay get the rest over the period, the sale of the sniper included
+    transfer(&mut deps, 1_010, "addr0001", "addr0002", 10);
+    let drip: ReflectionDripResponse =
+        from_json(query(deps.as_ref(), env_at(1_010), QueryMsg::ReflectionDrip {}).unwrap())
+            .unwrap();
+    let spread = pending(&deps, "addr0000") + pending(&deps, "addr0001") + sniped;
+    assert_eq!(pending(&deps, "sniper0000"), sniped);
+    // the tax of that last transfer is still dripping
+    assert_eq!(drip.pending, Uint128::new(1));
+    assert!(spread > Uint128::new(14_490) && spread <= Uint128::new(14_500));
+}
+
+#[test]
+fn config_hash_tracks_economic_parameters() {
+    let config_hash = |deps: &MockDeps| -> ConfigHashResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ConfigHash {}).unwrap()).unwrap()
+    };
+    let mut deps = setup(&[("addr0000", 1000)]);
+    let other = setup(&[("addr0001", 5)]);
+
+    let initial = config_hash(&deps);
+    assert_eq!(initial, config_hash(&other));
+    assert_eq!(initial.hash.len(), 64);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let taxed = config_hash(&deps);
+    assert_ne!(taxed.hash, initial.hash);
+    assert_eq!(taxed.params.tax_rate, Decimal::percent(10));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetReflectionDrip { period: DAY },
+    )
+    .unwrap();
+    assert_ne!(config_hash(&deps).hash, taxed.hash);
+}
+
+#[test]
+fn transfer_exact_net_grosses_up_the_tax() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(3),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+
+    for net in [1u128, 97, 100, 333] {
+        let gross = gross_up(&deps.storage, Uint128::new(net)).unwrap();
+        let tax = gross * Decimal::percent(3);
+        assert_eq!(gross - tax, Uint128::new(net));
+        let below = gross - Uint128::one();
+        assert!(below - below * Decimal::percent(3) < Uint128::new(net));
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::TransferExactNet {
+            recipient: "addr0001".to_string(),
+            net_amount: Uint128::new(100),
+        },
+    )
+    .unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "gross_amount"));
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(100));
+    assert_eq!(
+        balance_of(&deps, "addr0000"),
+        Uint128::new(1_000) - gross_up(&deps.storage, Uint128::new(100)).unwrap()
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::TransferExactNet {
+            recipient: "addr0001".to_string(),
+            net_amount: Uint128::new(880),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Insufficient balance"));
+}
+
+#[test]
+fn min_receive_guards_against_tax_changes() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let set_tax = |deps: &mut MockDeps, percent: u64| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetTaxRate {
+                global_rate: Decimal::percent(percent),
+                reflection_rate: Decimal::percent(50),
+                burn_rate: Decimal::zero(),
+                antiwhale_rate: Decimal::one(),
+            },
+        )
+        .unwrap();
+    };
+    let transfer = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::new(100),
+        min_receive: Some(Uint128::new(95)),
+    };
+
+    set_tax(&mut deps, 5);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer.clone(),
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(95));
+
+    // the owner raises the tax before the next transfer is executed
+    set_tax(&mut deps, 6);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("below min_receive"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Send {
+            contract: "contract0000".to_string(),
+            amount: Uint128::new(100),
+            msg: Default::default(),
+            min_receive: Some(Uint128::new(95)),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("below min_receive"));
+}
+
+#[test]
+fn send_and_verify_requires_the_exact_net_amount() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let set_tax = |deps: &mut MockDeps, percent: u64| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetTaxRate {
+                global_rate: Decimal::percent(percent),
+                reflection_rate: Decimal::percent(50),
+                burn_rate: Decimal::zero(),
+                antiwhale_rate: Decimal::one(),
+            },
+        )
+        .unwrap();
+    };
+    let send = ExecuteMsg::SendAndVerify {
+        contract: "contract0000".to_string(),
+        amount: Uint128::new(100),
+        msg: Default::default(),
+        expected_net: Uint128::new(95),
+    };
+
+    set_tax(&mut deps, 5);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        send.clone(),
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "contract0000"), Uint128::new(95));
+
+    // a lower tax than quoted fails as well, the quote is stale either way
+    set_tax(&mut deps, 4);
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), send).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Net amount 96 differs from expected_net 95"));
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(900));
+}
+
+#[test]
+fn tax_changes_are_rate_limited() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    MAX_TAX_RATE
+        .save(deps.as_mut().storage, &Decimal::percent(20))
+        .unwrap();
+    MAX_TAX_STEP
+        .save(deps.as_mut().storage, &Decimal::percent(5))
+        .unwrap();
+    let set_tax = |deps: &mut MockDeps, env: Env, percent: u64| {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetTaxRate {
+                global_rate: Decimal::percent(percent),
+                reflection_rate: Decimal::percent(50),
+                burn_rate: Decimal::zero(),
+                antiwhale_rate: Decimal::one(),
+            },
+        )
+    };
+    let limits = |deps: &MockDeps, env: Env| -> TaxLimitsResponse {
+        from_json(query(deps.as_ref(), env, QueryMsg::TaxLimits {}).unwrap()).unwrap()
+    };
+
+    set_tax(&mut deps, mock_env(), 3).unwrap();
+    // several small moves cannot add up to more than one step within the period
+    let err = set_tax(&mut deps, mock_env(), 6).unwrap_err();
+    assert!(err.to_string().contains("until the step period elapses"));
+    set_tax(&mut deps, mock_env(), 5).unwrap();
+    let res = limits(&deps, mock_env());
+    assert_eq!(res.reference_rate, Decimal::zero());
+    assert_eq!(res.min_allowed, Decimal::zero());
+    assert_eq!(res.max_allowed, Decimal::percent(5));
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    set_tax(&mut deps, env.clone(), 10).unwrap();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    set_tax(&mut deps, env.clone(), 15).unwrap();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    let res = limits(&deps, env.clone());
+    assert_eq!(res.reference_rate, Decimal::percent(15));
+    assert_eq!(res.max_allowed, Decimal::percent(20));
+    let err = set_tax(&mut deps, env.clone(), 21).unwrap_err();
+    assert!(err.to_string().contains("max_tax_rate"));
+
+    // lowering is bounded by the same step
+    let err = set_tax(&mut deps, env.clone(), 9).unwrap_err();
+    assert!(err.to_string().contains("until the step period elapses"));
+    set_tax(&mut deps, env, 10).unwrap();
+}
+
+#[test]
+fn renounced_ownership_locks_economic_parameters() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let renounce = |confirm: &str| ExecuteMsg::RenounceOwnership {
+        confirm: confirm.to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        renounce(MOCK_CONTRACT_ADDR),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        renounce("yes"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("confirm must be the address"));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        renounce(MOCK_CONTRACT_ADDR),
+    )
+    .unwrap();
+    let ownership: OwnershipResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+    assert_eq!(
+        ownership,
+        OwnershipResponse {
+            admin: None,
+            immutable: true,
+        }
+    );
+    let config: ConfigHashResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ConfigHash {}).unwrap()).unwrap();
+    assert!(config.params.immutable);
+
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetWhitelist {
+            user: "addr0000".to_string(),
+            enable: true,
+        },
+        ExecuteMsg::SetReflectionDrip { period: DAY },
+        ExecuteMsg::MigrateTreasury { code_id: 2 },
+        renounce(MOCK_CONTRACT_ADDR),
+    ] {
+        assert!(execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).is_err());
+    }
+}
+
+#[test]
+fn claim_mode_accrues_reflections_to_holders() {
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let pending = |deps: &MockDeps, address: &str| -> Uint128 {
+        let res: PendingReflectionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pending
+    };
+    let transfer = |deps: &mut MockDeps, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+
+    // half of the 10_000 tax goes to the treasury, the other half to the 990_000 eligible supply
+    transfer(&mut deps, "addr0000", "addr0001", 100_000);
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(5_000));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(5_000));
+    assert_eq!(pending(&deps, "addr0000"), Uint128::new(2_525));
+    assert_eq!(pending(&deps, "addr0001"), Uint128::new(2_474));
+    assert_eq!(pending(&deps, TREASURY), Uint128::zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "amount" && attr.value == "2525"));
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(502_525));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(2_475));
+    assert_eq!(pending(&deps, "addr0000"), Uint128::zero());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("No reflections to claim"));
+
+    // a new holder only accrues what is collected after it received tokens. The first credit is
+    // set aside rounded up, so nothing is carried into the second one
+    transfer(&mut deps, "addr0001", "addr0002", 100_000);
+    let per_token = Decimal::from_ratio(5_000u128, 982_525u128);
+    assert_eq!(
+        pending(&deps, "addr0002"),
+        Uint128::new(90_000).mul_floor(per_token)
+    );
+    assert_eq!(
+        pending(&deps, "addr0001"),
+        Uint128::new(2_474) + Uint128::new(390_000).mul_floor(per_token)
+    );
+}
+
+#[test]
+fn reflection_index_reports_what_holders_earned() {
+    let mut deps = setup_with(&[("addr0000", 500_000), ("addr0001", 500_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let index = |deps: &MockDeps| -> ReflectionIndexResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ReflectionIndex {}).unwrap()).unwrap()
+    };
+    let reflections = |deps: &MockDeps, address: &str| -> PendingReflectionsResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(index(&deps).per_token, Decimal::zero());
+    assert_eq!(index(&deps).eligible_supply, Uint128::new(1_000_000));
+
+    // 5_000 reflected over the 990_000 tokens left outside the treasury and the token
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let res = index(&deps);
+    assert_eq!(res.reflection_mode, ReflectionMode::Claim);
+    assert_eq!(res.eligible_supply, Uint128::new(990_000));
+    assert_eq!(res.per_token, Decimal::from_ratio(5_000u128, 990_000u128));
+    let earned = Uint128::new(500_000).mul_floor(res.per_token);
+    assert_eq!(reflections(&deps, "addr0000").pending, earned);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap();
+    let res = reflections(&deps, "addr0000");
+    assert_eq!(res.pending, Uint128::zero());
+    assert_eq!(res.claimed, earned);
+}
+
+#[test]
+fn treasury_mode_has_nothing_to_claim() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("credited to the treasury"));
+}
+
+#[test]
+fn claims_are_reported_for_accounting() {
+    let mut deps = setup_with(&[("addr0000", 500_000), ("addr0001", 500_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let start = mock_env().block.time.seconds();
+    let at = |offset: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(offset);
+        env
+    };
+
+    for round in 1..=CLAIM_HISTORY_SIZE as u64 + 1 {
+        execute(
+            deps.as_mut(),
+            at(round * DAY - 1),
+            mock_info("addr0001", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::new(1_000),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            at(round * DAY),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::ClaimReflections {},
+        )
+        .unwrap();
+        if round == 1 {
+            let event = &res.events[0];
+            assert_eq!(event.ty, "reflection_credited");
+            let value = |key: &str| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .unwrap()
+                    .value
+                    .clone()
+            };
+            // 50 reflected over the 999_900 tokens held outside the treasury and the token
+            let rate = Decimal::from_ratio(50u128, 999_900u128);
+            assert_eq!(value("rate"), rate.to_string());
+            assert_eq!(
+                value("amount"),
+                Uint128::new(500_000).mul_floor(rate).to_string()
+            );
+            assert_eq!(value("from"), (start + DAY - 1).to_string());
+            assert_eq!(value("to"), (start + DAY).to_string());
+        }
+    }
+
+    let res: ClaimHistoryResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ClaimHistory {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.claims.len(), CLAIM_HISTORY_SIZE);
+    // the first claim was dropped, every other one covers the day since the previous claim
+    assert_eq!(res.claims[0].from, start + DAY);
+    assert_eq!(res.claims[0].to, start + 2 * DAY);
+    let last = res.claims.last().unwrap();
+    assert_eq!(last.to, start + (CLAIM_HISTORY_SIZE as u64 + 1) * DAY);
+    assert!(!last.amount.is_zero());
+}
+
+#[test]
+fn insurance_fund_is_withdrawn_through_governance_only() {
+    let mut deps = setup(&[("addr0000", 100_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::percent(20),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(10_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(800));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(200));
+
+    let queue = |amount: u128| SudoMsg::QueueInsuranceWithdrawal {
+        recipient: "addr0002".to_string(),
+        amount: Uint128::new(amount),
+        reason: "compensation for the pair exploit".to_string(),
+    };
+    sudo(deps.as_mut(), mock_env(), queue(150)).unwrap();
+    let err = sudo(deps.as_mut(), mock_env(), queue(100)).unwrap_err();
+    assert!(err.to_string().contains("cannot cover the withdrawal"));
+    let fund: InsuranceFundResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::InsuranceFund {}).unwrap()).unwrap();
+    assert_eq!(fund.balance, Uint128::new(200));
+    assert_eq!(fund.queued, Uint128::new(150));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone0000", &[]),
+        ExecuteMsg::ExecuteInsuranceWithdrawal { id: 1 },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("executable at"));
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(INSURANCE_WITHDRAWAL_DELAY);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone0000", &[]),
+        ExecuteMsg::ExecuteInsuranceWithdrawal { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0002"), Uint128::new(150));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(50));
+    assert!(execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone0000", &[]),
+        ExecuteMsg::ExecuteInsuranceWithdrawal { id: 1 },
+    )
+    .is_err());
+
+    let history: InsuranceWithdrawalsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::InsuranceWithdrawals {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history.withdrawals.len(), 1);
+    assert_eq!(
+        history.withdrawals[0].reason,
+        "compensation for the pair exploit"
+    );
+    assert_eq!(
+        history.withdrawals[0].executed_at,
+        Some(env.block.time.seconds())
+    );
+}
+
+#[test]
+fn signed_relays_are_bound_to_the_domain() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTrustedForwarder {
+            forwarder: "forwarder0000".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap();
+    let domain = |deps: &MockDeps, env: Env| -> SignatureDomainResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::SignatureDomain {
+                    address: Some("addr0000".to_string()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let res = domain(&deps, mock_env());
+    assert_eq!(
+        res.separator,
+        hex::encode(domain_separator("cosmos-testnet-14002", MOCK_CONTRACT_ADDR))
+    );
+    assert_eq!(res.next_relay_nonce, 0);
+    let mut fork = mock_env();
+    fork.block.chain_id = "cosmos-testnet-14003".to_string();
+    assert_ne!(domain(&deps, fork).separator, res.separator);
+
+    let relay_to = |nonce: u64, chain_id: &str, contract: &str| ExecuteMsg::Relay {
+        sender: "addr0000".to_string(),
+        msg: RelayMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100),
+        },
+        nonce,
+        chain_id: chain_id.to_string(),
+        contract: contract.to_string(),
+    };
+    let relay = |nonce: u64| relay_to(nonce, "cosmos-testnet-14002", MOCK_CONTRACT_ADDR);
+    // a relay signed for another chain or deployment
+    for (chain_id, contract) in [
+        ("cosmos-testnet-14003", MOCK_CONTRACT_ADDR),
+        ("cosmos-testnet-14002", "token0001"),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("forwarder0000", &[]),
+            relay_to(0, chain_id, contract),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not this token"));
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("forwarder0000", &[]),
+        relay(0),
+    )
+    .unwrap();
+    assert!(!res.attributes.iter().any(|attr| attr.key == "deprecated"));
+    // the same signed relay cannot be submitted twice
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("forwarder0000", &[]),
+        relay(0),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Invalid relay nonce 0, expected 1"));
+    assert_eq!(domain(&deps, mock_env()).next_relay_nonce, 1);
+}
+
+#[test]
+fn scheduled_actions_run_once_due() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let now = mock_env().block.time.seconds();
+    let schedule = |deps: &mut MockDeps, sender: &str, action, execute_at, interval| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ScheduleAction {
+                action,
+                execute_at,
+                interval,
+            },
+        )
+    };
+    let tax = |global: u64, burn: u64| ScheduledAction::SetTaxRate {
+        global_rate: Decimal::percent(global),
+        reflection_rate: Decimal::percent(50),
+        burn_rate: Decimal::percent(burn),
+        antiwhale_rate: Decimal::one(),
+    };
+    let execute_due = |deps: &mut MockDeps, time: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(time - now);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("keeper0000", &[]),
+            ExecuteMsg::ExecuteDue {},
+        )
+        .unwrap()
+    };
+
+    let err = schedule(&mut deps, "addr0000", tax(10, 0), now, None).unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    schedule(&mut deps, ADMIN, tax(10, 0), now + DAY, None).unwrap();
+    // fails when it runs, the other actions still run
+    schedule(&mut deps, ADMIN, tax(10, 60), now + DAY, None).unwrap();
+    schedule(
+        &mut deps,
+        ADMIN,
+        ScheduledAction::LiquifyTreasury {},
+        now,
+        Some(7 * DAY),
+    )
+    .unwrap();
+
+    let res = execute_due(&mut deps, now);
+    assert_eq!(res.messages.len(), 1);
+    let res = execute_due(&mut deps, now + DAY);
+    assert!(res.messages.is_empty());
+    let (global_rate, _, _, _): (Decimal, Decimal, Decimal, Decimal) =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::QueryRates {}).unwrap()).unwrap();
+    assert_eq!(global_rate, Decimal::percent(10));
+
+    let receipts: ScheduleReceiptsResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ScheduleReceipts {}).unwrap())
+            .unwrap();
+    let results: Vec<(u64, u64, bool)> = receipts
+        .receipts
+        .iter()
+        .map(|receipt| (receipt.id, receipt.executed_at, receipt.error.is_none()))
+        .collect();
+    assert_eq!(
+        results,
+        vec![(3, now, true), (1, now + DAY, true), (2, now + DAY, false)]
+    );
+
+    // only the recurring action is left, moved to its next run
+    let scheduled: ScheduledActionsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ScheduledActions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(scheduled.actions.len(), 1);
+    assert_eq!(scheduled.actions[0].execute_at, now + 7 * DAY);
+    assert!(execute_due(&mut deps, now + 6 * DAY).messages.is_empty());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::CancelScheduledAction { id: 3 },
+    )
+    .unwrap();
+    assert!(execute_due(&mut deps, now + 7 * DAY).messages.is_empty());
+}
+
+#[test]
+fn guardian_vetoes_scheduled_actions() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let now = mock_env().block.time.seconds();
+    let guardian = mock_info("guardian0000", &[]);
+    let schedule = ExecuteMsg::ScheduleAction {
+        action: ScheduledAction::LiquifyTreasury {},
+        execute_at: now + DAY,
+        interval: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        schedule.clone(),
+    )
+    .unwrap();
+
+    // the guardian removes queued actions but cannot queue any
+    let err = execute(deps.as_mut(), mock_env(), guardian.clone(), schedule).unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Veto { id: 1 },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not guardian"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Veto { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0].value, "veto");
+    let scheduled: ScheduledActionsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ScheduledActions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(scheduled.actions.is_empty());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Veto { id: 1 },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    // renouncing leaves the token without guardian
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::RenounceGuardian {},
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), mock_env(), guardian, ExecuteMsg::Pause {}).unwrap_err();
+    assert!(err.to_string().contains("not guardian"));
+}
+
+#[test]
+fn emergency_drain_sweeps_fee_buckets_only() {
+    let mut deps = setup(&[("addr0000", 100_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::percent(20),
+        },
+        ExecuteMsg::SetReflectionDrip { period: 1_000 },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(10_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::QueueInsuranceWithdrawal {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(50),
+            reason: "compensation for the pair exploit".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(1_000));
+
+    let drain = || SudoMsg::EmergencyDrain {
+        reason: "distribution bug".to_string(),
+    };
+    let err = sudo(deps.as_mut(), mock_env(), drain()).unwrap_err();
+    assert!(err.to_string().contains("no rescue address set"));
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::SetRescueAddress {
+            address: "rescue0000".to_string(),
+        },
+    )
+    .unwrap();
+
+    let res = sudo(deps.as_mut(), mock_env(), drain()).unwrap();
+    let buckets: Vec<(String, String)> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "emergency_drain_bucket")
+        .map(|event| {
+            (
+                event.attributes[0].value.clone(),
+                event.attributes[1].value.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        buckets,
+        vec![
+            ("drip".to_string(), "800".to_string()),
+            ("insurance".to_string(), "150".to_string()),
+        ]
+    );
+    assert_eq!(balance_of(&deps, "rescue0000"), Uint128::new(950));
+    // the queued withdrawal and holder balances are left alone
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(50));
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(90_000));
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(9_000));
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(INSURANCE_WITHDRAWAL_DELAY);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ExecuteInsuranceWithdrawal { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0002"), Uint128::new(50));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+}
+
+/// Drives random transfers, burns and claims and checks after every step that the eligible supply
+/// is exactly what the holders own, and that holders are never owed more reflections than the
+/// token contract set aside for them
+fn check_eligible_supply_invariants(reflection_mode: ReflectionMode, seed: u64) {
+    const HOLDERS: [&str; 4] = ["addr0000", "addr0001", "addr0002", "addr0003"];
+    let mut deps = setup_with(&[("addr0000", 1_000_000), ("addr0001", 10_000)], |msg| {
+        msg.reflection_mode = Some(reflection_mode)
+    });
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::percent(10),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::percent(10),
+        },
+        ExecuteMsg::SetReflectionDrip { period: 100 },
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+        ExecuteMsg::ExcludeDeadAddress {
+            address: "dead0000".to_string(),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    let mut frozen = [false; 4];
+
+    // xorshift, good enough to shuffle the operations
+    let mut state = seed;
+    let mut next = |bound: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % bound
+    };
+    let mut env = mock_env();
+    for _ in 0..200 {
+        env.block.time = env.block.time.plus_seconds(next(50));
+        let holder = next(4) as usize;
+        let mut sender = HOLDERS[holder];
+        let balance = balance_of(&deps, sender).u128() as u64;
+        let amount = Uint128::from(next(balance.max(1)) + 1);
+        let msg = match next(6) {
+            0 => ExecuteMsg::Burn { amount },
+            1 => ExecuteMsg::ClaimReflections {},
+            2 => ExecuteMsg::Transfer {
+                recipient: TREASURY.to_string(),
+                amount,
+                min_receive: None,
+            },
+            3 => ExecuteMsg::Transfer {
+                recipient: "dead0000".to_string(),
+                amount,
+                min_receive: None,
+            },
+            4 => {
+                sender = "guardian0000";
+                frozen[holder] = !frozen[holder];
+                let address = HOLDERS[holder].to_string();
+                if frozen[holder] {
+                    ExecuteMsg::Freeze { address }
+                } else {
+                    ExecuteMsg::Unfreeze { address }
+                }
+            }
+            _ => ExecuteMsg::Transfer {
+                recipient: HOLDERS[next(4) as usize].to_string(),
+                amount,
+                min_receive: None,
+            },
+        };
+        // overdrawn balances, frozen senders and empty claims are expected to fail
+        let _ = execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), msg);
+
+        // frozen and dead balances receive nothing
+        let held: Uint128 = HOLDERS
+            .iter()
+            .zip(frozen)
+            .filter(|(_, frozen)| !frozen)
+            .map(|(holder, _)| balance_of(&deps, holder))
+            .sum();
+        let eligible_supply = eligible_supply(deps.as_ref().storage).unwrap();
+        assert_eq!(eligible_supply, held);
+        let res: ReflectionAprResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ReflectionApr { window: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.eligible_supply, eligible_supply);
+
+        let drip: ReflectionDripResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::ReflectionDrip {}).unwrap())
+                .unwrap();
+        let insurance: InsuranceFundResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::InsuranceFund {}).unwrap())
+                .unwrap();
+        let set_aside = balance_of(&deps, MOCK_CONTRACT_ADDR) - drip.pending - insurance.balance;
+        let owed: Uint128 = HOLDERS
+            .iter()
+            .map(|holder| {
+                let res: PendingReflectionsResponse = from_json(
+                    query(
+                        deps.as_ref(),
+                        env.clone(),
+                        QueryMsg::PendingReflections {
+                            address: holder.to_string(),
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+                res.pending
+            })
+            .sum();
+        assert!(
+            owed <= set_aside,
+            "{} owed for {} set aside",
+            owed,
+            set_aside
+        );
+        if reflection_mode == ReflectionMode::Treasury {
+            assert!(set_aside.is_zero());
+        }
+    }
+}
+
+#[test]
+fn eligible_supply_excludes_fee_holders() {
+    for seed in [1, 7, 42, 1_234_567] {
+        check_eligible_supply_invariants(ReflectionMode::Treasury, seed);
+        check_eligible_supply_invariants(ReflectionMode::Claim, seed);
+    }
+}
+
+#[test]
+fn large_burns_mint_a_trophy() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    let burn = |deps: &mut MockDeps, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Burn {
+                amount: Uint128::new(amount),
+            },
+        )
+        .unwrap()
+    };
+
+    assert!(burn(&mut deps, 2_000).messages.is_empty());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetBurnTrophy {
+            collection: Some("trophies0000".to_string()),
+            threshold: Uint128::new(1_000),
+        },
+    )
+    .unwrap();
+    assert!(burn(&mut deps, 999).messages.is_empty());
+
+    let res = burn(&mut deps, 1_000);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, BURN_TROPHY_REPLY_ID);
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            assert_eq!(contract_addr, "trophies0000");
+            assert_eq!(
+                from_json::<Cw721ExecuteMsg>(msg).unwrap(),
+                Cw721ExecuteMsg::Mint {
+                    token_id: "burn-1".to_string(),
+                    owner: "addr0000".to_string(),
+                    token_uri: None,
+                    extension: Empty {},
+                }
+            );
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+
+    // a collection rejecting the mint does not revert the burn
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: BURN_TROPHY_REPLY_ID,
+            result: SubMsgResult::Err("Unauthorized".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0].value, "burn_trophy_failed");
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(6_001));
+
+    let trophy: BurnTrophyResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::BurnTrophy {}).unwrap()).unwrap();
+    assert_eq!(trophy.collection, Some("trophies0000".to_string()));
+    assert_eq!(trophy.minted, 1);
+
+    // burns by an operator earn the trophy for the owner too
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::GrantOperator {
+            operator: "operator0000".to_string(),
+            action: GrantAction::Burn,
+            cap: Uint128::new(1_000),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator0000", &[]),
+        ExecuteMsg::OperatorExecute {
+            owner: "addr0000".to_string(),
+            msg: OperatorMsg::Burn {
+                amount: Uint128::new(1_000),
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => assert_eq!(
+            from_json::<Cw721ExecuteMsg>(msg).unwrap(),
+            Cw721ExecuteMsg::Mint {
+                token_id: "burn-2".to_string(),
+                owner: "addr0000".to_string(),
+                token_uri: None,
+                extension: Empty {},
+            }
+        ),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(5_001));
+}
+
+fn mock_pool(deps: &mut MockDeps, token_reserve: u128, quote_reserve: u128) {
+    deps.querier.update_wasm(move |_| {
+        let pool = PoolResponse {
+            assets: [
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "inj".to_string(),
+                    },
+                    amount: Uint128::new(quote_reserve),
+                },
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                    },
+                    amount: Uint128::new(token_reserve),
+                },
+            ],
+            total_share: Uint128::new(1_000),
+        };
+        SystemResult::Ok(ContractResult::Ok(to_json_binary(&pool).unwrap()))
+    });
+}
+
+#[test]
+fn trading_enables_once_the_pair_is_seeded() {
+    let mut deps = setup(&[(ADMIN, 10_000), ("addr0000", 10_000)]);
+    let transfer = |deps: &mut MockDeps, sender: &str, recipient: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: Uint128::new(100),
+                min_receive: None,
+            },
+        )
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTradingGate {
+            pair: Some("pair0000".to_string()),
+            min_token_reserve: Uint128::new(1_000),
+            min_quote_reserve: Uint128::new(500),
+        },
+    )
+    .unwrap();
+
+    mock_pool(&mut deps, 1_000, 499);
+    transfer(&mut deps, "addr0000", "pair0000").unwrap_err();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::EnableTrading {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Trading not enabled"));
+    // transfers away from the pair are held as well, the whitelisted admin seeds the liquidity
+    // and other transfers are not affected
+    transfer(&mut deps, "pair0000", "addr0000").unwrap_err();
+    transfer(&mut deps, ADMIN, "pair0000").unwrap();
+    transfer(&mut deps, "addr0000", "addr0001").unwrap();
+
+    mock_pool(&mut deps, 1_000, 500);
+    transfer(&mut deps, "addr0000", "pair0000").unwrap();
+    let gate: TradingGateResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TradingGate {}).unwrap()).unwrap();
+    assert!(gate.enabled);
+    assert_eq!(gate.enabled_at, Some(mock_env().block.time.seconds()));
+
+    // the pair draining afterwards does not hold trading again, nor can the gate be changed
+    mock_pool(&mut deps, 0, 0);
+    transfer(&mut deps, "addr0000", "pair0000").unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTradingGate {
+            pair: None,
+            min_token_reserve: Uint128::zero(),
+            min_quote_reserve: Uint128::zero(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("already enabled"));
+}
+
+#[test]
+fn custodial_balances_do_not_drift() {
+    let mut deps = setup_with(&[("addr0000", 600_000), ("exchange0000", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetCustodial {
+            address: "exchange0000".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap();
+    let transfer = |deps: &mut MockDeps, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap()
+    };
+    let custodial = |deps: &MockDeps| -> CustodialResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Custodial {
+                    address: "exchange0000".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    transfer(&mut deps, "addr0000", "addr0001", 100_000);
+    let pending: PendingReflectionsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PendingReflections {
+                address: "exchange0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(pending.pending, Uint128::zero());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("exchange0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("custodial"));
+
+    // the hot wallet balance moves by exactly what it sends, its accrual is set aside
+    transfer(&mut deps, "exchange0000", "addr0002", 10_000);
+    assert_eq!(balance_of(&deps, "exchange0000"), Uint128::new(390_000));
+    let routed = custodial(&deps).routed;
+    assert_eq!(
+        routed,
+        Uint128::new(400_000).mul_floor(Decimal::from_ratio(5_000u128, 990_000u128))
+    );
+
+    // and sent to the treasury on the next execution
+    let treasury = balance_of(&deps, TREASURY);
+    let res = transfer(&mut deps, "addr0001", "addr0000", 1);
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "custodial_routed" && attr.value == routed.to_string()));
+    assert_eq!(balance_of(&deps, TREASURY), treasury + routed);
+    assert_eq!(balance_of(&deps, "exchange0000"), Uint128::new(390_000));
+    assert!(custodial(&deps).custodial);
+}
+
+#[test]
+fn labels_are_reported_in_transfer_events() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    let label = |deps: &mut MockDeps, address: &str, label: Option<&str>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetAddressLabel {
+                address: address.to_string(),
+                label: label.map(str::to_string),
+            },
+        )
+    };
+    label(&mut deps, "pair0000", Some("pair:INJ")).unwrap();
+    label(&mut deps, TREASURY, Some("treasury")).unwrap();
+    label(&mut deps, "team0000", Some("team")).unwrap();
+    label(&mut deps, "team0000", None).unwrap();
+    label(&mut deps, "addr0000", Some("")).unwrap_err();
+    label(&mut deps, "addr0000", Some(&"x".repeat(33))).unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "pair0000".to_string(),
+            amount: Uint128::new(100),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let labels: Vec<(&str, &str)> = res
+        .attributes
+        .iter()
+        .filter(|attr| attr.key.ends_with("_label"))
+        .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+        .collect();
+    assert_eq!(labels, vec![("to_label", "pair:INJ")]);
+
+    let res: AddressLabelsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AddressLabels {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.labels,
+        vec![
+            AddressLabel {
+                address: "pair0000".to_string(),
+                label: "pair:INJ".to_string(),
+            },
+            AddressLabel {
+                address: TREASURY.to_string(),
+                label: "treasury".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn failed_receive_hooks_are_quarantined() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    let send = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Send {
+                contract: "receiver0000".to_string(),
+                amount: Uint128::new(1_000),
+                msg: Binary::default(),
+                min_receive: None,
+            },
+        )
+        .unwrap()
+    };
+    let hook_reply = |deps: &mut MockDeps, result: SubMsgResult| {
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: HOOK_QUARANTINE_REPLY_ID,
+                result,
+            },
+        )
+        .unwrap()
+    };
+    let quarantined = |deps: &MockDeps| -> QuarantinedSendsResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::QuarantinedSends {
+                    sender: "addr0000".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // disabled by default, a failing hook reverts the send
+    assert_eq!(
+        send(&mut deps).messages.last().unwrap().reply_on,
+        ReplyOn::Never
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetHookQuarantine { enable: true },
+    )
+    .unwrap();
+
+    let res = send(&mut deps);
+    let hook = res.messages.last().unwrap();
+    assert_eq!(hook.id, HOOK_QUARANTINE_REPLY_ID);
+    assert_eq!(hook.reply_on, ReplyOn::Always);
+    hook_reply(
+        &mut deps,
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    );
+    assert_eq!(balance_of(&deps, "receiver0000"), Uint128::new(2_000));
+    assert!(quarantined(&deps).sends.is_empty());
+
+    send(&mut deps);
+    let res = hook_reply(&mut deps, SubMsgResult::Err("unknown variant".to_string()));
+    assert_eq!(res.attributes[0].value, "quarantine_send");
+    assert_eq!(balance_of(&deps, "receiver0000"), Uint128::new(2_000));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(1_000));
+    let res = quarantined(&deps);
+    assert!(res.enabled);
+    assert_eq!(res.sends.len(), 1);
+    assert_eq!(res.sends[0].amount, Uint128::new(1_000));
+    assert_eq!(res.sends[0].reason, "unknown variant");
+
+    let claim = |deps: &mut MockDeps, sender: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ClaimQuarantined {
+                id: res.sends[0].id,
+            },
+        )
+    };
+    claim(&mut deps, "addr0001").unwrap_err();
+    claim(&mut deps, "addr0000").unwrap();
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(8_000));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::zero());
+    assert!(quarantined(&deps).sends.is_empty());
+}
+
+#[test]
+fn swap_round_trip_combines_taxes_and_pair_fees() {
+    let mut deps = setup(&[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let commission = Decimal::permille(3);
+    let (quote_pool, token_pool) = (Uint128::new(1_000_000), Uint128::new(2_000_000));
+    deps.querier.update_wasm(move |query| {
+        let msg = match query {
+            WasmQuery::Smart { msg, .. } => from_json(msg).unwrap(),
+            query => panic!("unexpected query {:?}", query),
+        };
+        let res = match msg {
+            PairQueryMsg::Pool {} => to_json_binary(&PoolResponse {
+                assets: [
+                    Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                        },
+                        amount: token_pool,
+                    },
+                    Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "inj".to_string(),
+                        },
+                        amount: quote_pool,
+                    },
+                ],
+                total_share: Uint128::new(1_000),
+            }),
+            PairQueryMsg::Simulation { offer_asset } => {
+                let (offer_pool, ask_pool) = match offer_asset.info {
+                    AssetInfo::NativeToken { .. } => (quote_pool, token_pool),
+                    AssetInfo::Token { .. } => (token_pool, quote_pool),
+                };
+                let (return_amount, spread_amount, commission_amount) =
+                    compute_swap(offer_pool, ask_pool, offer_asset.amount, commission).unwrap();
+                to_json_binary(&SimulationResponse {
+                    return_amount,
+                    spread_amount,
+                    commission_amount,
+                })
+            }
+            msg => panic!("unexpected query {:?}", msg),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    });
+
+    let simulate = |deps: &MockDeps| -> SimulateSwapRoundTripResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateSwapRoundTrip {
+                    pair: "pair0000".to_string(),
+                    amount: Uint128::new(1_000),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let (bought, _, buy_commission) =
+        compute_swap(quote_pool, token_pool, Uint128::new(1_000), commission).unwrap();
+    let res = simulate(&deps);
+    assert_eq!(res.buy_commission, buy_commission);
+    assert_eq!(res.buy_tax, bought * Decimal::percent(10));
+    assert_eq!(res.tokens_received, bought - res.buy_tax);
+    assert_eq!(res.sell_tax, res.tokens_received * Decimal::percent(10));
+    let (returned, _, _) = compute_swap(
+        token_pool,
+        quote_pool,
+        res.tokens_received - res.sell_tax,
+        commission,
+    )
+    .unwrap();
+    assert_eq!(res.return_amount, returned);
+    assert_eq!(res.cost, Uint128::new(1_000) - returned);
+    // two 10% taxes and two 0.3% commissions, the spread of such a small trade is negligible
+    assert!(res.cost_rate > Decimal::percent(19) && res.cost_rate < Decimal::percent(20));
+
+    // a whitelisted pair is not taxed, only its commissions remain
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetWhitelist {
+            user: "pair0000".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap();
+    let res = simulate(&deps);
+    assert_eq!(res.buy_tax + res.sell_tax, Uint128::zero());
+    assert!(res.cost_rate < Decimal::percent(1));
+}
+
+#[test]
+fn linked_addresses_skip_antiwhale_but_pay_tax() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::percent(2),
+        },
+    )
+    .unwrap();
+    let transfer = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(1_000),
+                min_receive: None,
+            },
+        )
+    };
+    // the same move taken from an allowance of the linked address
+    let transfer_from = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            ExecuteMsg::TransferFrom {
+                owner: "addr0000".to_string(),
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(1_000),
+                min_receive: None,
+            },
+        )
+    };
+    let link = |deps: &mut MockDeps, sender: &str, msg: ExecuteMsg| {
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+    };
+    let linked = |deps: &MockDeps, address: &str| -> LinkedAddressesResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::LinkedAddresses {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let err = transfer(&mut deps).unwrap_err();
+    assert!(err.to_string().contains("anti-whale"));
+
+    // a link needs both addresses, accepting an offer nobody made fails
+    let err = link(
+        &mut deps,
+        "addr0001",
+        ExecuteMsg::AcceptLink {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("No link proposed"));
+    link(
+        &mut deps,
+        "addr0000",
+        ExecuteMsg::ProposeLink {
+            address: "addr0001".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(linked(&deps, "addr0000").proposed, vec!["addr0001"]);
+    transfer(&mut deps).unwrap_err();
+    link(
+        &mut deps,
+        "addr0001",
+        ExecuteMsg::AcceptLink {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        linked(&deps, "addr0001"),
+        LinkedAddressesResponse {
+            linked: vec!["addr0000".to_string()],
+            proposed: vec![],
+        }
+    );
+
+    transfer(&mut deps).unwrap();
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(9_000));
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(900));
+    link(
+        &mut deps,
+        "addr0000",
+        ExecuteMsg::IncreaseAllowance {
+            spender: "addr0001".to_string(),
+            amount: Uint128::new(2_000),
+            expires: None,
+        },
+    )
+    .unwrap();
+    transfer_from(&mut deps).unwrap();
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(8_000));
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(1_800));
+
+    link(
+        &mut deps,
+        "addr0001",
+        ExecuteMsg::Unlink {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        linked(&deps, "addr0000"),
+        LinkedAddressesResponse::default()
+    );
+    transfer(&mut deps).unwrap_err();
+    let err = transfer_from(&mut deps).unwrap_err();
+    assert!(err.to_string().contains("anti-whale"));
+}
+
+#[test]
+fn snapshots_are_taken_lazily_on_schedule() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetSnapshotSchedule {
+            schedule: Some(SnapshotSchedule {
+                interval: 100,
+                retain: 2,
+            }),
+        },
+    )
+    .unwrap();
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let transfer = |deps: &mut MockDeps, env: Env| {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(1_000),
+                min_receive: None,
+            },
+        )
+        .unwrap()
+    };
+    let snapshot_balance = |deps: &MockDeps, address: &str, id: u64| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SnapshotBalance {
+                address: address.to_string(),
+                id,
+            },
+        )
+        .map(|res| from_json::<SnapshotBalanceResponse>(&res).unwrap().balance)
+    };
+    let snapshots = |deps: &MockDeps| -> Vec<(u64, u64)> {
+        from_json::<SnapshotsResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Snapshots {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .snapshots
+        .iter()
+        .map(|snapshot| (snapshot.id, snapshot.height))
+        .collect()
+    };
+
+    // the schedule starts at the next boundary, 12_400
+    let res = transfer(&mut deps, at(mock_env().block.height + 5));
+    assert!(!res.attributes.iter().any(|a| a.key == "snapshot"));
+    assert!(snapshots(&deps).is_empty());
+
+    // nobody touched the token at 12_400, the first transfer after it takes the snapshot first
+    let before = balance_of(&deps, "addr0000");
+    let res = transfer(&mut deps, at(12_431));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "snapshot" && a.value == "1"));
+    transfer(&mut deps, at(12_432));
+    assert_eq!(snapshots(&deps), vec![(1, 12_400)]);
+    assert_eq!(snapshot_balance(&deps, "addr0000", 1).unwrap(), before);
+
+    // a few boundaries without activity make a single snapshot, at the last one
+    let at_second = balance_of(&deps, "addr0001");
+    transfer(&mut deps, at(12_750));
+    assert_eq!(snapshots(&deps), vec![(1, 12_400), (2, 12_700)]);
+    let at_third = balance_of(&deps, "addr0001");
+    transfer(&mut deps, at(12_800));
+    assert_eq!(snapshots(&deps), vec![(2, 12_700), (3, 12_800)]);
+
+    // the oldest snapshot is pruned once more than `retain` are taken
+    let err = snapshot_balance(&deps, "addr0000", 1).unwrap_err();
+    assert!(err.to_string().contains("pruned"));
+    assert_eq!(snapshot_balance(&deps, "addr0001", 2).unwrap(), at_second);
+    assert_eq!(snapshot_balance(&deps, "addr0001", 3).unwrap(), at_third);
+    assert!(at_second < at_third);
+    // the treasury kept collecting taxes after the snapshot
+    assert_eq!(
+        snapshot_balance(&deps, TREASURY, 3).unwrap() + Uint128::new(100),
+        balance_of(&deps, TREASURY)
+    );
+    // addresses which did not move since a snapshot report their current balance
+    assert_eq!(
+        snapshot_balance(&deps, "addr0002", 3).unwrap(),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn epoch_rewards_are_shared_by_snapshot_balance() {
+    let mut deps = setup(&[("addr0000", 6_000), ("addr0001", 2_000), (ADMIN, 2_000)]);
+    let run = |deps: &mut MockDeps, sender: &str, seconds: u64, msg: ExecuteMsg| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        execute(deps.as_mut(), env, mock_info(sender, &[]), msg)
+    };
+    let claim = |epoch: u64| ExecuteMsg::ClaimEpochReward { epoch };
+    run(&mut deps, ADMIN, 0, ExecuteMsg::TakeSnapshot {}).unwrap();
+    // what moves after the snapshot does not change the shares
+    run(
+        &mut deps,
+        "addr0001",
+        0,
+        ExecuteMsg::Burn {
+            amount: Uint128::new(500),
+        },
+    )
+    .unwrap();
+
+    let err = run(&mut deps, "addr0000", 0, claim(1)).unwrap_err();
+    assert!(err.to_string().contains("No reward"));
+    run(
+        &mut deps,
+        ADMIN,
+        0,
+        ExecuteMsg::FundEpochReward {
+            epoch: 1,
+            amount: Uint128::new(1_000),
+            claim_period: 100,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, ADMIN), Uint128::new(1_000));
+
+    run(&mut deps, "addr0000", 10, claim(1)).unwrap();
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(6_600));
+    let err = run(&mut deps, "addr0000", 10, claim(1)).unwrap_err();
+    assert!(err.to_string().contains("Nothing to claim"));
+    let res: EpochRewardClaimResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EpochRewardClaim {
+                epoch: 1,
+                address: "addr0001".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.claimable, Uint128::new(200));
+    run(&mut deps, "addr0001", 20, claim(1)).unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(1_700));
+
+    // the snapshot of a funded epoch outlives the retained count
+    run(
+        &mut deps,
+        ADMIN,
+        20,
+        ExecuteMsg::SetSnapshotSchedule {
+            schedule: Some(SnapshotSchedule {
+                interval: 1_000_000,
+                retain: 1,
+            }),
+        },
+    )
+    .unwrap();
+    run(&mut deps, ADMIN, 30, ExecuteMsg::TakeSnapshot {}).unwrap();
+    let res: SnapshotsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Snapshots {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.snapshots.len(), 2);
+
+    let sweep = ExecuteMsg::SweepEpochReward { epoch: 1 };
+    let err = run(&mut deps, ADMIN, 99, sweep.clone()).unwrap_err();
+    assert!(err.to_string().contains("claimable until"));
+    let err = run(&mut deps, ADMIN, 100, claim(1)).unwrap_err();
+    assert!(err.to_string().contains("expired"));
+    // the admin gets back its own share along with everything unclaimed
+    run(&mut deps, ADMIN, 100, sweep.clone()).unwrap();
+    assert_eq!(balance_of(&deps, ADMIN), Uint128::new(1_200));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::zero());
+    let err = run(&mut deps, ADMIN, 100, sweep).unwrap_err();
+    assert!(err.to_string().contains("already swept"));
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn raw_ranges_list_storage_maps() {
+    use crate::msg::RawRangeResponse;
+
+    let deps = setup(&[
+        ("addr0000", 1_000),
+        ("addr0001", 2_000),
+        ("addr0002", 3_000),
+    ]);
+    let range = |start_after: Option<Binary>| -> RawRangeResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RawRange {
+                    namespace: "balance".to_string(),
+                    start_after,
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let first = range(None);
+    assert_eq!(first.entries.len(), 2);
+    assert_eq!(first.entries[0].key, Binary::from(b"addr0000"));
+    assert_eq!(
+        from_json::<Uint128>(&first.entries[0].value).unwrap(),
+        Uint128::new(1_000)
+    );
+    let rest = range(Some(first.entries[1].key.clone()));
+    assert_eq!(rest.entries.len(), 1);
+    assert_eq!(rest.entries[0].key, Binary::from(b"addr0002"));
+}
+
+#[test]
+fn snapshot_diff_reports_net_balance_changes() {
+    let mut deps = setup(&[
+        ("addr0000", 1_000),
+        ("addr0001", 1_000),
+        ("addr0002", 1_000),
+    ]);
+    let transfer = |deps: &mut MockDeps, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+    let snapshot = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::TakeSnapshot {},
+        )
+        .unwrap();
+    };
+    let diff = |deps: &MockDeps, from: u64, to: u64, start_after: Option<&str>, limit: u32| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SnapshotDiff {
+                from,
+                to,
+                start_after: start_after.map(String::from),
+                limit: Some(limit),
+            },
+        )
+        .map(|res| {
+            from_json::<SnapshotDiffResponse>(&res)
+                .unwrap()
+                .changes
+                .into_iter()
+                .map(|change| (change.address, change.delta.i128()))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    snapshot(&mut deps);
+    transfer(&mut deps, "addr0000", "addr0001", 300);
+    snapshot(&mut deps);
+    // addr0000 and addr0001 come back to their balances of the first snapshot
+    transfer(&mut deps, "addr0001", "addr0000", 300);
+    transfer(&mut deps, "addr0002", "addr0003", 100);
+    snapshot(&mut deps);
+
+    assert_eq!(
+        diff(&deps, 1, 2, None, 10).unwrap(),
+        vec![
+            ("addr0000".to_string(), -300),
+            ("addr0001".to_string(), 300)
+        ]
+    );
+    assert_eq!(
+        diff(&deps, 1, 3, None, 10).unwrap(),
+        vec![
+            ("addr0002".to_string(), -100),
+            ("addr0003".to_string(), 100)
+        ]
+    );
+    // unchanged addresses are skipped without ending the page early
+    assert_eq!(
+        diff(&deps, 1, 3, None, 1).unwrap(),
+        vec![("addr0002".to_string(), -100)]
+    );
+    assert_eq!(
+        diff(&deps, 1, 3, Some("addr0002"), 1).unwrap(),
+        vec![("addr0003".to_string(), 100)]
+    );
+    assert!(diff(&deps, 1, 3, Some("addr0003"), 1).unwrap().is_empty());
+    assert!(diff(&deps, 2, 2, None, 10).is_err());
+}
+
+#[test]
+fn gated_forwards_attest_the_holder() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let forward = |deps: &mut MockDeps, sender: &str, funds: &[Coin]| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, funds),
+            ExecuteMsg::GatedForward {
+                min_balance: Uint128::new(1_000),
+                contract: "dao0000".to_string(),
+                msg: Binary::from(b"{\"vote\":{}}"),
+                funds: coins(5, "inj"),
+            },
+        )
+    };
+
+    let err = forward(&mut deps, "addr0001", &coins(5, "inj")).unwrap_err();
+    assert!(err.to_string().contains("below the required"));
+    let err = forward(&mut deps, "addr0000", &[]).unwrap_err();
+    assert!(err.to_string().contains("funds must match"));
+
+    let res = forward(&mut deps, "addr0000", &coins(5, "inj")).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "dao0000".to_string(),
+            msg: to_json_binary(&GatedForwardExecuteMsg::GatedForward(GatedForwardMsg {
+                sender: "addr0000".to_string(),
+                balance: Uint128::new(1_000),
+                msg: Binary::from(b"{\"vote\":{}}"),
+            }))
+            .unwrap(),
+            funds: coins(5, "inj"),
+        })
+    );
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "holder" && a.value == "addr0000"));
+}
+
+#[test]
+fn spending_policies_gate_outbound_transfers() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    deps.querier.update_wasm(|query| {
+        let (contract_addr, msg) = match query {
+            WasmQuery::Smart { contract_addr, msg } => (contract_addr, msg),
+            query => panic!("unexpected query {:?}", query),
+        };
+        assert_eq!(contract_addr, "policy0000");
+        let verdict = match from_json(msg).unwrap() {
+            SpendingPolicyQueryMsg::Allow { owner, amount, .. } => {
+                assert_eq!(owner, "addr0000");
+                SpendingVerdict {
+                    allowed: amount <= Uint128::new(100),
+                    reason: Some("daily limit".to_string()),
+                }
+            }
+        };
+        SystemResult::Ok(ContractResult::Ok(to_json_binary(&verdict).unwrap()))
+    });
+    let run = |deps: &mut MockDeps, seconds: u64, msg: ExecuteMsg| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        execute(deps.as_mut(), env, mock_info("addr0000", &[]), msg)
+    };
+    let transfer = |amount: u128| ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::new(amount),
+        min_receive: None,
+    };
+
+    run(
+        &mut deps,
+        0,
+        ExecuteMsg::SetSpendingPolicy {
+            policy: Some("policy0000".to_string()),
+        },
+    )
+    .unwrap();
+    run(&mut deps, 0, transfer(100)).unwrap();
+    let err = run(&mut deps, 0, transfer(200)).unwrap_err();
+    assert!(err.to_string().contains("daily limit"));
+
+    // lifting the policy only takes effect after the delay
+    run(&mut deps, 0, ExecuteMsg::SetSpendingPolicy { policy: None }).unwrap();
+    let res: SpendingPolicyResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SpendingPolicy {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.policy, Some("policy0000".to_string()));
+    assert_eq!(res.pending.unwrap().policy, None);
+    run(&mut deps, SPENDING_POLICY_DELAY - 1, transfer(200)).unwrap_err();
+    run(&mut deps, SPENDING_POLICY_DELAY, transfer(200)).unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(300));
+}
+
+#[test]
+fn effective_balance_adds_up_the_pieces() {
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+
+    let res: EffectiveBalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EffectiveBalance {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        EffectiveBalanceResponse {
+            balance: Uint128::new(500_000),
+            pending_reflections: Uint128::new(2_525),
+            quarantined: Uint128::zero(),
+            locked: Uint128::zero(),
+            frozen: Uint128::zero(),
+            spendable: Uint128::new(500_000),
+            total: Uint128::new(502_525),
+        }
+    );
+}
+
+#[test]
+fn tax_config_splits_the_tax_over_its_destinations() {
+    let mut deps = setup_with(&[("addr0000", 10_000), ("addr0001", 10_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim);
+        msg.max_tax_rate = Some(Decimal::percent(10));
+    });
+    let update = |deps: &mut MockDeps, tax_config: Option<TaxConfig>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::UpdateTaxConfig { tax_config },
+        )
+    };
+    let split = |treasury_bps: u16| TaxConfig {
+        reflection_bps: 200,
+        burn_bps: 100,
+        treasury: Some(TaxDestination {
+            address: "fees0000".to_string(),
+            bps: treasury_bps,
+        }),
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(4),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+
+    // the total is capped by the max tax rate
+    let err = update(&mut deps, Some(split(800))).unwrap_err();
+    assert!(err.to_string().contains("max_tax_rate"));
+    update(&mut deps, Some(split(300))).unwrap();
+    let (tax_rate, ..) = query_rate(&deps.storage).unwrap();
+    assert_eq!(tax_rate, Decimal::percent(6));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(5),
+            reflection_rate: Decimal::zero(),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("tax config"));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0002"), Uint128::new(940));
+    assert_eq!(balance_of(&deps, "fees0000"), Uint128::new(30));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+    assert_eq!(
+        TOKEN_INFO.load(&deps.storage).unwrap().total_supply,
+        Uint128::new(19_990)
+    );
+    let pending: PendingReflectionsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PendingReflections {
+                address: "addr0001".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!pending.pending.is_zero());
+    // the transfer to the treasury address is logged like the one to the treasury
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteMsg::TransferEvent {
+                from: "addr0000".to_string(),
+                to: "fees0000".to_string(),
+                amount: Uint128::new(30),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // removing the config restores the global rate from before it, with the reflection and burn
+    // rates
+    update(&mut deps, None).unwrap();
+    let res: TaxConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TaxConfig {}).unwrap()).unwrap();
+    assert_eq!(res.tax_config, None);
+    let taxes: QueryTaxResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::QueryTax {
+                amount: Uint128::new(1_000),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(taxes.taxed_amount, Uint128::new(40));
+    assert_eq!(taxes.reflection_amount, Uint128::new(20));
+    assert_eq!(taxes.destination_amount, Uint128::zero());
+}
+
+#[test]
+fn tax_configs_without_destination_exclude_insurance() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    let admin = |deps: &mut MockDeps, msg: ExecuteMsg| {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+    };
+    let burn_only = || ExecuteMsg::UpdateTaxConfig {
+        tax_config: Some(TaxConfig {
+            reflection_bps: 200,
+            burn_bps: 100,
+            treasury: None,
+        }),
+    };
+
+    admin(
+        &mut deps,
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::percent(10),
+        },
+    )
+    .unwrap();
+    let err = admin(&mut deps, burn_only()).unwrap_err();
+    assert!(err.to_string().contains("taken from the treasury address"));
+
+    admin(
+        &mut deps,
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::zero(),
+        },
+    )
+    .unwrap();
+    admin(&mut deps, burn_only()).unwrap();
+    let err = admin(
+        &mut deps,
+        ExecuteMsg::SetInsuranceRate {
+            rate: Decimal::percent(10),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("no treasury address"));
+}
+
+#[test]
+fn fee_exempt_addresses_move_tokens_without_tax() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    let admin = |deps: &mut MockDeps, msg: ExecuteMsg| {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+    };
+    admin(
+        &mut deps,
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::zero(),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::SetFeeExempt {
+            address: "pair0000".to_string(),
+            exempt: true,
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    for address in ["router0000", "pair0000"] {
+        admin(
+            &mut deps,
+            ExecuteMsg::SetFeeExempt {
+                address: address.to_string(),
+                exempt: true,
+            },
+        )
+        .unwrap();
+    }
+
+    // into the pair and out of it with a hook, both untaxed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "pair0000".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "pair0000"), Uint128::new(1_000));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pair0000", &[]),
+        ExecuteMsg::Send {
+            contract: "contract0000".to_string(),
+            amount: Uint128::new(500),
+            msg: Binary::default(),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "contract0000"), Uint128::new(500));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+
+    let exemptions = |deps: &MockDeps, start_after: Option<&str>| -> FeeExemptionsResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::FeeExemptions {
+                    start_after: start_after.map(|address| address.to_string()),
+                    limit: Some(1),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(exemptions(&deps, None).addresses, vec!["pair0000"]);
+    assert_eq!(
+        exemptions(&deps, Some("pair0000")).addresses,
+        vec!["router0000"]
+    );
+
+    // once the exemption is lifted the pair pays the tax again
+    admin(
+        &mut deps,
+        ExecuteMsg::SetFeeExempt {
+            address: "pair0000".to_string(),
+            exempt: false,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pair0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(500),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(450));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(50));
+}
+
+#[test]
+fn wallet_limits_cap_transfers_and_balances() {
+    let mut deps = setup_with(&[("addr0000", 10_000)], |msg| {
+        msg.max_tx_amount = Some(Uint128::new(100));
+        msg.max_wallet_amount = Some(Uint128::new(150));
+    });
+    let transfer = |deps: &mut MockDeps, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+    };
+
+    let err = transfer(&mut deps, "addr0000", "addr0001", 101).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Transfer amount 101 exceeds max_tx_amount 100"));
+    transfer(&mut deps, "addr0000", "addr0001", 100).unwrap();
+    let err = transfer(&mut deps, "addr0000", "addr0001", 100).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("would exceed max_wallet_amount 150"));
+
+    // the pair holds the liquidity and sells more than a transaction at once
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetLimitsExempt {
+            address: "pair0000".to_string(),
+            exempt: true,
+        },
+    )
+    .unwrap();
+    transfer(&mut deps, "addr0000", "pair0000", 5_000).unwrap();
+    transfer(&mut deps, "pair0000", "addr0002", 120).unwrap();
+    let err = transfer(&mut deps, "pair0000", "addr0002", 120).unwrap_err();
+    assert!(err.to_string().contains("max_wallet_amount"));
+    let res: LimitExemptionsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::LimitExemptions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.addresses, vec!["pair0000".to_string()]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::UpdateLimits {
+            max_tx_amount: Some(Uint128::zero()),
+            max_wallet_amount: None,
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("must be above 0"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::UpdateLimits {
+            max_tx_amount: None,
+            max_wallet_amount: None,
+        },
+    )
+    .unwrap();
+    let res: LimitsResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Limits {}).unwrap()).unwrap();
+    assert_eq!(res, LimitsResponse::default());
+    transfer(&mut deps, "addr0000", "addr0001", 1_000).unwrap();
+}
+
+#[test]
+fn tax_free_hooks_only_exempt_their_exact_message() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let provide = Binary::from(br#"{"provide_liquidity":{}}"#.to_vec());
+    let send = |deps: &mut MockDeps, msg: &Binary| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Send {
+                contract: "pair0000".to_string(),
+                amount: Uint128::new(100),
+                msg: msg.clone(),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxFreeHook {
+            contract: "pair0000".to_string(),
+            msg_hash: "provide_liquidity".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("lowercase hex sha256"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxFreeHook {
+            contract: "pair0000".to_string(),
+            msg_hash: hook_msg_hash(&provide),
+            enable: true,
+        },
+    )
+    .unwrap();
+
+    send(&mut deps, &provide);
+    assert_eq!(balance_of(&deps, "pair0000"), Uint128::new(100));
+    // a swap sent to the same pair is still taxed
+    send(&mut deps, &Binary::from(br#"{"swap":{}}"#.to_vec()));
+    assert_eq!(balance_of(&deps, "pair0000"), Uint128::new(190));
+
+    let res: TaxFreeHooksResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TaxFreeHooks {
+                contract: "pair0000".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.msg_hashes, vec![hook_msg_hash(&provide)]);
+}
+
+#[test]
+fn spendable_via_names_the_limit_reached_first() {
+    let mut deps = setup_with(&[("addr0000", 1_000), ("addr0002", 1_000)], |msg| {
+        msg.max_tx_amount = Some(Uint128::new(200));
+    });
+    let allow = |deps: &mut MockDeps, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "addr0001".to_string(),
+                amount: Uint128::new(amount),
+                expires: None,
+            },
+        )
+        .unwrap();
+    };
+    let spendable = |deps: &MockDeps| -> SpendableViaResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SpendableVia {
+                    owner: "addr0000".to_string(),
+                    spender: "addr0001".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let res = spendable(&deps);
+    assert_eq!(res.limiting_factor, SpendLimit::Allowance);
+    assert_eq!(res.spendable, Uint128::zero());
+    assert_eq!(res.balance, Uint128::new(1_000));
+
+    allow(&mut deps, 300);
+    let res = spendable(&deps);
+    assert_eq!(res.limiting_factor, SpendLimit::MaxTxAmount);
+    assert_eq!(res.spendable, Uint128::new(200));
+    assert_eq!(res.allowance, Uint128::new(300));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::UpdateLimits {
+            max_tx_amount: None,
+            max_wallet_amount: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(spendable(&deps).limiting_factor, SpendLimit::Allowance);
+
+    allow(&mut deps, 5_000);
+    let res = spendable(&deps);
+    assert_eq!(res.limiting_factor, SpendLimit::Balance);
+    assert_eq!(res.spendable, Uint128::new(1_000));
+
+    // a tenth of the supply per transfer, the limit itself is refused
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::zero(),
+            reflection_rate: Decimal::zero(),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::percent(10),
+        },
+    )
+    .unwrap();
+    let res = spendable(&deps);
+    assert_eq!(res.limiting_factor, SpendLimit::AntiWhale);
+    assert_eq!(res.spendable, Uint128::new(199));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::TransferFrom {
+            owner: "addr0000".to_string(),
+            recipient: "addr0003".to_string(),
+            amount: res.spendable,
+            min_receive: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn bridge_moves_tokens_without_tax() {
+    let mut deps = setup_with(&[("addr0000", 1_000)], |msg| {
+        msg.max_wallet_amount = Some(Uint128::new(1_000));
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::percent(50),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetBridge {
+            contract: Some("bridge0000".to_string()),
+        },
+    )
+    .unwrap();
+    let res: BridgeConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::BridgeConfig {}).unwrap()).unwrap();
+    assert_eq!(res.bridge, Some("bridge0000".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Send {
+            contract: "bridge0000".to_string(),
+            amount: Uint128::new(400),
+            msg: Binary::default(),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, "bridge_out");
+    assert_eq!(balance_of(&deps, "bridge0000"), Uint128::new(400));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+
+    // a refund of more than the anti-whale limit goes through untaxed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "bridge0000".to_string(),
+            amount: Uint128::new(400),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bridge0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(800),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, "bridge_in");
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(800));
+}
+
+#[test]
+fn multi_transfer_taxes_each_recipient_and_sums_the_tax() {
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(3),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let coins = |recipients: &[(&str, u128)]| -> Vec<Cw20Coin> {
+        recipients
+            .iter()
+            .map(|(address, amount)| Cw20Coin {
+                address: address.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .collect()
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MultiTransfer { recipients: vec![] },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("No recipients"));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MultiTransfer {
+            recipients: coins(&[("addr0001", 1_000), ("addr0002", 1_000)]),
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(970));
+    assert_eq!(balance_of(&deps, "addr0002"), Uint128::new(970));
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(60));
+    // one tax event for the treasury instead of one per recipient
+    assert_eq!(res.messages.len(), 1);
+    let attribute = |key: &str| {
+        res.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attribute("total_sent"), "2000");
+    assert_eq!(attribute("total_received"), "1940");
+    assert_eq!(attribute("total_tax"), "60");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MultiTransferExactNet {
+            recipients: coins(&[("addr0003", 97), ("addr0004", 333)]),
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0003"), Uint128::new(97));
+    assert_eq!(balance_of(&deps, "addr0004"), Uint128::new(333));
+    assert_eq!(
+        balance_of(&deps, "addr0000"),
+        Uint128::new(8_000)
+            - gross_up(&deps.storage, Uint128::new(97)).unwrap()
+            - gross_up(&deps.storage, Uint128::new(333)).unwrap()
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MultiTransfer {
+            recipients: coins(&[("addr0005", 0)]),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Invalid zero amount"));
+}
+
+#[test]
+fn apply_preset_migration_matches_its_preview() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let preview = |deps: &MockDeps, preset: &str| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PresetPreview {
+                preset: preset.to_string(),
+            },
+        )
+        .map(|res| from_json::<ConfigHashResponse>(&res).unwrap())
+    };
+    let apply = |preset: &str| {
+        MigrateMsg::Action(MigrateAction::ApplyPreset {
+            preset: preset.to_string(),
+        })
+    };
+
+    let err = preview(&deps, "unknown").unwrap_err();
+    assert!(err.to_string().contains("Unknown preset unknown"));
+    let expected = preview(&deps, "reflection_heavy").unwrap();
+    assert_eq!(expected.params.tax_rate, Decimal::percent(8));
+    assert_eq!(expected.params.drip_period, 86_400);
+    // dry run only
+    let res: ConfigHashResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ConfigHash {}).unwrap()).unwrap();
+    assert_ne!(res.hash, expected.hash);
+
+    let res = migrate(deps.as_mut(), mock_env(), apply("reflection_heavy")).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|a| a.key == "config_hash" && a.value == expected.hash));
+    let res: ConfigHashResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ConfigHash {}).unwrap()).unwrap();
+    assert_eq!(res, expected);
+
+    // a plain upgrade keeps the parameters
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Upgrade {
+            msg: "".to_string(),
+        },
+    )
+    .unwrap();
+    let res: ConfigHashResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ConfigHash {}).unwrap()).unwrap();
+    assert_eq!(res.hash, expected.hash);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirm: MOCK_CONTRACT_ADDR.to_string(),
+        },
+    )
+    .unwrap();
+    let err = preview(&deps, "standard").unwrap_err();
+    assert!(err.to_string().contains("economic parameters are locked"));
+    migrate(deps.as_mut(), mock_env(), apply("standard")).unwrap_err();
+}
+
+#[test]
+fn balance_at_includes_the_reflections_accrued_by_then() {
+    let mut deps = setup_with(&[("addr0000", 500_000), ("addr0001", 500_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    let at = |height: u64| {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    };
+    let start = mock_env().block.height;
+    execute(
+        deps.as_mut(),
+        at(start),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let balance_at = |deps: &MockDeps, address: &str, height: u64| -> Uint128 {
+        let res: BalanceAtResponse = from_json(
+            query(
+                deps.as_ref(),
+                at(start + 10),
+                QueryMsg::BalanceAt {
+                    address: address.to_string(),
+                    height: Some(height),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.balance
+    };
+    let total_supply_at = |deps: &MockDeps, height: u64| -> Uint128 {
+        let res: TotalSupplyAtResponse = from_json(
+            query(
+                deps.as_ref(),
+                at(start + 10),
+                QueryMsg::TotalSupplyAt {
+                    height: Some(height),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.total_supply
+    };
+
+    execute(
+        deps.as_mut(),
+        at(start + 1),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let per_token = Decimal::from_ratio(5_000u128, 990_000u128);
+    let earned = Uint128::new(500_000).mul_floor(per_token);
+
+    // the token did not exist at the start of its instantiation block
+    assert_eq!(total_supply_at(&deps, start), Uint128::zero());
+    assert_eq!(balance_at(&deps, "addr0000", start), Uint128::zero());
+    assert_eq!(total_supply_at(&deps, start + 1), Uint128::new(1_000_000));
+    assert_eq!(
+        balance_at(&deps, "addr0001", start + 1),
+        Uint128::new(500_000)
+    );
+    // addr0000 did not move but accrued reflections
+    assert_eq!(
+        balance_at(&deps, "addr0000", start + 1),
+        Uint128::new(500_000)
+    );
+    assert_eq!(
+        balance_at(&deps, "addr0000", start + 2),
+        Uint128::new(500_000) + earned
+    );
+    assert_eq!(
+        balance_at(&deps, "addr0001", start + 2),
+        Uint128::new(400_000) + Uint128::new(400_000).mul_floor(per_token)
+    );
+    // credited before the reflections of its own transfer
+    assert_eq!(
+        balance_at(&deps, "addr0002", start + 2),
+        Uint128::new(90_000) + Uint128::new(90_000).mul_floor(per_token)
+    );
+
+    // claiming moves the reflections into the balance, the voting power stays the same
+    execute(
+        deps.as_mut(),
+        at(start + 3),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap();
+    assert_eq!(
+        balance_at(&deps, "addr0000", start + 3),
+        Uint128::new(500_000) + earned
+    );
+    assert_eq!(
+        balance_at(&deps, "addr0000", start + 4),
+        Uint128::new(500_000) + earned
+    );
+    assert_eq!(
+        balance_of(&deps, "addr0000"),
+        Uint128::new(500_000) + earned
+    );
+
+    let err = query(
+        deps.as_ref(),
+        at(start + 10),
+        QueryMsg::TotalSupplyAt {
+            height: Some(start - 1),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("checkpointed from height"));
+}
+
+#[test]
+fn vesting_locks_the_initial_balance_until_released() {
+    let vested_token = || {
+        setup_with(&[("addr0000", 1_000_000)], |msg| {
+            msg.vesting = Some(vec![VestingSchedule {
+                address: "addr0000".to_string(),
+                amount: Uint128::new(600_000),
+                cliff: 100,
+                duration: 1_000,
+                period: 100,
+            }])
+        })
+    };
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    };
+    let transfer = |deps: &mut MockDeps, seconds: u64, amount: u128| {
+        execute(
+            deps.as_mut(),
+            at(seconds),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+    };
+
+    let mut deps = vested_token();
+    let err = transfer(&mut deps, 0, 400_001).unwrap_err();
+    assert!(err.to_string().contains("locked by its vesting schedule"));
+    let mut deps = vested_token();
+    transfer(&mut deps, 0, 400_000).unwrap();
+    let info: VestingInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            at(250),
+            QueryMsg::VestingInfo {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    // two periods released after the cliff
+    assert_eq!(info.locked, Uint128::new(480_000));
+    assert_eq!(
+        info.next_release,
+        Some(mock_env().block.time.seconds() + 300)
+    );
+    let res: EffectiveBalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            at(250),
+            QueryMsg::EffectiveBalance {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.spendable, Uint128::new(120_000));
+    transfer(&mut deps, 250, 120_000).unwrap();
+
+    // burning is held to the same lock
+    let err = execute(
+        deps.as_mut(),
+        at(250),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Burn {
+            amount: Uint128::one(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("locked by its vesting schedule"));
+
+    let mut deps = vested_token();
+    transfer(&mut deps, 1_000, 999_000).unwrap();
+    let info: VestingInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            at(1_000),
+            QueryMsg::VestingInfo {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(info, VestingInfoResponse::default());
+
+    let initial_balances = vec![Cw20Coin {
+        address: "addr0000".to_string(),
+        amount: Uint128::new(1_000_000),
+    }];
+    let schedule = |amount: u128, period: u64| VestingSchedule {
+        address: "addr0000".to_string(),
+        amount: Uint128::new(amount),
+        cliff: 0,
+        duration: 1_000,
+        period,
+    };
+    let err = validate_vesting(&[schedule(2_000_000, 100)], &initial_balances).unwrap_err();
+    assert!(err.to_string().contains("exceeds its initial balance"));
+    validate_vesting(&[schedule(1_000, 0)], &initial_balances).unwrap_err();
+    validate_vesting(
+        &[schedule(1_000, 100), schedule(1_000, 100)],
+        &initial_balances,
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn display_decimals_are_set_by_the_marketing_address() {
+    let mut deps = setup_with(&[("addr0000", 1_000_000)], |msg| {
+        msg.marketing = Some(InstantiateMarketingInfo {
+            project: None,
+            description: None,
+            marketing: Some("marketing0000".to_string()),
+            logo: None,
+            display_decimals: Some(2),
+        });
+    });
+    let display_info = |deps: &MockDeps| -> DisplayInfoResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::DisplayInfo {}).unwrap()).unwrap()
+    };
+    assert_eq!(
+        display_info(&deps),
+        DisplayInfoResponse {
+            decimals: 6,
+            display_decimals: 2,
+        }
+    );
+
+    let set = |display_decimals| ExecuteMsg::SetDisplayDecimals { display_decimals };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        set(Some(4)),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("marketing0000", &[]),
+        set(Some(7)),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("must not exceed decimals"));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("marketing0000", &[]),
+        set(Some(4)),
+    )
+    .unwrap();
+    assert_eq!(display_info(&deps).display_decimals, 4);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("marketing0000", &[]),
+        set(None),
+    )
+    .unwrap();
+    assert_eq!(display_info(&deps).display_decimals, 6);
+
+    // without marketing info the token decimals are shown
+    let deps = setup(&[("addr0000", 1_000_000)]);
+    assert_eq!(display_info(&deps).display_decimals, 6);
+}
+
+#[test]
+fn guardian_pauses_the_token_and_freezes_addresses() {
+    let mut deps = setup(&[("addr0000", 500_000), ("addr0001", 500_000)]);
+    let transfer = |amount| ExecuteMsg::Transfer {
+        recipient: "addr0002".to_string(),
+        amount: Uint128::new(amount),
+        min_receive: None,
+    };
+    let guardian = mock_info("guardian0000", &[]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not guardian"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+    )
+    .unwrap();
+
+    // paused, nothing but the security messages goes through
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer(1_000),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Token is paused"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Freeze {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let status: SecurityStatusResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SecurityStatus {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(status.guardian, Some("guardian0000".to_string()));
+    assert_eq!(status.paused_at, Some(mock_env().block.time.seconds()));
+    assert_eq!(status.frozen.len(), 1);
+    assert_eq!(status.frozen[0].address, "addr0000");
+    for (address, reason) in [
+        (MOCK_CONTRACT_ADDR, "The token cannot be frozen"),
+        (ADMIN, "The admin cannot be frozen"),
+        (TREASURY, "The treasury cannot be frozen"),
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            guardian.clone(),
+            ExecuteMsg::Freeze {
+                address: address.to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(reason));
+    }
+
+    // unpaused, the frozen address is still blocked but can receive
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian.clone(),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer(1_000),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("addr0000 is frozen"));
+    let res: EffectiveBalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EffectiveBalance {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.frozen, Uint128::new(500_000));
+    assert_eq!(res.spendable, Uint128::zero());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0000".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert!(balance_of(&deps, "addr0000") > Uint128::new(500_000));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian,
+        ExecuteMsg::Unfreeze {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer(1_000),
+    )
+    .unwrap();
+}
+
+#[test]
+fn frozen_balances_cannot_be_moved_by_a_spender() {
+    let mut deps = setup(&[("addr0000", 500_000), ("addr0001", 500_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::IncreaseAllowance {
+            spender: "addr0001".to_string(),
+            amount: Uint128::new(10_000),
+            expires: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        ExecuteMsg::Freeze {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::TransferFrom {
+            owner: "addr0000".to_string(),
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("addr0000 is frozen"));
+}
+
+#[test]
+fn auto_liquidity_swaps_half_and_provides_the_rest() {
+    let mut deps = setup(&[("addr0000", 1_000_000), ("addr0001", 1_000_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetAutoLiquidity {
+            config: Some(AutoLiquidityConfig {
+                pair: "pair0000".to_string(),
+                quote: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                rate: Decimal::percent(50),
+                threshold: Uint128::new(5_000),
+                lock_address: "locker0000".to_string(),
+                max_spread: Some(Decimal::percent(1)),
+            }),
+        },
+    )
+    .unwrap();
+    let transfer_and_log = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::TransferEvent {
+                from: "addr0000".to_string(),
+                to: TREASURY.to_string(),
+                amount: Uint128::new(5_000),
+            },
+        )
+        .unwrap()
+    };
+    let auto_liquidity = |deps: &MockDeps| -> AutoLiquidityResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::AutoLiquidity {}).unwrap()).unwrap()
+    };
+    let liquidity_reply = |deps: &mut MockDeps, id, result| {
+        reply(deps.as_mut(), mock_env(), Reply { id, result }).unwrap()
+    };
+    let ok = || {
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        })
+    };
+
+    // half of the 10_000 tax is set aside, the treasury is credited the rest
+    let res = transfer_and_log(&mut deps);
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::new(5_000));
+    let swap = res.messages.last().unwrap();
+    assert_eq!(swap.id, AUTO_LIQUIDITY_SWAP_REPLY_ID);
+    assert_eq!(swap.reply_on, ReplyOn::Always);
+    assert_eq!(balance_of(&deps, "pair0000"), Uint128::new(2_500));
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(2_500));
+
+    // the swap returned 1_000 inj, provided with the 2_500 tokens kept
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(1_000, "inj"));
+    let res = liquidity_reply(&mut deps, AUTO_LIQUIDITY_SWAP_REPLY_ID, ok());
+    let provide = res.messages.last().unwrap();
+    assert_eq!(provide.id, AUTO_LIQUIDITY_PROVIDE_REPLY_ID);
+    assert_eq!(
+        provide.msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "pair0000".to_string(),
+            msg: to_json_binary(&dojoswap::pair::ExecuteMsg::ProvideLiquidity {
+                assets: [
+                    Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                        },
+                        amount: Uint128::new(2_500),
+                    },
+                    Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "inj".to_string(),
+                        },
+                        amount: Uint128::new(1_000),
+                    },
+                ],
+                receiver: Some("locker0000".to_string()),
+                deadline: None,
+                slippage_tolerance: Some(Decimal::percent(1)),
+            })
+            .unwrap(),
+            funds: coins(1_000, "inj"),
+        })
+    );
+    let allowance: cw20::AllowanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allowance {
+                owner: MOCK_CONTRACT_ADDR.to_string(),
+                spender: "pair0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(allowance.allowance, Uint128::new(2_500));
+    liquidity_reply(&mut deps, AUTO_LIQUIDITY_PROVIDE_REPLY_ID, ok());
+    let res = auto_liquidity(&deps);
+    assert_eq!(res.state.provided, Uint128::new(2_500));
+    assert_eq!(res.state.in_flight, None);
+
+    // a swap above the max spread gives the tokens back to be set aside again
+    transfer_and_log(&mut deps);
+    let res = liquidity_reply(
+        &mut deps,
+        AUTO_LIQUIDITY_SWAP_REPLY_ID,
+        SubMsgResult::Err("Operation exceeds max spread limit".to_string()),
+    );
+    assert_eq!(res.attributes[0].value, "auto_liquidity_swap_failed");
+    assert_eq!(balance_of(&deps, "pair0000"), Uint128::new(2_500));
+    let res = auto_liquidity(&deps);
+    assert_eq!(res.state.accumulated, Uint128::new(5_000));
+    assert_eq!(
+        res.state.last_failure,
+        Some("Operation exceeds max spread limit".to_string())
+    );
+}
+
+#[test]
+fn sunset_redeems_balances_for_the_settlement_pool() {
+    let mut deps = setup(&[("addr0000", 600_000), ("addr0001", 400_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::ScheduleSunset {
+            denom: "usdt".to_string(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &coins(1_000, "inj")),
+        ExecuteMsg::FundSettlement {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Send only usdt"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &coins(1_000, "usdt")),
+        ExecuteMsg::FundSettlement {},
+    )
+    .unwrap();
+    let redeem = |deps: &mut MockDeps, env: Env, sender: &str| {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info(sender, &[]),
+            ExecuteMsg::RedeemFinal {},
+        )
+    };
+    let transfer = ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::new(1_000),
+        min_receive: None,
+    };
+
+    // holders can still leave during the timelock
+    let err = redeem(&mut deps, mock_env(), "addr0000").unwrap_err();
+    assert!(err.to_string().contains("Sunset activates at"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        transfer.clone(),
+    )
+    .unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(SUNSET_DELAY);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        transfer,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("only RedeemFinal"));
+    let status: SunsetResponse = from_json(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Sunset {
+                address: Some("addr0001".to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(status.active);
+    assert_eq!(status.redeemable_supply, Uint128::new(1_000_000));
+    assert_eq!(status.payout, Some(Uint128::new(401)));
+
+    let res = redeem(&mut deps, env.clone(), "addr0001").unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0001".to_string(),
+            amount: coins(401, "usdt"),
+        })
+    );
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::zero());
+    assert_eq!(
+        TOKEN_INFO.load(&deps.storage).unwrap().total_supply,
+        Uint128::new(599_000)
+    );
+    let err = redeem(&mut deps, env.clone(), "addr0001").unwrap_err();
+    assert!(err.to_string().contains("Nothing to redeem"));
+
+    // the rate is fixed at the activation whatever the order of the redemptions
+    let res = redeem(&mut deps, env, "addr0000").unwrap();
+    assert_eq!(res.attributes[3].value, "599");
+}
+
+#[test]
+fn sunset_freezes_the_fee_buckets_and_sweeps_the_rest() {
+    use crate::sunset::{SETTLEMENT_CLAIM_PERIOD, SUNSET_DELAY};
+
+    let mut deps = setup(&[("addr0000", 600_000), ("addr0001", 400_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetReflectionDrip { period: DAY },
+        ExecuteMsg::ScheduleSunset {
+            denom: "usdt".to_string(),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &coins(1_000_000, "usdt")),
+        ExecuteMsg::FundSettlement {},
+    )
+    .unwrap();
+    // 10_000 of tax still dripping to the treasury when the sunset activates
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(100_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    let redeem = |deps: &mut MockDeps, env: &Env, sender: &str| -> String {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(sender, &[]),
+            ExecuteMsg::RedeemFinal {},
+        )
+        .unwrap();
+        res.attributes
+            .into_iter()
+            .find(|attr| attr.key == "payout")
+            .unwrap()
+            .value
+    };
+    let sweep = |deps: &mut MockDeps, env: &Env, sender: &str| {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(sender, &[]),
+            ExecuteMsg::SweepSettlement {},
+        )
+    };
+
+    // the drip stops with the activation, so the 990_000 redeemable tokens share the pool
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(SUNSET_DELAY);
+    assert_eq!(redeem(&mut deps, &env, "addr0000"), "505050");
+    env.block.time = env.block.time.plus_seconds(DAY);
+    assert_eq!(redeem(&mut deps, &env, "addr0001"), "404040");
+    assert_eq!(redeem(&mut deps, &env, "addr0002"), "90909");
+    assert_eq!(balance_of(&deps, TREASURY), Uint128::zero());
+    assert_eq!(balance_of(&deps, MOCK_CONTRACT_ADDR), Uint128::new(10_000));
+
+    // what nobody redeemed goes back to the admin after the claim period
+    let err = sweep(&mut deps, &env, ADMIN).unwrap_err();
+    assert!(err.to_string().contains("Settlement can be swept from"));
+    env.block.time = mock_env()
+        .block
+        .time
+        .plus_seconds(SUNSET_DELAY + SETTLEMENT_CLAIM_PERIOD);
+    sweep(&mut deps, &env, "addr0000").unwrap_err();
+    let res = sweep(&mut deps, &env, ADMIN).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADMIN.to_string(),
+            amount: coins(1, "usdt"),
+        })
+    );
+    let err = sweep(&mut deps, &env, ADMIN).unwrap_err();
+    assert!(err.to_string().contains("Nothing to sweep"));
+}
+
+#[test]
+fn features_report_the_build_variant() {
+    use crate::msg::FeaturesResponse;
+
+    let deps = setup(&[("addr0000", 1_000)]);
+    let features: FeaturesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Features {}).unwrap()).unwrap();
+    assert_eq!(
+        features,
+        FeaturesResponse {
+            clawback: cfg!(feature = "clawback"),
+            debug: cfg!(feature = "debug"),
+        }
+    );
+}
+
+#[cfg(feature = "clawback")]
+#[test]
+fn issuer_claws_back_frozen_tokens_with_a_reason() {
+    let mut deps = setup(&[("addr0000", 500_000), ("addr0001", 500_000)]);
+    let clawback = |amount, reason: &str| ExecuteMsg::Clawback {
+        from: "addr0000".to_string(),
+        amount: Uint128::new(amount),
+        reason: reason.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        ExecuteMsg::Freeze {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        clawback(100_000, "court order 42"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        clawback(100_000, " "),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("needs a reason"));
+
+    // the frozen balance moves to the issuer untaxed
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        clawback(100_000, "court order 42"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("clawback")
+            .add_attribute("from", "addr0000")
+            .add_attribute("to", ADMIN)
+            .add_attribute("amount", "100000")
+            .add_attribute("reason", "court order 42")]
+    );
+    assert_eq!(balance_of(&deps, "addr0000"), Uint128::new(400_000));
+    assert_eq!(balance_of(&deps, ADMIN), Uint128::new(100_000));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        clawback(400_001, "court order 42"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Overflow"));
+}
+
+#[test]
+fn ownership_is_transferred_in_two_steps() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let ownership = |deps: &MockDeps| -> RolesResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Roles {}).unwrap()).unwrap()
+    };
+    let transfer = |new_admin: &str, expiry| ExecuteMsg::TransferOwnership {
+        new_admin: new_admin.to_string(),
+        expiry,
+    };
+
+    // a mistyped proposal changes nothing and is replaced
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        transfer("addr0009", None),
+    )
+    .unwrap();
+    assert_eq!(ownership(&deps).admin, Some(ADMIN.to_string()));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not pending admin"));
+    let expiry = Expiration::AtHeight(mock_env().block.height + 10);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        transfer("addr0001", Some(expiry)),
+    )
+    .unwrap();
+    assert_eq!(
+        ownership(&deps).pending_admin.unwrap().address,
+        "addr0001".to_string()
+    );
+
+    let mut late = mock_env();
+    late.block.height += 10;
+    let err = execute(
+        deps.as_mut(),
+        late,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("expired"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    let roles = ownership(&deps);
+    assert_eq!(roles.admin, Some("addr0001".to_string()));
+    assert_eq!(roles.pending_admin, None);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        transfer(ADMIN, None),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+}
+
+#[test]
+fn roles_split_the_admin_powers() {
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let set_tax = ExecuteMsg::SetTaxRate {
+        global_rate: Decimal::percent(10),
+        reflection_rate: Decimal::percent(50),
+        burn_rate: Decimal::zero(),
+        antiwhale_rate: Decimal::one(),
+    };
+    let exempt = ExecuteMsg::SetFeeExempt {
+        address: "addr0002".to_string(),
+        exempt: true,
+    };
+    for (role, address) in [
+        (Role::TaxManager, "multisig0000"),
+        (Role::ExemptionManager, "exemptions0000"),
+        (Role::Guardian, "guardian0000"),
+    ] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::UpdateRole {
+                role,
+                address: Some(address.to_string()),
+            },
+        )
+        .unwrap();
+    }
+    let roles: RolesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Roles {}).unwrap()).unwrap();
+    assert_eq!(roles.tax_manager, Some("multisig0000".to_string()));
+    assert_eq!(roles.exemption_manager, Some("exemptions0000".to_string()));
+    assert_eq!(roles.guardian, Some("guardian0000".to_string()));
+
+    // each manager has its own powers and no others
+    let multisig = mock_info("multisig0000", &[]);
+    execute(deps.as_mut(), mock_env(), multisig.clone(), set_tax.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), multisig.clone(), exempt.clone()).unwrap_err();
+    assert!(err.to_string().contains("not admin or exemption_manager"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        multisig.clone(),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not guardian"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        multisig.clone(),
+        ExecuteMsg::UpdateRole {
+            role: Role::TaxManager,
+            address: None,
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("exemptions0000", &[]),
+        exempt,
+    )
+    .unwrap();
+    // the admin keeps the manager powers
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        set_tax.clone(),
+    )
+    .unwrap();
+
+    // renouncing ownership locks the tax for the managers too
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::RenounceOwnership {
+            confirm: MOCK_CONTRACT_ADDR.to_string(),
+        },
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), mock_env(), multisig, set_tax).unwrap_err();
+    assert!(err.to_string().contains("ownership renounced"));
+}
+
+#[test]
+fn large_transfers_need_the_co_signer() {
+    let mut deps = setup(&[("team0000", 1_000_000)]);
+    let team = mock_info("team0000", &[]);
+    let co_signer = mock_info("cosigner0000", &[]);
+    let transfer = |amount| ExecuteMsg::Transfer {
+        recipient: "addr0001".to_string(),
+        amount: Uint128::new(amount),
+        min_receive: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::SetCoSigner {
+            co_signer: Some("cosigner0000".to_string()),
+            threshold: Uint128::new(100_000),
+        },
+    )
+    .unwrap();
+
+    // up to the threshold the account transfers alone
+    execute(deps.as_mut(), mock_env(), team.clone(), transfer(100_000)).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), team.clone(), transfer(100_001)).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("need the approval of cosigner0000"));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::ProposeCoSignedTransfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(300_000),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::ExecuteCoSignedTransfer { id: 1 },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not approved"));
+    let approve = ExecuteMsg::ApproveCoSignedTransfer {
+        owner: "team0000".to_string(),
+        id: 1,
+    };
+    let err = execute(deps.as_mut(), mock_env(), team.clone(), approve.clone()).unwrap_err();
+    assert!(err.to_string().contains("not co-signer"));
+    execute(deps.as_mut(), mock_env(), co_signer.clone(), approve).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::ExecuteCoSignedTransfer { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(400_000));
+    // the approval is used once
+    let err = execute(deps.as_mut(), mock_env(), team.clone(), transfer(300_000)).unwrap_err();
+    assert!(err.to_string().contains("need the approval"));
+
+    // an approved proposal cannot be executed once expired
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::ProposeCoSignedTransfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(200_000),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        co_signer,
+        ExecuteMsg::ApproveCoSignedTransfer {
+            owner: "team0000".to_string(),
+            id: 2,
+        },
+    )
+    .unwrap();
+    let mut late = mock_env();
+    late.block.time = late.block.time.plus_seconds(CO_SIGNED_TRANSFER_EXPIRY);
+    let err = execute(
+        deps.as_mut(),
+        late,
+        team.clone(),
+        ExecuteMsg::ExecuteCoSignedTransfer { id: 2 },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("expired"));
+
+    // removing the co-signer waits for its delay
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        team.clone(),
+        ExecuteMsg::SetCoSigner {
+            co_signer: None,
+            threshold: Uint128::zero(),
+        },
+    )
+    .unwrap();
+    let rule: CoSignerResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CoSigner {
+                address: "team0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(rule.rule.is_some());
+    assert_eq!(rule.pending.unwrap().rule, None);
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(CO_SIGNER_DELAY);
+    execute(deps.as_mut(), later, team, transfer(300_000)).unwrap();
+}
+
+#[test]
+fn transfer_hooks_are_called_after_every_transfer() {
+    use dojoswap::taxed::TransferHookMsg;
+
+    let mut deps = setup(&[("addr0000", 1_000_000)]);
+    let admin = mock_info(ADMIN, &[]);
+    let add = ExecuteMsg::AddTransferHook {
+        contract: "staking0000".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        add.clone(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    execute(deps.as_mut(), mock_env(), admin.clone(), add.clone()).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), admin.clone(), add).unwrap_err();
+    assert!(err.to_string().contains("already registered"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin.clone(),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+
+    let hook_call = |to: &str, amount: u128, tax: u128| {
+        SubMsg::new(
+            TransferHookMsg {
+                from: "addr0000".to_string(),
+                to: to.to_string(),
+                amount: Uint128::new(amount),
+                tax: Uint128::new(tax),
+            }
+            .into_cosmos_msg("staking0000")
+            .unwrap(),
+        )
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert!(res.messages.contains(&hook_call("addr0001", 1_000, 100)));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MultiTransfer {
+            recipients: vec![
+                Cw20Coin {
+                    address: "addr0001".to_string(),
+                    amount: Uint128::new(2_000),
+                },
+                Cw20Coin {
+                    address: "addr0002".to_string(),
+                    amount: Uint128::new(3_000),
+                },
+            ],
+        },
+    )
+    .unwrap();
+    assert!(res.messages.contains(&hook_call("addr0001", 2_000, 200)));
+    assert!(res.messages.contains(&hook_call("addr0002", 3_000, 300)));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin,
+        ExecuteMsg::RemoveTransferHook {
+            contract: "staking0000".to_string(),
+        },
+    )
+    .unwrap();
+    let hooks: TransferHooksResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TransferHooks {}).unwrap()).unwrap();
+    assert!(hooks.hooks.is_empty());
+}
+
+#[test]
+fn permits_set_allowances_signed_off_chain() {
+    use crate::msg::PermitMsg;
+
+    // the test key of `dojoswap::signing`, signed over the domain of `mock_env`
+    const OWNER: &str = "cosmos1t0laxck97sh7n84zmu47y2dr7rekycfgctwhny";
+    let public_key = Binary::from(
+        hex::decode("03ac34e47e305954181334fb1f0abd8d146f64b8c0cac9bbc6ddc6a7f3020da8a1").unwrap(),
+    );
+    let signature = Binary::from(hex::decode("122e7ce207c4d1cd8856cdf7530d419923661fca4abc39cfcca4590383a2a4b6074c53e5031b85dd3ff82daa4e0f6eb58a5bb1aa3895b009f29167a176297a6b").unwrap());
+    let mut deps = setup(&[(OWNER, 1_000)]);
+    let permit = |amount: u128| ExecuteMsg::Permit {
+        permit: PermitMsg {
+            owner: OWNER.to_string(),
+            spender: "spender0000".to_string(),
+            amount: Uint128::new(amount),
+            expires: Some(Expiration::AtHeight(12_400)),
+            nonce: 0,
+        },
+        public_key: public_key.clone(),
+        signature: signature.clone(),
+        key_type: None,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        permit(5_000),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Invalid permit signature"));
+    let mut other = permit(500);
+    if let ExecuteMsg::Permit { permit, .. } = &mut other {
+        permit.owner = "cosmos1owner".to_string();
+    }
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        other,
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Permit public key does not match the owner"));
+
+    // submitted by the spender itself, which takes the tokens in the same transaction
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        permit(500),
+    )
+    .unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.value == "permit"));
+    let allowance: cw20::AllowanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allowance {
+                owner: OWNER.to_string(),
+                spender: "spender0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(allowance.allowance, Uint128::new(500));
+    assert_eq!(allowance.expires, Expiration::AtHeight(12_400));
+    let domain: SignatureDomainResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SignatureDomain {
+                address: Some(OWNER.to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(domain.next_permit_nonce, 1);
+    assert_eq!(domain.next_relay_nonce, 0);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        ExecuteMsg::TransferFrom {
+            owner: OWNER.to_string(),
+            recipient: "spender0000".to_string(),
+            amount: Uint128::new(500),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(balance_of(&deps, OWNER), Uint128::new(500));
+    // spent, the permit cannot be submitted again to restore the allowance
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        permit(500),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Invalid permit nonce 0, expected 1"));
+}
+
+#[test]
+fn permits_accept_injective_keys() {
+    use crate::msg::PermitMsg;
+    use dojoswap::signing::KeyType;
+
+    // the eth_secp256k1 test key of `dojoswap::signing`, signed over the domain of `mock_env`
+    const OWNER: &str = "inj15ydua0el49cgz34qm68l9j5addsgdqydpazsdw";
+    let public_key = Binary::from(
+        hex::decode("0203f74be71f283514e83cfe207cd8225c2feeebbca96f5e2fbd26c79be82e54e3").unwrap(),
+    );
+    let signature = Binary::from(hex::decode("9509b459a9d1bf8dcee7e1e201cf86acae49d0324d090a12a33b49ee9ae5a380317f6755d74da08673a099a170a0183f74e4005378379051a6b7875e3c06a68b").unwrap());
+    let mut deps = setup(&[(OWNER, 1_000)]);
+    let permit = |key_type: Option<KeyType>| ExecuteMsg::Permit {
+        permit: PermitMsg {
+            owner: OWNER.to_string(),
+            spender: "spender0000".to_string(),
+            amount: Uint128::new(500),
+            expires: Some(Expiration::AtHeight(12_400)),
+            nonce: 0,
+        },
+        public_key: public_key.clone(),
+        signature: signature.clone(),
+        key_type,
+    };
+
+    // taken for a cosmos key, the public key derives another address
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        permit(None),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Permit public key does not match the owner"));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender0000", &[]),
+        permit(Some(KeyType::EthSecp256k1)),
+    )
+    .unwrap();
+    let allowance: cw20::AllowanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allowance {
+                owner: OWNER.to_string(),
+                spender: "spender0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(allowance.allowance, Uint128::new(500));
+}
+
+#[test]
+fn paused_accrual_is_withheld_until_released() {
+    use crate::msg::{AccrualOutcome, AccrualPauseResponse};
+
+    let mut deps = setup_with(
+        &[("addr0000", 600_000), ("exploiter0000", 400_000)],
+        |msg| msg.reflection_mode = Some(ReflectionMode::Claim),
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let transfer = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+    let pending = |deps: &MockDeps| -> Uint128 {
+        let res: PendingReflectionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: "exploiter0000".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pending
+    };
+    let withheld = |deps: &MockDeps| -> Option<Uint128> {
+        let res: AccrualPauseResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::AccrualPause {
+                    address: "exploiter0000".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pause.map(|pause| pause.withheld)
+    };
+    let pause = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::PauseAccrual {
+                address: "exploiter0000".to_string(),
+                reason: "exploit proceeds".to_string(),
+            },
+        )
+    };
+    let release = |deps: &mut MockDeps, outcome| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::ReleaseAccrual {
+                address: "exploiter0000".to_string(),
+                outcome,
+            },
+        )
+        .unwrap()
+    };
+
+    transfer(&mut deps);
+    let before = pending(&deps);
+    assert!(!before.is_zero());
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("exploiter0000", &[]),
+        ExecuteMsg::PauseAccrual {
+            address: "exploiter0000".to_string(),
+            reason: "x".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Unauthorized: not admin");
+    let res = pause(&mut deps).unwrap();
+    assert_eq!(res.events[0].ty, "accrual_paused");
+    assert_eq!(withheld(&deps), Some(Uint128::zero()));
+
+    // the address keeps trading, what it accrues meanwhile is withheld
+    transfer(&mut deps);
+    let accrual = withheld(&deps).unwrap();
+    assert!(!accrual.is_zero());
+    assert_eq!(pending(&deps), before);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("exploiter0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    // its own taxed transfer reflected to every holder, itself included
+    let accrual = withheld(&deps).unwrap();
+    assert_eq!(pending(&deps), before);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("exploiter0000", &[]),
+        ExecuteMsg::ClaimReflections {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("paused"));
+
+    // cleared: credited as if the address never was paused
+    let res = release(&mut deps, AccrualOutcome::Credit);
+    assert_eq!(res.events[0].ty, "accrual_released");
+    let credited = pending(&deps);
+    assert_eq!(credited, before + accrual);
+    assert_eq!(withheld(&deps), None);
+
+    // confirmed: forfeited to the treasury
+    pause(&mut deps).unwrap();
+    transfer(&mut deps);
+    let accrual = withheld(&deps).unwrap();
+    let treasury = balance_of(&deps, TREASURY);
+    let res = release(&mut deps, AccrualOutcome::Forfeit);
+    assert!(res.events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "outcome" && attr.value == "forfeit"));
+    assert_eq!(balance_of(&deps, TREASURY), treasury + accrual);
+    assert_eq!(pending(&deps), credited);
+}
+
+#[test]
+fn transfers_emit_tax_events_and_tax_stats() {
+    use crate::msg::TaxStatsResponse;
+
+    let mut deps = setup(&[("addr0000", 10_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let tax_event = |res: &cosmwasm_std::Response| -> Vec<(String, String)> {
+        res.events
+            .iter()
+            .find(|event| event.ty == "tax")
+            .unwrap()
+            .attributes
+            .iter()
+            .map(|attr| (attr.key.clone(), attr.value.clone()))
+            .collect()
+    };
+    let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(1_000),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        tax_event(&res),
+        pairs(&[
+            ("from", "addr0000"),
+            ("to", "addr0001"),
+            ("amount", "1000"),
+            ("tax_amount", "100"),
+            ("burn_amount", "0"),
+            ("reflection_amount", "50"),
+            ("effective_rate", "0.1"),
+            ("tax_exempt", "false"),
+        ])
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetWhitelist {
+            user: "addr0002".to_string(),
+            enable: true,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Send {
+            contract: "addr0002".to_string(),
+            amount: Uint128::new(500),
+            msg: Binary::default(),
+            min_receive: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        tax_event(&res),
+        pairs(&[
+            ("from", "addr0000"),
+            ("to", "addr0002"),
+            ("amount", "500"),
+            ("tax_amount", "0"),
+            ("burn_amount", "0"),
+            ("reflection_amount", "0"),
+            ("effective_rate", "0"),
+            ("tax_exempt", "true"),
+        ])
+    );
+
+    let res: TaxStatsResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TaxStats {}).unwrap()).unwrap();
+    assert_eq!(res.stats.since, mock_env().block.time.seconds());
+    assert_eq!(res.stats.taxed_transfers, 1);
+    assert_eq!(res.stats.exempt_transfers, 1);
+    assert_eq!(res.stats.taxed_volume, Uint128::new(1_000));
+    assert_eq!(res.stats.total_tax, Uint128::new(100));
+    assert_eq!(res.stats.total_burned, Uint128::zero());
+    assert_eq!(res.stats.total_reflected, Uint128::new(50));
+    assert_eq!(res.effective_rate, Decimal::percent(10));
+}
+
+#[test]
+fn timed_messages_run_only_within_their_height_window() {
+    use crate::msg::ExecutionBounds;
+
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let height = mock_env().block.height;
+    let timed = |bounds: ExecutionBounds| ExecuteMsg::Timed {
+        inner: Box::new(ExecuteMsg::Transfer {
+            recipient: "addr0001".to_string(),
+            amount: Uint128::new(100),
+            min_receive: None,
+        }),
+        bounds,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        timed(ExecutionBounds {
+            not_before_height: Some(height + 1),
+            not_after_height: None,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Generic error: Not executable before height {}, current height is {}",
+            height + 1,
+            height
+        )
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        timed(ExecutionBounds {
+            not_before_height: None,
+            not_after_height: Some(height - 1),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Generic error: Not executable after height {}, current height is {}",
+            height - 1,
+            height
+        )
+    );
+
+    // both ends are included
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        timed(ExecutionBounds {
+            not_before_height: Some(height),
+            not_after_height: Some(height),
+        }),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0].value, "transfer");
+    assert_eq!(balance_of(&deps, "addr0001"), Uint128::new(100));
+
+    // the inner message is authorized as if it was sent on its own
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Timed {
+            inner: Box::new(ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::new(100),
+            }),
+            bounds: ExecutionBounds::default(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+}
+
+#[test]
+fn reflection_summary_combines_what_token_lists_show() {
+    use crate::msg::ReflectionSummaryResponse;
+
+    let mut deps = setup(&[("addr0000", 1_000)]);
+    let summary = |deps: &MockDeps| -> ReflectionSummaryResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ReflectionSummary {}).unwrap())
+            .unwrap()
+    };
+    let initial = summary(&deps);
+    assert_eq!(initial.symbol, "RFL");
+    assert_eq!(initial.decimals, 6);
+    assert!(initial.trading_enabled);
+    assert!(!initial.immutable);
+
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        // counted once
+        ExecuteMsg::SetWhitelist {
+            user: "addr0001".to_string(),
+            enable: true,
+        },
+        ExecuteMsg::SetFeeExempt {
+            address: "addr0001".to_string(),
+            exempt: true,
+        },
+        ExecuteMsg::SetFeeExempt {
+            address: "addr0002".to_string(),
+            exempt: true,
+        },
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+        ExecuteMsg::RenounceOwnership {
+            confirm: MOCK_CONTRACT_ADDR.to_string(),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian0000", &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        summary(&deps),
+        ReflectionSummaryResponse {
+            symbol: "RFL".to_string(),
+            decimals: 6,
+            buy_tax: Decimal::percent(10),
+            sell_tax: Decimal::percent(10),
+            transfer_tax: Decimal::percent(10),
+            exemptions: initial.exemptions + 2,
+            trading_enabled: false,
+            immutable: true,
+        }
+    );
+}
+
+#[test]
+fn tax_strategies_choose_the_rate_of_each_transfer() {
+    use crate::msg::TaxStrategyResponse;
+    use crate::state::{TaxBracket, TaxStrategyConfig, VolumeTier};
+
+    let mut deps = setup(&[("addr0000", 100_000)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let set_strategy = |deps: &mut MockDeps, strategy: TaxStrategyConfig| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetTaxStrategy { strategy },
+        )
+    };
+    // net amount received by addr0001
+    let transfer = |deps: &mut MockDeps, env: Env, amount: u128| -> Uint128 {
+        let before = balance_of(deps, "addr0001");
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+        balance_of(deps, "addr0001") - before
+    };
+
+    let err = set_strategy(
+        &mut deps,
+        TaxStrategyConfig::Bracketed {
+            brackets: vec![
+                TaxBracket {
+                    min_amount: Uint128::new(10_000),
+                    rate: Decimal::percent(20),
+                },
+                TaxBracket {
+                    min_amount: Uint128::new(1_000),
+                    rate: Decimal::percent(15),
+                },
+            ],
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("strictly increasing min_amount"));
+    let brackets = TaxStrategyConfig::Bracketed {
+        brackets: vec![TaxBracket {
+            min_amount: Uint128::new(10_000),
+            rate: Decimal::percent(20),
+        }],
+    };
+    set_strategy(&mut deps, brackets.clone()).unwrap();
+    assert_eq!(transfer(&mut deps, mock_env(), 1_000), Uint128::new(900));
+    assert_eq!(transfer(&mut deps, mock_env(), 10_000), Uint128::new(8_000));
+    let res: TaxStrategyResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TaxStrategy {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        TaxStrategyResponse {
+            strategy: brackets,
+            global_rate: Decimal::percent(10),
+            current_rate: Decimal::percent(10),
+        }
+    );
+
+    // a launch tax of 30% decaying to the global rate over 100 seconds, halfway through
+    set_strategy(
+        &mut deps,
+        TaxStrategyConfig::Decaying {
+            start_rate: Decimal::percent(30),
+            start: mock_env().block.time.seconds(),
+            duration: 100,
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(50);
+    assert_eq!(transfer(&mut deps, env.clone(), 1_000), Uint128::new(800));
+    env.block.time = env.block.time.plus_seconds(50);
+    assert_eq!(transfer(&mut deps, env, 1_000), Uint128::new(900));
+
+    // senders past 5_000 tokens in the day pay 2%
+    set_strategy(
+        &mut deps,
+        TaxStrategyConfig::VolumeBased {
+            window: DAY,
+            tiers: vec![VolumeTier {
+                min_volume: Uint128::new(5_000),
+                rate: Decimal::percent(2),
+            }],
+        },
+    )
+    .unwrap();
+    // untaxed legs do not count towards the volume
+    for msg in [
+        ExecuteMsg::SetWhitelist {
+            user: "addr0002".to_string(),
+            enable: true,
+        },
+        ExecuteMsg::Transfer {
+            recipient: "addr0002".to_string(),
+            amount: Uint128::new(5_000),
+            min_receive: None,
+        },
+    ] {
+        let sender = match msg {
+            ExecuteMsg::Transfer { .. } => "addr0000",
+            _ => ADMIN,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+    }
+    assert_eq!(transfer(&mut deps, mock_env(), 1_000), Uint128::new(900));
+    assert_eq!(transfer(&mut deps, mock_env(), 4_000), Uint128::new(3_600));
+    assert_eq!(transfer(&mut deps, mock_env(), 1_000), Uint128::new(980));
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    assert_eq!(transfer(&mut deps, env, 1_000), Uint128::new(900));
+
+    let err = set_strategy(
+        &mut deps,
+        TaxStrategyConfig::Decaying {
+            start_rate: Decimal::percent(101),
+            start: 0,
+            duration: 100,
+        },
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Strategy rates must be <= max_tax_rate 1"));
+    set_strategy(&mut deps, TaxStrategyConfig::Flat {}).unwrap();
+    assert_eq!(transfer(&mut deps, mock_env(), 10_000), Uint128::new(9_000));
+}
+
+#[test]
+fn tax_strategies_stay_within_the_tax_step() {
+    use crate::state::{TaxBracket, TaxStrategyConfig};
+
+    let mut deps = setup_with(&[("addr0000", 100_000)], |msg| {
+        msg.max_tax_step = Some(Decimal::percent(10))
+    });
+    let admin = |deps: &mut MockDeps, env: Env, msg: ExecuteMsg| {
+        execute(deps.as_mut(), env, mock_info(ADMIN, &[]), msg)
+    };
+    let bracketed = |rate: u64| ExecuteMsg::SetTaxStrategy {
+        strategy: TaxStrategyConfig::Bracketed {
+            brackets: vec![TaxBracket {
+                min_amount: Uint128::zero(),
+                rate: Decimal::percent(rate),
+            }],
+        },
+    };
+    admin(
+        &mut deps,
+        mock_env(),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+
+    // measured against the rate of a day ago, like the global rate
+    let err = admin(&mut deps, mock_env(), bracketed(15)).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("global_rate must stay within 0 and 0.1 until the step period elapses"));
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    admin(&mut deps, env.clone(), bracketed(20)).unwrap();
+    admin(&mut deps, env.clone(), bracketed(25)).unwrap_err();
+
+    // the strategy pins the reference, it cannot creep by a step a day
+    env.block.time = env.block.time.plus_seconds(DAY - 1);
+    let err = admin(&mut deps, env, bracketed(30)).unwrap_err();
+    assert!(err.to_string().contains("within 0 and 0.2"));
+}
+
+#[test]
+fn invariant_violation_pauses_the_token() {
+    use crate::state::REFLECTION_INDEX;
+
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let transfer = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+    };
+    let verify = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper0000", &[]),
+            ExecuteMsg::VerifyInvariants {},
+        )
+        .unwrap()
+    };
+    transfer(&mut deps).unwrap();
+    transfer(&mut deps).unwrap();
+
+    // reflections owed are backed by the balance of the token contract
+    let res = verify(&mut deps);
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "violations" && attr.value == "0"));
+    assert!(res.events.is_empty());
+    transfer(&mut deps).unwrap();
+
+    // an excluded supply out of line with the excluded balances
+    let mut index = REFLECTION_INDEX.load(deps.as_ref().storage).unwrap();
+    index.excluded_supply += Uint128::new(1);
+    REFLECTION_INDEX
+        .save(deps.as_mut().storage, &index)
+        .unwrap();
+    let res = verify(&mut deps);
+    assert_eq!(res.events[0].ty, "invariant_violation");
+    assert!(res.events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "invariant" && attr.value == "eligible_supply"));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "paused" && attr.value == "true"));
+    let err = transfer(&mut deps).unwrap_err();
+    assert!(err.to_string().contains("Token is paused"));
+
+    // still checked while paused, without pausing twice
+    let res = verify(&mut deps);
+    assert!(!res.events.is_empty());
+    assert!(!res.attributes.iter().any(|attr| attr.key == "paused"));
+}
+
+#[test]
+fn distribution_is_backfilled_after_migration() {
+    use crate::state::{
+        DISTRIBUTION_TRACKED_SINCE, HOLDER_BUCKETS, HOLDER_BUCKET_BALANCES, TOP_HOLDERS,
+    };
+
+    let mut deps = setup(&[("addr0000", 5), ("addr0001", 50), ("addr0002", 5000)]);
+    let distribution = |deps: &MockDeps| -> HolderDistributionResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HolderDistribution { buckets: None },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let backfill = |deps: &mut MockDeps, limit: u32| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper0000", &[]),
+            ExecuteMsg::BackfillDistribution { limit: Some(limit) },
+        )
+    };
+    let transfer = |deps: &mut MockDeps, from: &str, to: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(from, &[]),
+            ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+    assert_eq!(
+        distribution(&deps).tracked_since,
+        Some(mock_env().block.height)
+    );
+    let err = backfill(&mut deps, 10).unwrap_err();
+    assert!(err.to_string().contains("not being backfilled"));
+
+    // a token from before the counters were kept
+    DISTRIBUTION_TRACKED_SINCE.remove(deps.as_mut().storage);
+    for decade in 0..4 {
+        HOLDER_BUCKETS.remove(deps.as_mut().storage, decade);
+        HOLDER_BUCKET_BALANCES.remove(deps.as_mut().storage, decade);
+    }
+    TOP_HOLDERS.remove(deps.as_mut().storage);
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Upgrade {
+            msg: "".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "distribution_backfill"));
+    assert_eq!(distribution(&deps).tracked_since, None);
+    assert_eq!(distribution(&deps).total_holders, 0);
+
+    // addr0000 is counted, its transfer to addr0003 moves it as it goes
+    backfill(&mut deps, 1).unwrap();
+    transfer(&mut deps, "addr0000", "addr0003", 5);
+    transfer(&mut deps, "addr0002", "addr0001", 4950);
+    assert_eq!(distribution(&deps).total_holders, 0);
+    let res = backfill(&mut deps, 10).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "complete" && attr.value == "true"));
+
+    let res = distribution(&deps);
+    assert_eq!(res.tracked_since, Some(mock_env().block.height));
+    assert_eq!(res.total_holders, 3);
+    assert_eq!(
+        res.buckets,
+        vec![
+            bucket(1, Some(10), 1),
+            bucket(10, Some(100), 1),
+            bucket(100, Some(1000), 0),
+            bucket(1000, None, 1),
+        ]
+    );
+    let res: ConcentrationResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Concentration {}).unwrap()).unwrap();
+    assert_eq!(res.top_holders[0].address, "addr0001");
+    assert_eq!(res.top_holders.len(), 3);
+}
+
+#[test]
+fn migration_excludes_the_fee_holders_from_reflections() {
+    use crate::reflections::eligible_supply;
+    use crate::state::{REFLECTION_EXCLUDED, REFLECTION_INDEX};
+    use cosmwasm_std::Addr;
+
+    let mut deps = setup_with(&[("addr0000", 600_000), ("addr0001", 400_000)], |msg| {
+        msg.reflection_mode = Some(ReflectionMode::Claim)
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+    )
+    .unwrap();
+    let transfer = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+    let pending = |deps: &MockDeps, address: &str| -> Uint128 {
+        let res: PendingReflectionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pending
+    };
+
+    // a token from before the exclusions, the fee holders take a share of the reflections
+    for address in [MOCK_CONTRACT_ADDR, TREASURY] {
+        REFLECTION_EXCLUDED.remove(deps.as_mut().storage, &Addr::unchecked(address));
+    }
+    let mut index = REFLECTION_INDEX.load(deps.as_ref().storage).unwrap();
+    index.excluded_supply = Uint128::zero();
+    REFLECTION_INDEX
+        .save(deps.as_mut().storage, &index)
+        .unwrap();
+    transfer(&mut deps);
+    transfer(&mut deps);
+    let absorbed = pending(&deps, MOCK_CONTRACT_ADDR) + pending(&deps, TREASURY);
+    assert!(!absorbed.is_zero());
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Upgrade {
+            msg: "".to_string(),
+        },
+    )
+    .unwrap();
+    let held = balance_of(&deps, "addr0000") + balance_of(&deps, "addr0001");
+    assert_eq!(eligible_supply(deps.as_ref().storage).unwrap(), held);
+    assert_eq!(
+        REFLECTION_INDEX
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .excluded_supply,
+        balance_of(&deps, MOCK_CONTRACT_ADDR) + balance_of(&deps, TREASURY)
+    );
+
+    // the holders get what the fee holders absorbed with the next reflections
+    let before = pending(&deps, "addr0000") + pending(&deps, "addr0001");
+    transfer(&mut deps);
+    assert_eq!(pending(&deps, TREASURY), Uint128::zero());
+    let accrued = pending(&deps, "addr0000") + pending(&deps, "addr0001") - before;
+    assert!(Uint128::new(5_000) + absorbed - accrued <= Uint128::new(1));
+}
+
+#[test]
+fn frozen_and_dead_balances_earn_no_reflections() {
+    use crate::reflections::eligible_supply;
+
+    let mut deps = setup_with(
+        &[
+            ("addr0000", 500_000),
+            ("addr0001", 300_000),
+            ("dead0000", 200_000),
+        ],
+        |msg| msg.reflection_mode = Some(ReflectionMode::Claim),
+    );
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        ExecuteMsg::SetGuardian {
+            guardian: Some("guardian0000".to_string()),
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    let transfer = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::new(100_000),
+                min_receive: None,
+            },
+        )
+        .unwrap();
+    };
+    let pending = |deps: &MockDeps, address: &str| -> Uint128 {
+        let res: PendingReflectionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PendingReflections {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.pending
+    };
+    let guardian = |deps: &mut MockDeps, msg: ExecuteMsg| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian0000", &[]),
+            msg,
+        )
+        .unwrap();
+    };
+    let holders = |deps: &MockDeps, addresses: &[&str]| -> Uint128 {
+        addresses
+            .iter()
+            .map(|address| balance_of(deps, address))
+            .sum()
+    };
+
+    let exclude_dead = |deps: &mut MockDeps, sender: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ExcludeDeadAddress {
+                address: "dead0000".to_string(),
+            },
+        )
+    };
+    exclude_dead(&mut deps, "addr0000").unwrap_err();
+    exclude_dead(&mut deps, ADMIN).unwrap();
+    let err = exclude_dead(&mut deps, ADMIN).unwrap_err();
+    assert!(err.to_string().contains("already a dead address"));
+
+    transfer(&mut deps);
+    let accrued = pending(&deps, "addr0001");
+    assert!(!accrued.is_zero());
+    assert_eq!(pending(&deps, "dead0000"), Uint128::zero());
+    guardian(
+        &mut deps,
+        ExecuteMsg::Freeze {
+            address: "addr0001".to_string(),
+        },
+    );
+    assert_eq!(
+        eligible_supply(deps.as_ref().storage).unwrap(),
+        holders(&deps, &["addr0000", "addr0002"])
+    );
+
+    // nothing accrues while frozen, what it accrued before is kept
+    transfer(&mut deps);
+    guardian(
+        &mut deps,
+        ExecuteMsg::Unfreeze {
+            address: "addr0001".to_string(),
+        },
+    );
+    assert_eq!(pending(&deps, "addr0001"), accrued);
+    assert_eq!(
+        eligible_supply(deps.as_ref().storage).unwrap(),
+        holders(&deps, &["addr0000", "addr0001", "addr0002"])
+    );
+    transfer(&mut deps);
+    assert!(pending(&deps, "addr0001") > accrued);
+    assert_eq!(pending(&deps, "dead0000"), Uint128::zero());
+}
+
+#[test]
+fn allowance_transfers_are_taxed_like_direct_transfers() {
+    let mut deps = setup(&[("addr0000", 500_000), ("addr0001", 500_000)]);
+    for msg in [
+        ExecuteMsg::SetTaxRate {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::zero(),
+            antiwhale_rate: Decimal::one(),
+        },
+        // a whitelisted spender does not exempt the tokens of the owners it moves
+        ExecuteMsg::SetWhitelist {
+            user: "spender0000".to_string(),
+            enable: true,
+        },
+        ExecuteMsg::SetWhitelist {
+            user: "addr0001".to_string(),
+            enable: true,
+        },
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    for owner in ["addr0000", "addr0001"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner, &[]),
+            ExecuteMsg::IncreaseAllowance {
+                spender: "spender0000".to_string(),
+                amount: Uint128::new(100_000),
+                expires: None,
+            },
+        )
+        .unwrap();
+    }
+
+    let received = |deps: &mut MockDeps, owner: &str, spender: Option<&str>| -> Uint128 {
+        let before = balance_of(deps, "addr0002");
+        let (sender, msg) = match spender {
+            Some(spender) => (
+                spender,
+                ExecuteMsg::TransferFrom {
+                    owner: owner.to_string(),
+                    recipient: "addr0002".to_string(),
+                    amount: Uint128::new(10_000),
+                    min_receive: None,
+                },
+            ),
+            None => (
+                owner,
+                ExecuteMsg::Transfer {
+                    recipient: "addr0002".to_string(),
+                    amount: Uint128::new(10_000),
+                    min_receive: None,
+                },
+            ),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+        balance_of(deps, "addr0002") - before
+    };
+
+    for owner in ["addr0000", "addr0001"] {
+        assert_eq!(
+            received(&mut deps, owner, Some("spender0000")),
+            received(&mut deps, owner, None),
+        );
+    }
+    assert_eq!(
+        received(&mut deps, "addr0000", Some("spender0000")),
+        Uint128::new(9_000)
+    );
+    assert_eq!(
+        received(&mut deps, "addr0001", Some("spender0000")),
+        Uint128::new(10_000)
+    );
+}
+
+#[test]
+fn instantiate_msg_validation_reports_the_reason() {
+    use cosmwasm_std::testing::MockApi;
+    use cw20::MinterResponse;
+    use dojoswap::token::InstantiateMsgError;
+
+    let coin = |address: &str, amount: u128| Cw20Coin {
+        address: address.to_string(),
+        amount: Uint128::new(amount),
+    };
+    let msg = |initial_balances: Vec<Cw20Coin>, cap: Option<u128>| InstantiateMsg {
+        name: "Reflection".to_string(),
+        symbol: "RFL".to_string(),
+        decimals: 6,
+        treasury_code_id: 1,
+        cw20_code_id: None,
+        initial_balances,
+        admin: ADMIN.to_string(),
+        router: "router0000".to_string(),
+        mint: cap.map(|cap| MinterResponse {
+            minter: ADMIN.to_string(),
+            cap: Some(Uint128::new(cap)),
+        }),
+        marketing: None,
+        max_tax_rate: None,
+        max_tax_step: None,
+        reflection_mode: None,
+        max_tx_amount: None,
+        max_wallet_amount: None,
+        vesting: None,
+    };
+    let api = MockApi::default();
+
+    assert_eq!(
+        msg(vec![coin("addr0000", 1), coin("addr0000", 2)], None).validate(&api),
+        Err(InstantiateMsgError::DuplicateAddress {
+            address: "addr0000".to_string()
+        })
+    );
+    assert_eq!(
+        msg(vec![coin("addr0000", 1), coin("addr0001", 2)], Some(2)).validate(&api),
+        Err(InstantiateMsgError::CapExceeded {
+            supply: Uint128::new(3),
+            cap: Uint128::new(2)
+        })
+    );
+    let mut taxed = msg(vec![], None);
+    taxed.max_tax_rate = Some(Decimal::percent(101));
+    assert_eq!(
+        taxed.validate(&api),
+        Err(InstantiateMsgError::InvalidTaxLimits {})
+    );
+    assert_eq!(
+        msg(vec![coin("addr0000", 1)], Some(1)).validate(&api),
+        Ok(())
+    );
+}
+
+#[test]
+fn legacy_cw20_code_id_is_flagged_deprecated() {
+    let instantiate_with = |treasury_code_id, cw20_code_id| {
+        let mut deps = mock_dependencies();
+        let mut msg: InstantiateMsg = from_json(
+            br#"{"name":"Reflection","symbol":"RFL","decimals":6,"initial_balances":[],
+            "admin":"admin0000","router":"router0000","mint":null,"marketing":null}"#,
+        )
+        .unwrap();
+        msg.treasury_code_id = treasury_code_id;
+        msg.cw20_code_id = cw20_code_id;
+        instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+    };
+
+    let res = instantiate_with(0, Some(4)).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "deprecated" && attr.value == "cw20_code_id"));
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { code_id, .. }) => assert_eq!(*code_id, 4),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    let res = instantiate_with(4, None).unwrap();
+    assert!(!res.attributes.iter().any(|attr| attr.key == "deprecated"));
+
+    let err = instantiate_with(0, None).unwrap_err();
+    assert!(err.to_string().contains("treasury_code_id is required"));
+    let err = instantiate_with(4, Some(4)).unwrap_err();
+    assert!(err.to_string().contains("set only one"));
+
+    let deps = setup(&[]);
+    let api: ApiVersionResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::ApiVersion {}).unwrap()).unwrap();
+    assert!(api
+        .deprecated
+        .iter()
+        .any(|deprecated| deprecated.variant == "cw20_code_id"
+            && deprecated.replacement == "treasury_code_id"));
+}
@@ -0,0 +1,26 @@
+use cosmwasm_std::{Env, StdError};
+use cw20_base::ContractError;
+
+use crate::msg::ExecutionBounds;
+
+/// Fails unless the current block height is within `bounds`
+pub fn ensure_within_bounds(env: &Env, bounds: &ExecutionBounds) -> Result<(), ContractError> {
+    let height = env.block.height;
+    if let Some(not_before) = bounds.not_before_height {
+        if height < not_before {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Not executable before height {}, current height is {}",
+                not_before, height
+            ))));
+        }
+    }
+    if let Some(not_after) = bounds.not_after_height {
+        if height > not_after {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Not executable after height {}, current height is {}",
+                not_after, height
+            ))));
+        }
+    }
+    Ok(())
+}
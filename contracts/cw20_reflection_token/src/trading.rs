@@ -0,0 +1,148 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, QuerierWrapper, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20_base::ContractError;
+use dojoswap::asset::AssetInfo;
+use dojoswap::pair::{PoolResponse, QueryMsg as PairQueryMsg};
+
+use crate::contract::{ensure_admin, WHITELIST};
+use crate::msg::TradingGateResponse;
+use crate::state::{TradingGate, TRADING_GATE};
+
+/// Holds trading with `pair` until its reserves reach `min_token_reserve` tokens and
+/// `min_quote_reserve` of the quote asset. None removes the gate while trading is not enabled yet
+pub fn set_trading_gate(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair: Option<String>,
+    min_token_reserve: Uint128,
+    min_quote_reserve: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    if let Some(gate) = TRADING_GATE.may_load(deps.storage)? {
+        if gate.enabled_at.is_some() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Trading is already enabled",
+            )));
+        }
+    }
+    match pair {
+        Some(pair) => {
+            let pair = deps.api.addr_validate(&pair)?;
+            TRADING_GATE.save(
+                deps.storage,
+                &TradingGate {
+                    pair,
+                    min_token_reserve,
+                    min_quote_reserve,
+                    enabled_at: None,
+                },
+            )?;
+        }
+        None => TRADING_GATE.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_trading_gate")
+        .add_attribute("min_token_reserve", min_token_reserve)
+        .add_attribute("min_quote_reserve", min_quote_reserve))
+}
+
+/// Permissionless crank enabling trading once the pair is seeded, transfers with the pair try the
+/// same check on their own
+pub fn execute_enable_trading(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let gate = TRADING_GATE
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No trading gate set"))?;
+    if gate.enabled_at.is_some() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Trading is already enabled",
+        )));
+    }
+    try_enable_trading(deps.storage, &deps.querier, &env, gate)?;
+
+    Ok(Response::new().add_attribute("action", "enable_trading"))
+}
+
+/// Rejects transfers with the gated pair until trading is enabled, the first transfer seeing the
+/// pair seeded enables it. Whitelisted parties, which seed the liquidity, are never held
+pub fn ensure_trading_enabled(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    from: &str,
+    to: &str,
+) -> Result<(), ContractError> {
+    let gate = match TRADING_GATE.may_load(storage)? {
+        Some(gate) if gate.enabled_at.is_none() => gate,
+        _ => return Ok(()),
+    };
+    if gate.pair != from && gate.pair != to {
+        return Ok(());
+    }
+    for party in [from, to] {
+        if WHITELIST
+            .may_load(storage, party.to_string())?
+            .unwrap_or_default()
+        {
+            return Ok(());
+        }
+    }
+    try_enable_trading(storage, querier, env, gate)
+}
+
+fn try_enable_trading(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    mut gate: TradingGate,
+) -> Result<(), ContractError> {
+    let (token_reserve, quote_reserve) = pair_reserves(querier, &env.contract.address, &gate.pair)?;
+    if token_reserve < gate.min_token_reserve || quote_reserve < gate.min_quote_reserve {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Trading not enabled: pair reserves {} / {} below {} / {}",
+            token_reserve, quote_reserve, gate.min_token_reserve, gate.min_quote_reserve
+        ))));
+    }
+    gate.enabled_at = Some(env.block.time.seconds());
+    TRADING_GATE.save(storage, &gate)?;
+    Ok(())
+}
+
+/// (token, quote) reserves of `pair`
+fn pair_reserves(
+    querier: &QuerierWrapper,
+    contract: &Addr,
+    pair: &Addr,
+) -> StdResult<(Uint128, Uint128)> {
+    let pool: PoolResponse = querier.query_wasm_smart(pair, &PairQueryMsg::Pool {})?;
+    let is_token = |info: &AssetInfo| match info {
+        AssetInfo::Token { contract_addr } => contract_addr == contract.as_str(),
+        AssetInfo::NativeToken { .. } => false,
+    };
+    match &pool.assets {
+        [token, quote] | [quote, token] if is_token(&token.info) && !is_token(&quote.info) => {
+            Ok((token.amount, quote.amount))
+        }
+        _ => Err(StdError::generic_err("Pair does not trade this token")),
+    }
+}
+
+pub fn query_trading_gate(deps: Deps) -> StdResult<TradingGateResponse> {
+    let gate = TRADING_GATE.may_load(deps.storage)?;
+    let enabled_at = gate.as_ref().and_then(|gate| gate.enabled_at);
+    Ok(TradingGateResponse {
+        pair: gate.as_ref().map(|gate| gate.pair.to_string()),
+        min_token_reserve: gate
+            .as_ref()
+            .map(|gate| gate.min_token_reserve)
+            .unwrap_or_default(),
+        min_quote_reserve: gate
+            .as_ref()
+            .map(|gate| gate.min_quote_reserve)
+            .unwrap_or_default(),
+        enabled: gate.is_none() || enabled_at.is_some(),
+        enabled_at,
+    })
+}
@@ -0,0 +1,85 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Deps, DepsMut, Empty, MessageInfo, Response, StdResult, Storage, SubMsg,
+    Uint128, WasmMsg,
+};
+use cw20_base::ContractError;
+
+use crate::contract::ensure_admin;
+use crate::msg::{BurnTrophyResponse, Cw721ExecuteMsg};
+use crate::state::{BurnTrophy, BURN_TROPHY, TROPHIES_MINTED};
+
+/// Reply id of the trophy mints, their failure never reverts the burn
+pub const BURN_TROPHY_REPLY_ID: u64 = 2;
+
+/// Sets the cw721 collection minting a trophy to whoever burns at least `threshold` tokens in a
+/// single message, None disables trophies. The token must be allowed to mint on the collection
+pub fn set_burn_trophy(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: Option<String>,
+    threshold: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match collection {
+        Some(collection) => {
+            if threshold.is_zero() {
+                return Err(ContractError::InvalidZeroAmount {});
+            }
+            let collection = deps.api.addr_validate(&collection)?;
+            BURN_TROPHY.save(
+                deps.storage,
+                &BurnTrophy {
+                    collection,
+                    threshold,
+                },
+            )?;
+        }
+        None => BURN_TROPHY.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_burn_trophy")
+        .add_attribute("threshold", threshold))
+}
+
+/// Mint of the trophy earned by burning `amount`, if any
+pub fn burn_trophy(
+    storage: &mut dyn Storage,
+    burner: &Addr,
+    amount: Uint128,
+) -> StdResult<Option<SubMsg>> {
+    let trophy = match BURN_TROPHY.may_load(storage)? {
+        Some(trophy) if amount >= trophy.threshold => trophy,
+        _ => return Ok(None),
+    };
+    let minted = TROPHIES_MINTED.may_load(storage)?.unwrap_or_default() + 1;
+    TROPHIES_MINTED.save(storage, &minted)?;
+
+    let mint = WasmMsg::Execute {
+        contract_addr: trophy.collection.to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+            token_id: format!("burn-{}", minted),
+            owner: burner.to_string(),
+            token_uri: None,
+            extension: Empty {},
+        })?,
+        funds: vec![],
+    };
+    Ok(Some(SubMsg::reply_on_error(mint, BURN_TROPHY_REPLY_ID)))
+}
+
+/// Keeps the burn when the collection rejects the mint
+pub fn burn_trophy_failed(error: String) -> Response {
+    Response::new()
+        .add_attribute("action", "burn_trophy_failed")
+        .add_attribute("error", error)
+}
+
+pub fn query_burn_trophy(deps: Deps) -> StdResult<BurnTrophyResponse> {
+    let trophy = BURN_TROPHY.may_load(deps.storage)?;
+    Ok(BurnTrophyResponse {
+        collection: trophy.as_ref().map(|trophy| trophy.collection.to_string()),
+        threshold: trophy.map(|trophy| trophy.threshold).unwrap_or_default(),
+        minted: TROPHIES_MINTED.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Deps, Env, Order, StdError, StdResult, Storage, Uint128};
+use cw20::Cw20Coin;
+
+use crate::msg::{VestingInfoResponse, VestingSchedule};
+use crate::state::{Vesting, VESTING, VESTING_CLOCK};
+
+impl VestingSchedule {
+    /// Rejects schedules which never release anything or release in steps that do not add up
+    pub fn validate(&self) -> StdResult<()> {
+        if self.amount.is_zero() {
+            return Err(StdError::generic_err("Vesting amount must be above 0"));
+        }
+        if self.duration == 0 || self.period == 0 {
+            return Err(StdError::generic_err(
+                "Vesting duration and period must be above 0",
+            ));
+        }
+        if self.cliff > self.duration || self.period > self.duration {
+            return Err(StdError::generic_err(
+                "Vesting cliff and period must be at most the duration",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks the schedules against the initial balances: one schedule per address, locking at most
+/// what the address is created with
+pub fn validate_vesting(
+    vesting: &[VestingSchedule],
+    initial_balances: &[Cw20Coin],
+) -> StdResult<()> {
+    let mut initial = BTreeMap::new();
+    for row in initial_balances {
+        *initial
+            .entry(row.address.as_str())
+            .or_insert(Uint128::zero()) += row.amount;
+    }
+    for (i, schedule) in vesting.iter().enumerate() {
+        schedule.validate()?;
+        if vesting[..i]
+            .iter()
+            .any(|other| other.address == schedule.address)
+        {
+            return Err(StdError::generic_err(format!(
+                "More than one vesting schedule for {}",
+                schedule.address
+            )));
+        }
+        if schedule.amount
+            > initial
+                .get(schedule.address.as_str())
+                .copied()
+                .unwrap_or_default()
+        {
+            return Err(StdError::generic_err(format!(
+                "Vesting of {} exceeds its initial balance",
+                schedule.address
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl Vesting {
+    /// Part of the schedule released at `time`: nothing before the cliff, then whole periods pro
+    /// rata to the duration, everything once the duration elapsed
+    pub fn vested(&self, time: u64) -> Uint128 {
+        let elapsed = time.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return Uint128::zero();
+        }
+        if elapsed >= self.duration {
+            return self.amount;
+        }
+        let released = elapsed - elapsed % self.period;
+        self.amount.multiply_ratio(released, self.duration)
+    }
+
+    pub fn locked(&self, time: u64) -> Uint128 {
+        self.amount - self.vested(time)
+    }
+
+    /// Time of the next release after `time`, None once everything is released
+    pub fn next_release(&self, time: u64) -> Option<u64> {
+        let elapsed = time.saturating_sub(self.start);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let next = (elapsed / self.period + 1) * self.period;
+        Some(self.start + next.max(self.cliff).min(self.duration))
+    }
+}
+
+/// Locks the schedules from now, called at instantiation once the initial balances are created
+pub fn save_vesting(
+    storage: &mut dyn Storage,
+    env: &Env,
+    vesting: Vec<(Addr, VestingSchedule)>,
+) -> StdResult<()> {
+    for (address, schedule) in vesting {
+        VESTING.save(
+            storage,
+            &address,
+            &Vesting {
+                amount: schedule.amount,
+                start: env.block.time.seconds(),
+                cliff: schedule.cliff,
+                duration: schedule.duration,
+                period: schedule.period,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Unlock tick: keeps the block time of the execution for `ensure_vested`, which runs where only
+/// the storage is at hand. Called at the start of every entry point while any schedule is left,
+/// like `take_due_snapshot`
+pub fn tick_vesting(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    if VESTING
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_none()
+    {
+        return Ok(());
+    }
+    VESTING_CLOCK.save(storage, &env.block.time.seconds())
+}
+
+/// Fails if a debit takes the balance of `address` from `old` to `new` below its locked amount,
+/// called from `after_balance_change`. Reflections are never locked: the schedule locks a fixed
+/// amount, whatever the balance grows by is spendable. Released schedules are removed
+pub fn ensure_vested(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    if new >= old {
+        return Ok(());
+    }
+    let vesting = match VESTING.may_load(storage, address)? {
+        Some(vesting) => vesting,
+        None => return Ok(()),
+    };
+    let now = VESTING_CLOCK.may_load(storage)?.unwrap_or(vesting.start);
+    let locked = vesting.locked(now);
+    if locked.is_zero() {
+        VESTING.remove(storage, address);
+        return Ok(());
+    }
+    if new < locked {
+        return Err(StdError::generic_err(format!(
+            "{} of the balance of {} is locked by its vesting schedule",
+            locked, address
+        )));
+    }
+    Ok(())
+}
+
+/// Amount of the balance of `address` locked at `env.block.time`
+pub fn locked_balance(storage: &dyn Storage, env: &Env, address: &Addr) -> StdResult<Uint128> {
+    Ok(VESTING
+        .may_load(storage, address)?
+        .map(|vesting| vesting.locked(env.block.time.seconds()))
+        .unwrap_or_default())
+}
+
+pub fn query_vesting_info(deps: Deps, env: Env, address: String) -> StdResult<VestingInfoResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let now = env.block.time.seconds();
+    let vesting = VESTING.may_load(deps.storage, &address)?;
+
+    Ok(VestingInfoResponse {
+        locked: vesting
+            .as_ref()
+            .map(|vesting| vesting.locked(now))
+            .unwrap_or_default(),
+        next_release: vesting
+            .as_ref()
+            .and_then(|vesting| vesting.next_release(now)),
+        vesting,
+    })
+}
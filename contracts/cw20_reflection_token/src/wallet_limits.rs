@@ -0,0 +1,166 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20_base::state::BALANCES;
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+
+use crate::bridge::is_bridge;
+use crate::contract::{ensure_admin, TREASURY};
+use crate::msg::{LimitExemptionsResponse, LimitsResponse, Role};
+use crate::roles::ensure_role;
+use crate::state::{WalletLimits, LIMITS_EXEMPT, WALLET_LIMITS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+impl WalletLimits {
+    /// Rejects zero limits, a limit is lifted by leaving it out
+    pub fn validate(&self) -> StdResult<()> {
+        if self.max_tx_amount.is_some_and(|amount| amount.is_zero())
+            || self
+                .max_wallet_amount
+                .is_some_and(|amount| amount.is_zero())
+        {
+            return Err(StdError::generic_err(
+                "max_tx_amount and max_wallet_amount must be above 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Saves the limits, or removes them when neither is set so the config hash is left unchanged
+pub fn save_limits(storage: &mut dyn Storage, limits: &WalletLimits) -> StdResult<()> {
+    if limits.max_tx_amount.is_none() && limits.max_wallet_amount.is_none() {
+        WALLET_LIMITS.remove(storage);
+        return Ok(());
+    }
+    WALLET_LIMITS.save(storage, limits)
+}
+
+/// Replaces the max transaction and max wallet amounts, None lifts a limit
+pub fn update_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_tx_amount: Option<Uint128>,
+    max_wallet_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let limits = WalletLimits {
+        max_tx_amount,
+        max_wallet_amount,
+    };
+    limits.validate()?;
+    save_limits(deps.storage, &limits)?;
+
+    let attribute = |amount: Option<Uint128>| amount.map(|amount| amount.to_string());
+    Ok(Response::new()
+        .add_attribute("action", "update_limits")
+        .add_attribute(
+            "max_tx_amount",
+            attribute(max_tx_amount).unwrap_or_default(),
+        )
+        .add_attribute(
+            "max_wallet_amount",
+            attribute(max_wallet_amount).unwrap_or_default(),
+        ))
+}
+
+/// Exempts `address` from both limits, e.g. the pair which needs to hold the liquidity
+pub fn set_limits_exempt(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    ensure_role(&deps, &info, Role::ExemptionManager)?;
+    let address = deps.api.addr_validate(&address)?;
+    if exempt {
+        LIMITS_EXEMPT.save(deps.storage, &address, &Empty {})?;
+    } else {
+        LIMITS_EXEMPT.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_limits_exempt")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+/// True for the exempted addresses, the token contract, the treasury and the bridge
+pub fn is_limits_exempt(storage: &dyn Storage, env: &Env, address: &str) -> StdResult<bool> {
+    Ok(address == env.contract.address.as_str()
+        || TREASURY.may_load(storage)?.as_deref() == Some(address)
+        || LIMITS_EXEMPT.has(storage, &Addr::unchecked(address))
+        || is_bridge(storage, address))
+}
+
+/// Called by the transfer handlers before the balances move: `amount` sent from `from` must be
+/// within the max transaction amount unless either side is exempt, and the balance of `to` once
+/// credited `received` within the max wallet amount unless it is exempt
+pub fn ensure_wallet_limits(
+    storage: &dyn Storage,
+    env: &Env,
+    from: &str,
+    to: &Addr,
+    amount: Uint128,
+    received: Uint128,
+) -> Result<(), ContractError> {
+    let limits = match WALLET_LIMITS.may_load(storage)? {
+        Some(limits) => limits,
+        None => return Ok(()),
+    };
+    let to_exempt = is_limits_exempt(storage, env, to.as_str())?;
+    if let Some(max_tx_amount) = limits.max_tx_amount {
+        if amount > max_tx_amount && !to_exempt && !is_limits_exempt(storage, env, from)? {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Transfer amount {} exceeds max_tx_amount {}",
+                amount, max_tx_amount
+            ))));
+        }
+    }
+    if let Some(max_wallet_amount) = limits.max_wallet_amount {
+        // a transfer to itself only loses the tax
+        let balance = BALANCES.may_load(storage, to)?.unwrap_or_default();
+        if from != to.as_str() && balance + received > max_wallet_amount && !to_exempt {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Balance of {} would exceed max_wallet_amount {}",
+                to, max_wallet_amount
+            ))));
+        }
+    }
+    Ok(())
+}
+
+pub fn query_limits(deps: Deps) -> StdResult<LimitsResponse> {
+    let limits = WALLET_LIMITS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(LimitsResponse {
+        max_tx_amount: limits.max_tx_amount,
+        max_wallet_amount: limits.max_wallet_amount,
+    })
+}
+
+pub fn query_limit_exemptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LimitExemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let addresses = LIMITS_EXEMPT
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| address.map(|address| address.to_string()))
+        .collect::<StdResult<_>>()?;
+
+    Ok(LimitExemptionsResponse { addresses })
+}
@@ -3,18 +3,31 @@ use std::ops::{Div, Mul, Sub};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, from_json, to_json_binary, Addr, Api, Binary, Decimal, Deps, DepsMut, Env, MessageInfo,
-    QuerierWrapper, QueryRequest, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
-    WasmQuery,
+    attr, coin, from_json, to_json_binary, Addr, Api, Attribute, Binary, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, MessageInfo, QuerierWrapper, QueryRequest, Reply, ReplyOn, Response, StdError,
+    StdResult, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg, WasmQuery,
 };
 use cw20::{BalanceResponse, Cw20ExecuteMsg};
 use dojoswap::pair::SimulationResponse;
 
 use cw2::set_contract_version;
 
+use crate::keepers::{query_keeper_stats, query_keepers, record_crank, set_keepers};
 use crate::msg::{
-    Cw20HookMsg, Cw20ReceiveMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, TokenQueryMsg,
+    Crank, Cw20HookMsg, Cw20ReceiveMsg, ExecuteMsg, InstantiateMsg, LiquiditySimulation,
+    LiquifySwap, MigrateMsg, QueryMsg, ReflectionSimulation, SimulateDistributeResponse,
+    TokenQueryMsg,
 };
+use crate::payroll::{
+    claim_payroll, flush_payroll, payroll_reserved, query_payroll, query_payroll_accrued,
+    set_payroll,
+};
+use crate::proceeds::{query_swap_ledger, start_swap, swap_replied, REFLECTION_SWAP_REPLY_ID};
+use crate::protection::{
+    protected, query_swap_protection, set_swap_protection, swap_skipped, PROTECTED_SWAP_REPLY_ID,
+};
+use crate::state::SWAP_PROTECTION;
+use crate::venue::{query_swap_venues, set_swap_venue, swap_assets, swap_venue};
 use cw20_base::ContractError;
 use cw_storage_plus::Item;
 use dojoswap::asset::{Asset, AssetInfo, PairInfo};
@@ -77,8 +90,24 @@ pub fn execute(
             set_min_liquify_amt(deps, env, info, min_liquify_amt)
         }
         // ExecuteMsg::SetToken { address } => set_token(deps, env, info, address),
-        ExecuteMsg::Liquify {} => liquify_treasury(&deps.querier, env, deps.storage),
+        ExecuteMsg::Liquify {} => {
+            record_crank(deps.storage, &env, Crank::Liquify, &info.sender)?;
+            liquify_treasury(&deps.querier, env, deps.storage)
+        }
         ExecuteMsg::WithdrawToken { token } => withdraw_token(deps, env, info, token),
+        ExecuteMsg::SetPayroll {
+            recipients,
+            assets,
+            interval,
+        } => set_payroll(deps, env, info, recipients, assets, interval),
+        ExecuteMsg::FlushPayroll {} => {
+            record_crank(deps.storage, &env, Crank::FlushPayroll, &info.sender)?;
+            flush_payroll(deps, env)
+        }
+        ExecuteMsg::ClaimPayroll {} => claim_payroll(deps, env, info),
+        ExecuteMsg::SetKeepers { crank, keepers } => set_keepers(deps, info, crank, keepers),
+        ExecuteMsg::SetSwapProtection { protection } => set_swap_protection(deps, info, protection),
+        ExecuteMsg::SetSwapVenue { swap, venue } => set_swap_venue(deps, info, swap, venue),
     }
 }
 
@@ -89,6 +118,34 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let token: Addr = TOKEN.load(deps.storage)?;
             to_json_binary(&query_balance(&deps.querier, token, env.contract.address)?)
         }
+        QueryMsg::Payroll {} => to_json_binary(&query_payroll(deps)?),
+        QueryMsg::PayrollAccrued { address } => {
+            to_json_binary(&query_payroll_accrued(deps, env, address)?)
+        }
+        QueryMsg::Keepers { crank } => to_json_binary(&query_keepers(deps, crank)?),
+        QueryMsg::KeeperStats { crank, keeper } => {
+            to_json_binary(&query_keeper_stats(deps, crank, keeper)?)
+        }
+        QueryMsg::SwapProtection {} => to_json_binary(&query_swap_protection(deps)?),
+        QueryMsg::SwapVenues {} => to_json_binary(&query_swap_venues(deps)?),
+        QueryMsg::SwapLedger { start_after, limit } => {
+            to_json_binary(&query_swap_ledger(deps, start_after, limit)?)
+        }
+        QueryMsg::SimulateDistribute {} => to_json_binary(&query_simulate_distribute(deps, env)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        REFLECTION_SWAP_REPLY_ID => swap_replied(deps.storage, &env, msg.result),
+        PROTECTED_SWAP_REPLY_ID => match msg.result {
+            SubMsgResult::Err(error) => Ok(swap_skipped(error)),
+            SubMsgResult::Ok(_) => Ok(Response::default()),
+        },
+        _ => Err(ContractError::Std(StdError::generic_err(
+            "Unknown reply id",
+        ))),
     }
 }
 
@@ -126,7 +183,6 @@ pub fn liquify_treasury(
 ) -> Result<Response, ContractError> {
     let querier = querier.clone();
 
-    let router = ROUTER.may_load(storage)?.unwrap_or_default();
     // let admin = ADMIN.may_load(storage)?.unwrap_or_default();
     let token = TOKEN.load(storage)?;
     let contract_balance = query_balance(&querier, token.clone(), env.contract.address.clone())?;
@@ -143,22 +199,17 @@ pub fn liquify_treasury(
         return Ok(Response::default());
     }
 
-    // Loads all the tax rates from the modified CW20 token
-    let (_tax_rate, reflection_rate, burn_rate, _transfer_rate): (
-        Decimal,
-        Decimal,
-        Decimal,
-        Decimal,
-    ) = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: token.to_string(),
-        msg: to_json_binary(&TokenQueryMsg::QueryRates {})?,
-    }))?;
+    let (reflect_amt, burn_amt, liquidity_amt) =
+        liquify_amounts(&querier, &token, contract_balance)?;
 
-    let mut messages: Vec<WasmMsg> = vec![];
+    let protection = SWAP_PROTECTION.may_load(storage)?;
+    let max_spread = protection.as_ref().map(|protection| protection.max_spread);
+    let deadline = protection
+        .as_ref()
+        .map(|protection| protection.deadline_from(&env));
+    let mut messages: Vec<SubMsg> = vec![];
+    let mut attributes: Vec<Attribute> = vec![];
 
-    let reflect_amt = contract_balance.mul(reflection_rate);
-    let burn_amt = contract_balance.mul(burn_rate);
-    let liquidity_amt = contract_balance.sub(reflect_amt).sub(burn_amt);
     // Taxes - 100000
     // Reflection - 50000
     // Burn - 10000
@@ -166,141 +217,237 @@ pub fn liquify_treasury(
     if liquidity_amt > Uint128::zero() {
         // Swaps half of babyTOKEN into INJ
         let swap_amount = liquidity_amt.div(Uint128::from(2u128));
-        // Increases allowance of babyTOKEN to liquidity pair contract (allows adding liquidity)
-        messages.push(WasmMsg::Execute {
-            contract_addr: token.to_string(),
-            msg: to_json_binary(&cw20::Cw20ExecuteMsg::IncreaseAllowance {
-                spender: liquidity_pair_contract.clone(),
-                amount: liquidity_amt.sub(swap_amount),
-                expires: None,
-            })?,
-            funds: vec![],
-        });
-
-        // Simulates swapping of half of babyTOKEN into INJ
-        let simulation = simulate(
-            &querier,
-            liquidity_pair_contract.clone(),
-            &Asset {
-                amount: swap_amount,
-                info: liquidity_pair[0].clone(),
-            },
-        )?;
-        // We formulate a swap message to swap babyTOKEN into INJ
-        messages.push(WasmMsg::Execute {
-            contract_addr: token.to_string(),
-            msg: to_json_binary(&cw20::Cw20ExecuteMsg::Send {
-                contract: liquidity_pair_contract.to_string(),
-                amount: swap_amount,
-                msg: to_json_binary(&dojoswap::pair::Cw20HookMsg::Swap {
-                    belief_price: None,
-                    max_spread: None,
-                    to: None,
-                    deadline: None,
-                })?,
-            })?,
-            funds: vec![],
-        });
-
-        // Formulate variable to allow us to add liquidity to the pool
-        let assets: [Asset; 2] = [
-            Asset {
-                amount: liquidity_amt.sub(swap_amount), // add remaining amount of babyTOKEN as liquidity
-                info: liquidity_pair[0].clone(),        // babyTOKEN
-            },
-            Asset {
-                amount: simulation.return_amount, // add simulated INJ return amount to be added as liquidity
-                info: liquidity_pair[1].clone(),  // INJ
-            },
-        ];
-
-        // We formulate a ProvideLiquidity message to add babyTOKEN liquidity to the pool
-        match reflection_pair[1].clone() {
-            AssetInfo::NativeToken { denom } => {
-                // If the asset is a native token, we provide liquidity via a denom message
-                messages.push(WasmMsg::Execute {
-                    contract_addr: liquidity_pair_contract.to_string(),
-                    msg: to_json_binary(&dojoswap::pair::ExecuteMsg::ProvideLiquidity {
-                        assets,
-                        receiver: None,
-                        deadline: None,
-                        slippage_tolerance: None,
-                    })?,
-                    funds: vec![coin(simulation.return_amount.u128(), denom)],
-                });
-            }
-            AssetInfo::Token { contract_addr } => {
-                // If asset is a CW20, we provide liquidity via increase allowance message
-                messages.push(WasmMsg::Execute {
-                    contract_addr,
-                    msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
-                        spender: liquidity_pair_contract.to_string(),
-                        amount: simulation.return_amount,
-                        expires: None,
-                    })?,
-                    funds: vec![],
-                });
-                messages.push(WasmMsg::Execute {
-                    contract_addr: liquidity_pair_contract.to_string(),
-                    msg: to_json_binary(&dojoswap::pair::ExecuteMsg::ProvideLiquidity {
-                        assets,
-                        receiver: None,
-                        deadline: None,
-                        slippage_tolerance: None,
-                    })?,
-                    funds: vec![],
-                });
-            }
+        // Simulates swapping of half of babyTOKEN into INJ on the venue of the liquidity swap
+        let venue = swap_venue(storage, LiquifySwap::Liquidity)?;
+        let (ask, quote) = swap_assets(storage, LiquifySwap::Liquidity)?;
+        let simulation = venue.simulate(&querier, &token, swap_amount, &ask, &quote)?;
+        // Under protection, no liquidity is added while the spread is above the max spread
+        let skipped = match &protection {
+            Some(protection) => protection.exceeded_by(&simulation),
+            None => false,
         };
+        if skipped {
+            attributes.push(attr("liquidity_skipped", simulation.spread_amount));
+        } else {
+            // Increases allowance of babyTOKEN to liquidity pair contract (allows adding liquidity)
+            messages.push(SubMsg::new(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: liquidity_pair_contract.clone(),
+                    amount: liquidity_amt.sub(swap_amount),
+                    expires: None,
+                })?,
+                funds: vec![],
+            }));
+
+            // We formulate a swap message to swap babyTOKEN into INJ
+            messages.push(protected(
+                venue.swap_msg(
+                    &token,
+                    swap_amount,
+                    &ask,
+                    &quote,
+                    Some(simulation.return_amount),
+                    max_spread,
+                    deadline,
+                )?,
+                &protection,
+            ));
+
+            // Formulate variable to allow us to add liquidity to the pool
+            let assets: [Asset; 2] = [
+                Asset {
+                    amount: liquidity_amt.sub(swap_amount), // add remaining amount of babyTOKEN as liquidity
+                    info: liquidity_pair[0].clone(),        // babyTOKEN
+                },
+                Asset {
+                    amount: simulation.return_amount, // add simulated INJ return amount to be added as liquidity
+                    info: liquidity_pair[1].clone(),  // INJ
+                },
+            ];
+
+            // We formulate a ProvideLiquidity message to add babyTOKEN liquidity to the pool
+            match reflection_pair[1].clone() {
+                AssetInfo::NativeToken { denom } => {
+                    // If the asset is a native token, we provide liquidity via a denom message
+                    messages.push(protected(
+                        WasmMsg::Execute {
+                            contract_addr: liquidity_pair_contract.to_string(),
+                            msg: to_json_binary(&dojoswap::pair::ExecuteMsg::ProvideLiquidity {
+                                assets,
+                                receiver: None,
+                                deadline,
+                                slippage_tolerance: max_spread,
+                            })?,
+                            funds: vec![coin(simulation.return_amount.u128(), denom)],
+                        },
+                        &protection,
+                    ));
+                }
+                AssetInfo::Token { contract_addr } => {
+                    // If asset is a CW20, we provide liquidity via increase allowance message
+                    messages.push(SubMsg::new(WasmMsg::Execute {
+                        contract_addr,
+                        msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                            spender: liquidity_pair_contract.to_string(),
+                            amount: simulation.return_amount,
+                            expires: None,
+                        })?,
+                        funds: vec![],
+                    }));
+                    messages.push(protected(
+                        WasmMsg::Execute {
+                            contract_addr: liquidity_pair_contract.to_string(),
+                            msg: to_json_binary(&dojoswap::pair::ExecuteMsg::ProvideLiquidity {
+                                assets,
+                                receiver: None,
+                                deadline,
+                                slippage_tolerance: max_spread,
+                            })?,
+                            funds: vec![],
+                        },
+                        &protection,
+                    ));
+                }
+            };
+        }
     }
 
     if reflect_amt > Uint128::zero() {
         // 1. swap babyToken into INJ
         // 2. swap INJ into reflection target token (DOJO)
         // 3. sends reflection token to fee collector
-        let operations = vec![
-            dojoswap::router::SwapOperation::DojoSwap {
-                offer_asset_info: dojoswap::asset::AssetInfo::Token {
-                    contract_addr: token.to_string(),
-                },
-                ask_asset_info: reflection_pair[1].clone(),
-            },
-            dojoswap::router::SwapOperation::DojoSwap {
-                offer_asset_info: reflection_pair[1].clone(),
-                ask_asset_info: reflection_pair[0].clone(),
-            },
-        ];
+        let venue = swap_venue(storage, LiquifySwap::Reflection)?;
+        let (ask, quote) = swap_assets(storage, LiquifySwap::Reflection)?;
+        // Under protection, the venue must return the simulated amount minus the max spread
+        let expected = match max_spread {
+            Some(_) => Some(
+                venue
+                    .simulate(&querier, &token, reflect_amt, &ask, &quote)?
+                    .return_amount,
+            ),
+            None => None,
+        };
         // Executes a sell of babyTOKEN into INJ, then INJ into reflection target token (DOJO) via router contract
-        messages.push(WasmMsg::Execute {
-            contract_addr: token.to_string(),
-            msg: to_json_binary(&cw20::Cw20ExecuteMsg::Send {
-                contract: router.to_string(),
-                amount: reflect_amt,
-                msg: to_json_binary(&dojoswap::router::ExecuteMsg::ExecuteSwapOperations {
-                    operations,
-                    minimum_receive: None,
-                    to: None, // reflected token is sent here into treasury
-                    deadline: None,
-                })?,
-            })?,
-            funds: vec![],
+        // by default, the reflected token is sent back to the treasury. The reply records what the last pair
+        // returned in the swap ledger, or skips a protected swap which failed
+        start_swap(storage, reflect_amt, &ask)?;
+        messages.push(SubMsg {
+            id: REFLECTION_SWAP_REPLY_ID,
+            msg: CosmosMsg::Wasm(venue.swap_msg(
+                &token,
+                reflect_amt,
+                &ask,
+                &quote,
+                expected,
+                max_spread,
+                deadline,
+            )?),
+            gas_limit: None,
+            reply_on: if protection.is_some() {
+                ReplyOn::Always
+            } else {
+                ReplyOn::Success
+            },
         });
     }
 
     if burn_amt > Uint128::zero() {
         // Burns babyTOKEN
-        messages.push(WasmMsg::Execute {
+        messages.push(SubMsg::new(WasmMsg::Execute {
             contract_addr: token.to_string(),
             msg: to_json_binary(&cw20::Cw20ExecuteMsg::Burn { amount: burn_amt })?,
             funds: vec![],
-        });
+        }));
     }
 
-    let res = Response::new().add_messages(messages);
+    let res = Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes);
 
     Ok(res)
 }
 
+/// Splits `balance` by the tax rates of the token into (reflection, burn, liquidity) shares
+fn liquify_amounts(
+    querier: &QuerierWrapper,
+    token: &Addr,
+    balance: Uint128,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // Loads all the tax rates from the modified CW20 token
+    let (_tax_rate, reflection_rate, burn_rate, _transfer_rate): (
+        Decimal,
+        Decimal,
+        Decimal,
+        Decimal,
+    ) = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&TokenQueryMsg::QueryRates {})?,
+    }))?;
+
+    let reflect_amt = balance.mul(reflection_rate);
+    let burn_amt = balance.mul(burn_rate);
+    Ok((
+        reflect_amt,
+        burn_amt,
+        balance.sub(reflect_amt).sub(burn_amt),
+    ))
+}
+
+/// What `Liquify` would do with the treasury balance now, with the swaps simulated on their venue
+pub fn query_simulate_distribute(deps: Deps, env: Env) -> StdResult<SimulateDistributeResponse> {
+    let token = TOKEN.load(deps.storage)?;
+    let balance = query_balance(&deps.querier, token.clone(), env.contract.address)?;
+    let min_liquify_amt = MIN_LIQUIFY_AMT.may_load(deps.storage)?.unwrap_or_default();
+    if balance < min_liquify_amt {
+        return Ok(SimulateDistributeResponse {
+            balance,
+            min_liquify_amt,
+            ..SimulateDistributeResponse::default()
+        });
+    }
+
+    let (reflect_amt, burn_amt, liquidity_amt) = liquify_amounts(&deps.querier, &token, balance)?;
+    let protection = SWAP_PROTECTION.may_load(deps.storage)?;
+    let mut liquidity = LiquiditySimulation {
+        amount: liquidity_amt,
+        ..LiquiditySimulation::default()
+    };
+    if !liquidity_amt.is_zero() {
+        let swap_amount = liquidity_amt.div(Uint128::from(2u128));
+        let venue = swap_venue(deps.storage, LiquifySwap::Liquidity)?;
+        let (ask, quote) = swap_assets(deps.storage, LiquifySwap::Liquidity)?;
+        let simulation = venue.simulate(&deps.querier, &token, swap_amount, &ask, &quote)?;
+        liquidity.swap_amount = swap_amount;
+        liquidity.quote_amount = simulation.return_amount;
+        liquidity.spread_amount = simulation.spread_amount;
+        liquidity.skipped = protection
+            .as_ref()
+            .is_some_and(|protection| protection.exceeded_by(&simulation));
+    }
+    let mut reflection = ReflectionSimulation {
+        amount: reflect_amt,
+        ..ReflectionSimulation::default()
+    };
+    if !reflect_amt.is_zero() {
+        let venue = swap_venue(deps.storage, LiquifySwap::Reflection)?;
+        let (ask, quote) = swap_assets(deps.storage, LiquifySwap::Reflection)?;
+        reflection.return_amount = venue
+            .simulate(&deps.querier, &token, reflect_amt, &ask, &quote)?
+            .return_amount;
+        reflection.ask = Some(ask);
+    }
+
+    Ok(SimulateDistributeResponse {
+        balance,
+        min_liquify_amt,
+        runs: true,
+        liquidity,
+        reflection,
+        burn_amount: burn_amt,
+    })
+}
+
 /// Used to simulate swap operations against DojoSwap pair
 pub fn simulate(
     querier: &QuerierWrapper,
@@ -468,14 +615,22 @@ pub fn withdraw_token(
             address: env.contract.address.to_string(),
         },
     )?;
+    // shares accrued to payroll recipients are not the admin's to withdraw
+    let reserved = payroll_reserved(
+        deps.storage,
+        &AssetInfo::Token {
+            contract_addr: token.to_string(),
+        },
+    )?;
+    let amount = response.balance.saturating_sub(reserved);
 
     let res = Response::new()
-        .add_attribute("withdraw_token", response.balance)
+        .add_attribute("withdraw_token", amount)
         .add_message(WasmMsg::Execute {
             contract_addr: token.to_string(),
             msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
                 recipient: info.sender.to_string(),
-                amount: response.balance,
+                amount,
             })?,
             funds: vec![],
         });
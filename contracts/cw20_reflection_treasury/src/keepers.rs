@@ -0,0 +1,86 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
+use cw20_base::ContractError;
+
+use crate::contract::ensure_admin;
+use crate::msg::{Crank, KeeperStatsResponse, KeepersResponse};
+use crate::state::{CRANK_KEEPERS, KEEPER_STATS};
+
+/// Replaces the keepers allowed to fire `crank`, an empty list opens it to anyone again
+pub fn set_keepers(
+    deps: DepsMut,
+    info: MessageInfo,
+    crank: Crank,
+    keepers: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+
+    let keepers = keepers
+        .iter()
+        .map(|keeper| deps.api.addr_validate(keeper))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    if keepers.is_empty() {
+        CRANK_KEEPERS.remove(deps.storage, crank.as_str());
+    } else {
+        CRANK_KEEPERS.save(deps.storage, crank.as_str(), &keepers)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_keepers")
+        .add_attribute("crank", crank.as_str())
+        .add_attribute("keepers", keepers.len().to_string()))
+}
+
+/// Fails unless `keeper` may fire `crank`, then counts the execution in its stats
+pub fn record_crank(
+    storage: &mut dyn Storage,
+    env: &Env,
+    crank: Crank,
+    keeper: &Addr,
+) -> Result<(), ContractError> {
+    if let Some(keepers) = CRANK_KEEPERS.may_load(storage, crank.as_str())? {
+        if !keepers.contains(keeper) {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "Unauthorized: {} is restricted to its keepers",
+                crank.as_str()
+            ))));
+        }
+    }
+
+    let key = (crank.as_str(), keeper);
+    let mut stats = KEEPER_STATS.may_load(storage, key)?.unwrap_or_default();
+    stats.executions += 1;
+    stats.last_execution = env.block.time.seconds();
+    KEEPER_STATS.save(storage, key, &stats)?;
+
+    Ok(())
+}
+
+pub fn query_keepers(deps: Deps, crank: Crank) -> StdResult<KeepersResponse> {
+    let keepers = CRANK_KEEPERS
+        .may_load(deps.storage, crank.as_str())?
+        .unwrap_or_default();
+
+    Ok(KeepersResponse {
+        permissionless: keepers.is_empty(),
+        keepers: keepers
+            .into_iter()
+            .map(|keeper| keeper.to_string())
+            .collect(),
+        crank,
+    })
+}
+
+pub fn query_keeper_stats(
+    deps: Deps,
+    crank: Crank,
+    keeper: String,
+) -> StdResult<KeeperStatsResponse> {
+    let stats = KEEPER_STATS
+        .may_load(deps.storage, (crank.as_str(), &Addr::unchecked(keeper)))?
+        .unwrap_or_default();
+
+    Ok(KeeperStatsResponse {
+        executions: stats.executions,
+        last_execution: stats.last_execution,
+    })
+}
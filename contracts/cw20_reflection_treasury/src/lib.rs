@@ -1,2 +1,11 @@
 pub mod contract;
-pub mod msg;
\ No newline at end of file
+pub mod keepers;
+pub mod msg;
+pub mod payroll;
+pub mod proceeds;
+pub mod protection;
+pub mod state;
+pub mod venue;
+
+#[cfg(test)]
+mod testing;
@@ -1,10 +1,13 @@
 use cosmwasm_std::Addr;
 use cosmwasm_std::Binary;
+use cosmwasm_std::Decimal;
 use cosmwasm_std::Uint128;
-use dojoswap::asset::AssetInfo;
+use dojoswap::asset::{Asset, AssetInfo};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::SwapProceeds;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub admin: String,
@@ -34,6 +37,116 @@ pub enum ExecuteMsg {
         token: Addr,
     },
     Liquify {},
+    /// Replaces the payroll. What the treasury holds of `assets` is split between `recipients`
+    /// pro rata to their weight, shares accrued under the previous payroll stay claimable
+    SetPayroll {
+        recipients: Vec<PayrollRecipient>,
+        assets: Vec<AssetInfo>,
+        interval: u64,
+    },
+    /// Pays every recipient its accrued share, callable by anyone once every `interval` seconds
+    FlushPayroll {},
+    /// Pays the accrued share of `env.sender` without waiting for the next flush
+    ClaimPayroll {},
+    /// Restricts `crank` to `keepers`, an empty list lets anyone fire it again
+    SetKeepers { crank: Crank, keepers: Vec<String> },
+    /// Enforces a max spread and a deadline on the swaps of `Liquify`, None removes them
+    SetSwapProtection { protection: Option<SwapProtection> },
+    /// Swaps the treasury token for `swap` on `venue`, None goes back to the default venue
+    SetSwapVenue {
+        swap: LiquifySwap,
+        venue: Option<SwapVenue>,
+    },
+}
+
+/// Swaps of the treasury token made by `Liquify`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquifySwap {
+    /// Half of the liquidity share into the quote token of the liquidity pair
+    Liquidity,
+    /// The reflection share into the reflection target token
+    Reflection,
+}
+
+impl LiquifySwap {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiquifySwap::Liquidity => "liquidity",
+            LiquifySwap::Reflection => "reflection",
+        }
+    }
+}
+
+/// Where a swap of the treasury token is executed, the tokens are sent to `contract` with a cw20
+/// `Send` and what it returns must be sent back to the treasury
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapVenue {
+    /// A DojoSwap pair trading the treasury token for the asked asset
+    Pair { contract: String },
+    /// A DojoSwap router, routing through the quote token of the pair when needed
+    Router { contract: String },
+    /// An adapter to another DEX or aggregator implementing `SwapAdapterHookMsg` and
+    /// `SwapAdapterQueryMsg`
+    Adapter { contract: String },
+}
+
+/// Hook sent along the treasury tokens to a swap adapter
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapAdapterHookMsg {
+    /// Swaps the tokens received for `ask_asset_info` and sends them to the cw20 sender. The
+    /// adapter fails when it returns less than `minimum_receive` or executes after `deadline`, and
+    /// reports the amount returned in a `return_amount` attribute
+    Swap {
+        ask_asset_info: AssetInfo,
+        minimum_receive: Option<Uint128>,
+        deadline: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapAdapterQueryMsg {
+    /// Return type: dojoswap SimulationResponse.
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    },
+}
+
+/// Limits enforced on the swaps of `Liquify`. A protected swap which would exceed them is skipped
+/// and its tokens wait in the treasury for the next `Liquify`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapProtection {
+    /// Largest share of a swap lost to the spread, also the slippage tolerance of the liquidity
+    pub max_spread: Decimal,
+    /// Seconds after the `Liquify` by which its swaps must execute
+    pub deadline: u64,
+}
+
+/// Permissionless entry points that keepers can be assigned to
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Crank {
+    Liquify,
+    FlushPayroll,
+}
+
+impl Crank {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Crank::Liquify => "liquify",
+            Crank::FlushPayroll => "flush_payroll",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PayrollRecipient {
+    pub address: String,
+    pub weight: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -42,6 +155,109 @@ pub enum QueryMsg {
     /// Returns the current balance of the given address, 0 if unset.
     /// Return type: BalanceResponse.
     Balance {},
+    /// Returns the payroll configuration and when it can be flushed next.
+    /// Return type: PayrollResponse.
+    Payroll {},
+    /// Returns what `address` would be paid if the payroll was flushed now.
+    /// Return type: PayrollAccruedResponse.
+    PayrollAccrued { address: String },
+    /// Returns who can fire `crank`.
+    /// Return type: KeepersResponse.
+    Keepers { crank: Crank },
+    /// Returns how many times `keeper` fired `crank` and when it last did.
+    /// Return type: KeeperStatsResponse.
+    KeeperStats { crank: Crank, keeper: String },
+    /// Returns the protection enforced on the swaps of `Liquify`.
+    /// Return type: SwapProtectionResponse.
+    SwapProtection {},
+    /// Returns the venue of each swap of `Liquify`, None until its pair is set.
+    /// Return type: SwapVenuesResponse.
+    SwapVenues {},
+    /// Returns the realized proceeds of every swap of the reflection share, oldest first.
+    /// Return type: SwapLedgerResponse.
+    SwapLedger {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns what `Liquify` would do if it ran now: how the balance is split between liquidity,
+    /// reflection and burn, and what the swaps would return on their venue.
+    /// Return type: SimulateDistributeResponse.
+    SimulateDistribute {},
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SimulateDistributeResponse {
+    /// Treasury token balance split by `Liquify`
+    pub balance: Uint128,
+    pub min_liquify_amt: Uint128,
+    /// False below `min_liquify_amt`, `Liquify` does nothing then
+    pub runs: bool,
+    pub liquidity: LiquiditySimulation,
+    pub reflection: ReflectionSimulation,
+    pub burn_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct LiquiditySimulation {
+    /// Liquidity share, half of it is swapped for the quote asset provided with the other half
+    pub amount: Uint128,
+    pub swap_amount: Uint128,
+    pub quote_amount: Uint128,
+    pub spread_amount: Uint128,
+    /// True when the swap protection would skip adding liquidity
+    pub skipped: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ReflectionSimulation {
+    /// Reflection share, swapped for `ask`
+    pub amount: Uint128,
+    pub ask: Option<AssetInfo>,
+    pub return_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PayrollResponse {
+    pub recipients: Vec<PayrollRecipient>,
+    pub assets: Vec<AssetInfo>,
+    pub interval: u64,
+    pub last_flush: u64,
+    pub next_flush: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PayrollAccruedResponse {
+    pub accrued: Vec<Asset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct KeepersResponse {
+    pub crank: Crank,
+    pub keepers: Vec<String>,
+    /// True when no keeper is set and anyone can fire the crank
+    pub permissionless: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct KeeperStatsResponse {
+    pub executions: u64,
+    pub last_execution: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapProtectionResponse {
+    pub protection: Option<SwapProtection>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapVenuesResponse {
+    pub liquidity: Option<SwapVenue>,
+    pub reflection: Option<SwapVenue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapLedgerResponse {
+    pub swaps: Vec<SwapProceeds>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
@@ -74,5 +290,5 @@ pub struct Cw20ReceiveMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
-    Liquify {}
-}
\ No newline at end of file
+    Liquify {},
+}
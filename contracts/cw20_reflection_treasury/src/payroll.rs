@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use cw20_base::ContractError;
+use dojoswap::asset::{Asset, AssetInfo};
+
+use crate::contract::{ensure_admin, LIQUIDTY_TOKEN, TOKEN};
+use crate::msg::{PayrollAccruedResponse, PayrollRecipient, PayrollResponse};
+use crate::state::{Payroll, PAYROLL, PAYROLL_ACCRUED, PAYROLL_RESERVED};
+
+/// Amount of `asset` held by the treasury that is already owed to payroll recipients
+pub fn payroll_reserved(storage: &dyn Storage, asset: &AssetInfo) -> StdResult<Uint128> {
+    Ok(PAYROLL_RESERVED
+        .may_load(storage, &asset.to_string())?
+        .unwrap_or_default())
+}
+
+/// Shares of every recipient in what the treasury holds of each payroll asset and is not owed yet
+fn unallocated_shares(
+    deps: Deps,
+    env: &Env,
+    payroll: &Payroll,
+) -> StdResult<Vec<(AssetInfo, Vec<Uint128>)>> {
+    let total_weight: u64 = payroll.recipients.iter().map(|r| r.weight).sum();
+    let mut shares = vec![];
+    if total_weight == 0 {
+        return Ok(shares);
+    }
+
+    for asset in payroll.assets.iter() {
+        let available = asset
+            .query_pool(&deps.querier, deps.api, env.contract.address.clone())?
+            .saturating_sub(payroll_reserved(deps.storage, asset)?);
+        let split = payroll
+            .recipients
+            .iter()
+            .map(|r| available.multiply_ratio(r.weight, total_weight))
+            .collect();
+        shares.push((asset.clone(), split));
+    }
+
+    Ok(shares)
+}
+
+/// Moves what the treasury holds of the payroll assets into the accrued share of each recipient,
+/// rounding leftovers stay with the treasury until the next allocation
+fn allocate(deps: &mut DepsMut, env: &Env, payroll: &Payroll) -> StdResult<()> {
+    for (asset, split) in unallocated_shares(deps.as_ref(), env, payroll)? {
+        let key = asset.to_string();
+        let mut allocated = Uint128::zero();
+        for (recipient, share) in payroll.recipients.iter().zip(split) {
+            if share.is_zero() {
+                continue;
+            }
+            PAYROLL_ACCRUED.update(
+                deps.storage,
+                (&recipient.address, &key),
+                |accrued| -> StdResult<_> {
+                    let mut accrued = accrued.unwrap_or(Asset {
+                        info: asset.clone(),
+                        amount: Uint128::zero(),
+                    });
+                    accrued.amount = accrued.amount.checked_add(share)?;
+                    Ok(accrued)
+                },
+            )?;
+            allocated += share;
+        }
+        if !allocated.is_zero() {
+            let reserved = payroll_reserved(deps.storage, &asset)?;
+            PAYROLL_RESERVED.save(deps.storage, &key, &reserved.checked_add(allocated)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Clears everything accrued to `recipient` and returns the messages paying it
+fn pay(storage: &mut dyn Storage, recipient: &str) -> StdResult<Vec<CosmosMsg>> {
+    let accrued = PAYROLL_ACCRUED
+        .prefix(recipient)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, Asset)>>>()?;
+
+    let mut messages = vec![];
+    for (key, asset) in accrued {
+        PAYROLL_ACCRUED.remove(storage, (recipient, &key));
+        let reserved = payroll_reserved(storage, &asset.info)?;
+        PAYROLL_RESERVED.save(storage, &key, &reserved.checked_sub(asset.amount)?)?;
+        if !asset.amount.is_zero() {
+            messages.push(asset.into_msg(Addr::unchecked(recipient))?);
+        }
+    }
+
+    Ok(messages)
+}
+
+pub fn set_payroll(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<PayrollRecipient>,
+    assets: Vec<AssetInfo>,
+    interval: u64,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+
+    let mut addresses = HashSet::new();
+    for recipient in recipients.iter() {
+        deps.api.addr_validate(&recipient.address)?;
+        if recipient.weight == 0 || !addresses.insert(recipient.address.clone()) {
+            return Err(ContractError::Std(StdError::generic_err(
+                "payroll recipients must be unique with a weight above 0",
+            )));
+        }
+    }
+    // the treasury token is liquified as a whole and LP tokens can never leave the treasury
+    let token = TOKEN.load(deps.storage)?.to_string();
+    let liquidity_token = LIQUIDTY_TOKEN.may_load(deps.storage)?.unwrap_or_default();
+    for asset in assets.iter() {
+        if let AssetInfo::Token { contract_addr } = asset {
+            deps.api.addr_validate(contract_addr)?;
+            if *contract_addr == token || *contract_addr == liquidity_token {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "{} cannot be paid out by the payroll",
+                    contract_addr
+                ))));
+            }
+        }
+    }
+
+    // whatever the previous recipients earned so far is kept for them
+    let last_flush = match PAYROLL.may_load(deps.storage)? {
+        Some(previous) => {
+            allocate(&mut deps, &env, &previous)?;
+            previous.last_flush
+        }
+        None => 0,
+    };
+    PAYROLL.save(
+        deps.storage,
+        &Payroll {
+            recipients,
+            assets,
+            interval,
+            last_flush,
+        },
+    )?;
+
+    Ok(Response::new().add_attribute("action", "set_payroll"))
+}
+
+pub fn flush_payroll(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut payroll = PAYROLL
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No payroll configured"))?;
+    let now = env.block.time.seconds();
+    let next_flush = payroll.last_flush + payroll.interval;
+    if payroll.last_flush != 0 && now < next_flush {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Payroll can be flushed again at {}",
+            next_flush
+        ))));
+    }
+
+    allocate(&mut deps, &env, &payroll)?;
+    let mut messages = vec![];
+    for recipient in payroll.recipients.iter() {
+        messages.extend(pay(deps.storage, &recipient.address)?);
+    }
+    payroll.last_flush = now;
+    PAYROLL.save(deps.storage, &payroll)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "flush_payroll"))
+}
+
+pub fn claim_payroll(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if let Some(payroll) = PAYROLL.may_load(deps.storage)? {
+        allocate(&mut deps, &env, &payroll)?;
+    }
+    let messages = pay(deps.storage, info.sender.as_str())?;
+    if messages.is_empty() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Nothing accrued on the payroll",
+        )));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "claim_payroll")
+        .add_attribute("to", info.sender))
+}
+
+pub fn query_payroll(deps: Deps) -> StdResult<PayrollResponse> {
+    let payroll = PAYROLL
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No payroll configured"))?;
+
+    Ok(PayrollResponse {
+        next_flush: if payroll.last_flush == 0 {
+            0
+        } else {
+            payroll.last_flush + payroll.interval
+        },
+        recipients: payroll.recipients,
+        assets: payroll.assets,
+        interval: payroll.interval,
+        last_flush: payroll.last_flush,
+    })
+}
+
+pub fn query_payroll_accrued(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<PayrollAccruedResponse> {
+    let mut accrued = PAYROLL_ACCRUED
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, asset)| asset))
+        .collect::<StdResult<Vec<Asset>>>()?;
+
+    if let Some(payroll) = PAYROLL.may_load(deps.storage)? {
+        let position = payroll.recipients.iter().position(|r| r.address == address);
+        if let Some(position) = position {
+            for (info, split) in unallocated_shares(deps, &env, &payroll)? {
+                let amount = split[position];
+                match accrued.iter_mut().find(|asset| asset.info == info) {
+                    Some(asset) => asset.amount += amount,
+                    None if !amount.is_zero() => accrued.push(Asset { info, amount }),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    Ok(PayrollAccruedResponse { accrued })
+}
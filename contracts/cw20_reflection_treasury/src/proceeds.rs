@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{
+    Decimal, Deps, Env, Event, Order, Response, StdError, StdResult, Storage, SubMsgResult, Uint128,
+};
+use cw20_base::ContractError;
+use cw_storage_plus::Bound;
+use dojoswap::asset::{Asset, AssetInfo};
+
+use crate::contract::TOKEN;
+use crate::msg::SwapLedgerResponse;
+use crate::protection::swap_skipped;
+use crate::state::{PendingSwap, SwapProceeds, NEXT_SWAP_ID, PENDING_SWAP, SWAP_LEDGER};
+
+/// Reply id of the swap of the reflection share into the reflection target token
+pub const REFLECTION_SWAP_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Remembers the swap of `offer_amount` treasury tokens into `ask_asset` until it replies
+pub fn start_swap(
+    storage: &mut dyn Storage,
+    offer_amount: Uint128,
+    ask_asset: &AssetInfo,
+) -> StdResult<()> {
+    PENDING_SWAP.save(
+        storage,
+        &PendingSwap {
+            offer_amount,
+            ask_asset: ask_asset.clone(),
+        },
+    )
+}
+
+/// Return amount of the last hop of a swap, as reported by the pair which executed it
+fn last_return_amount(events: &[Event]) -> StdResult<Uint128> {
+    events
+        .iter()
+        .rev()
+        .flat_map(|event| event.attributes.iter().rev())
+        .find(|attr| attr.key == "return_amount")
+        .map(|attr| Uint128::from_str(&attr.value))
+        .unwrap_or_else(|| Ok(Uint128::zero()))
+}
+
+/// Records the realized proceeds of the swap started by `start_swap`
+pub fn swap_replied(
+    storage: &mut dyn Storage,
+    env: &Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_SWAP
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("No pending swap"))?;
+    PENDING_SWAP.remove(storage);
+    // only protected swaps reply on errors
+    let events = match result {
+        SubMsgResult::Ok(response) => response.events,
+        SubMsgResult::Err(error) => return Ok(swap_skipped(error)),
+    };
+    let return_amount = last_return_amount(&events)?;
+
+    let id = NEXT_SWAP_ID.may_load(storage)?.unwrap_or(1);
+    NEXT_SWAP_ID.save(storage, &(id + 1))?;
+    let proceeds = SwapProceeds {
+        id,
+        time: env.block.time.seconds(),
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: TOKEN.load(storage)?.to_string(),
+            },
+            amount: pending.offer_amount,
+        },
+        return_asset: Asset {
+            info: pending.ask_asset,
+            amount: return_amount,
+        },
+        price: if pending.offer_amount.is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(return_amount, pending.offer_amount)
+        },
+    };
+    SWAP_LEDGER.save(storage, id, &proceeds)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "record_swap")
+        .add_attribute("id", id.to_string())
+        .add_attribute("offer_amount", proceeds.offer_asset.amount)
+        .add_attribute("return_amount", return_amount)
+        .add_attribute("price", proceeds.price.to_string()))
+}
+
+pub fn query_swap_ledger(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SwapLedgerResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let swaps = SWAP_LEDGER
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, proceeds)| proceeds))
+        .collect::<StdResult<_>>()?;
+
+    Ok(SwapLedgerResponse { swaps })
+}
@@ -0,0 +1,72 @@
+use cosmwasm_std::{
+    Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, SubMsg, WasmMsg,
+};
+use cw20_base::ContractError;
+use dojoswap::pair::SimulationResponse;
+
+use crate::contract::ensure_admin;
+use crate::msg::{SwapProtection, SwapProtectionResponse};
+use crate::state::SWAP_PROTECTION;
+
+/// Reply id of the liquidity swaps of a protected `Liquify`, their failure skips them
+pub const PROTECTED_SWAP_REPLY_ID: u64 = 2;
+
+/// Sets the max spread and deadline enforced on the swaps of `Liquify`, None removes them
+pub fn set_swap_protection(
+    deps: DepsMut,
+    info: MessageInfo,
+    protection: Option<SwapProtection>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &protection {
+        Some(protection) => {
+            if protection.max_spread >= Decimal::one() || protection.deadline == 0 {
+                return Err(ContractError::Std(StdError::generic_err(
+                    "max_spread must be below 1 and deadline above 0",
+                )));
+            }
+            SWAP_PROTECTION.save(deps.storage, protection)?;
+        }
+        None => SWAP_PROTECTION.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_swap_protection")
+        .add_attribute("enabled", protection.is_some().to_string()))
+}
+
+impl SwapProtection {
+    /// Latest time the swaps of a `Liquify` starting now may execute
+    pub fn deadline_from(&self, env: &Env) -> u64 {
+        env.block.time.seconds() + self.deadline
+    }
+
+    /// True when the spread of `simulation` is above the max spread
+    pub fn exceeded_by(&self, simulation: &SimulationResponse) -> bool {
+        let total = simulation.return_amount + simulation.spread_amount;
+        !total.is_zero() && Decimal::from_ratio(simulation.spread_amount, total) > self.max_spread
+    }
+}
+
+/// Submessage running a swap of `Liquify`, under protection it is skipped instead of reverting
+/// the transaction which fired `Liquify`, a token transfer most of the time
+pub fn protected(msg: WasmMsg, protection: &Option<SwapProtection>) -> SubMsg {
+    match protection {
+        Some(_) => SubMsg::reply_on_error(msg, PROTECTED_SWAP_REPLY_ID),
+        None => SubMsg::new(msg),
+    }
+}
+
+/// Keeps `Liquify` going when the pair rejects a protected swap, the tokens stay in the treasury
+/// until the next one
+pub fn swap_skipped(error: String) -> Response {
+    Response::new()
+        .add_attribute("action", "swap_skipped")
+        .add_attribute("error", error)
+}
+
+pub fn query_swap_protection(deps: Deps) -> StdResult<SwapProtectionResponse> {
+    Ok(SwapProtectionResponse {
+        protection: SWAP_PROTECTION.may_load(deps.storage)?,
+    })
+}
@@ -0,0 +1,68 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Item, Map};
+use dojoswap::asset::{Asset, AssetInfo};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::{PayrollRecipient, SwapProtection, SwapVenue};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Payroll {
+    pub recipients: Vec<PayrollRecipient>,
+    pub assets: Vec<AssetInfo>,
+    /// Minimum number of seconds between two flushes
+    pub interval: u64,
+    pub last_flush: u64,
+}
+
+pub const PAYROLL: Item<Payroll> = Item::new("payroll");
+
+// (recipient, asset) -> share allocated to the recipient and not paid yet
+pub const PAYROLL_ACCRUED: Map<(&str, &str), Asset> = Map::new("payroll_accrued");
+
+// asset -> sum of the accrued shares, held by the treasury but no longer available to it
+pub const PAYROLL_RESERVED: Map<&str, Uint128> = Map::new("payroll_reserved");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct KeeperStats {
+    pub executions: u64,
+    /// Time of the last execution, 0 if never executed
+    pub last_execution: u64,
+}
+
+// crank -> addresses allowed to fire it, cranks without keepers are open to anyone
+pub const CRANK_KEEPERS: Map<&str, Vec<Addr>> = Map::new("crank_keepers");
+
+// (crank, keeper) -> executions of the crank by the keeper
+pub const KEEPER_STATS: Map<(&str, &Addr), KeeperStats> = Map::new("keeper_stats");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingSwap {
+    pub offer_amount: Uint128,
+    pub ask_asset: AssetInfo,
+}
+
+// swap of the reflection share waiting for its reply
+pub const PENDING_SWAP: Item<PendingSwap> = Item::new("pending_swap");
+
+/// Realized proceeds of a swap of the treasury token
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapProceeds {
+    pub id: u64,
+    pub time: u64,
+    /// Treasury tokens sold
+    pub offer_asset: Asset,
+    /// What the treasury received for them
+    pub return_asset: Asset,
+    /// Return amount per treasury token sold
+    pub price: Decimal,
+}
+
+pub const SWAP_LEDGER: Map<u64, SwapProceeds> = Map::new("swap_ledger");
+
+pub const NEXT_SWAP_ID: Item<u64> = Item::new("next_swap_id");
+
+pub const SWAP_PROTECTION: Item<SwapProtection> = Item::new("swap_protection");
+
+// swap -> venue set by the admin, swaps without one use their default venue
+pub const SWAP_VENUES: Map<&str, SwapVenue> = Map::new("swap_venues");
@@ -0,0 +1,707 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, coins, from_json, to_json_binary, Addr, BankMsg, CosmosMsg, Decimal, Env, Event,
+    OwnedDeps, Reply, ReplyOn, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use dojoswap::asset::{Asset, AssetInfo, PairInfo};
+use dojoswap::mock_querier::{mock_dojo_dependencies, MockDojoQuerier};
+use dojoswap::pair::{Cw20HookMsg as PairCw20HookMsg, SimulationResponse};
+use dojoswap::router::{ExecuteMsg as RouterExecuteMsg, SimulateSwapOperationsResponse};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{
+    Crank, ExecuteMsg, InstantiateMsg, KeeperStatsResponse, KeepersResponse, LiquifySwap,
+    PayrollAccruedResponse, PayrollRecipient, QueryMsg, SwapAdapterHookMsg, SwapLedgerResponse,
+    SwapProtection, SwapVenue, SwapVenuesResponse, TokenQueryMsg,
+};
+use crate::proceeds::REFLECTION_SWAP_REPLY_ID;
+use crate::protection::PROTECTED_SWAP_REPLY_ID;
+use crate::state::SwapProceeds;
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockDojoQuerier>;
+
+const ADMIN: &str = "admin0000";
+const TOKEN: &str = "token0000";
+const DOJO: &str = "dojo0000";
+const DAY: u64 = 86_400;
+
+fn setup() -> MockDeps {
+    let mut deps = mock_dojo_dependencies(&[]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TOKEN, &[]),
+        InstantiateMsg {
+            admin: ADMIN.to_string(),
+            router: "router0000".to_string(),
+            token: Addr::unchecked(TOKEN),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn set_holdings(deps: &mut MockDeps, dojo: u128, inj: u128) {
+    deps.querier.with_token_balances(&[(
+        &DOJO.to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::new(dojo))],
+    )]);
+    deps.querier
+        .with_balance(&[(&MOCK_CONTRACT_ADDR.to_string(), coins(inj, "inj"))]);
+}
+
+fn dojo(amount: u128) -> Asset {
+    Asset {
+        info: AssetInfo::Token {
+            contract_addr: DOJO.to_string(),
+        },
+        amount: Uint128::new(amount),
+    }
+}
+
+fn payments(messages: Vec<CosmosMsg>) -> Vec<(String, Asset)> {
+    messages
+        .into_iter()
+        .map(|msg| match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) => match from_json(&msg).unwrap() {
+                cw20::Cw20ExecuteMsg::Transfer { recipient, amount } => (
+                    recipient,
+                    Asset {
+                        info: AssetInfo::Token { contract_addr },
+                        amount,
+                    },
+                ),
+                msg => panic!("unexpected message {:?}", msg),
+            },
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => (
+                to_address,
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: amount[0].denom.clone(),
+                    },
+                    amount: amount[0].amount,
+                },
+            ),
+            msg => panic!("unexpected message {:?}", msg),
+        })
+        .collect()
+}
+
+#[test]
+fn payroll_splits_treasury_holdings_by_weight() {
+    let mut deps = setup();
+    let payroll = |assets: Vec<AssetInfo>| ExecuteMsg::SetPayroll {
+        recipients: vec![
+            PayrollRecipient {
+                address: "addr0000".to_string(),
+                weight: 3,
+            },
+            PayrollRecipient {
+                address: "addr0001".to_string(),
+                weight: 1,
+            },
+        ],
+        assets,
+        interval: DAY,
+    };
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        payroll(vec![AssetInfo::Token {
+            contract_addr: TOKEN.to_string(),
+        }]),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("cannot be paid out by the payroll"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        payroll(vec![dojo(0).info, inj.clone()]),
+    )
+    .unwrap();
+
+    set_holdings(&mut deps, 1_000, 400);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::FlushPayroll {},
+    )
+    .unwrap();
+    let inj_amount = |amount: u128| Asset {
+        info: inj.clone(),
+        amount: Uint128::new(amount),
+    };
+    assert_eq!(
+        payments(res.messages.into_iter().map(|sub| sub.msg).collect()),
+        vec![
+            ("addr0000".to_string(), dojo(750)),
+            ("addr0000".to_string(), inj_amount(300)),
+            ("addr0001".to_string(), dojo(250)),
+            ("addr0001".to_string(), inj_amount(100)),
+        ]
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::FlushPayroll {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("can be flushed again"));
+
+    // a recipient pulls its share before the next flush, the other share stays reserved
+    set_holdings(&mut deps, 100, 0);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimPayroll {},
+    )
+    .unwrap();
+    assert_eq!(
+        payments(res.messages.into_iter().map(|sub| sub.msg).collect()),
+        vec![("addr0001".to_string(), dojo(25))]
+    );
+    set_holdings(&mut deps, 175, 0);
+    let accrued: PayrollAccruedResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PayrollAccrued {
+                address: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(accrued.accrued, vec![dojo(150)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::WithdrawToken {
+            token: Addr::unchecked(DOJO),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        payments(res.messages.into_iter().map(|sub| sub.msg).collect()),
+        vec![(ADMIN.to_string(), dojo(100))]
+    );
+
+    let mut env: Env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DAY);
+    set_holdings(&mut deps, 75, 0);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::FlushPayroll {},
+    )
+    .unwrap();
+    assert_eq!(
+        payments(res.messages.into_iter().map(|sub| sub.msg).collect()),
+        vec![("addr0000".to_string(), dojo(75))]
+    );
+}
+
+#[test]
+fn cranks_can_be_restricted_to_keepers() {
+    let mut deps = setup();
+    let set_keepers = |keepers: Vec<&str>| ExecuteMsg::SetKeepers {
+        crank: Crank::FlushPayroll,
+        keepers: keepers.into_iter().map(String::from).collect(),
+    };
+    let flush = |deps: &mut MockDeps, sender: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::FlushPayroll {},
+        )
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        set_keepers(vec!["keeper0000"]),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not admin"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        set_keepers(vec!["keeper0000"]),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetPayroll {
+            recipients: vec![PayrollRecipient {
+                address: "addr0000".to_string(),
+                weight: 1,
+            }],
+            assets: vec![dojo(0).info],
+            interval: DAY,
+        },
+    )
+    .unwrap();
+    set_holdings(&mut deps, 1_000, 0);
+
+    let err = flush(&mut deps, "addr0001").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("flush_payroll is restricted to its keepers"));
+    flush(&mut deps, "keeper0000").unwrap();
+
+    let stats: KeeperStatsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::KeeperStats {
+                crank: Crank::FlushPayroll,
+                keeper: "keeper0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        stats,
+        KeeperStatsResponse {
+            executions: 1,
+            last_execution: mock_env().block.time.seconds(),
+        }
+    );
+    // other cranks stay open
+    let keepers: KeepersResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Keepers {
+                crank: Crank::Liquify,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(keepers.permissionless);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        set_keepers(vec![]),
+    )
+    .unwrap();
+    let err = flush(&mut deps, "addr0001").unwrap_err();
+    assert!(err.to_string().contains("can be flushed again"));
+}
+
+/// Points the treasury at a TOKEN/inj liquidity pair and a DOJO/inj reflection pair, and makes
+/// TOKEN report `balance` for the treasury and the given reflection rate
+fn setup_liquify(deps: &mut MockDeps, reflection_rate: Decimal, balance: u128) {
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let token = AssetInfo::Token {
+        contract_addr: TOKEN.to_string(),
+    };
+    for (pair, asset_infos) in [
+        ("pair0000", [token, inj.clone()]),
+        ("pair0001", [dojo(0).info, inj]),
+    ] {
+        deps.querier.with_pair(
+            PairInfo {
+                asset_infos: asset_infos.clone(),
+                contract_addr: pair.to_string(),
+                liquidity_token: format!("lp{}", pair),
+                asset_decimals: [6, 6],
+            },
+            [Uint128::new(1_000_000), Uint128::new(1_000_000)],
+            Decimal::permille(3),
+        );
+        let msg = if pair == "pair0000" {
+            ExecuteMsg::SetLiquidityPair {
+                asset_infos,
+                pair_contract: pair.to_string(),
+            }
+        } else {
+            ExecuteMsg::SetReflectionPair {
+                asset_infos,
+                pair_contract: pair.to_string(),
+            }
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+    }
+    deps.querier.with_contract_handler(TOKEN, move |msg| {
+        if let Ok(TokenQueryMsg::QueryRates {}) = from_json(msg) {
+            return to_json_binary(&(
+                Decimal::percent(10),
+                reflection_rate,
+                Decimal::zero(),
+                Decimal::zero(),
+            ));
+        }
+        to_json_binary(&cw20::BalanceResponse {
+            balance: Uint128::new(balance),
+        })
+    });
+}
+
+#[test]
+fn reflection_swaps_are_recorded_in_the_ledger() {
+    let mut deps = setup();
+    // the whole balance is reflected, nothing is liquified or burnt
+    setup_liquify(&mut deps, Decimal::one(), 1_000);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Liquify {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, REFLECTION_SWAP_REPLY_ID);
+
+    // the last hop of the router returned 250 DOJO to the treasury
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(5);
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: REFLECTION_SWAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![
+                    Event::new("wasm")
+                        .add_attribute("action", "swap")
+                        .add_attribute("return_amount", "800"),
+                    Event::new("wasm")
+                        .add_attribute("action", "swap")
+                        .add_attribute("return_amount", "250"),
+                ],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    let ledger: SwapLedgerResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SwapLedger {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        ledger.swaps,
+        vec![SwapProceeds {
+            id: 1,
+            time: env.block.time.seconds(),
+            offer_asset: Asset {
+                info: AssetInfo::Token {
+                    contract_addr: TOKEN.to_string(),
+                },
+                amount: Uint128::new(1_000),
+            },
+            return_asset: dojo(250),
+            price: Decimal::percent(25),
+        }]
+    );
+}
+
+#[test]
+fn protected_swaps_are_skipped_instead_of_failing() {
+    let mut deps = setup();
+    // half of the balance is reflected, 25_000 tokens are swapped to add the other half as liquidity
+    setup_liquify(&mut deps, Decimal::percent(50), 100_000);
+    deps.querier.with_contract_handler("router0000", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::new(40_000),
+        })
+    });
+    let protect = |deps: &mut MockDeps, max_spread: Decimal| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetSwapProtection {
+                protection: Some(SwapProtection {
+                    max_spread,
+                    deadline: 60,
+                }),
+            },
+        )
+    };
+    let liquify = |deps: &mut MockDeps| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper0000", &[]),
+            ExecuteMsg::Liquify {},
+        )
+        .unwrap()
+    };
+    let deadline = Some(mock_env().block.time.seconds() + 60);
+
+    let err = protect(&mut deps, Decimal::one()).unwrap_err();
+    assert!(err.to_string().contains("max_spread must be below 1"));
+    // swapping 25_000 tokens against a 1_000_000 pool costs about 2.4% of spread
+    protect(&mut deps, Decimal::percent(1)).unwrap();
+    let res = liquify(&mut deps);
+    assert_eq!(res.attributes, vec![attr("liquidity_skipped", "609")]);
+    assert_eq!(res.messages.len(), 1);
+    let swap = &res.messages[0];
+    assert_eq!(
+        (swap.id, swap.reply_on.clone()),
+        (REFLECTION_SWAP_REPLY_ID, ReplyOn::Always)
+    );
+    match &swap.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_json(msg).unwrap() {
+            cw20::Cw20ExecuteMsg::Send { msg, .. } => match from_json(&msg).unwrap() {
+                RouterExecuteMsg::ExecuteSwapOperations {
+                    minimum_receive,
+                    deadline: swap_deadline,
+                    ..
+                } => {
+                    assert_eq!(minimum_receive, Some(Uint128::new(39_600)));
+                    assert_eq!(swap_deadline, deadline);
+                }
+                msg => panic!("unexpected message {:?}", msg),
+            },
+            msg => panic!("unexpected message {:?}", msg),
+        },
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    // the router rejects the swap, the treasury keeps its tokens and records nothing
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: REFLECTION_SWAP_REPLY_ID,
+            result: SubMsgResult::Err("assertion failed; minimum receive amount".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "swap_skipped"));
+    let ledger: SwapLedgerResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SwapLedger {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(ledger.swaps.is_empty());
+
+    // within the max spread the liquidity is added, and the pair enforces it as well
+    protect(&mut deps, Decimal::percent(5)).unwrap();
+    let res = liquify(&mut deps);
+    assert!(res.attributes.is_empty());
+    let swap = &res.messages[1];
+    assert_eq!(
+        (swap.id, swap.reply_on.clone()),
+        (PROTECTED_SWAP_REPLY_ID, ReplyOn::Error)
+    );
+    match &swap.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_json(msg).unwrap() {
+            cw20::Cw20ExecuteMsg::Send { msg, .. } => assert_eq!(
+                from_json::<PairCw20HookMsg>(&msg).unwrap(),
+                PairCw20HookMsg::Swap {
+                    belief_price: None,
+                    max_spread: Some(Decimal::percent(5)),
+                    to: None,
+                    deadline,
+                }
+            ),
+            msg => panic!("unexpected message {:?}", msg),
+        },
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    assert_eq!(res.messages[2].reply_on, ReplyOn::Error);
+}
+
+#[test]
+fn liquify_swaps_run_on_their_venue() {
+    let mut deps = setup();
+    setup_liquify(&mut deps, Decimal::percent(50), 100_000);
+    deps.querier.with_contract_handler("adapter0000", |_| {
+        to_json_binary(&SimulationResponse {
+            return_amount: Uint128::new(20_000),
+            spread_amount: Uint128::zero(),
+            commission_amount: Uint128::zero(),
+        })
+    });
+    let set_venue = |deps: &mut MockDeps, venue: SwapVenue| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            ExecuteMsg::SetSwapVenue {
+                swap: LiquifySwap::Reflection,
+                venue: Some(venue),
+            },
+        )
+    };
+
+    // the liquidity pair does not trade the reflection target token
+    let err = set_venue(
+        &mut deps,
+        SwapVenue::Pair {
+            contract: "pair0000".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Pair does not trade"));
+    let adapter = SwapVenue::Adapter {
+        contract: "adapter0000".to_string(),
+    };
+    set_venue(&mut deps, adapter.clone()).unwrap();
+    let venues: SwapVenuesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SwapVenues {}).unwrap()).unwrap();
+    assert_eq!(
+        venues,
+        SwapVenuesResponse {
+            liquidity: Some(SwapVenue::Pair {
+                contract: "pair0000".to_string(),
+            }),
+            reflection: Some(adapter),
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetSwapProtection {
+            protection: Some(SwapProtection {
+                max_spread: Decimal::percent(5),
+                deadline: 60,
+            }),
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Liquify {},
+    )
+    .unwrap();
+    // the liquidity is still added on its pair, the reflection share is sent to the adapter
+    let swap = res.messages.last().unwrap();
+    assert_eq!(swap.id, REFLECTION_SWAP_REPLY_ID);
+    match &swap.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_json(msg).unwrap() {
+            cw20::Cw20ExecuteMsg::Send {
+                contract,
+                amount,
+                msg,
+            } => {
+                assert_eq!(
+                    (contract.as_str(), amount),
+                    ("adapter0000", Uint128::new(50_000))
+                );
+                assert_eq!(
+                    from_json::<SwapAdapterHookMsg>(&msg).unwrap(),
+                    SwapAdapterHookMsg::Swap {
+                        ask_asset_info: dojo(0).info,
+                        minimum_receive: Some(Uint128::new(19_000)),
+                        deadline: Some(mock_env().block.time.seconds() + 60),
+                    }
+                );
+            }
+            msg => panic!("unexpected message {:?}", msg),
+        },
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn simulate_distribute_previews_liquify() {
+    use crate::msg::{LiquiditySimulation, ReflectionSimulation, SimulateDistributeResponse};
+
+    let mut deps = setup();
+    setup_liquify(&mut deps, Decimal::percent(50), 100_000);
+    deps.querier.with_contract_handler("router0000", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::new(40_000),
+        })
+    });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        ExecuteMsg::SetSwapProtection {
+            protection: Some(SwapProtection {
+                max_spread: Decimal::percent(1),
+                deadline: 60,
+            }),
+        },
+    )
+    .unwrap();
+    let simulate = |deps: &MockDeps| -> SimulateDistributeResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SimulateDistribute {}).unwrap())
+            .unwrap()
+    };
+
+    let simulation = simulate(&deps);
+    assert_eq!(
+        simulation,
+        SimulateDistributeResponse {
+            balance: Uint128::new(100_000),
+            min_liquify_amt: Uint128::zero(),
+            runs: true,
+            liquidity: LiquiditySimulation {
+                amount: Uint128::new(50_000),
+                swap_amount: Uint128::new(25_000),
+                quote_amount: Uint128::new(24_318),
+                spread_amount: Uint128::new(609),
+                skipped: true,
+            },
+            reflection: ReflectionSimulation {
+                amount: Uint128::new(50_000),
+                ask: Some(dojo(0).info),
+                return_amount: Uint128::new(40_000),
+            },
+            burn_amount: Uint128::zero(),
+        }
+    );
+    // Liquify does what was simulated
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Liquify {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes, vec![attr("liquidity_skipped", "609")]);
+    assert_eq!(res.messages.len(), 1);
+}
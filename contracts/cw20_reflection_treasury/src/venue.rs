@@ -0,0 +1,233 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Decimal, Deps, DepsMut, MessageInfo, QuerierWrapper, Response, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw20_base::ContractError;
+use dojoswap::asset::{Asset, AssetInfo, PairInfo};
+use dojoswap::pair::{
+    Cw20HookMsg as PairCw20HookMsg, QueryMsg as PairQueryMsg, SimulationResponse,
+};
+use dojoswap::router::{
+    ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
+};
+
+use crate::contract::{
+    ensure_admin, LIQUIDITY_PAIR, LIQUIDITY_PAIR_CONTRACT, REFLECTION_PAIR, ROUTER, TOKEN,
+};
+use crate::msg::{
+    LiquifySwap, SwapAdapterHookMsg, SwapAdapterQueryMsg, SwapVenue, SwapVenuesResponse,
+};
+use crate::state::SWAP_VENUES;
+
+/// Sets where the treasury token is swapped for `swap`, None goes back to the default venue
+pub fn set_swap_venue(
+    deps: DepsMut,
+    info: MessageInfo,
+    swap: LiquifySwap,
+    venue: Option<SwapVenue>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    match &venue {
+        Some(venue) => {
+            deps.api.addr_validate(venue.contract())?;
+            // a pair must trade the treasury token for what the swap asks
+            if let SwapVenue::Pair { contract } = venue {
+                let token = AssetInfo::Token {
+                    contract_addr: TOKEN.load(deps.storage)?.to_string(),
+                };
+                let (ask, _) = swap_assets(deps.storage, swap)?;
+                let pair: PairInfo = deps
+                    .querier
+                    .query_wasm_smart(contract, &PairQueryMsg::Pair {})?;
+                if ![token, ask]
+                    .iter()
+                    .all(|asset| pair.asset_infos.iter().any(|info| info.equal(asset)))
+                {
+                    return Err(ContractError::Std(StdError::generic_err(
+                        "Pair does not trade the treasury token for the asked asset",
+                    )));
+                }
+            }
+            SWAP_VENUES.save(deps.storage, swap.as_str(), venue)?;
+        }
+        None => SWAP_VENUES.remove(deps.storage, swap.as_str()),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_swap_venue")
+        .add_attribute("swap", swap.as_str())
+        .add_attribute(
+            "venue",
+            venue
+                .map(|venue| venue.contract().to_string())
+                .unwrap_or_default(),
+        ))
+}
+
+/// (asset asked, quote asset routed through) of `swap`
+pub fn swap_assets(storage: &dyn Storage, swap: LiquifySwap) -> StdResult<(AssetInfo, AssetInfo)> {
+    match swap {
+        LiquifySwap::Liquidity => {
+            let pair = LIQUIDITY_PAIR
+                .may_load(storage)?
+                .ok_or_else(|| StdError::generic_err("Liquidity pair not set"))?;
+            Ok((pair[1].clone(), pair[1].clone()))
+        }
+        LiquifySwap::Reflection => {
+            let pair = REFLECTION_PAIR
+                .may_load(storage)?
+                .ok_or_else(|| StdError::generic_err("Reflection pair not set"))?;
+            Ok((pair[0].clone(), pair[1].clone()))
+        }
+    }
+}
+
+/// Venue of `swap`: the liquidity pair for the liquidity swap and the router for the reflection
+/// swap unless another one is set
+pub fn swap_venue(storage: &dyn Storage, swap: LiquifySwap) -> StdResult<SwapVenue> {
+    if let Some(venue) = SWAP_VENUES.may_load(storage, swap.as_str())? {
+        return Ok(venue);
+    }
+    Ok(match swap {
+        LiquifySwap::Liquidity => SwapVenue::Pair {
+            contract: LIQUIDITY_PAIR_CONTRACT
+                .may_load(storage)?
+                .ok_or_else(|| StdError::generic_err("Liquidity pair not set"))?,
+        },
+        LiquifySwap::Reflection => SwapVenue::Router {
+            contract: ROUTER.load(storage)?,
+        },
+    })
+}
+
+impl SwapVenue {
+    pub fn contract(&self) -> &str {
+        match self {
+            SwapVenue::Pair { contract }
+            | SwapVenue::Router { contract }
+            | SwapVenue::Adapter { contract } => contract,
+        }
+    }
+
+    /// Router operations from `token` to `ask` through `quote`
+    fn operations(token: &Addr, ask: &AssetInfo, quote: &AssetInfo) -> Vec<SwapOperation> {
+        let mut operations = vec![SwapOperation::DojoSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: token.to_string(),
+            },
+            ask_asset_info: quote.clone(),
+        }];
+        if !ask.equal(quote) {
+            operations.push(SwapOperation::DojoSwap {
+                offer_asset_info: quote.clone(),
+                ask_asset_info: ask.clone(),
+            });
+        }
+        operations
+    }
+
+    /// Simulates selling `offer_amount` of `token` for `ask`. Only pairs report a spread, a router
+    /// or an adapter only reports the return amount
+    pub fn simulate(
+        &self,
+        querier: &QuerierWrapper,
+        token: &Addr,
+        offer_amount: Uint128,
+        ask: &AssetInfo,
+        quote: &AssetInfo,
+    ) -> StdResult<SimulationResponse> {
+        let offer_asset = Asset {
+            info: AssetInfo::Token {
+                contract_addr: token.to_string(),
+            },
+            amount: offer_amount,
+        };
+        match self {
+            SwapVenue::Pair { contract } => {
+                querier.query_wasm_smart(contract, &PairQueryMsg::Simulation { offer_asset })
+            }
+            SwapVenue::Router { contract } => {
+                let simulation: SimulateSwapOperationsResponse = querier.query_wasm_smart(
+                    contract,
+                    &RouterQueryMsg::SimulateSwapOperations {
+                        offer_amount,
+                        operations: Self::operations(token, ask, quote),
+                    },
+                )?;
+                Ok(SimulationResponse {
+                    return_amount: simulation.amount,
+                    spread_amount: Uint128::zero(),
+                    commission_amount: Uint128::zero(),
+                })
+            }
+            SwapVenue::Adapter { contract } => querier.query_wasm_smart(
+                contract,
+                &SwapAdapterQueryMsg::Simulation {
+                    offer_asset,
+                    ask_asset_info: ask.clone(),
+                },
+            ),
+        }
+    }
+
+    /// Message selling `offer_amount` of `token` for `ask`, returned to the treasury. A pair
+    /// enforces `max_spread` itself, a router or an adapter must return `expected` minus the max
+    /// spread
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap_msg(
+        &self,
+        token: &Addr,
+        offer_amount: Uint128,
+        ask: &AssetInfo,
+        quote: &AssetInfo,
+        expected: Option<Uint128>,
+        max_spread: Option<Decimal>,
+        deadline: Option<u64>,
+    ) -> StdResult<WasmMsg> {
+        let minimum_receive = match (expected, max_spread) {
+            (Some(expected), Some(max_spread)) => Some(expected * (Decimal::one() - max_spread)),
+            _ => None,
+        };
+        let hook = match self {
+            SwapVenue::Pair { .. } => to_json_binary(&PairCw20HookMsg::Swap {
+                belief_price: None,
+                max_spread,
+                to: None,
+                deadline,
+            })?,
+            SwapVenue::Router { .. } => to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations: Self::operations(token, ask, quote),
+                minimum_receive,
+                to: None,
+                deadline,
+            })?,
+            SwapVenue::Adapter { .. } => to_json_binary(&SwapAdapterHookMsg::Swap {
+                ask_asset_info: ask.clone(),
+                minimum_receive,
+                deadline,
+            })?,
+        };
+        Ok(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: self.contract().to_string(),
+                amount: offer_amount,
+                msg: hook,
+            })?,
+            funds: vec![],
+        })
+    }
+}
+
+pub fn query_swap_venues(deps: Deps) -> StdResult<SwapVenuesResponse> {
+    let venue = |swap| {
+        // the default venues are only known once the pairs are set
+        swap_venue(deps.storage, swap).ok()
+    };
+    Ok(SwapVenuesResponse {
+        liquidity: venue(LiquifySwap::Liquidity),
+        reflection: venue(LiquifySwap::Reflection),
+    })
+}
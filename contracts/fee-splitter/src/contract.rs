@@ -0,0 +1,263 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Addr, Api, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, OwedResponse, QueryMsg, RecipientMsg,
+    RecipientsResponse,
+};
+use crate::state::{Config, Recipient, CONFIG, OWED, RECIPIENTS, TOTAL_OWED};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "dojoswap:fee-splitter";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Most recipients of a splitter, every split credits each of them
+pub const MAX_RECIPIENTS: usize = 20;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin: deps.api.addr_validate(&msg.admin)?,
+            token: deps.api.addr_validate(&msg.token)?,
+        },
+    )?;
+    RECIPIENTS.save(
+        deps.storage,
+        &validate_recipients(deps.api, msg.recipients)?,
+    )?;
+    TOTAL_OWED.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Fund {} => execute_fund(deps, env),
+        ExecuteMsg::Withdraw {} => execute_withdraw(deps, env, info),
+        ExecuteMsg::UpdateRecipients { recipients } => {
+            update_recipients(deps, env, info, recipients)
+        }
+        ExecuteMsg::UpdateAdmin { admin } => update_admin(deps, info, admin),
+    }
+}
+
+fn validate_recipients(
+    api: &dyn Api,
+    recipients: Vec<RecipientMsg>,
+) -> Result<Vec<Recipient>, ContractError> {
+    let invalid = ContractError::InvalidRecipients {
+        max: MAX_RECIPIENTS,
+    };
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(invalid);
+    }
+    let mut validated: Vec<Recipient> = vec![];
+    for recipient in recipients {
+        let address = api.addr_validate(&recipient.address)?;
+        if recipient.weight == 0 || validated.iter().any(|other| other.address == address) {
+            return Err(invalid);
+        }
+        validated.push(Recipient {
+            address,
+            weight: recipient.weight,
+        });
+    }
+    Ok(validated)
+}
+
+fn ensure_admin(deps: &DepsMut, info: &MessageInfo) -> Result<Config, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(config)
+}
+
+/// Fees credited to the splitter and not split yet: its balance above what it owes
+fn unsplit_fees(deps: Deps, env: &Env) -> StdResult<Uint128> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &config.token,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    Ok(balance
+        .balance
+        .saturating_sub(TOTAL_OWED.load(deps.storage)?))
+}
+
+/// Share of `fees` owed to each recipient, rounded down. What the weights cannot split evenly
+/// stays unsplit until the next split
+fn shares(recipients: &[Recipient], fees: Uint128) -> Vec<(&Addr, Uint128)> {
+    let total_weight: u64 = recipients.iter().map(|recipient| recipient.weight).sum();
+    recipients
+        .iter()
+        .map(|recipient| {
+            (
+                &recipient.address,
+                fees.multiply_ratio(recipient.weight, total_weight),
+            )
+        })
+        .collect()
+}
+
+/// Splits the unsplit fees with the current weights, returns the amount split
+fn split_fees(deps: &mut DepsMut, env: &Env) -> StdResult<Uint128> {
+    let fees = unsplit_fees(deps.as_ref(), env)?;
+    if fees.is_zero() {
+        return Ok(fees);
+    }
+    let recipients = RECIPIENTS.load(deps.storage)?;
+    let mut split = Uint128::zero();
+    for (address, share) in shares(&recipients, fees) {
+        if share.is_zero() {
+            continue;
+        }
+        OWED.update(deps.storage, address, |owed| -> StdResult<_> {
+            Ok(owed.unwrap_or_default() + share)
+        })?;
+        split += share;
+    }
+    TOTAL_OWED.update(deps.storage, |total| -> StdResult<_> { Ok(total + split) })?;
+    Ok(split)
+}
+
+pub fn execute_fund(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let split = split_fees(&mut deps, &env)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund")
+        .add_attribute("amount", split))
+}
+
+pub fn execute_withdraw(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    split_fees(&mut deps, &env)?;
+    let amount = OWED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    OWED.remove(deps.storage, &info.sender);
+    TOTAL_OWED.update(deps.storage, |total| -> StdResult<_> { Ok(total - amount) })?;
+
+    let config = CONFIG.load(deps.storage)?;
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: config.token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("action", "withdraw")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn update_recipients(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<RecipientMsg>,
+) -> Result<Response, ContractError> {
+    ensure_admin(&deps, &info)?;
+    let recipients = validate_recipients(deps.api, recipients)?;
+    // the fees credited under the previous weights are split with them
+    split_fees(&mut deps, &env)?;
+    RECIPIENTS.save(deps.storage, &recipients)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_recipients")
+        .add_attribute("recipients", recipients.len().to_string()))
+}
+
+pub fn update_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: String,
+) -> Result<Response, ContractError> {
+    let mut config = ensure_admin(&deps, &info)?;
+    config.admin = deps.api.addr_validate(&admin)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_admin")
+        .add_attribute("admin", admin))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Recipients {} => to_json_binary(&query_recipients(deps)?),
+        QueryMsg::Owed { address } => to_json_binary(&query_owed(deps, env, address)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: config.admin.to_string(),
+        token: config.token.to_string(),
+    })
+}
+
+pub fn query_recipients(deps: Deps) -> StdResult<RecipientsResponse> {
+    let recipients = RECIPIENTS.load(deps.storage)?;
+    Ok(RecipientsResponse {
+        total_weight: recipients.iter().map(|recipient| recipient.weight).sum(),
+        recipients: recipients
+            .into_iter()
+            .map(|recipient| RecipientMsg {
+                address: recipient.address.to_string(),
+                weight: recipient.weight,
+            })
+            .collect(),
+    })
+}
+
+pub fn query_owed(deps: Deps, env: Env, address: String) -> StdResult<OwedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let recipients = RECIPIENTS.load(deps.storage)?;
+    let unsplit = shares(&recipients, unsplit_fees(deps, &env)?)
+        .into_iter()
+        .find(|(recipient, _)| **recipient == address)
+        .map(|(_, share)| share)
+        .unwrap_or_default();
+    Ok(OwedResponse {
+        owed: OWED.may_load(deps.storage, &address)?.unwrap_or_default() + unsplit,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(Response::default())
+}
@@ -0,0 +1,17 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Recipients must be 1 to {max} distinct addresses with a non zero weight")]
+    InvalidRecipients { max: usize },
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+}
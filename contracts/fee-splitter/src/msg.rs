@@ -0,0 +1,68 @@
+use cosmwasm_std::Uint128;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RecipientMsg {
+    pub address: String,
+    /// Share of the fees relative to the weights of the other recipients
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub admin: String,
+    /// Token crediting the fees, e.g. a reflection token with the splitter as the treasury address
+    /// of its tax config
+    pub token: String,
+    pub recipients: Vec<RecipientMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Splits the fees credited since the last split between the recipients by weight. Anyone
+    /// can call it, `Withdraw` and `UpdateRecipients` split first as well
+    Fund {},
+    /// Transfers the share of the sender to it
+    Withdraw {},
+    /// Replaces the recipients, the fees credited until now are split with the previous weights
+    UpdateRecipients {
+        recipients: Vec<RecipientMsg>,
+    },
+    UpdateAdmin {
+        admin: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Return type: ConfigResponse.
+    Config {},
+    /// Return type: RecipientsResponse.
+    Recipients {},
+    /// Returns what `address` can withdraw, the fees not split yet included.
+    /// Return type: OwedResponse.
+    Owed { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RecipientsResponse {
+    pub recipients: Vec<RecipientMsg>,
+    pub total_weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OwedResponse {
+    pub owed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct MigrateMsg {}
@@ -0,0 +1,27 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    pub admin: Addr,
+    /// Token whose balance is split
+    pub token: Addr,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Recipient {
+    pub address: Addr,
+    pub weight: u64,
+}
+
+pub const RECIPIENTS: Item<Vec<Recipient>> = Item::new("recipients");
+
+// recipient -> share of the fees split so far and not withdrawn yet
+pub const OWED: Map<&Addr, Uint128> = Map::new("owed");
+
+// Sum of `OWED`, the balance above it is fees not split yet
+pub const TOTAL_OWED: Item<Uint128> = Item::new("total_owed");
@@ -0,0 +1,262 @@
+//! The splitter wired as the treasury address of a reflection token tax config against
+//! multi-test: the tax credited by every transfer is split by weight and withdrawn by each
+//! recipient.
+
+use cosmwasm_std::{
+    Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+};
+use cw20::{BalanceResponse, Cw20Coin};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+use cw20_reflection_token::msg::{
+    ExecuteMsg as TokenExecuteMsg, InstantiateMsg as TokenInstantiateMsg, QueryMsg as TokenQueryMsg,
+};
+use cw20_reflection_token::state::{TaxConfig, TaxDestination};
+use fee_splitter::error::ContractError;
+use fee_splitter::msg::{ExecuteMsg, InstantiateMsg, OwedResponse, QueryMsg, RecipientMsg};
+
+const ADMIN: &str = "admin";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const TEAM: &str = "team";
+const MARKETING: &str = "marketing";
+const DEV: &str = "dev";
+
+/// Treasury accepting whatever the token sends it, the taxes go to the splitter here
+mod mock_treasury {
+    use super::*;
+
+    pub fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        Err(StdError::generic_err("unsupported"))
+    }
+}
+
+fn token_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            cw20_reflection_token::contract::execute,
+            cw20_reflection_token::contract::instantiate,
+            cw20_reflection_token::contract::query,
+        )
+        .with_reply(cw20_reflection_token::contract::reply),
+    )
+}
+
+fn splitter_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        fee_splitter::contract::execute,
+        fee_splitter::contract::instantiate,
+        fee_splitter::contract::query,
+    ))
+}
+
+fn recipients(weights: &[(&str, u64)]) -> Vec<RecipientMsg> {
+    weights
+        .iter()
+        .map(|(address, weight)| RecipientMsg {
+            address: address.to_string(),
+            weight: *weight,
+        })
+        .collect()
+}
+
+struct Suite {
+    app: App,
+    token: Addr,
+    splitter: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = App::default();
+        let token_code_id = app.store_code(token_contract());
+        let treasury_code_id = app.store_code(Box::new(ContractWrapper::new(
+            mock_treasury::execute,
+            mock_treasury::instantiate,
+            mock_treasury::query,
+        )));
+        let splitter_code_id = app.store_code(splitter_contract());
+
+        let token = app
+            .instantiate_contract(
+                token_code_id,
+                Addr::unchecked(ADMIN),
+                &TokenInstantiateMsg {
+                    name: "Baby Token".to_string(),
+                    symbol: "BABY".to_string(),
+                    decimals: 6,
+                    treasury_code_id,
+                    cw20_code_id: None,
+                    initial_balances: vec![Cw20Coin {
+                        address: ALICE.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                    admin: ADMIN.to_string(),
+                    router: "router".to_string(),
+                    mint: None,
+                    marketing: None,
+                    max_tax_rate: None,
+                    max_tax_step: None,
+                    reflection_mode: None,
+                    max_tx_amount: None,
+                    max_wallet_amount: None,
+                    vesting: None,
+                },
+                &[],
+                "token",
+                None,
+            )
+            .unwrap();
+        let splitter = app
+            .instantiate_contract(
+                splitter_code_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg {
+                    admin: ADMIN.to_string(),
+                    token: token.to_string(),
+                    recipients: recipients(&[(TEAM, 2), (MARKETING, 1)]),
+                },
+                &[],
+                "splitter",
+                None,
+            )
+            .unwrap();
+
+        // a 4% tax credited to the splitter, whose withdrawals are not taxed
+        for msg in [
+            TokenExecuteMsg::UpdateTaxConfig {
+                tax_config: Some(TaxConfig {
+                    reflection_bps: 0,
+                    burn_bps: 0,
+                    treasury: Some(TaxDestination {
+                        address: splitter.to_string(),
+                        bps: 400,
+                    }),
+                }),
+            },
+            TokenExecuteMsg::SetWhitelist {
+                user: splitter.to_string(),
+                enable: true,
+            },
+        ] {
+            app.execute_contract(Addr::unchecked(ADMIN), token.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        Suite {
+            app,
+            token,
+            splitter,
+        }
+    }
+
+    fn trade(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(ALICE),
+                self.token.clone(),
+                &TokenExecuteMsg::Transfer {
+                    recipient: BOB.to_string(),
+                    amount: Uint128::new(amount),
+                    min_receive: None,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn execute(&mut self, sender: &str, msg: ExecuteMsg) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.splitter.clone(), &msg, &[])
+            .map(|_| ())
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn balance(&self, address: &str) -> Uint128 {
+        let response: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.token,
+                &TokenQueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance
+    }
+
+    fn owed(&self, address: &str) -> Uint128 {
+        let response: OwedResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.splitter,
+                &QueryMsg::Owed {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.owed
+    }
+}
+
+#[test]
+fn taxes_are_split_by_weight_and_pulled() {
+    let mut suite = Suite::new();
+
+    // the tax is credited without a call to the splitter, the shares are owed already
+    suite.trade(100_000);
+    assert_eq!(suite.balance(suite.splitter.as_str()), Uint128::new(4_000));
+    assert_eq!(suite.owed(TEAM), Uint128::new(2_666));
+    assert_eq!(suite.owed(MARKETING), Uint128::new(1_333));
+
+    suite.execute(TEAM, ExecuteMsg::Withdraw {}).unwrap();
+    assert_eq!(suite.balance(TEAM), Uint128::new(2_666));
+    assert_eq!(suite.owed(TEAM), Uint128::zero());
+    assert_eq!(
+        suite.execute(TEAM, ExecuteMsg::Withdraw {}).unwrap_err(),
+        ContractError::NothingToWithdraw {}
+    );
+
+    // new weights apply to the fees credited from now on
+    let update = ExecuteMsg::UpdateRecipients {
+        recipients: recipients(&[(TEAM, 1), (MARKETING, 1), (DEV, 2)]),
+    };
+    assert_eq!(
+        suite.execute(TEAM, update.clone()).unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+    assert_eq!(
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateRecipients {
+                    recipients: recipients(&[(TEAM, 1), (TEAM, 1)]),
+                },
+            )
+            .unwrap_err(),
+        ContractError::InvalidRecipients { max: 20 }
+    );
+    suite.execute(ADMIN, update).unwrap();
+    suite.trade(100_000);
+    suite.execute(BOB, ExecuteMsg::Fund {}).unwrap();
+    assert_eq!(suite.owed(TEAM), Uint128::new(1_000));
+    assert_eq!(suite.owed(DEV), Uint128::new(2_000));
+
+    // marketing kept its share of the first round
+    suite.execute(MARKETING, ExecuteMsg::Withdraw {}).unwrap();
+    assert_eq!(suite.balance(MARKETING), Uint128::new(2_333));
+    suite.execute(DEV, ExecuteMsg::Withdraw {}).unwrap();
+    suite.execute(TEAM, ExecuteMsg::Withdraw {}).unwrap();
+    // only the rounding of the splits is left, split with the next fees
+    assert_eq!(suite.balance(suite.splitter.as_str()), Uint128::new(1));
+}
@@ -0,0 +1,26 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use reflection_factory::msg::{
+    BootstrapDepositResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, LaunchesResponse,
+    QueryMsg,
+};
+use reflection_factory::state::{BootstrapInfo, LaunchInfo};
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(LaunchInfo), &out_dir);
+    export_schema(&schema_for!(LaunchesResponse), &out_dir);
+    export_schema(&schema_for!(BootstrapInfo), &out_dir);
+    export_schema(&schema_for!(BootstrapDepositResponse), &out_dir);
+}
@@ -0,0 +1,295 @@
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, BankMsg, Deps, DepsMut, Env, MessageInfo, QuerierWrapper,
+    Response, StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::contract::{quote_funds, start_launch, TokenParams};
+use crate::error::ContractError;
+use crate::msg::{BootstrapDepositResponse, BootstrapStatus, LaunchParams};
+use crate::state::{
+    BootstrapInfo, LaunchInfo, BOOTSTRAPS, BOOTSTRAP_DEPOSITS, CONFIG, NEXT_BOOTSTRAP_ID,
+};
+
+pub fn start_bootstrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    launch: LaunchParams,
+    hard_cap: Uint128,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    if hard_cap.is_zero() || end_time <= env.block.time.seconds() {
+        return Err(ContractError::InvalidBootstrap {});
+    }
+    if launch.liquidity_amount.is_zero() || launch.liquidity_amount > launch.initial_supply {
+        return Err(ContractError::InvalidLiquidityAmount {});
+    }
+
+    let id = NEXT_BOOTSTRAP_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_BOOTSTRAP_ID.save(deps.storage, &(id + 1))?;
+    BOOTSTRAPS.save(
+        deps.storage,
+        id,
+        &BootstrapInfo {
+            id,
+            creator: info.sender.clone(),
+            launch,
+            hard_cap,
+            end_time,
+            total_deposited: Uint128::zero(),
+            status: BootstrapStatus::Open,
+            token: None,
+            liquidity_token: None,
+            total_liquidity: None,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_bootstrap")
+        .add_attribute("id", id.to_string())
+        .add_attribute("creator", info.sender))
+}
+
+pub fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut bootstrap = load_with_status(deps.storage, id, BootstrapStatus::Open)?;
+    if env.block.time.seconds() >= bootstrap.end_time {
+        return Err(ContractError::InvalidBootstrapStatus {
+            status: "ended".to_string(),
+        });
+    }
+    let amount = quote_funds(&config, &info)?;
+    let remaining = bootstrap.hard_cap - bootstrap.total_deposited;
+    if amount > remaining {
+        return Err(ContractError::HardCapExceeded { remaining });
+    }
+
+    bootstrap.total_deposited += amount;
+    BOOTSTRAPS.save(deps.storage, id, &bootstrap)?;
+    BOOTSTRAP_DEPOSITS.update(
+        deps.storage,
+        (id, &info.sender),
+        |deposit| -> StdResult<_> { Ok(deposit.unwrap_or_default() + amount) },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "bootstrap_deposit")
+        .add_attribute("id", id.to_string())
+        .add_attribute("depositor", info.sender)
+        .add_attribute("amount", amount))
+}
+
+/// Launches the token with every deposit as the quote side of the liquidity, `seed_pair` marks the
+/// bootstrap launched once the pair is seeded
+pub fn close_bootstrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let bootstrap = load_with_status(deps.storage, id, BootstrapStatus::Open)?;
+    let ended = env.block.time.seconds() >= bootstrap.end_time
+        || bootstrap.total_deposited == bootstrap.hard_cap;
+    if info.sender != bootstrap.creator && !ended {
+        return Err(ContractError::BootstrapNotEnded {});
+    }
+    if bootstrap.total_deposited.is_zero() {
+        return Err(ContractError::NothingDeposited {});
+    }
+
+    let launch = bootstrap.launch;
+    let res = start_launch(
+        deps,
+        env,
+        bootstrap.creator,
+        TokenParams {
+            name: launch.name,
+            symbol: launch.symbol,
+            decimals: launch.decimals,
+            marketing: launch.marketing,
+        },
+        launch.initial_supply,
+        launch.liquidity_amount,
+        bootstrap.total_deposited,
+        launch.tax,
+        Some(id),
+    )?;
+    Ok(res.add_attribute("bootstrap", id.to_string()))
+}
+
+/// Called by `seed_pair` once the pair of a bootstrap is seeded with its deposits
+pub fn bootstrap_launched(
+    storage: &mut dyn Storage,
+    id: u64,
+    launch: &LaunchInfo,
+) -> Result<(), ContractError> {
+    let mut bootstrap = load_with_status(storage, id, BootstrapStatus::Open)?;
+    bootstrap.status = BootstrapStatus::Launched;
+    bootstrap.token = Some(launch.token.clone());
+    bootstrap.liquidity_token = Some(launch.liquidity_token.clone());
+    BOOTSTRAPS.save(storage, id, &bootstrap)?;
+    Ok(())
+}
+
+pub fn cancel_bootstrap(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let mut bootstrap = load_with_status(deps.storage, id, BootstrapStatus::Open)?;
+    if info.sender != bootstrap.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    bootstrap.status = BootstrapStatus::Cancelled;
+    BOOTSTRAPS.save(deps.storage, id, &bootstrap)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_bootstrap")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Sends the depositor its pro-rata share of the LP tokens, the rounding dust stays with the factory
+pub fn claim_bootstrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let mut bootstrap = load_with_status(deps.storage, id, BootstrapStatus::Launched)?;
+    let deposit = take_deposit(deps.storage, id, &info.sender)?;
+    let liquidity_token = bootstrap
+        .liquidity_token
+        .clone()
+        .ok_or_else(|| StdError::generic_err("liquidity token not known"))?;
+    if bootstrap.total_liquidity.is_none() {
+        bootstrap.total_liquidity = Some(total_liquidity(&deps.querier, &env, &bootstrap)?);
+        BOOTSTRAPS.save(deps.storage, id, &bootstrap)?;
+    }
+    let amount = liquidity_share(&bootstrap, deposit);
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: liquidity_token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("action", "claim_bootstrap")
+        .add_attribute("id", id.to_string())
+        .add_attribute("depositor", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn refund_bootstrap(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    load_with_status(deps.storage, id, BootstrapStatus::Cancelled)?;
+    let deposit = take_deposit(deps.storage, id, &info.sender)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(deposit.u128(), config.quote_denom),
+        })
+        .add_attribute("action", "refund_bootstrap")
+        .add_attribute("id", id.to_string())
+        .add_attribute("depositor", info.sender)
+        .add_attribute("amount", deposit))
+}
+
+pub fn query_bootstrap_deposit(
+    deps: Deps,
+    env: Env,
+    id: u64,
+    depositor: String,
+) -> StdResult<BootstrapDepositResponse> {
+    let mut bootstrap = BOOTSTRAPS.load(deps.storage, id)?;
+    let depositor = deps.api.addr_validate(&depositor)?;
+    let deposited = BOOTSTRAP_DEPOSITS
+        .may_load(deps.storage, (id, &depositor))?
+        .unwrap_or_default();
+
+    let mut res = BootstrapDepositResponse {
+        deposited,
+        ..BootstrapDepositResponse::default()
+    };
+    match bootstrap.status {
+        BootstrapStatus::Open => {}
+        BootstrapStatus::Launched => {
+            if bootstrap.total_liquidity.is_none() {
+                bootstrap.total_liquidity = Some(total_liquidity(&deps.querier, &env, &bootstrap)?);
+            }
+            res.claimable_liquidity = liquidity_share(&bootstrap, deposited);
+        }
+        BootstrapStatus::Cancelled => res.refundable = deposited,
+    }
+    Ok(res)
+}
+
+fn load_with_status(
+    storage: &dyn Storage,
+    id: u64,
+    status: BootstrapStatus,
+) -> Result<BootstrapInfo, ContractError> {
+    let bootstrap = BOOTSTRAPS.load(storage, id)?;
+    if bootstrap.status != status {
+        return Err(ContractError::InvalidBootstrapStatus {
+            status: format!("{:?}", bootstrap.status).to_lowercase(),
+        });
+    }
+    Ok(bootstrap)
+}
+
+/// Removes the deposit of `depositor`, which is then claimed or refunded
+fn take_deposit(
+    storage: &mut dyn Storage,
+    id: u64,
+    depositor: &Addr,
+) -> Result<Uint128, ContractError> {
+    let deposit = BOOTSTRAP_DEPOSITS
+        .may_load(storage, (id, depositor))?
+        .unwrap_or_default();
+    if deposit.is_zero() {
+        return Err(ContractError::NothingDeposited {});
+    }
+    BOOTSTRAP_DEPOSITS.remove(storage, (id, depositor));
+    Ok(deposit)
+}
+
+/// LP tokens held by the factory for a launched bootstrap, which are all minted to its depositors
+/// as long as none claimed
+fn total_liquidity(
+    querier: &QuerierWrapper,
+    env: &Env,
+    bootstrap: &BootstrapInfo,
+) -> StdResult<Uint128> {
+    let liquidity_token = match &bootstrap.liquidity_token {
+        Some(liquidity_token) => liquidity_token,
+        None => return Ok(Uint128::zero()),
+    };
+    let balance: BalanceResponse = querier.query_wasm_smart(
+        liquidity_token,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    Ok(balance.balance)
+}
+
+fn liquidity_share(bootstrap: &BootstrapInfo, deposit: Uint128) -> Uint128 {
+    bootstrap
+        .total_liquidity
+        .unwrap_or_default()
+        .multiply_ratio(deposit, bootstrap.total_deposited)
+}
@@ -0,0 +1,555 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, SubMsg, SubMsgResponse, Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{Cw20Coin, Cw20ExecuteMsg};
+use cw_storage_plus::Bound;
+use dojoswap::asset::{Asset, AssetInfo, PairInfo};
+use dojoswap::factory::{ExecuteMsg as DojoFactoryExecuteMsg, QueryMsg as DojoFactoryQueryMsg};
+use dojoswap::pair::ExecuteMsg as PairExecuteMsg;
+
+use cw20_reflection_token::msg::{
+    ExecuteMsg as TokenExecuteMsg, InstantiateMarketingInfo, InstantiateMsg as TokenInstantiateMsg,
+};
+use cw20_reflection_treasury::msg::ExecuteMsg as TreasuryExecuteMsg;
+
+use crate::bootstrap::{
+    bootstrap_launched, cancel_bootstrap, claim_bootstrap, close_bootstrap, execute_deposit,
+    query_bootstrap_deposit, refund_bootstrap, start_bootstrap,
+};
+use crate::error::ContractError;
+use crate::msg::{
+    AdminTarget, ConfigResponse, ExecuteMsg, InstantiateMsg, LaunchesResponse, MigrateMsg,
+    QueryMsg, TaxRates,
+};
+use crate::state::{
+    Config, LaunchInfo, PendingLaunch, BOOTSTRAPS, CONFIG, CREATOR_LAUNCHES, LAUNCHES,
+    PENDING_LAUNCH,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "dojoswap:reflection-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+pub const CREATE_PAIR_REPLY_ID: u64 = 2;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // the treasury adds liquidity and swaps reflections through the same quote asset
+    let quote = AssetInfo::NativeToken {
+        denom: msg.quote_denom.clone(),
+    };
+    if !msg.reflection_pair[1].equal(&quote) {
+        return Err(ContractError::InvalidReflectionPair {
+            denom: msg.quote_denom,
+        });
+    }
+
+    let config = Config {
+        admin: deps.api.addr_validate(&msg.admin)?,
+        token_code_id: msg.token_code_id,
+        treasury_code_id: msg.treasury_code_id,
+        dojoswap_factory: deps.api.addr_validate(&msg.dojoswap_factory)?,
+        router: deps.api.addr_validate(&msg.router)?,
+        quote_denom: msg.quote_denom,
+        reflection_pair: msg.reflection_pair,
+        reflection_pair_contract: deps.api.addr_validate(&msg.reflection_pair_contract)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Launch {
+            name,
+            symbol,
+            decimals,
+            initial_supply,
+            liquidity_amount,
+            tax,
+            marketing,
+        } => execute_launch(
+            deps,
+            env,
+            info,
+            TokenParams {
+                name,
+                symbol,
+                decimals,
+                marketing,
+            },
+            initial_supply,
+            liquidity_amount,
+            tax,
+        ),
+        ExecuteMsg::AdminExecute { token, target, msg } => {
+            execute_admin(deps, info, token, target, msg)
+        }
+        ExecuteMsg::StartBootstrap {
+            launch,
+            hard_cap,
+            end_time,
+        } => start_bootstrap(deps, env, info, launch, hard_cap, end_time),
+        ExecuteMsg::Deposit { id } => execute_deposit(deps, env, info, id),
+        ExecuteMsg::CloseBootstrap { id } => close_bootstrap(deps, env, info, id),
+        ExecuteMsg::CancelBootstrap { id } => cancel_bootstrap(deps, info, id),
+        ExecuteMsg::ClaimBootstrap { id } => claim_bootstrap(deps, env, info, id),
+        ExecuteMsg::RefundBootstrap { id } => refund_bootstrap(deps, info, id),
+        ExecuteMsg::UpdateConfig {
+            admin,
+            token_code_id,
+            treasury_code_id,
+        } => update_config(deps, info, admin, token_code_id, treasury_code_id),
+    }
+}
+
+pub struct TokenParams {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// Starts a launch by instantiating the token, the rest of the wiring happens in the replies.
+/// Every step is a submessage of this execution, so a failure anywhere reverts the whole launch
+pub fn execute_launch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: TokenParams,
+    initial_supply: Uint128,
+    liquidity_amount: Uint128,
+    tax: TaxRates,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let quote_amount = quote_funds(&config, &info)?;
+    start_launch(
+        deps,
+        env,
+        info.sender,
+        token,
+        initial_supply,
+        liquidity_amount,
+        quote_amount,
+        tax,
+        None,
+    )
+}
+
+/// Amount of the single `quote_denom` coin attached to a message
+pub fn quote_funds(config: &Config, info: &MessageInfo) -> Result<Uint128, ContractError> {
+    match info.funds.as_slice() {
+        [coin] if coin.denom == config.quote_denom && !coin.amount.is_zero() => Ok(coin.amount),
+        _ => Err(ContractError::InvalidLiquidityFunds {
+            denom: config.quote_denom.clone(),
+        }),
+    }
+}
+
+/// Instantiates the token of a launch whose `quote_amount` of liquidity the factory already holds.
+/// The LP tokens go to the creator, or stay with the factory for the depositors of `bootstrap`
+#[allow(clippy::too_many_arguments)]
+pub fn start_launch(
+    deps: DepsMut,
+    env: Env,
+    creator: Addr,
+    token: TokenParams,
+    initial_supply: Uint128,
+    liquidity_amount: Uint128,
+    quote_amount: Uint128,
+    tax: TaxRates,
+    bootstrap: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if PENDING_LAUNCH.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::LaunchInProgress {});
+    }
+    if liquidity_amount.is_zero() || liquidity_amount > initial_supply {
+        return Err(ContractError::InvalidLiquidityAmount {});
+    }
+
+    // the factory holds the liquidity share until it seeds the pair
+    let mut initial_balances = vec![Cw20Coin {
+        address: env.contract.address.to_string(),
+        amount: liquidity_amount,
+    }];
+    let creator_amount = initial_supply - liquidity_amount;
+    if !creator_amount.is_zero() {
+        initial_balances.push(Cw20Coin {
+            address: creator.to_string(),
+            amount: creator_amount,
+        });
+    }
+
+    let label = format!("{} reflection token", token.symbol);
+    let token_msg = TokenInstantiateMsg {
+        name: token.name,
+        symbol: token.symbol,
+        decimals: token.decimals,
+        treasury_code_id: config.treasury_code_id,
+        cw20_code_id: None,
+        initial_balances,
+        admin: env.contract.address.to_string(),
+        router: config.router.to_string(),
+        mint: None,
+        marketing: token.marketing,
+        max_tax_rate: None,
+        max_tax_step: None,
+        reflection_mode: None,
+        max_tx_amount: None,
+        max_wallet_amount: None,
+        vesting: None,
+    };
+    // rejected here with the reason rather than by the instantiation of the token
+    token_msg.validate(deps.api)?;
+
+    PENDING_LAUNCH.save(
+        deps.storage,
+        &PendingLaunch {
+            creator: creator.clone(),
+            liquidity_amount,
+            quote_amount,
+            tax,
+            token: None,
+            treasury: None,
+            bootstrap,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Instantiate {
+                admin: Some(creator.to_string()),
+                code_id: config.token_code_id,
+                msg: to_json_binary(&token_msg)?,
+                funds: vec![],
+                label,
+            },
+            INSTANTIATE_TOKEN_REPLY_ID,
+        ))
+        .add_attribute("action", "launch")
+        .add_attribute("creator", creator))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    let response = reply.result.into_result().map_err(StdError::generic_err)?;
+    match reply.id {
+        INSTANTIATE_TOKEN_REPLY_ID => create_pair(deps, response),
+        CREATE_PAIR_REPLY_ID => seed_pair(deps, env),
+        _id => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Returns the address of the contract with `code_id` among the instantiate events of a reply
+pub fn find_instantiated(events: &[Event], code_id: u64) -> Result<Addr, ContractError> {
+    let code_id_str = code_id.to_string();
+    events
+        .iter()
+        .filter(|event| event.ty == "instantiate")
+        .find(|event| {
+            event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "code_id" && attr.value == code_id_str)
+        })
+        .and_then(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "_contract_address" || attr.key == "_contract_addr")
+        })
+        .map(|attr| Addr::unchecked(attr.value.clone()))
+        .ok_or(ContractError::InstantiateEventNotFound { code_id })
+}
+
+/// The token and the treasury it instantiated are known, creates the pair against the quote denom
+fn create_pair(deps: DepsMut, response: SubMsgResponse) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut pending = PENDING_LAUNCH.load(deps.storage)?;
+    let token = find_instantiated(&response.events, config.token_code_id)?;
+    let treasury = find_instantiated(&response.events, config.treasury_code_id)?;
+    pending.token = Some(token.clone());
+    pending.treasury = Some(treasury);
+    PENDING_LAUNCH.save(deps.storage, &pending)?;
+
+    let assets = liquidity_pair(&token, &config.quote_denom).map(|info| Asset {
+        info,
+        amount: Uint128::zero(),
+    });
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: config.dojoswap_factory.to_string(),
+            msg: to_json_binary(&DojoFactoryExecuteMsg::CreatePair { assets })?,
+            funds: vec![],
+        },
+        CREATE_PAIR_REPLY_ID,
+    )))
+}
+
+/// Points the treasury at the new pair, seeds it and enables the taxes last, so that seeding is not
+/// taxed and the first taxed transfer already finds a fully configured treasury
+fn seed_pair(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending = PENDING_LAUNCH.load(deps.storage)?;
+    PENDING_LAUNCH.remove(deps.storage);
+    let token = pending
+        .token
+        .ok_or_else(|| StdError::generic_err("token not instantiated"))?;
+    let treasury = pending
+        .treasury
+        .ok_or_else(|| StdError::generic_err("treasury not instantiated"))?;
+
+    let asset_infos = liquidity_pair(&token, &config.quote_denom);
+    let pair_info: PairInfo = deps.querier.query_wasm_smart(
+        &config.dojoswap_factory,
+        &DojoFactoryQueryMsg::Pair {
+            asset_infos: asset_infos.clone(),
+        },
+    )?;
+    let pair = deps.api.addr_validate(&pair_info.contract_addr)?;
+
+    let messages = vec![
+        WasmMsg::Execute {
+            contract_addr: treasury.to_string(),
+            msg: to_json_binary(&TreasuryExecuteMsg::SetReflectionPair {
+                asset_infos: config.reflection_pair.clone(),
+                pair_contract: config.reflection_pair_contract.to_string(),
+            })?,
+            funds: vec![],
+        },
+        WasmMsg::Execute {
+            contract_addr: treasury.to_string(),
+            msg: to_json_binary(&TreasuryExecuteMsg::SetLiquidityPair {
+                asset_infos: asset_infos.clone(),
+                pair_contract: pair.to_string(),
+            })?,
+            funds: vec![],
+        },
+        WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                spender: pair.to_string(),
+                amount: pending.liquidity_amount,
+                expires: None,
+            })?,
+            funds: vec![],
+        },
+        WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&PairExecuteMsg::ProvideLiquidity {
+                assets: [
+                    Asset {
+                        info: asset_infos[0].clone(),
+                        amount: pending.liquidity_amount,
+                    },
+                    Asset {
+                        info: asset_infos[1].clone(),
+                        amount: pending.quote_amount,
+                    },
+                ],
+                receiver: Some(match pending.bootstrap {
+                    Some(_) => env.contract.address.to_string(),
+                    None => pending.creator.to_string(),
+                }),
+                deadline: None,
+                slippage_tolerance: None,
+            })?,
+            funds: coins(pending.quote_amount.u128(), &config.quote_denom),
+        },
+        WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&TokenExecuteMsg::SetTaxRate {
+                global_rate: pending.tax.global_rate,
+                reflection_rate: pending.tax.reflection_rate,
+                burn_rate: pending.tax.burn_rate,
+                antiwhale_rate: pending.tax.antiwhale_rate,
+            })?,
+            funds: vec![],
+        },
+    ];
+
+    let launch = LaunchInfo {
+        creator: pending.creator,
+        token,
+        treasury,
+        pair,
+        liquidity_token: deps.api.addr_validate(&pair_info.liquidity_token)?,
+        launched_at: env.block.time.seconds(),
+    };
+    LAUNCHES.save(deps.storage, &launch.token, &launch)?;
+    CREATOR_LAUNCHES.save(
+        deps.storage,
+        (&launch.creator, &launch.token),
+        &launch.launched_at,
+    )?;
+    if let Some(id) = pending.bootstrap {
+        bootstrap_launched(deps.storage, id, &launch)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "seed_pair")
+        .add_attribute("token", launch.token)
+        .add_attribute("treasury", launch.treasury)
+        .add_attribute("pair", launch.pair))
+}
+
+fn liquidity_pair(token: &Addr, quote_denom: &str) -> [AssetInfo; 2] {
+    [
+        AssetInfo::Token {
+            contract_addr: token.to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: quote_denom.to_string(),
+        },
+    ]
+}
+
+/// Forwards an admin message to the token or the treasury of a launch, only for its creator
+pub fn execute_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: String,
+    target: AdminTarget,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let token = deps.api.addr_validate(&token)?;
+    let launch = LAUNCHES
+        .may_load(deps.storage, &token)?
+        .ok_or(ContractError::Unauthorized {})?;
+    if launch.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let contract_addr = match target {
+        AdminTarget::Token => launch.token,
+        AdminTarget::Treasury => launch.treasury,
+    };
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg,
+            funds: info.funds,
+        })
+        .add_attribute("action", "admin_execute")
+        .add_attribute("contract", contract_addr))
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Option<String>,
+    token_code_id: Option<u64>,
+    treasury_code_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(admin) = admin {
+        config.admin = deps.api.addr_validate(&admin)?;
+    }
+    if let Some(code_id) = token_code_id {
+        config.token_code_id = code_id;
+    }
+    if let Some(code_id) = treasury_code_id {
+        config.treasury_code_id = code_id;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Launch { token } => {
+            to_json_binary(&LAUNCHES.load(deps.storage, &deps.api.addr_validate(&token)?)?)
+        }
+        QueryMsg::LaunchesByCreator {
+            creator,
+            start_after,
+            limit,
+        } => to_json_binary(&query_launches_by_creator(
+            deps,
+            creator,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Bootstrap { id } => to_json_binary(&BOOTSTRAPS.load(deps.storage, id)?),
+        QueryMsg::BootstrapDeposit { id, depositor } => {
+            to_json_binary(&query_bootstrap_deposit(deps, env, id, depositor)?)
+        }
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: config.admin.to_string(),
+        token_code_id: config.token_code_id,
+        treasury_code_id: config.treasury_code_id,
+        dojoswap_factory: config.dojoswap_factory.to_string(),
+        router: config.router.to_string(),
+        quote_denom: config.quote_denom,
+        reflection_pair: config.reflection_pair,
+        reflection_pair_contract: config.reflection_pair_contract.to_string(),
+    })
+}
+
+pub fn query_launches_by_creator(
+    deps: Deps,
+    creator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LaunchesResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let start_after = start_after
+        .map(|token| deps.api.addr_validate(&token))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let launches = CREATOR_LAUNCHES
+        .prefix(&creator)
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|token| LAUNCHES.load(deps.storage, &token?))
+        .collect::<StdResult<Vec<LaunchInfo>>>()?;
+
+    Ok(LaunchesResponse { launches })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(Response::default())
+}
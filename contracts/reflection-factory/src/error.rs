@@ -0,0 +1,45 @@
+use cosmwasm_std::{StdError, Uint128};
+use dojoswap::token::InstantiateMsgError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(#[from] InstantiateMsgError),
+
+    #[error("Liquidity must be funded with exactly one coin of {denom}")]
+    InvalidLiquidityFunds { denom: String },
+
+    #[error("Liquidity amount must be non zero and at most the initial supply")]
+    InvalidLiquidityAmount {},
+
+    #[error("Reflection pair must be quoted in {denom}")]
+    InvalidReflectionPair { denom: String },
+
+    #[error("Another launch is in progress")]
+    LaunchInProgress {},
+
+    #[error("Cannot find address of the instantiated contract with code id {code_id}")]
+    InstantiateEventNotFound { code_id: u64 },
+
+    #[error("Bootstrap needs a non zero hard cap and an end time in the future")]
+    InvalidBootstrap {},
+
+    #[error("Bootstrap is {status}")]
+    InvalidBootstrapStatus { status: String },
+
+    #[error("Deposit exceeds the hard cap, at most {remaining} can be deposited")]
+    HardCapExceeded { remaining: Uint128 },
+
+    #[error("Only the creator can close the bootstrap before its end time or hard cap")]
+    BootstrapNotEnded {},
+
+    #[error("Nothing deposited")]
+    NothingDeposited {},
+}
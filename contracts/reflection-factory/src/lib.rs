@@ -0,0 +1,8 @@
+pub mod bootstrap;
+pub mod contract;
+pub mod error;
+pub mod msg;
+pub mod state;
+
+#[cfg(test)]
+mod testing;
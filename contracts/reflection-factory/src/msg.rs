@@ -0,0 +1,157 @@
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20_reflection_token::msg::InstantiateMarketingInfo;
+use dojoswap::asset::AssetInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::LaunchInfo;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub admin: String,
+    /// Code id of the reflection token
+    pub token_code_id: u64,
+    /// Code id of the treasury instantiated by every token
+    pub treasury_code_id: u64,
+    /// DojoSwap factory used to create the pair of every token
+    pub dojoswap_factory: String,
+    /// DojoSwap router used by the treasuries to swap reflections
+    pub router: String,
+    /// Native denom every token is paired with, e.g. inj
+    pub quote_denom: String,
+    /// Reflection target pair (e.g. DOJO / INJ), quoted in `quote_denom`
+    pub reflection_pair: [AssetInfo; 2],
+    pub reflection_pair_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Instantiates a reflection token, creates its pair, points its treasury at the pair, seeds the
+    /// pair with `liquidity_amount` tokens and the attached `quote_denom` funds, then enables the taxes.
+    /// The rest of the supply and the LP tokens are sent to the sender
+    Launch {
+        name: String,
+        symbol: String,
+        decimals: u8,
+        initial_supply: Uint128,
+        liquidity_amount: Uint128,
+        tax: TaxRates,
+        marketing: Option<InstantiateMarketingInfo>,
+    },
+    /// The factory stays admin of the tokens it launched, their creator manages them through it
+    AdminExecute {
+        token: String,
+        target: AdminTarget,
+        msg: Binary,
+    },
+    UpdateConfig {
+        admin: Option<String>,
+        token_code_id: Option<u64>,
+        treasury_code_id: Option<u64>,
+    },
+    /// Opens a fair launch of `launch`: anyone deposits `quote_denom` funds until `end_time` or
+    /// `hard_cap`, and the deposits seed the pair at close instead of the creator's funds
+    StartBootstrap {
+        launch: LaunchParams,
+        hard_cap: Uint128,
+        end_time: u64,
+    },
+    /// Deposits the attached `quote_denom` funds into an open bootstrap
+    Deposit { id: u64 },
+    /// Launches the token of a bootstrap with its deposits. The creator can close it at any time,
+    /// anyone once the end time or the hard cap is reached
+    CloseBootstrap { id: u64 },
+    /// Cancels an open bootstrap, depositors get their funds back with `RefundBootstrap`
+    CancelBootstrap { id: u64 },
+    /// Sends the sender's share of the LP tokens of a launched bootstrap, pro-rata of its deposit
+    ClaimBootstrap { id: u64 },
+    /// Refunds the sender's deposit into a cancelled bootstrap
+    RefundBootstrap { id: u64 },
+}
+
+/// Parameters of the token launched by a bootstrap, see `ExecuteMsg::Launch`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LaunchParams {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub initial_supply: Uint128,
+    pub liquidity_amount: Uint128,
+    pub tax: TaxRates,
+    pub marketing: Option<InstantiateMarketingInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapStatus {
+    Open,
+    Launched,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TaxRates {
+    pub global_rate: Decimal,
+    pub reflection_rate: Decimal,
+    pub burn_rate: Decimal,
+    pub antiwhale_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminTarget {
+    Token,
+    Treasury,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Return type: ConfigResponse.
+    Config {},
+    /// Returns the launch of the given token.
+    /// Return type: LaunchInfo.
+    Launch { token: String },
+    /// Returns the tokens launched by `creator`, ordered by token address.
+    /// Return type: LaunchesResponse.
+    LaunchesByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Return type: BootstrapInfo.
+    Bootstrap { id: u64 },
+    /// Returns what `depositor` deposited into a bootstrap and can claim or get refunded.
+    /// Return type: BootstrapDepositResponse.
+    BootstrapDeposit { id: u64, depositor: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub token_code_id: u64,
+    pub treasury_code_id: u64,
+    pub dojoswap_factory: String,
+    pub router: String,
+    pub quote_denom: String,
+    pub reflection_pair: [AssetInfo; 2],
+    pub reflection_pair_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LaunchesResponse {
+    pub launches: Vec<LaunchInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BootstrapDepositResponse {
+    pub deposited: Uint128,
+    /// LP tokens `ClaimBootstrap` would send
+    pub claimable_liquidity: Uint128,
+    /// Quote funds `RefundBootstrap` would send
+    pub refundable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct MigrateMsg {}
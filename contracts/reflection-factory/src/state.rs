@@ -0,0 +1,79 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use dojoswap::asset::AssetInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::{BootstrapStatus, LaunchParams, TaxRates};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    pub admin: Addr,
+    pub token_code_id: u64,
+    pub treasury_code_id: u64,
+    pub dojoswap_factory: Addr,
+    pub router: Addr,
+    pub quote_denom: String,
+    pub reflection_pair: [AssetInfo; 2],
+    pub reflection_pair_contract: Addr,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// Launch being wired between the instantiate and create pair replies, only lives within a transaction
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingLaunch {
+    pub creator: Addr,
+    pub liquidity_amount: Uint128,
+    pub quote_amount: Uint128,
+    pub tax: TaxRates,
+    pub token: Option<Addr>,
+    pub treasury: Option<Addr>,
+    /// Bootstrap whose deposits fund the liquidity, the factory keeps the LP tokens for its depositors
+    pub bootstrap: Option<u64>,
+}
+
+pub const PENDING_LAUNCH: Item<PendingLaunch> = Item::new("pending_launch");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LaunchInfo {
+    pub creator: Addr,
+    pub token: Addr,
+    pub treasury: Addr,
+    pub pair: Addr,
+    pub liquidity_token: Addr,
+    /// Block time of the launch in seconds
+    pub launched_at: u64,
+}
+
+// token -> launch
+pub const LAUNCHES: Map<&Addr, LaunchInfo> = Map::new("launches");
+
+// (creator, token) -> launch time, index of `LAUNCHES` by creator
+pub const CREATOR_LAUNCHES: Map<(&Addr, &Addr), u64> = Map::new("creator_launches");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BootstrapInfo {
+    pub id: u64,
+    pub creator: Addr,
+    pub launch: LaunchParams,
+    /// Maximum amount of quote funds deposited
+    pub hard_cap: Uint128,
+    /// Time after which deposits are refused and anyone can close the bootstrap
+    pub end_time: u64,
+    pub total_deposited: Uint128,
+    pub status: BootstrapStatus,
+    /// Token and LP token of the launch once closed
+    pub token: Option<Addr>,
+    pub liquidity_token: Option<Addr>,
+    /// LP tokens minted to the factory, known from the first claim
+    pub total_liquidity: Option<Uint128>,
+}
+
+pub const BOOTSTRAPS: Map<u64, BootstrapInfo> = Map::new("bootstraps");
+
+// Id of the next bootstrap
+pub const NEXT_BOOTSTRAP_ID: Item<u64> = Item::new("next_bootstrap_id");
+
+// (bootstrap, depositor) -> quote funds deposited and not claimed or refunded yet
+pub const BOOTSTRAP_DEPOSITS: Map<(u64, &Addr), Uint128> = Map::new("bootstrap_deposits");
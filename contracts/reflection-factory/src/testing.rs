@@ -0,0 +1,558 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, coins, from_json, to_json_binary, Addr, BankMsg, CosmosMsg, Decimal, Event, OwnedDeps,
+    Reply, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use dojoswap::asset::{AssetInfo, PairInfo};
+use dojoswap::mock_querier::{mock_dojo_dependencies, MockDojoQuerier};
+use dojoswap::pair::ExecuteMsg as PairExecuteMsg;
+
+use cw20_reflection_token::msg::{
+    ExecuteMsg as TokenExecuteMsg, InstantiateMsg as TokenInstantiateMsg,
+};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::error::ContractError;
+use crate::msg::{
+    AdminTarget, BootstrapDepositResponse, BootstrapStatus, ExecuteMsg, InstantiateMsg,
+    LaunchParams, LaunchesResponse, QueryMsg, TaxRates,
+};
+use crate::state::BootstrapInfo;
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockDojoQuerier>;
+
+const CREATOR: &str = "creator0000";
+const DOJOSWAP_FACTORY: &str = "dojoswapfactory0000";
+const TOKEN: &str = "token0000";
+const TREASURY: &str = "treasury0000";
+const PAIR: &str = "pair0000";
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        admin: "admin0000".to_string(),
+        token_code_id: 1,
+        treasury_code_id: 2,
+        dojoswap_factory: DOJOSWAP_FACTORY.to_string(),
+        router: "router0000".to_string(),
+        quote_denom: "inj".to_string(),
+        reflection_pair: [
+            AssetInfo::Token {
+                contract_addr: "dojo0000".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+        ],
+        reflection_pair_contract: "dojopair0000".to_string(),
+    }
+}
+
+fn launch_msg() -> ExecuteMsg {
+    ExecuteMsg::Launch {
+        name: "Reflection".to_string(),
+        symbol: "RFL".to_string(),
+        decimals: 6,
+        initial_supply: Uint128::new(1_000_000),
+        liquidity_amount: Uint128::new(400_000),
+        tax: TaxRates {
+            global_rate: Decimal::percent(10),
+            reflection_rate: Decimal::percent(50),
+            burn_rate: Decimal::percent(10),
+            antiwhale_rate: Decimal::percent(2),
+        },
+        marketing: None,
+    }
+}
+
+fn instantiate_event(code_id: u64, address: &str) -> Event {
+    Event::new("instantiate")
+        .add_attribute("_contract_address", address)
+        .add_attribute("code_id", code_id.to_string())
+}
+
+fn setup() -> MockDeps {
+    let mut deps = mock_dojo_dependencies(&[]);
+    deps.querier.with_contract_handler(DOJOSWAP_FACTORY, |_| {
+        to_json_binary(&PairInfo {
+            asset_infos: [
+                AssetInfo::Token {
+                    contract_addr: TOKEN.to_string(),
+                },
+                AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+            ],
+            contract_addr: PAIR.to_string(),
+            liquidity_token: "liquidity0000".to_string(),
+            asset_decimals: [6, 18],
+        })
+    });
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin0000", &[]),
+        instantiate_msg(),
+    )
+    .unwrap();
+    deps
+}
+
+fn launch(deps: &mut MockDeps) -> Vec<CosmosMsg> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR, &coins(500, "inj")),
+        launch_msg(),
+    )
+    .unwrap();
+    wire_launch(deps)
+}
+
+/// Replies to the token instantiation and pair creation of the launch in progress
+fn wire_launch(deps: &mut MockDeps) -> Vec<CosmosMsg> {
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![instantiate_event(1, TOKEN), instantiate_event(2, TREASURY)],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 2,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap()
+    .messages
+    .into_iter()
+    .map(|sub_msg| sub_msg.msg)
+    .collect()
+}
+
+#[test]
+fn reflection_pair_must_be_quoted_in_quote_denom() {
+    let mut deps = mock_dojo_dependencies(&[]);
+    let mut msg = instantiate_msg();
+    msg.quote_denom = "usdt".to_string();
+
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("admin0000", &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidReflectionPair {
+            denom: "usdt".to_string()
+        }
+    );
+}
+
+#[test]
+fn launch_requires_quote_funds_and_liquidity() {
+    let mut deps = setup();
+
+    for funds in [
+        vec![],
+        coins(500, "usdt"),
+        vec![coin(500, "inj"), coin(1, "usdt")],
+    ] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &funds),
+            launch_msg(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidLiquidityFunds {
+                denom: "inj".to_string()
+            }
+        );
+    }
+
+    let mut msg = launch_msg();
+    if let ExecuteMsg::Launch {
+        liquidity_amount, ..
+    } = &mut msg
+    {
+        *liquidity_amount = Uint128::new(1_000_001);
+    }
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR, &coins(500, "inj")),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidLiquidityAmount {});
+}
+
+#[test]
+fn launch_rejects_invalid_tokens_with_the_reason() {
+    use cw20_reflection_token::msg::InstantiateMarketingInfo;
+    use dojoswap::token::InstantiateMsgError;
+
+    let mut deps = setup();
+    let mut launch_with = |configure: &dyn Fn(&mut ExecuteMsg)| {
+        let mut msg = launch_msg();
+        configure(&mut msg);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &coins(500, "inj")),
+            msg,
+        )
+        .unwrap_err()
+    };
+
+    let err = launch_with(&|msg| {
+        if let ExecuteMsg::Launch { symbol, .. } = msg {
+            *symbol = "R".to_string();
+        }
+    });
+    assert_eq!(
+        err,
+        ContractError::InvalidToken(InstantiateMsgError::InvalidSymbol {})
+    );
+    let err = launch_with(&|msg| {
+        if let ExecuteMsg::Launch { decimals, .. } = msg {
+            *decimals = 19;
+        }
+    });
+    assert_eq!(
+        err,
+        ContractError::InvalidToken(InstantiateMsgError::InvalidDecimals {})
+    );
+    let err = launch_with(&|msg| {
+        if let ExecuteMsg::Launch { marketing, .. } = msg {
+            *marketing = Some(InstantiateMarketingInfo {
+                project: None,
+                description: None,
+                marketing: None,
+                logo: None,
+                display_decimals: Some(7),
+            });
+        }
+    });
+    assert_eq!(
+        err,
+        ContractError::InvalidToken(InstantiateMsgError::InvalidDisplayDecimals {})
+    );
+}
+
+#[test]
+fn launch_wires_token_pair_and_liquidity() {
+    let mut deps = setup();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR, &coins(500, "inj")),
+        launch_msg(),
+    )
+    .unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { code_id, msg, .. }) => {
+            let msg: TokenInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(*code_id, 1);
+            assert_eq!(msg.treasury_code_id, 2);
+            assert_eq!(msg.admin, MOCK_CONTRACT_ADDR);
+            assert_eq!(msg.initial_balances[0].amount, Uint128::new(400_000));
+            assert_eq!(msg.initial_balances[1].address, CREATOR);
+            assert_eq!(msg.initial_balances[1].amount, Uint128::new(600_000));
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    // a second launch cannot start before the first one is wired
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR, &coins(500, "inj")),
+        launch_msg(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LaunchInProgress {});
+
+    let reply_to_instantiate = |events| Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse { events, data: None }),
+    };
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        reply_to_instantiate(vec![instantiate_event(1, TOKEN)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InstantiateEventNotFound { code_id: 2 });
+
+    deps = setup();
+    let messages = launch(&mut deps);
+    let targets: Vec<&str> = messages
+        .iter()
+        .map(|msg| match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => contract_addr.as_str(),
+            msg => panic!("unexpected message {:?}", msg),
+        })
+        .collect();
+    assert_eq!(targets, vec![TREASURY, TREASURY, TOKEN, PAIR, TOKEN]);
+    match &messages[3] {
+        CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) => assert_eq!(funds, &coins(500, "inj")),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    // taxes are only enabled once the pair is seeded
+    match &messages[4] {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => assert!(matches!(
+            from_json(msg).unwrap(),
+            TokenExecuteMsg::SetTaxRate { global_rate, .. } if global_rate == Decimal::percent(10)
+        )),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+
+    let res: LaunchesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::LaunchesByCreator {
+                creator: CREATOR.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.launches.len(), 1);
+    assert_eq!(res.launches[0].token, Addr::unchecked(TOKEN));
+    assert_eq!(res.launches[0].treasury, Addr::unchecked(TREASURY));
+    assert_eq!(res.launches[0].pair, Addr::unchecked(PAIR));
+
+    let res: LaunchesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::LaunchesByCreator {
+                creator: CREATOR.to_string(),
+                start_after: Some(TOKEN.to_string()),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.launches.is_empty());
+}
+
+#[test]
+fn only_creator_administers_its_launch() {
+    let mut deps = setup();
+    launch(&mut deps);
+    let msg = ExecuteMsg::AdminExecute {
+        token: TOKEN.to_string(),
+        target: AdminTarget::Treasury,
+        msg: to_json_binary(&"set_min_liquify").unwrap(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+            assert_eq!(contract_addr, TREASURY)
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn bootstrap_deposits_seed_the_pair() {
+    let mut deps = setup();
+    let start = |deps: &mut MockDeps| {
+        let launch = match launch_msg() {
+            ExecuteMsg::Launch {
+                name,
+                symbol,
+                decimals,
+                initial_supply,
+                liquidity_amount,
+                tax,
+                marketing,
+            } => LaunchParams {
+                name,
+                symbol,
+                decimals,
+                initial_supply,
+                liquidity_amount,
+                tax,
+                marketing,
+            },
+            msg => panic!("unexpected message {:?}", msg),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            ExecuteMsg::StartBootstrap {
+                launch,
+                hard_cap: Uint128::new(500),
+                end_time: mock_env().block.time.seconds() + 100,
+            },
+        )
+        .unwrap();
+    };
+    let deposit = |deps: &mut MockDeps, id: u64, depositor: &str, amount: u128| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(depositor, &coins(amount, "inj")),
+            ExecuteMsg::Deposit { id },
+        )
+    };
+    let query_deposit = |deps: &MockDeps, id: u64, depositor: &str| -> BootstrapDepositResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::BootstrapDeposit {
+                    id,
+                    depositor: depositor.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    start(&mut deps);
+    deposit(&mut deps, 1, "addr0000", 300).unwrap();
+    deposit(&mut deps, 1, "addr0001", 100).unwrap();
+    let err = deposit(&mut deps, 1, "addr0001", 101).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::HardCapExceeded {
+            remaining: Uint128::new(100)
+        }
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CloseBootstrap { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BootstrapNotEnded {});
+
+    // anyone can close once the end time is reached, the deposits seed the pair
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CloseBootstrap { id: 1 },
+    )
+    .unwrap();
+    let messages = wire_launch(&mut deps);
+    match &messages[3] {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, funds, .. }) => {
+            assert_eq!(funds, &coins(400, "inj"));
+            assert!(matches!(
+                from_json(msg).unwrap(),
+                PairExecuteMsg::ProvideLiquidity { receiver: Some(receiver), .. }
+                    if receiver == MOCK_CONTRACT_ADDR
+            ));
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    let bootstrap: BootstrapInfo =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Bootstrap { id: 1 }).unwrap())
+            .unwrap();
+    assert_eq!(bootstrap.status, BootstrapStatus::Launched);
+    assert_eq!(bootstrap.token, Some(Addr::unchecked(TOKEN)));
+
+    // the LP tokens are shared pro-rata of the deposits
+    deps.querier.with_token_balances(&[(
+        &"liquidity0000".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::new(1_000))],
+    )]);
+    assert_eq!(
+        query_deposit(&deps, 1, "addr0001").claimable_liquidity,
+        Uint128::new(250)
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimBootstrap { id: 1 },
+    )
+    .unwrap();
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            assert_eq!(contract_addr, "liquidity0000");
+            assert_eq!(
+                from_json::<Cw20ExecuteMsg>(msg).unwrap(),
+                Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::new(750),
+                }
+            );
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimBootstrap { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingDeposited {});
+
+    // a cancelled bootstrap refunds its depositors
+    start(&mut deps);
+    deposit(&mut deps, 2, "addr0000", 200).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR, &[]),
+        ExecuteMsg::CancelBootstrap { id: 2 },
+    )
+    .unwrap();
+    deposit(&mut deps, 2, "addr0000", 1).unwrap_err();
+    assert_eq!(
+        query_deposit(&deps, 2, "addr0000").refundable,
+        Uint128::new(200)
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::RefundBootstrap { id: 2 },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: coins(200, "inj"),
+        })
+    );
+}
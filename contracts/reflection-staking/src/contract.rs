@@ -0,0 +1,338 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{BalanceResponse, Cw20ReceiveMsg};
+
+use cw20_reflection_token::msg::{
+    ExecuteMsg as TokenExecuteMsg, PendingReflectionsResponse, QueryMsg as TokenQueryMsg,
+};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, StakerResponse,
+    StateResponse,
+};
+use crate::state::{Claim, Config, CLAIMS, CONFIG, SHARES, TOTAL_SHARES, TOTAL_UNBONDING};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "dojoswap:reflection-staking";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Virtual share and token added to both sides of every conversion: the first staker cannot inflate
+// the share price to round the deposits of the next ones down to nothing
+const VIRTUAL_SHARES: Uint128 = Uint128::new(1);
+const VIRTUAL_ASSETS: Uint128 = Uint128::new(1);
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            token: deps.api.addr_validate(&msg.token)?,
+            unbonding_period: msg.unbonding_period,
+        },
+    )?;
+    TOTAL_SHARES.save(deps.storage, &Uint128::zero())?;
+    TOTAL_UNBONDING.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::Unstake { shares } => execute_unstake(deps, env, info, shares),
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::Harvest {} => execute_harvest(deps, env),
+    }
+}
+
+/// Tokens held by the vault for its stakers. The balance of a reflection token does not move when
+/// reflections accrue to it, they are pending until claimed: the share price counts them so that
+/// it does not depend on when the vault last harvested
+struct Vault {
+    balance: Uint128,
+    pending_reflections: Uint128,
+    unbonding: Uint128,
+    total_shares: Uint128,
+}
+
+impl Vault {
+    fn load(deps: Deps, env: &Env, config: &Config) -> StdResult<Self> {
+        let address = env.contract.address.to_string();
+        let balance: BalanceResponse = deps.querier.query_wasm_smart(
+            &config.token,
+            &TokenQueryMsg::Balance {
+                address: address.clone(),
+            },
+        )?;
+        let pending: PendingReflectionsResponse = deps.querier.query_wasm_smart(
+            &config.token,
+            &TokenQueryMsg::PendingReflections { address },
+        )?;
+
+        Ok(Vault {
+            balance: balance.balance,
+            pending_reflections: pending.pending,
+            unbonding: TOTAL_UNBONDING.load(deps.storage)?,
+            total_shares: TOTAL_SHARES.load(deps.storage)?,
+        })
+    }
+
+    /// Tokens backing the shares, what is unbonding belongs to the claims
+    fn staked(&self) -> Uint128 {
+        (self.balance + self.pending_reflections).saturating_sub(self.unbonding)
+    }
+
+    /// Shares minted for `amount` tokens when `staked` tokens back the current shares
+    fn shares_for(&self, amount: Uint128, staked: Uint128) -> Uint128 {
+        amount.multiply_ratio(self.total_shares + VIRTUAL_SHARES, staked + VIRTUAL_ASSETS)
+    }
+
+    /// Tokens `shares` are worth now, rounded down
+    fn assets_for(&self, shares: Uint128) -> Uint128 {
+        let staked = self.staked();
+        shares
+            .multiply_ratio(staked + VIRTUAL_ASSETS, self.total_shares + VIRTUAL_SHARES)
+            .min(staked)
+    }
+
+    /// Messages paying `amount` to `recipient`, harvesting first so that the balance covers it
+    fn payout(
+        &self,
+        config: &Config,
+        recipient: &Addr,
+        amount: Uint128,
+    ) -> StdResult<Vec<WasmMsg>> {
+        let mut messages = vec![];
+        if !self.pending_reflections.is_zero() {
+            messages.push(harvest_msg(config)?);
+        }
+        messages.push(WasmMsg::Execute {
+            contract_addr: config.token.to_string(),
+            msg: to_json_binary(&TokenExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+                min_receive: None,
+            })?,
+            funds: vec![],
+        });
+        Ok(messages)
+    }
+}
+
+fn harvest_msg(config: &Config) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: config.token.to_string(),
+        msg: to_json_binary(&TokenExecuteMsg::ClaimReflections {})?,
+        funds: vec![],
+    })
+}
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.token {
+        return Err(ContractError::Unauthorized {});
+    }
+    match from_json(&cw20_msg.msg)? {
+        ReceiveMsg::Stake {} => {
+            let staker = deps.api.addr_validate(&cw20_msg.sender)?;
+            execute_stake(deps, env, &config, staker, cw20_msg.amount)
+        }
+    }
+}
+
+/// Mints shares for `amount` tokens the token already credited to the vault, priced against the
+/// tokens staked before them
+pub fn execute_stake(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    staker: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let vault = Vault::load(deps.as_ref(), &env, config)?;
+    let shares = vault.shares_for(amount, vault.staked().saturating_sub(amount));
+    if shares.is_zero() {
+        return Err(ContractError::ZeroShares {});
+    }
+
+    TOTAL_SHARES.save(deps.storage, &(vault.total_shares + shares))?;
+    SHARES.update(deps.storage, &staker, |current| -> StdResult<_> {
+        Ok(current.unwrap_or_default() + shares)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "stake")
+        .add_attribute("staker", staker)
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares))
+}
+
+pub fn execute_unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    if shares.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let available = SHARES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if shares > available {
+        return Err(ContractError::InsufficientShares { available });
+    }
+    let vault = Vault::load(deps.as_ref(), &env, &config)?;
+    let amount = vault.assets_for(shares);
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    TOTAL_SHARES.save(deps.storage, &(vault.total_shares - shares))?;
+    if shares == available {
+        SHARES.remove(deps.storage, &info.sender);
+    } else {
+        SHARES.save(deps.storage, &info.sender, &(available - shares))?;
+    }
+
+    let mut response = Response::new();
+    if config.unbonding_period == 0 {
+        response = response.add_messages(vault.payout(&config, &info.sender, amount)?);
+    } else {
+        let release_at = env.block.time.seconds() + config.unbonding_period;
+        let mut claims = CLAIMS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        claims.push(Claim { amount, release_at });
+        CLAIMS.save(deps.storage, &info.sender, &claims)?;
+        TOTAL_UNBONDING.save(deps.storage, &(vault.unbonding + amount))?;
+        response = response.add_attribute("release_at", release_at.to_string());
+    }
+
+    Ok(response
+        .add_attribute("action", "unstake")
+        .add_attribute("staker", info.sender)
+        .add_attribute("shares", shares)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let now = env.block.time.seconds();
+    let (released, unbonding): (Vec<Claim>, Vec<Claim>) = CLAIMS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|claim| claim.release_at <= now);
+    let amount: Uint128 = released.iter().map(|claim| claim.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    if unbonding.is_empty() {
+        CLAIMS.remove(deps.storage, &info.sender);
+    } else {
+        CLAIMS.save(deps.storage, &info.sender, &unbonding)?;
+    }
+    let vault = Vault::load(deps.as_ref(), &env, &config)?;
+    TOTAL_UNBONDING.save(deps.storage, &(vault.unbonding - amount))?;
+
+    Ok(Response::new()
+        .add_messages(vault.payout(&config, &info.sender, amount)?)
+        .add_attribute("action", "claim")
+        .add_attribute("staker", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_harvest(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let vault = Vault::load(deps.as_ref(), &env, &config)?;
+    if vault.pending_reflections.is_zero() {
+        return Err(ContractError::NothingToHarvest {});
+    }
+
+    Ok(Response::new()
+        .add_message(harvest_msg(&config)?)
+        .add_attribute("action", "harvest")
+        .add_attribute("amount", vault.pending_reflections))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::State {} => to_json_binary(&query_state(deps, env)?),
+        QueryMsg::Staker { address } => to_json_binary(&query_staker(deps, env, address)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        token: config.token.to_string(),
+        unbonding_period: config.unbonding_period,
+    })
+}
+
+pub fn query_state(deps: Deps, env: Env) -> StdResult<StateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let vault = Vault::load(deps, &env, &config)?;
+    Ok(StateResponse {
+        total_shares: vault.total_shares,
+        total_assets: vault.staked(),
+        total_unbonding: vault.unbonding,
+        pending_reflections: vault.pending_reflections,
+        share_price: Decimal::from_ratio(
+            vault.staked() + VIRTUAL_ASSETS,
+            vault.total_shares + VIRTUAL_SHARES,
+        ),
+    })
+}
+
+pub fn query_staker(deps: Deps, env: Env, address: String) -> StdResult<StakerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+    let vault = Vault::load(deps, &env, &config)?;
+    let shares = SHARES.may_load(deps.storage, &address)?.unwrap_or_default();
+    Ok(StakerResponse {
+        shares,
+        assets: vault.assets_for(shares),
+        claims: CLAIMS.may_load(deps.storage, &address)?.unwrap_or_default(),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(Response::default())
+}
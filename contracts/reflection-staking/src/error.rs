@@ -0,0 +1,26 @@
+use cosmwasm_std::{StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid zero amount")]
+    InvalidZeroAmount {},
+
+    #[error("Deposit too small to mint a share")]
+    ZeroShares {},
+
+    #[error("Not enough shares, {available} available")]
+    InsufficientShares { available: Uint128 },
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("No reflections to harvest")]
+    NothingToHarvest {},
+}
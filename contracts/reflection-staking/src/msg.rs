@@ -0,0 +1,80 @@
+use cosmwasm_std::{Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::Claim;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    /// Reflection token staked in the vault, it must accrue reflections to the vault: claim mode,
+    /// and the vault neither excluded from reflections nor custodial
+    pub token: String,
+    /// Seconds between an unstake and the claim of its tokens, 0 pays out on unstake
+    pub unbonding_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Stakes the tokens sent with `ReceiveMsg::Stake`
+    Receive(Cw20ReceiveMsg),
+    /// Burns `shares` of the sender for the tokens they are worth now, claimable once the
+    /// unbonding period is over
+    Unstake { shares: Uint128 },
+    /// Pays out the unstaked tokens of the sender whose unbonding period is over
+    Claim {},
+    /// Pulls the reflections accrued by the vault into its balance. Anyone can call it, shares are
+    /// worth the same before and after
+    Harvest {},
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Mints shares to the sender of the tokens
+    Stake {},
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Return type: ConfigResponse.
+    Config {},
+    /// Shares issued and the assets backing them.
+    /// Return type: StateResponse.
+    State {},
+    /// Shares of `address`, what they are worth now and its pending claims.
+    /// Return type: StakerResponse.
+    Staker { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    pub token: String,
+    pub unbonding_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StateResponse {
+    pub total_shares: Uint128,
+    /// Staked tokens: the balance of the vault and its pending reflections, minus the unbonding
+    /// tokens
+    pub total_assets: Uint128,
+    pub total_unbonding: Uint128,
+    /// Reflections accrued by the vault and not harvested yet, part of `total_assets`
+    pub pending_reflections: Uint128,
+    /// Tokens one share is worth
+    pub share_price: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakerResponse {
+    pub shares: Uint128,
+    /// Tokens the shares are worth now
+    pub assets: Uint128,
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct MigrateMsg {}
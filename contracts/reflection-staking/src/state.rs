@@ -0,0 +1,33 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    /// Reflection token staked in the vault
+    pub token: Addr,
+    /// Seconds between an unstake and the claim of its tokens
+    pub unbonding_period: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// Shares issued to the stakers, each one is a fraction of the staked assets
+pub const TOTAL_SHARES: Item<Uint128> = Item::new("total_shares");
+
+// staker -> shares
+pub const SHARES: Map<&Addr, Uint128> = Map::new("shares");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Claim {
+    pub amount: Uint128,
+    /// Time from which the tokens can be claimed
+    pub release_at: u64,
+}
+
+// staker -> tokens unstaked and not claimed yet, oldest first
+pub const CLAIMS: Map<&Addr, Vec<Claim>> = Map::new("claims");
+
+// Sum of every claim, those tokens are still held by the vault but are no longer staked
+pub const TOTAL_UNBONDING: Item<Uint128> = Item::new("total_unbonding");
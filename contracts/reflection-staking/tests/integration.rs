@@ -0,0 +1,365 @@
+//! The vault staking a reflection token in claim mode against multi-test: reflections accrued by
+//! the vault raise the share price, and stakers leave with their share of them.
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128,
+};
+use cw20::{BalanceResponse, Cw20Coin};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+use cw20_reflection_token::msg::{
+    ExecuteMsg as TokenExecuteMsg, InstantiateMsg as TokenInstantiateMsg,
+    QueryMsg as TokenQueryMsg, ReflectionMode,
+};
+use reflection_staking::error::ContractError;
+use reflection_staking::msg::{
+    ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakerResponse, StateResponse,
+};
+
+const ADMIN: &str = "admin";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const CAROL: &str = "carol";
+const DAVE: &str = "dave";
+
+const UNBONDING_PERIOD: u64 = 100;
+
+/// Treasury accepting whatever the token sends it, taxes only need a treasury address here
+mod mock_treasury {
+    use super::*;
+
+    pub fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        Err(StdError::generic_err("unsupported"))
+    }
+}
+
+fn token_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            cw20_reflection_token::contract::execute,
+            cw20_reflection_token::contract::instantiate,
+            cw20_reflection_token::contract::query,
+        )
+        .with_reply(cw20_reflection_token::contract::reply),
+    )
+}
+
+fn staking_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        reflection_staking::contract::execute,
+        reflection_staking::contract::instantiate,
+        reflection_staking::contract::query,
+    ))
+}
+
+struct Suite {
+    app: App,
+    token: Addr,
+    vault: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = App::default();
+        let token_code_id = app.store_code(token_contract());
+        let treasury_code_id = app.store_code(Box::new(ContractWrapper::new(
+            mock_treasury::execute,
+            mock_treasury::instantiate,
+            mock_treasury::query,
+        )));
+        let staking_code_id = app.store_code(staking_contract());
+
+        let initial_balances = [(ALICE, 1_000_000u128), (BOB, 1_000_000), (CAROL, 2_000_000)]
+            .iter()
+            .map(|(address, amount)| Cw20Coin {
+                address: address.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .collect();
+        let token = app
+            .instantiate_contract(
+                token_code_id,
+                Addr::unchecked(ADMIN),
+                &TokenInstantiateMsg {
+                    name: "Baby Token".to_string(),
+                    symbol: "BABY".to_string(),
+                    decimals: 6,
+                    treasury_code_id,
+                    cw20_code_id: None,
+                    initial_balances,
+                    admin: ADMIN.to_string(),
+                    router: "router".to_string(),
+                    mint: None,
+                    marketing: None,
+                    max_tax_rate: None,
+                    max_tax_step: None,
+                    reflection_mode: Some(ReflectionMode::Claim),
+                    max_tx_amount: None,
+                    max_wallet_amount: None,
+                    vesting: None,
+                },
+                &[],
+                "token",
+                None,
+            )
+            .unwrap();
+        let vault = app
+            .instantiate_contract(
+                staking_code_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg {
+                    token: token.to_string(),
+                    unbonding_period: UNBONDING_PERIOD,
+                },
+                &[],
+                "vault",
+                None,
+            )
+            .unwrap();
+
+        // 10% tax, half of it reflected. Stakes and payouts of the vault are not taxed
+        for msg in [
+            TokenExecuteMsg::SetTaxRate {
+                global_rate: Decimal::percent(10),
+                reflection_rate: Decimal::percent(50),
+                burn_rate: Decimal::zero(),
+                antiwhale_rate: Decimal::one(),
+            },
+            TokenExecuteMsg::SetWhitelist {
+                user: vault.to_string(),
+                enable: true,
+            },
+        ] {
+            app.execute_contract(Addr::unchecked(ADMIN), token.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        Suite { app, token, vault }
+    }
+
+    fn stake(&mut self, staker: &str, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(staker),
+                self.token.clone(),
+                &TokenExecuteMsg::Send {
+                    contract: self.vault.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_json_binary(&ReceiveMsg::Stake {}).unwrap(),
+                    min_receive: None,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    /// Taxed transfer whose reflections accrue to every holder, the vault included
+    fn trade(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(CAROL),
+                self.token.clone(),
+                &TokenExecuteMsg::Transfer {
+                    recipient: DAVE.to_string(),
+                    amount: Uint128::new(amount),
+                    min_receive: None,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn balance(&self, address: &str) -> Uint128 {
+        let response: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.token,
+                &TokenQueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance
+    }
+
+    fn state(&self) -> StateResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.vault, &QueryMsg::State {})
+            .unwrap()
+    }
+
+    fn staker(&self, address: &str) -> StakerResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                &self.vault,
+                &QueryMsg::Staker {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap()
+    }
+}
+
+#[test]
+fn stakers_share_the_reflections_of_the_vault() {
+    let mut suite = Suite::new();
+    suite.stake(ALICE, 100_000);
+    assert_eq!(suite.staker(ALICE).shares, Uint128::new(100_000));
+    assert_eq!(suite.state().share_price, Decimal::one());
+
+    // the reflections accrue to the vault as pending, the shares are worth them already
+    suite.trade(1_000_000);
+    let state = suite.state();
+    assert!(!state.pending_reflections.is_zero());
+    assert_eq!(suite.balance(suite.vault.as_str()), Uint128::new(100_000));
+    assert_eq!(
+        state.total_assets,
+        Uint128::new(100_000) + state.pending_reflections
+    );
+    assert!(state.share_price > Decimal::one());
+
+    // a later staker pays the higher price, harvesting changes nothing to it
+    suite.stake(BOB, 100_000);
+    let bob = suite.staker(BOB);
+    assert!(bob.shares < Uint128::new(100_000));
+    assert!(Uint128::new(100_000) - bob.assets <= Uint128::one());
+    let price = suite.state().share_price;
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(DAVE),
+            suite.vault.clone(),
+            &ExecuteMsg::Harvest {},
+            &[],
+        )
+        .unwrap();
+    let state = suite.state();
+    assert!(state.pending_reflections.is_zero());
+    assert_eq!(state.share_price, price);
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(DAVE),
+            suite.vault.clone(),
+            &ExecuteMsg::Harvest {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NothingToHarvest {}
+    );
+
+    // more reflections, then alice leaves with her deposit and her share of both rounds
+    suite.trade(500_000);
+    let alice = suite.staker(ALICE);
+    assert!(alice.assets > Uint128::new(100_000));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.vault.clone(),
+            &ExecuteMsg::Unstake {
+                shares: alice.shares,
+            },
+            &[],
+        )
+        .unwrap();
+    let unbonding = suite.staker(ALICE);
+    assert!(unbonding.shares.is_zero());
+    assert_eq!(unbonding.claims[0].amount, alice.assets);
+    // the unbonding tokens no longer earn for alice, bob keeps what he is owed
+    assert_eq!(suite.state().total_unbonding, alice.assets);
+    assert!(suite.staker(BOB).assets >= bob.assets);
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.vault.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NothingToClaim {}
+    );
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(UNBONDING_PERIOD));
+    let before = suite.balance(ALICE);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.vault.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.balance(ALICE), before + alice.assets);
+    assert!(suite.staker(ALICE).claims.is_empty());
+
+    // what is left backs bob's shares alone
+    let state = suite.state();
+    assert!(state.total_unbonding.is_zero());
+    assert_eq!(state.total_shares, bob.shares);
+    assert_eq!(
+        state.total_assets,
+        suite.balance(suite.vault.as_str()) + state.pending_reflections
+    );
+}
+
+#[test]
+fn only_the_token_stakes() {
+    let mut suite = Suite::new();
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.vault.clone(),
+            &ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                sender: ALICE.to_string(),
+                amount: Uint128::new(100_000),
+                msg: to_json_binary(&ReceiveMsg::Stake {}).unwrap(),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    suite.stake(ALICE, 100_000);
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.vault.clone(),
+            &ExecuteMsg::Unstake {
+                shares: Uint128::new(100_001),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InsufficientShares {
+            available: Uint128::new(100_000)
+        }
+    );
+}
@@ -0,0 +1,110 @@
+use cosmwasm_std::{StdError, StdResult, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How the digits dropped by a conversion are rounded
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Towards zero, e.g. for the amount a wallet can spend
+    Down,
+    /// Away from zero, e.g. for an amount the user must provide
+    Up,
+    /// To the nearest, halves away from zero
+    HalfUp,
+}
+
+fn pow10(exponent: u32) -> StdResult<u128> {
+    10u128
+        .checked_pow(exponent)
+        .ok_or_else(|| StdError::generic_err(format!("10^{} does not fit an amount", exponent)))
+}
+
+/// Rounds `quotient` with the `remainder` of its division by `divisor`
+fn round(quotient: u128, remainder: u128, divisor: u128, rounding: Rounding) -> u128 {
+    let up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::HalfUp => remainder >= divisor - remainder,
+    };
+    if up {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+/// Raw `amount` of a token with `decimals` as a human string with exactly `display_decimals`
+/// fractional digits (at most `decimals`), e.g. 1_234_567_890 with 6 decimals shown with 2 is
+/// "1234.57" rounded half up. Reflection tokens accrue dust in the last digits of every balance,
+/// showing fewer digits than the token has hides it
+pub fn format_amount(
+    amount: Uint128,
+    decimals: u8,
+    display_decimals: u8,
+    rounding: Rounding,
+) -> StdResult<String> {
+    let shown = display_decimals.min(decimals) as u32;
+    let dropped = pow10(decimals as u32 - shown)?;
+    let scaled = round(
+        amount.u128() / dropped,
+        amount.u128() % dropped,
+        dropped,
+        rounding,
+    );
+
+    let unit = pow10(shown)?;
+    if shown == 0 {
+        return Ok(scaled.to_string());
+    }
+    Ok(format!(
+        "{}.{:0width$}",
+        scaled / unit,
+        scaled % unit,
+        width = shown as usize
+    ))
+}
+
+/// Raw amount of a token with `decimals` from a human string such as "1234.5", "0.000001" or
+/// "12". Fractional digits beyond `decimals` are rounded with `rounding`
+pub fn parse_amount(human: &str, decimals: u8, rounding: Rounding) -> StdResult<Uint128> {
+    let invalid = || StdError::generic_err(format!("Invalid amount {}", human));
+    let (whole, fraction) = match human.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (human, ""),
+    };
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let decimals = decimals as usize;
+    let (kept, dropped) = fraction.split_at(fraction.len().min(decimals));
+    let unit = pow10(decimals as u32)?;
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let kept: u128 = format!("{:0<width$}", kept, width = decimals)
+        .parse()
+        .unwrap_or_default();
+    let amount = whole
+        .checked_mul(unit)
+        .and_then(|amount| amount.checked_add(kept))
+        .ok_or_else(invalid)?;
+
+    let up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => dropped.bytes().any(|b| b != b'0'),
+        Rounding::HalfUp => dropped.bytes().next().is_some_and(|b| b >= b'5'),
+    };
+    if up {
+        return amount.checked_add(1).map(Uint128::new).ok_or_else(invalid);
+    }
+    Ok(Uint128::new(amount))
+}
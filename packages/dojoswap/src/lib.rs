@@ -1,8 +1,12 @@
+pub mod amount;
 pub mod asset;
 pub mod factory;
 pub mod pair;
+pub mod presets;
 pub mod querier;
 pub mod router;
+pub mod signing;
+pub mod taxed;
 pub mod token;
 pub mod util;
 
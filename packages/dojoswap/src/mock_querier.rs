@@ -1,16 +1,18 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, Empty, OwnedDeps, Querier,
-    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_json, from_slice, to_binary, to_json_binary, Binary, Coin, ContractResult,
+    Decimal, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, StdError, StdResult,
+    SystemError, SystemResult, Uint128, Uint256, WasmQuery,
 };
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::panic;
 
-use crate::asset::{AssetInfo, PairInfo};
+use crate::asset::{Asset, AssetInfo, PairInfo};
 use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
 use crate::pair::QueryMsg as PairQueryMsg;
-use crate::pair::{ReverseSimulationResponse, SimulationResponse};
+use crate::pair::{PoolResponse, ReverseSimulationResponse, SimulationResponse};
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
 use std::iter::FromIterator;
@@ -289,6 +291,258 @@ impl WasmMockQuerier {
     }
 }
 
+type ContractHandler = Box<dyn Fn(&Binary) -> StdResult<Binary>>;
+
+/// Constant product pool simulated by `MockDojoQuerier`
+#[derive(Clone, Debug)]
+pub struct MockPair {
+    pub info: PairInfo,
+    pub reserves: [Uint128; 2],
+    pub commission_rate: Decimal,
+    pub total_share: Uint128,
+}
+
+/// Querier simulating DojoSwap pairs from their reserves, cw20 balances and arbitrary contracts
+/// (oracles, verifiers, ...) answered by user supplied handlers. Meant for unit tests of contracts
+/// built on top of dojoswap which do not want to spin up a multi-test app
+pub struct MockDojoQuerier {
+    base: MockQuerier,
+    token_querier: TokenQuerier,
+    pairs: HashMap<String, MockPair>,
+    handlers: HashMap<String, ContractHandler>,
+}
+
+/// Same as `mock_dependencies` but backed by a `MockDojoQuerier`
+pub fn mock_dojo_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, MockDojoQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MockDojoQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)])),
+        custom_query_type: PhantomData,
+    }
+}
+
+impl MockDojoQuerier {
+    pub fn new(base: MockQuerier) -> Self {
+        MockDojoQuerier {
+            base,
+            token_querier: TokenQuerier::default(),
+            pairs: HashMap::new(),
+            handlers: HashMap::new(),
+        }
+    }
+
+    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
+
+    pub fn with_balance(&mut self, balances: &[(&String, Vec<Coin>)]) {
+        for (addr, balance) in balances {
+            self.base.update_balance(addr.to_string(), balance.clone());
+        }
+    }
+
+    /// Registers a pair answering `Pair`, `Pool`, `Simulation` and `ReverseSimulation` from its reserves.
+    /// Reserves follow the order of `info.asset_infos`
+    pub fn with_pair(&mut self, info: PairInfo, reserves: [Uint128; 2], commission_rate: Decimal) {
+        self.pairs.insert(
+            info.contract_addr.clone(),
+            MockPair {
+                info,
+                reserves,
+                commission_rate,
+                total_share: Uint128::zero(),
+            },
+        );
+    }
+
+    /// Updates the reserves of a registered pair, e.g. after simulating a swap
+    pub fn set_pair_reserves(&mut self, pair: &str, reserves: [Uint128; 2]) {
+        if let Some(mock) = self.pairs.get_mut(pair) {
+            mock.reserves = reserves;
+        }
+    }
+
+    /// Answers every smart query sent to `contract_addr` with `handler`
+    pub fn with_contract_handler<F>(&mut self, contract_addr: &str, handler: F)
+    where
+        F: Fn(&Binary) -> StdResult<Binary> + 'static,
+    {
+        self.handlers
+            .insert(contract_addr.to_string(), Box::new(handler));
+    }
+
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if let Some(handler) = self.handlers.get(contract_addr) {
+                    return SystemResult::Ok(handler(msg).into());
+                }
+                if let Some(pair) = self.pairs.get(contract_addr) {
+                    return match from_json(msg) {
+                        Ok(query) => SystemResult::Ok(query_mock_pair(pair, query).into()),
+                        Err(e) => SystemResult::Err(SystemError::InvalidRequest {
+                            error: format!("Parsing pair query: {}", e),
+                            request: msg.clone(),
+                        }),
+                    };
+                }
+                self.handle_token_query(contract_addr, msg)
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+
+    fn handle_token_query(&self, contract_addr: &str, msg: &Binary) -> QuerierResult {
+        let balances = match self.token_querier.balances.get(contract_addr) {
+            Some(balances) => balances,
+            None => {
+                return SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.to_string(),
+                })
+            }
+        };
+        let res = match from_json(msg) {
+            Ok(Cw20QueryMsg::Balance { address }) => to_json_binary(&Cw20BalanceResponse {
+                balance: balances.get(&address).copied().unwrap_or_default(),
+            }),
+            Ok(Cw20QueryMsg::TokenInfo {}) => to_json_binary(&TokenInfoResponse {
+                name: contract_addr.to_string(),
+                symbol: "MOCK".to_string(),
+                decimals: 6,
+                total_supply: balances.values().copied().sum(),
+            }),
+            _ => {
+                return SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: format!("cw20 query on {}", contract_addr),
+                })
+            }
+        };
+        SystemResult::Ok(res.into())
+    }
+}
+
+impl Querier for MockDojoQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_json(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+fn query_mock_pair(pair: &MockPair, query: PairQueryMsg) -> StdResult<Binary> {
+    match query {
+        PairQueryMsg::Pair {} => to_json_binary(&pair.info),
+        PairQueryMsg::Pool {} => to_json_binary(&PoolResponse {
+            assets: [
+                Asset {
+                    info: pair.info.asset_infos[0].clone(),
+                    amount: pair.reserves[0],
+                },
+                Asset {
+                    info: pair.info.asset_infos[1].clone(),
+                    amount: pair.reserves[1],
+                },
+            ],
+            total_share: pair.total_share,
+        }),
+        PairQueryMsg::Simulation { offer_asset } => {
+            let (offer_pool, ask_pool) = mock_pools(pair, &offer_asset.info)?;
+            let (return_amount, spread_amount, commission_amount) = compute_swap(
+                offer_pool,
+                ask_pool,
+                offer_asset.amount,
+                pair.commission_rate,
+            )?;
+            to_json_binary(&SimulationResponse {
+                return_amount,
+                spread_amount,
+                commission_amount,
+            })
+        }
+        PairQueryMsg::ReverseSimulation { ask_asset } => {
+            let (ask_pool, offer_pool) = mock_pools(pair, &ask_asset.info)?;
+            let (offer_amount, spread_amount, commission_amount) =
+                compute_offer_amount(offer_pool, ask_pool, ask_asset.amount, pair.commission_rate)?;
+            to_json_binary(&ReverseSimulationResponse {
+                offer_amount,
+                spread_amount,
+                commission_amount,
+            })
+        }
+    }
+}
+
+/// Returns (pool of `info`, pool of the other asset)
+fn mock_pools(pair: &MockPair, info: &AssetInfo) -> StdResult<(Uint128, Uint128)> {
+    if info.equal(&pair.info.asset_infos[0]) {
+        Ok((pair.reserves[0], pair.reserves[1]))
+    } else if info.equal(&pair.info.asset_infos[1]) {
+        Ok((pair.reserves[1], pair.reserves[0]))
+    } else {
+        Err(StdError::generic_err(
+            "Given offer asset does not belong in the pair",
+        ))
+    }
+}
+
+/// Constant product swap, returns (return_amount, spread_amount, commission_amount)
+pub fn compute_swap(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    if offer_pool.is_zero() || ask_pool.is_zero() {
+        return Err(StdError::generic_err("Pool is empty"));
+    }
+    let cp = offer_pool.full_mul(ask_pool);
+    let return_amount: Uint128 =
+        (Uint256::from(ask_pool) - cp / Uint256::from(offer_pool + offer_amount)).try_into()?;
+    let spread_amount = offer_amount
+        .multiply_ratio(ask_pool, offer_pool)
+        .saturating_sub(return_amount);
+    let commission_amount = return_amount * commission_rate;
+
+    Ok((
+        return_amount - commission_amount,
+        spread_amount,
+        commission_amount,
+    ))
+}
+
+/// Inverse of `compute_swap`, returns (offer_amount, spread_amount, commission_amount)
+pub fn compute_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let before_commission = ask_amount * (Decimal::one() / (Decimal::one() - commission_rate));
+    if before_commission >= ask_pool {
+        return Err(StdError::generic_err("Not enough liquidity in the pool"));
+    }
+    let cp = offer_pool.full_mul(ask_pool);
+    let offer_amount: Uint128 = (cp / Uint256::from(ask_pool - before_commission)
+        - Uint256::from(offer_pool))
+    .try_into()?;
+    let spread_amount = offer_amount
+        .multiply_ratio(ask_pool, offer_pool)
+        .saturating_sub(before_commission);
+    let commission_amount = before_commission * commission_rate;
+
+    Ok((offer_amount, spread_amount, commission_amount))
+}
+
 #[cfg(test)]
 mod mock_exception {
     use cosmwasm_std::Binary;
@@ -390,3 +644,93 @@ mod mock_exception {
             }));
     }
 }
+
+#[cfg(test)]
+mod mock_dojo_querier {
+    use cosmwasm_std::{to_json_binary, Addr, Decimal, QuerierWrapper, StdError, Uint128};
+
+    use super::*;
+    use crate::asset::{Asset, AssetInfo, PairInfo};
+    use crate::pair::PoolResponse;
+    use crate::querier::{reverse_simulate, simulate};
+
+    fn pair_info() -> PairInfo {
+        PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: "token0000".to_string(),
+                },
+            ],
+            contract_addr: "pair0000".to_string(),
+            liquidity_token: "liquidity0000".to_string(),
+            asset_decimals: [18, 6],
+        }
+    }
+
+    #[test]
+    fn pair_answers_from_reserves() {
+        let mut deps = mock_dojo_dependencies(&[]);
+        deps.querier.with_pair(
+            pair_info(),
+            [Uint128::new(1_000_000), Uint128::new(2_000_000)],
+            Decimal::permille(3),
+        );
+        let querier = QuerierWrapper::<Empty>::new(&deps.querier);
+
+        let pool: PoolResponse = querier
+            .query_wasm_smart("pair0000", &PairQueryMsg::Pool {})
+            .unwrap();
+        assert_eq!(pool.assets[1].amount, Uint128::new(2_000_000));
+
+        let offer_asset = Asset {
+            info: pair_info().asset_infos[0].clone(),
+            amount: Uint128::new(1_000),
+        };
+        let res = simulate(&querier, Addr::unchecked("pair0000"), &offer_asset).unwrap();
+        // 2_000_000 - 1_000_000 * 2_000_000 / 1_001_000 = 1_999, minus 0.3% commission
+        assert_eq!(res.return_amount, Uint128::new(1_994));
+        assert_eq!(res.commission_amount, Uint128::new(5));
+        assert_eq!(res.spread_amount, Uint128::new(1));
+
+        let ask_asset = Asset {
+            info: pair_info().asset_infos[1].clone(),
+            amount: res.return_amount,
+        };
+        let res = reverse_simulate(&querier, Addr::unchecked("pair0000"), &ask_asset).unwrap();
+        assert!(res.offer_amount >= Uint128::new(999) && res.offer_amount <= Uint128::new(1_001));
+
+        deps.querier
+            .set_pair_reserves("pair0000", [Uint128::new(1_000_000), Uint128::zero()]);
+        let querier = QuerierWrapper::<Empty>::new(&deps.querier);
+        let err = simulate(&querier, Addr::unchecked("pair0000"), &offer_asset).unwrap_err();
+        assert!(err.to_string().contains("Pool is empty"));
+    }
+
+    #[test]
+    fn contract_handlers_mock_oracles_and_verifiers() {
+        let mut deps = mock_dojo_dependencies(&[]);
+        deps.querier
+            .with_contract_handler("oracle0000", |_| to_json_binary(&Decimal::percent(150)));
+        deps.querier.with_contract_handler("verifier0000", |msg| {
+            let proof: String = from_json(msg)?;
+            if proof == "valid" {
+                to_json_binary(&true)
+            } else {
+                Err(StdError::generic_err("invalid proof"))
+            }
+        });
+        let querier = QuerierWrapper::<Empty>::new(&deps.querier);
+
+        let price: Decimal = querier.query_wasm_smart("oracle0000", &"price").unwrap();
+        assert_eq!(price, Decimal::percent(150));
+        let verified: bool = querier.query_wasm_smart("verifier0000", &"valid").unwrap();
+        assert!(verified);
+        let err = querier
+            .query_wasm_smart::<bool>("verifier0000", &"forged")
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid proof"));
+    }
+}
@@ -0,0 +1,61 @@
+use cosmwasm_std::Decimal;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Economic parameters shared by the reflection tokens configured from a preset. Rates are
+/// fractions: `tax_rate` of every transfer is taxed, `reflection_rate` and `burn_rate` of the tax
+/// are reflected and burnt, and `antiwhale_rate` of the supply is the largest transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenPreset {
+    pub name: String,
+    pub tax_rate: Decimal,
+    pub reflection_rate: Decimal,
+    pub burn_rate: Decimal,
+    pub antiwhale_rate: Decimal,
+    /// Seconds over which the collected tax is released to the treasury, 0 credits it at once
+    pub drip_period: u64,
+    /// Fraction of the tax set aside in the insurance fund
+    pub insurance_rate: Decimal,
+}
+
+fn preset(
+    name: &str,
+    tax_percent: u64,
+    reflection_percent: u64,
+    burn_percent: u64,
+    antiwhale_permille: u64,
+    drip_period: u64,
+    insurance_percent: u64,
+) -> TokenPreset {
+    TokenPreset {
+        name: name.to_string(),
+        tax_rate: Decimal::percent(tax_percent),
+        reflection_rate: Decimal::percent(reflection_percent),
+        burn_rate: Decimal::percent(burn_percent),
+        antiwhale_rate: Decimal::permille(antiwhale_permille),
+        drip_period,
+        insurance_rate: Decimal::percent(insurance_percent),
+    }
+}
+
+/// Presets operators apply to their deployments instead of setting each parameter. A preset
+/// never changes once published, new parameters get a new name
+pub fn presets() -> Vec<TokenPreset> {
+    vec![
+        // 5% tax, half reflected and half to the treasury, 2% of the supply per transfer
+        preset("standard", 5, 50, 0, 20, 0, 0),
+        // 1% tax, all reflected, no anti-whale limit
+        preset("low_tax", 1, 100, 0, 1_000, 0, 0),
+        // 8% tax, 80% reflected and dripped over a day, 1% of the supply per transfer
+        preset("reflection_heavy", 8, 80, 0, 10, 86_400, 0),
+        // 5% tax, 60% burnt and 20% reflected
+        preset("deflationary", 5, 20, 60, 20, 0, 0),
+        // 6% tax, half reflected, a tenth of the tax insured
+        preset("insured", 6, 50, 0, 20, 0, 10),
+    ]
+}
+
+/// Preset published under `name`
+pub fn find_preset(name: &str) -> Option<TokenPreset> {
+    presets().into_iter().find(|preset| preset.name == name)
+}
@@ -0,0 +1,407 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{Api, Binary, StdError, StdResult};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd::{Digest as _, Ripemd160};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+/// Tag of the current signature domain, bumped whenever the construction below changes
+pub const SIGNATURE_DOMAIN_TAG: &str = "cw20-reflection/v1";
+
+/// `sha256(tag || len(chain_id) || chain_id || len(contract) || contract)` where every length is
+/// a 4 bytes big endian prefix. Signatures over a digest of this domain cannot be replayed on
+/// another chain, fork or deployment
+pub fn domain_separator(chain_id: &str, contract: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SIGNATURE_DOMAIN_TAG.as_bytes());
+    for field in [chain_id, contract] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Digest signed off-chain for `payload`: `sha256(domain_separator || sha256(payload))`
+pub fn signing_digest(domain_separator: &[u8; 32], payload: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(domain_separator);
+    hasher.update(Sha256::digest(payload));
+    hasher.finalize().into()
+}
+
+/// Checks a 64 bytes `r || s` secp256k1 signature of `payload` under `domain_separator`, the
+/// public key being either compressed (33 bytes) or uncompressed (65 bytes)
+pub fn verify_signature(
+    api: &dyn Api,
+    domain_separator: &[u8; 32],
+    payload: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> StdResult<bool> {
+    api.secp256k1_verify(
+        &signing_digest(domain_separator, payload),
+        signature,
+        public_key,
+    )
+    .map_err(|err| StdError::generic_err(format!("Invalid signature: {}", err)))
+}
+
+/// Amino sign doc of an ADR-36 `signArbitrary` of `data` by `signer`, with its keys sorted the way
+/// wallets serialize it
+pub fn adr36_sign_doc(signer: &str, data: &[u8]) -> Vec<u8> {
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{}","signer":"{}"}}}}],"sequence":"0"}}"#,
+        Binary::from(data).to_base64(),
+        signer
+    )
+    .into_bytes()
+}
+
+/// Type of the secp256k1 key of an account, which decides how its address is derived and how its
+/// ADR-36 sign doc is hashed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyType {
+    /// `/cosmos.crypto.secp256k1.PubKey` of most Cosmos chains: the address is
+    /// `ripemd160(sha256(public_key))` and the sign doc is hashed with sha256
+    #[default]
+    Secp256k1,
+    /// `/injective.crypto.v1beta1.ethsecp256k1.PubKey` of Injective: the address is the Ethereum
+    /// address of the key, the last 20 bytes of `keccak256` of its uncompressed form without the
+    /// `04` prefix, and the sign doc is hashed with keccak256
+    EthSecp256k1,
+}
+
+/// Checks an ADR-36 signature by `signer` of the `signing_digest` of `payload`, which is what a
+/// wallet produces when asked to sign the digest with `signArbitrary`. The sign doc is hashed the
+/// way accounts of `key_type` sign it
+pub fn verify_adr36_signature(
+    api: &dyn Api,
+    domain_separator: &[u8; 32],
+    signer: &str,
+    payload: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+    key_type: KeyType,
+) -> StdResult<bool> {
+    let sign_doc = adr36_sign_doc(signer, &signing_digest(domain_separator, payload));
+    let digest: [u8; 32] = match key_type {
+        KeyType::Secp256k1 => Sha256::digest(&sign_doc).into(),
+        KeyType::EthSecp256k1 => keccak256(&sign_doc),
+    };
+    api.secp256k1_verify(&digest, signature, public_key)
+        .map_err(|err| StdError::generic_err(format!("Invalid signature: {}", err)))
+}
+
+/// Bech32 address with prefix `hrp` of a compressed secp256k1 public key of `key_type`:
+/// `ripemd160(sha256(public_key))` or, for an eth_secp256k1 key, its Ethereum address
+pub fn address_of_public_key(hrp: &str, public_key: &[u8], key_type: KeyType) -> StdResult<String> {
+    if public_key.len() != 33 {
+        return Err(StdError::generic_err(
+            "Expected a compressed secp256k1 public key",
+        ));
+    }
+    let hash = match key_type {
+        KeyType::Secp256k1 => Ripemd160::digest(Sha256::digest(public_key)).to_vec(),
+        KeyType::EthSecp256k1 => {
+            let public_key = k256::PublicKey::from_sec1_bytes(public_key)
+                .map_err(|_| StdError::generic_err("Invalid secp256k1 public key"))?;
+            keccak256(&public_key.to_encoded_point(false).as_bytes()[1..])[12..].to_vec()
+        }
+    };
+    bech32::encode(hrp, hash.to_base32(), Variant::Bech32)
+        .map_err(|err| StdError::generic_err(format!("Invalid address prefix: {}", err)))
+}
+
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+const KECCAK_ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const KECCAK_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Keccak-f[1600] permutation of `state`
+fn keccak_f(state: &mut [u64; 25]) {
+    for round_constant in KECCAK_ROUND_CONSTANTS {
+        // theta
+        let mut columns = [0u64; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            for y in (0..25).step_by(5) {
+                state[y + x] ^= d;
+            }
+        }
+        // rho and pi
+        let mut last = state[1];
+        for (lane, rotation) in KECCAK_LANES.iter().zip(KECCAK_ROTATIONS) {
+            let next = state[*lane];
+            state[*lane] = last.rotate_left(rotation);
+            last = next;
+        }
+        // chi
+        for y in (0..25).step_by(5) {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            for x in 0..5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        // iota
+        state[0] ^= round_constant;
+    }
+}
+
+/// Keccak-256 of `data`, the original Keccak padding used by Ethereum rather than the SHA3-256 one
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    const RATE: usize = 136;
+    let mut state = [0u64; 25];
+    let mut padded = data.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(RATE) * RATE, 0);
+    *padded.last_mut().unwrap() |= 0x80;
+    for block in padded.chunks(RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f(&mut state);
+    }
+    let mut hash = [0u8; 32];
+    for (bytes, lane) in hash.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+
+    // Known answers, computed independently of this module so that wallets can check their own
+    // implementation against them
+    const CHAIN_ID: &str = "injective-1";
+    const CONTRACT: &str = "inj1contract";
+    const PAYLOAD: &str = r#"{"sender":"inj1sender","nonce":0,"msg":{"transfer":{"recipient":"inj1recipient","amount":"100"}}}"#;
+    const SEPARATOR: &str = "b85e13d1450181efacf59b926897981153afd8169f798fafeecf4193193624d7";
+    const DIGEST: &str = "72feb95971263af0cb5ed40ccf27b2a514d46524fad1391c0ad31efae5d6bc9a";
+    // key derived from sha256("cw20-reflection test key"), never use it outside of tests
+    const PUBLIC_KEY: &str = "03ac34e47e305954181334fb1f0abd8d146f64b8c0cac9bbc6ddc6a7f3020da8a1";
+    const SIGNATURE: &str = "43d6bcf3e18c7533a012db8ca7ba80dab653b5ed53f75fffd27e41b519dad5681035998535f961e6ac7ca9a8b248a7ba1c710ec7caec0ce3d092c52379ea05c4";
+
+    #[test]
+    fn domain_separator_vectors() {
+        assert_eq!(hex::encode(domain_separator(CHAIN_ID, CONTRACT)), SEPARATOR);
+        assert_eq!(
+            hex::encode(domain_separator("injective-888", CONTRACT)),
+            "28c47e56f164a6ece5216b563dfb1e19c7423b1d743a14449ebf7bc390dd213e"
+        );
+        assert_eq!(
+            hex::encode(domain_separator(CHAIN_ID, "inj1other")),
+            "235ac515a6aaf399124d7ee7eef954426baad0c8ad7999dd4074080eee964ce7"
+        );
+    }
+
+    #[test]
+    fn signing_digest_vectors() {
+        let separator = domain_separator(CHAIN_ID, CONTRACT);
+        assert_eq!(
+            hex::encode(signing_digest(&separator, PAYLOAD.as_bytes())),
+            DIGEST
+        );
+        assert_eq!(
+            hex::encode(signing_digest(&separator, b"")),
+            "0ca97328a702dcc63ab7c3bd5cbfb23b2f4d02a995666a89cbcb44c2f45e796e"
+        );
+    }
+
+    #[test]
+    fn verify_signature_vectors() {
+        let api = MockApi::default();
+        let separator = domain_separator(CHAIN_ID, CONTRACT);
+        let signature = hex::decode(SIGNATURE).unwrap();
+        let public_key = hex::decode(PUBLIC_KEY).unwrap();
+
+        assert!(verify_signature(
+            &api,
+            &separator,
+            PAYLOAD.as_bytes(),
+            &signature,
+            &public_key
+        )
+        .unwrap());
+        // the same signature does not verify on another chain or for another payload
+        assert!(!verify_signature(
+            &api,
+            &domain_separator("injective-888", CONTRACT),
+            PAYLOAD.as_bytes(),
+            &signature,
+            &public_key
+        )
+        .unwrap());
+        assert!(!verify_signature(&api, &separator, b"{}", &signature, &public_key).unwrap());
+        // malformed signatures are errors rather than mismatches
+        assert!(verify_signature(
+            &api,
+            &separator,
+            PAYLOAD.as_bytes(),
+            &signature[1..],
+            &public_key
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn adr36_vectors() {
+        let api = MockApi::default();
+        let separator = domain_separator(CHAIN_ID, CONTRACT);
+        let public_key = hex::decode(PUBLIC_KEY).unwrap();
+        let signer = address_of_public_key("inj", &public_key, KeyType::Secp256k1).unwrap();
+        assert_eq!(signer, "inj1t0laxck97sh7n84zmu47y2dr7rekycfgjzenpu");
+        assert!(address_of_public_key("inj", &public_key[1..], KeyType::Secp256k1).is_err());
+
+        let payload = format!(
+            r#"{{"owner":"{}","spender":"inj1spender","amount":"100","expires":null,"nonce":0}}"#,
+            signer
+        );
+        assert_eq!(
+            String::from_utf8(adr36_sign_doc(
+                &signer,
+                &signing_digest(&separator, payload.as_bytes())
+            ))
+            .unwrap(),
+            r#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"sign/MsgSignData","value":{"data":"eQbPJVEkAp02RpNxo1zgtnhbEh7g/yKpmFM1AWhvvp0=","signer":"inj1t0laxck97sh7n84zmu47y2dr7rekycfgjzenpu"}}],"sequence":"0"}"#
+        );
+        let signature = hex::decode("e5c79f63d95156575530b8cc1dfe713cd07cbaa5beca86a18bc4f3370c07d1b2699c78ff53fba746f2524b3a84cea0ac81067a2926606420120c5f7db77966b1").unwrap();
+        assert!(verify_adr36_signature(
+            &api,
+            &separator,
+            &signer,
+            payload.as_bytes(),
+            &signature,
+            &public_key,
+            KeyType::Secp256k1
+        )
+        .unwrap());
+        // the signer is part of the signed document
+        assert!(!verify_adr36_signature(
+            &api,
+            &separator,
+            "inj1spender",
+            payload.as_bytes(),
+            &signature,
+            &public_key,
+            KeyType::Secp256k1
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn keccak256_vectors() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        // longer than the 136 bytes absorbed at once
+        assert_eq!(
+            hex::encode(keccak256(&[b'a'; 200])),
+            "96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d"
+        );
+    }
+
+    #[test]
+    fn eth_secp256k1_vectors() {
+        let api = MockApi::default();
+        let separator = domain_separator(CHAIN_ID, CONTRACT);
+        // the generator, the public key of the private key 1, is Ethereum address
+        // 0x7e5f4552091a69125d5dfcb7b8c2659029395bdf
+        let generator =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(
+            address_of_public_key("inj", &generator, KeyType::EthSecp256k1).unwrap(),
+            "inj10e0525sfrf53yh2aljmm3sn9jq5njk7lwfmzjf"
+        );
+
+        // key derived from sha256("cw20-reflection eth test key"), never use it outside of tests
+        let public_key =
+            hex::decode("0203f74be71f283514e83cfe207cd8225c2feeebbca96f5e2fbd26c79be82e54e3")
+                .unwrap();
+        let signer = address_of_public_key("inj", &public_key, KeyType::EthSecp256k1).unwrap();
+        assert_eq!(signer, "inj15ydua0el49cgz34qm68l9j5addsgdqydpazsdw");
+        assert_ne!(
+            address_of_public_key("inj", &public_key, KeyType::Secp256k1).unwrap(),
+            signer
+        );
+        // not a point of the curve, its x coordinate being beyond the field
+        let mut invalid = public_key.clone();
+        invalid[1..].fill(0xff);
+        assert!(address_of_public_key("inj", &invalid, KeyType::EthSecp256k1).is_err());
+
+        let payload = format!(
+            r#"{{"owner":"{}","spender":"inj1spender","amount":"100","expires":null,"nonce":0}}"#,
+            signer
+        );
+        let signature = hex::decode("3eef3eaaf7b3f7a7efe31426cbf67c56ac392be48bcfc10eded999576c3907fd6960b4d07d5a95500f2349c00040260e6288a94b9d13372f3bc82b0780f44a46").unwrap();
+        assert!(verify_adr36_signature(
+            &api,
+            &separator,
+            &signer,
+            payload.as_bytes(),
+            &signature,
+            &public_key,
+            KeyType::EthSecp256k1
+        )
+        .unwrap());
+        // the sign doc of an eth_secp256k1 key is hashed with keccak256, not sha256
+        assert!(!verify_adr36_signature(
+            &api,
+            &separator,
+            &signer,
+            payload.as_bytes(),
+            &signature,
+            &public_key,
+            KeyType::Secp256k1
+        )
+        .unwrap());
+    }
+}
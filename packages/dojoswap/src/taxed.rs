@@ -0,0 +1,145 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::asset::{Asset, AssetInfo};
+use crate::querier::{query_balance, query_token_balance};
+
+/// Tax query answered by fee-on-transfer tokens such as cw20-reflection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxedTokenQueryMsg {
+    QueryTax { amount: Uint128 },
+}
+
+/// Admin message of cw20-reflection making a `Send` of one exact hook message to `contract` tax
+/// free, e.g. providing liquidity to the official pair while swaps sent to it stay taxed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxedTokenExecuteMsg {
+    SetTaxFreeHook {
+        contract: String,
+        /// `hook_msg_hash` of the hook message
+        msg_hash: String,
+        enable: bool,
+    },
+}
+
+/// Sent by cw20-reflection to each registered transfer hook after a transfer. `amount` is what
+/// `from` was debited, `to` was credited `amount - tax`. Reflections and the tax credits are not
+/// transfers and are not reported
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferHookMsg {
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+    pub tax: Uint128,
+}
+
+impl TransferHookMsg {
+    /// Serializes the message wrapped in `TransferHookExecuteMsg`
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_json_binary(&TransferHookExecuteMsg::TransferHook(self))
+    }
+
+    /// Execute message calling the hook `contract_addr`
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// Execute message a transfer hook contract must accept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferHookExecuteMsg {
+    TransferHook(TransferHookMsg),
+}
+
+/// Hash identifying the hook message of a `Send`: hex encoded sha256 of its exact bytes, so a
+/// message serialized with other fields, values or whitespace has another hash
+pub fn hook_msg_hash(msg: &[u8]) -> String {
+    hex::encode(Sha256::digest(msg))
+}
+
+/// Part of the token `QueryTaxResponse` needed to compute what a recipient is credited, the
+/// breakdown of the tax is ignored
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxResponse {
+    pub taxed_amount: Uint128,
+    pub after_tax: Uint128,
+}
+
+/// Amounts moved by tokens which may tax their transfers. The recipient of a taxed transfer is
+/// credited less than what was sent: cw20-reflection reports the credited amount in its
+/// `Cw20ReceiveMsg`, other fee-on-transfer tokens report the amount sent, and reflections are
+/// credited to a pair without any message. A pair keeping its reserves from the amounts it is told
+/// about drifts away from its balance
+pub trait TransferTaxedToken {
+    /// Amount credited to the recipient of a transfer of `amount`: the `after_tax` of the token
+    /// `QueryTax`, the whole amount for native tokens and tokens which do not answer it. The query
+    /// does not know the sender and recipient, so exemptions are not taken into account
+    fn simulate_transfer(&self, querier: &QuerierWrapper, amount: Uint128) -> StdResult<Uint128>;
+
+    /// Amount `account` received since its balance was `recorded`, exact whatever the token taxes
+    fn received_since(
+        &self,
+        querier: &QuerierWrapper,
+        account: &Addr,
+        recorded: Uint128,
+    ) -> StdResult<Uint128>;
+}
+
+impl TransferTaxedToken for AssetInfo {
+    fn simulate_transfer(&self, querier: &QuerierWrapper, amount: Uint128) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::NativeToken { .. } => Ok(amount),
+            AssetInfo::Token { contract_addr } => {
+                let tax: StdResult<TaxResponse> = querier
+                    .query_wasm_smart(contract_addr, &TaxedTokenQueryMsg::QueryTax { amount });
+                // plain cw20 tokens reject the unknown query
+                Ok(tax.map_or(amount, |tax| tax.after_tax))
+            }
+        }
+    }
+
+    fn received_since(
+        &self,
+        querier: &QuerierWrapper,
+        account: &Addr,
+        recorded: Uint128,
+    ) -> StdResult<Uint128> {
+        let balance = match self {
+            AssetInfo::NativeToken { denom } => {
+                query_balance(querier, account.clone(), denom.to_string())?
+            }
+            AssetInfo::Token { contract_addr } => {
+                query_token_balance(querier, Addr::unchecked(contract_addr), account.clone())?
+            }
+        };
+        Ok(balance.checked_sub(recorded)?)
+    }
+}
+
+/// Offer a pair actually received: its balance minus the pool it recorded before the offer was
+/// sent, whatever the token reported. To call from the receive hook, where the balance of the pair
+/// already includes the offer. Reflections credited to the pair since it recorded its pool count
+/// as offered, so pairs of reflection tokens record their pool after every swap
+pub fn received_offer(
+    querier: &QuerierWrapper,
+    pair: &Addr,
+    offer_info: &AssetInfo,
+    recorded_pool: Uint128,
+) -> StdResult<Asset> {
+    Ok(Asset {
+        info: offer_info.clone(),
+        amount: offer_info.received_since(querier, pair, recorded_pool)?,
+    })
+}
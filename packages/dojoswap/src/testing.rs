@@ -1,15 +1,21 @@
+use crate::amount::{format_amount, parse_amount, Rounding};
 use crate::asset::{Asset, AssetInfo, AssetInfoRaw, AssetRaw, PairInfo};
-use crate::mock_querier::mock_dependencies;
+use crate::mock_querier::{mock_dependencies, mock_dojo_dependencies};
+use crate::presets::{find_preset, presets};
 use crate::querier::{
     query_all_balances, query_balance, query_pair_info, query_token_balance, query_token_info,
 };
+use crate::taxed::{
+    hook_msg_hash, received_offer, TaxResponse, TaxedTokenQueryMsg, TransferHookMsg,
+    TransferTaxedToken,
+};
 
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
 use cosmwasm_std::{
-    coin, to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, MessageInfo, StdError, SubMsg, Uint128,
-    WasmMsg,
+    coin, from_json, to_binary, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg,
+    Decimal, Empty, MessageInfo, QuerierWrapper, StdError, SubMsg, Uint128, WasmMsg,
 };
-use cw20::Cw20ExecuteMsg;
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 
 #[test]
 fn token_balance_querier() {
@@ -416,3 +422,159 @@ fn query_dojoswap_pair_contract() {
     assert_eq!(pair_info.contract_addr, Addr::unchecked("pair0000"),);
     assert_eq!(pair_info.liquidity_token, Addr::unchecked("liquidity0000"),);
 }
+
+#[test]
+fn taxed_tokens_report_what_the_pair_received() {
+    let mut deps = mock_dojo_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &"plain0000".to_string(),
+        &[(&"pair0000".to_string(), &Uint128::from(1_100u128))],
+    )]);
+    // a 10% tax on transfers, the pair recorded a pool of 1_000 before being sent 100
+    deps.querier.with_contract_handler("taxed0000", |msg| {
+        if let Ok(TaxedTokenQueryMsg::QueryTax { amount }) = from_json(msg) {
+            let taxed_amount = amount.multiply_ratio(1u128, 10u128);
+            return to_json_binary(&TaxResponse {
+                taxed_amount,
+                after_tax: amount - taxed_amount,
+            });
+        }
+        match from_json(msg)? {
+            Cw20QueryMsg::Balance { .. } => to_json_binary(&Cw20BalanceResponse {
+                balance: Uint128::from(1_090u128),
+            }),
+            _ => Err(StdError::generic_err("unsupported query")),
+        }
+    });
+    let querier = QuerierWrapper::<Empty>::new(&deps.querier);
+    let pair = Addr::unchecked("pair0000");
+    let taxed = AssetInfo::Token {
+        contract_addr: "taxed0000".to_string(),
+    };
+    let plain = AssetInfo::Token {
+        contract_addr: "plain0000".to_string(),
+    };
+
+    assert_eq!(
+        taxed
+            .simulate_transfer(&querier, Uint128::from(100u128))
+            .unwrap(),
+        Uint128::from(90u128)
+    );
+    let offer = received_offer(&querier, &pair, &taxed, Uint128::from(1_000u128)).unwrap();
+    assert_eq!(offer.amount, Uint128::from(90u128));
+
+    // tokens without the tax query are credited the whole amount
+    assert_eq!(
+        plain
+            .simulate_transfer(&querier, Uint128::from(100u128))
+            .unwrap(),
+        Uint128::from(100u128)
+    );
+    let offer = received_offer(&querier, &pair, &plain, Uint128::from(1_000u128)).unwrap();
+    assert_eq!(offer.amount, Uint128::from(100u128));
+
+    // a pool above the balance is a desync, not an offer
+    received_offer(&querier, &pair, &taxed, Uint128::from(1_100u128)).unwrap_err();
+}
+
+#[test]
+fn hook_msg_hash_covers_the_exact_bytes() {
+    assert_eq!(
+        hook_msg_hash(br#"{"provide_liquidity":{}}"#),
+        "efe43e22026a981776b488e44c2d4c3b836abc4986c65e3a6530f7b420c0bfb6"
+    );
+    assert_ne!(
+        hook_msg_hash(br#"{"provide_liquidity": {}}"#),
+        hook_msg_hash(br#"{"provide_liquidity":{}}"#)
+    );
+}
+
+#[test]
+fn transfer_hook_msg_is_wrapped_for_the_hook() {
+    let msg = TransferHookMsg {
+        from: "addr0000".to_string(),
+        to: "addr0001".to_string(),
+        amount: Uint128::new(1_000),
+        tax: Uint128::new(100),
+    };
+    assert_eq!(
+        msg.clone().into_cosmos_msg("hook0000").unwrap(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "hook0000".to_string(),
+            msg: Binary::from(
+                br#"{"transfer_hook":{"from":"addr0000","to":"addr0001","amount":"1000","tax":"100"}}"#
+                    .to_vec()
+            ),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn presets_are_valid_token_parameters() {
+    let presets = presets();
+    for (i, preset) in presets.iter().enumerate() {
+        assert!(presets[..i].iter().all(|other| other.name != preset.name));
+        assert!(preset.tax_rate <= Decimal::one());
+        assert!(preset.reflection_rate + preset.burn_rate <= Decimal::one());
+        assert!(preset.antiwhale_rate <= Decimal::one());
+        assert!(preset.insurance_rate <= Decimal::one());
+        assert!(preset.drip_period <= 30 * 86_400);
+    }
+    assert_eq!(
+        find_preset("standard").unwrap().tax_rate,
+        Decimal::percent(5)
+    );
+    assert_eq!(find_preset("unknown"), None);
+}
+
+#[test]
+fn amounts_convert_to_and_from_human_strings() {
+    let amount = Uint128::new(1_234_567_890);
+    assert_eq!(
+        format_amount(amount, 6, 6, Rounding::Down).unwrap(),
+        "1234.567890"
+    );
+    assert_eq!(
+        format_amount(amount, 6, 2, Rounding::Down).unwrap(),
+        "1234.56"
+    );
+    assert_eq!(
+        format_amount(amount, 6, 2, Rounding::HalfUp).unwrap(),
+        "1234.57"
+    );
+    assert_eq!(format_amount(amount, 6, 0, Rounding::Up).unwrap(), "1235");
+    assert_eq!(
+        format_amount(Uint128::new(5), 6, 9, Rounding::Down).unwrap(),
+        "0.000005"
+    );
+
+    assert_eq!(
+        parse_amount("1234.56789", 6, Rounding::Down).unwrap(),
+        Uint128::new(1_234_567_890)
+    );
+    assert_eq!(
+        parse_amount("12", 6, Rounding::Down).unwrap(),
+        Uint128::new(12_000_000)
+    );
+    assert_eq!(
+        parse_amount(".5", 1, Rounding::Down).unwrap(),
+        Uint128::new(5)
+    );
+    assert_eq!(
+        parse_amount("0.0000015", 6, Rounding::Down).unwrap(),
+        Uint128::new(1)
+    );
+    assert_eq!(
+        parse_amount("0.0000015", 6, Rounding::HalfUp).unwrap(),
+        Uint128::new(2)
+    );
+    assert_eq!(
+        parse_amount("0.0000010001", 6, Rounding::Up).unwrap(),
+        Uint128::new(2)
+    );
+    for invalid in ["", ".", "1.2.3", "-1", "1e6", " 1"] {
+        parse_amount(invalid, 6, Rounding::Down).unwrap_err();
+    }
+}
@@ -1,8 +1,12 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use cosmwasm_std::{StdError, StdResult, Uint128};
-use cw20::{Cw20Coin, MinterResponse, Logo};
+use cosmwasm_std::{Addr, Api, StdError, Uint128};
+use cw20::{Cw20Coin, EmbeddedLogo, Logo, MinterResponse};
+
+/// Max size in bytes of an embedded logo, as enforced by cw20-base
+pub const LOGO_SIZE_CAP: usize = 5 * 1024;
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMarketingInfo {
@@ -23,28 +27,159 @@ pub struct InstantiateMsg {
     pub marketing: Option<InstantiateMarketingInfo>,
 }
 
+/// Why `InstantiateMsg::validate` rejected a message, so that callers can match on the reason
+#[derive(Error, Debug, PartialEq)]
+pub enum InstantiateMsgError {
+    #[error("Name is not in the expected format (3-50 UTF-8 bytes)")]
+    InvalidName {},
+
+    #[error("Ticker symbol is not in expected format [a-zA-Z\\-]{{3,12}}")]
+    InvalidSymbol {},
+
+    #[error("Decimals must not exceed 18")]
+    InvalidDecimals {},
+
+    #[error("Invalid address {address}: {reason}")]
+    InvalidAddress { address: String, reason: String },
+
+    #[error("Duplicate initial balance for {address}")]
+    DuplicateAddress { address: String },
+
+    #[error("Initial supply overflows")]
+    SupplyOverflow {},
+
+    #[error("Initial supply {supply} exceeds the mint cap {cap}")]
+    CapExceeded { supply: Uint128, cap: Uint128 },
+
+    #[error("Logo is bigger than {max_size} bytes")]
+    LogoTooBig { max_size: usize },
+
+    #[error("Logo is not a valid PNG, its header is missing")]
+    InvalidPngHeader {},
+
+    #[error("Logo is not a valid SVG, its XML preamble is missing")]
+    InvalidXmlPreamble {},
+
+    #[error("display_decimals must not exceed decimals")]
+    InvalidDisplayDecimals {},
+
+    #[error("max_tax_rate and max_tax_step must be <= 1")]
+    InvalidTaxLimits {},
+
+    /// Checks specific to a token, such as its wallet limits or vesting schedules
+    #[error("{0}")]
+    Std(#[from] StdError),
+}
+
+impl From<InstantiateMsgError> for StdError {
+    fn from(err: InstantiateMsgError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// Limits of `InstantiateMsg::validate_with`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidationConfig {
+    pub max_decimals: u8,
+    pub max_logo_size: usize,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            max_decimals: 18,
+            max_logo_size: LOGO_SIZE_CAP,
+        }
+    }
+}
+
 impl InstantiateMsg {
     pub fn get_cap(&self) -> Option<Uint128> {
         self.mint.as_ref().and_then(|v| v.cap)
     }
 
-    pub fn validate(&self) -> StdResult<()> {
-        // Check name, symbol, decimals
-        if !is_valid_name(&self.name) {
-            return Err(StdError::generic_err(
-                "Name is not in the expected format (3-50 UTF-8 bytes)",
-            ));
-        }
-        if !is_valid_symbol(&self.symbol) {
-            return Err(StdError::generic_err(
-                "Ticker symbol is not in expected format [a-zA-Z\\-]{3,12}",
-            ));
+    pub fn validate(&self, api: &dyn Api) -> Result<(), InstantiateMsgError> {
+        self.validate_with(api, &ValidationConfig::default())
+    }
+
+    pub fn validate_with(
+        &self,
+        api: &dyn Api,
+        config: &ValidationConfig,
+    ) -> Result<(), InstantiateMsgError> {
+        validate_token_info(
+            api,
+            config,
+            &self.name,
+            &self.symbol,
+            self.decimals,
+            &self.initial_balances,
+            self.mint.as_ref(),
+            self.marketing.as_ref().and_then(|m| m.logo.as_ref()),
+        )
+    }
+}
+
+/// Checks shared by every token instantiate message: name, symbol and decimals, initial balances
+/// against each other and the mint cap, the minter and the logo
+#[allow(clippy::too_many_arguments)]
+pub fn validate_token_info(
+    api: &dyn Api,
+    config: &ValidationConfig,
+    name: &str,
+    symbol: &str,
+    decimals: u8,
+    initial_balances: &[Cw20Coin],
+    mint: Option<&MinterResponse>,
+    logo: Option<&Logo>,
+) -> Result<(), InstantiateMsgError> {
+    // Check name, symbol, decimals
+    if !is_valid_name(name) {
+        return Err(InstantiateMsgError::InvalidName {});
+    }
+    if !is_valid_symbol(symbol) {
+        return Err(InstantiateMsgError::InvalidSymbol {});
+    }
+    if decimals > config.max_decimals {
+        return Err(InstantiateMsgError::InvalidDecimals {});
+    }
+
+    // Check initial balances against each other and the cap
+    let mut holders: Vec<Addr> = Vec::with_capacity(initial_balances.len());
+    let mut supply = Uint128::zero();
+    for coin in initial_balances {
+        let holder = validate_address(api, &coin.address)?;
+        if holders.contains(&holder) {
+            return Err(InstantiateMsgError::DuplicateAddress {
+                address: coin.address.clone(),
+            });
         }
-        if self.decimals > 18 {
-            return Err(StdError::generic_err("Decimals must not exceed 18"));
+        holders.push(holder);
+        supply = supply
+            .checked_add(coin.amount)
+            .map_err(|_| InstantiateMsgError::SupplyOverflow {})?;
+    }
+    if let Some(mint) = mint {
+        validate_address(api, &mint.minter)?;
+        if let Some(cap) = mint.cap {
+            if supply > cap {
+                return Err(InstantiateMsgError::CapExceeded { supply, cap });
+            }
         }
-        Ok(())
     }
+
+    if let Some(logo) = logo {
+        verify_logo(logo, config.max_logo_size)?;
+    }
+    Ok(())
+}
+
+fn validate_address(api: &dyn Api, address: &str) -> Result<Addr, InstantiateMsgError> {
+    api.addr_validate(address)
+        .map_err(|err| InstantiateMsgError::InvalidAddress {
+            address: address.to_string(),
+            reason: err.to_string(),
+        })
 }
 
 fn is_valid_name(name: &str) -> bool {
@@ -68,9 +203,44 @@ fn is_valid_symbol(symbol: &str) -> bool {
     true
 }
 
+/// Same checks as cw20-base on instantiation: an embedded logo must fit in `max_size` and start
+/// with a PNG header or an XML preamble, URLs are not checked
+fn verify_logo(logo: &Logo, max_size: usize) -> Result<(), InstantiateMsgError> {
+    let fits = |data: &[u8]| {
+        if data.len() > max_size {
+            return Err(InstantiateMsgError::LogoTooBig { max_size });
+        }
+        Ok(())
+    };
+    match logo {
+        Logo::Embedded(EmbeddedLogo::Png(data)) => {
+            fits(data)?;
+            // magic byte, "PNG", dos line ending, dos stop character, unix line ending
+            const HEADER: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+            if !data.starts_with(&HEADER) {
+                return Err(InstantiateMsgError::InvalidPngHeader {});
+            }
+        }
+        Logo::Embedded(EmbeddedLogo::Svg(data)) => {
+            fits(data)?;
+            let preamble = data
+                .split_inclusive(|c| *c == b'>')
+                .next()
+                .unwrap_or_default();
+            if !(preamble.starts_with(b"<?xml ") && preamble.ends_with(b"?>")) {
+                return Err(InstantiateMsgError::InvalidXmlPreamble {});
+            }
+        }
+        Logo::Url(_) => {}
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::Binary;
 
     #[test]
     fn get_cap() {
@@ -83,7 +253,7 @@ mod test {
             }),
             name: "test_token".to_string(),
             symbol: "TNT".to_string(),
-            marketing: None
+            marketing: None,
         };
 
         assert_eq!(msg.get_cap(), Some(Uint128::from(1u128)))
@@ -91,6 +261,7 @@ mod test {
 
     #[test]
     fn validate() {
+        let api = MockApi::default();
         let valid_msg = InstantiateMsg {
             decimals: 6u8,
             initial_balances: vec![],
@@ -100,10 +271,10 @@ mod test {
             }),
             name: "test_token".to_string(),
             symbol: "TNT".to_string(),
-            marketing: None
+            marketing: None,
         };
 
-        assert_eq!(valid_msg.validate(), Ok(()));
+        assert_eq!(valid_msg.validate(&api), Ok(()));
 
         let name_invalid_msg = InstantiateMsg {
             decimals: 6u8,
@@ -114,14 +285,14 @@ mod test {
             }),
             name: "a".to_string(),
             symbol: "TNT".to_string(),
-            marketing: None
+            marketing: None,
         };
 
+        let err = name_invalid_msg.validate(&api).unwrap_err();
+        assert_eq!(err, InstantiateMsgError::InvalidName {});
         assert_eq!(
-            name_invalid_msg.validate(),
-            Err(StdError::generic_err(
-                "Name is not in the expected format (3-50 UTF-8 bytes)",
-            ))
+            StdError::from(err),
+            StdError::generic_err("Name is not in the expected format (3-50 UTF-8 bytes)")
         );
 
         let symbol_invalid_msg = InstantiateMsg {
@@ -133,14 +304,14 @@ mod test {
             }),
             name: "test_token".to_string(),
             symbol: "TN".to_string(),
-            marketing: None
+            marketing: None,
         };
 
+        let err = symbol_invalid_msg.validate(&api).unwrap_err();
+        assert_eq!(err, InstantiateMsgError::InvalidSymbol {});
         assert_eq!(
-            symbol_invalid_msg.validate(),
-            Err(StdError::generic_err(
-                "Ticker symbol is not in expected format [a-zA-Z\\-]{3,12}",
-            ))
+            StdError::from(err),
+            StdError::generic_err("Ticker symbol is not in expected format [a-zA-Z\\-]{3,12}")
         );
 
         let decimal_invalid_msg = InstantiateMsg {
@@ -152,12 +323,147 @@ mod test {
             }),
             name: "test_token".to_string(),
             symbol: "TNT".to_string(),
-            marketing: None
+            marketing: None,
         };
 
+        let err = decimal_invalid_msg.validate(&api).unwrap_err();
+        assert_eq!(err, InstantiateMsgError::InvalidDecimals {});
+        assert_eq!(
+            StdError::from(err),
+            StdError::generic_err("Decimals must not exceed 18")
+        );
+        assert_eq!(
+            decimal_invalid_msg.validate_with(
+                &api,
+                &ValidationConfig {
+                    max_decimals: 24,
+                    ..ValidationConfig::default()
+                }
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validate_balances_and_marketing() {
+        let api = MockApi::default();
+        let msg = |initial_balances: Vec<(&str, u128)>, minter: &str, logo: Option<Logo>| {
+            InstantiateMsg {
+                decimals: 6u8,
+                initial_balances: initial_balances
+                    .into_iter()
+                    .map(|(address, amount)| Cw20Coin {
+                        address: address.to_string(),
+                        amount: Uint128::new(amount),
+                    })
+                    .collect(),
+                mint: Some(MinterResponse {
+                    cap: Some(Uint128::new(1_000)),
+                    minter: minter.to_string(),
+                }),
+                name: "test_token".to_string(),
+                symbol: "TNT".to_string(),
+                marketing: Some(InstantiateMarketingInfo {
+                    project: None,
+                    description: None,
+                    marketing: None,
+                    logo,
+                }),
+            }
+        };
+        let png = |len: usize| {
+            let mut data = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+            data.resize(len, 0);
+            Some(Logo::Embedded(EmbeddedLogo::Png(Binary::from(data))))
+        };
+
+        assert_eq!(
+            msg(
+                vec![("addr0000", 600), ("addr0001", 400)],
+                "minter0000",
+                png(LOGO_SIZE_CAP)
+            )
+            .validate(&api),
+            Ok(())
+        );
+        assert_eq!(
+            msg(vec![("addr0000", 600), ("addr0000", 1)], "minter0000", None).validate(&api),
+            Err(InstantiateMsgError::DuplicateAddress {
+                address: "addr0000".to_string()
+            })
+        );
+        assert_eq!(
+            msg(
+                vec![("addr0000", 600), ("addr0001", 401)],
+                "minter0000",
+                None
+            )
+            .validate(&api),
+            Err(InstantiateMsgError::CapExceeded {
+                supply: Uint128::new(1_001),
+                cap: Uint128::new(1_000)
+            })
+        );
+        assert_eq!(
+            msg(
+                vec![("addr0000", u128::MAX), ("addr0001", 1)],
+                "minter0000",
+                None
+            )
+            .validate(&api),
+            Err(InstantiateMsgError::SupplyOverflow {})
+        );
+        assert!(matches!(
+            msg(vec![], "Minter0000", None).validate(&api),
+            Err(InstantiateMsgError::InvalidAddress { address, .. }) if address == "Minter0000"
+        ));
+
+        assert_eq!(
+            msg(vec![], "minter0000", png(LOGO_SIZE_CAP + 1)).validate(&api),
+            Err(InstantiateMsgError::LogoTooBig {
+                max_size: LOGO_SIZE_CAP
+            })
+        );
+        assert_eq!(
+            msg(vec![], "minter0000", png(LOGO_SIZE_CAP + 1)).validate_with(
+                &api,
+                &ValidationConfig {
+                    max_logo_size: 2 * LOGO_SIZE_CAP,
+                    ..ValidationConfig::default()
+                }
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            msg(
+                vec![],
+                "minter0000",
+                Some(Logo::Embedded(EmbeddedLogo::Png(Binary::from(b"GIF89a"))))
+            )
+            .validate(&api),
+            Err(InstantiateMsgError::InvalidPngHeader {})
+        );
+        assert_eq!(
+            msg(
+                vec![],
+                "minter0000",
+                Some(Logo::Embedded(EmbeddedLogo::Svg(Binary::from(
+                    b"<svg></svg>"
+                ))))
+            )
+            .validate(&api),
+            Err(InstantiateMsgError::InvalidXmlPreamble {})
+        );
         assert_eq!(
-            decimal_invalid_msg.validate(),
-            Err(StdError::generic_err("Decimals must not exceed 18"))
+            msg(
+                vec![],
+                "minter0000",
+                Some(Logo::Embedded(EmbeddedLogo::Svg(Binary::from(
+                    b"<?xml version=\"1.0\"?><svg></svg>"
+                ))))
+            )
+            .validate(&api),
+            Ok(())
         );
     }
 }